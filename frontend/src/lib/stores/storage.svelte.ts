@@ -3,12 +3,14 @@ import type {
 	EngineConfig,
 	BufferPoolSnapshot,
 	DiskSnapshot,
+	FaultKind,
 	PageSnapshot,
 	TableInfo,
 	ColumnDef,
+	ColumnType,
 	ScanRow,
 } from '$lib/wasm/storage-types.js';
-import { formatBytes, parseCSV, inferColumnTypes } from '$lib/wasm/storage-types.js';
+import { formatBytes, parseCSV, inferColumnTypes, describeError } from '$lib/wasm/storage-types.js';
 
 // ── Reactive state using Svelte 5 runes ────────────────────────────
 
@@ -43,6 +45,27 @@ function refreshSnapshots() {
 	}
 }
 
+/** Make `next` the current engine and clear all per-engine UI state. */
+function adoptEngine(next: StorageEngineWrapper) {
+	if (engine) {
+		engine.destroy();
+	}
+	engine = next;
+	engineConfig = engine.config();
+	engineReady = true;
+	tables = engine.listTables();
+	selectedTable = tables.length > 0 ? tables[0] : null;
+	scanResults = [];
+	scanColumns = [];
+	tableInfo = selectedTable ? engine.snapshotTable(selectedTable) : null;
+	selectedSlotId = null;
+	selectedCell = null;
+	getRowResult = null;
+	selectedPageId = null;
+	pageSnap = null;
+	refreshSnapshots();
+}
+
 function setStatus(msg: string, type: 'info' | 'success' | 'error' = 'info') {
 	statusMsg = msg;
 	statusType = type;
@@ -69,18 +92,28 @@ const SAMPLE_CITIES = [
 
 const SAMPLE_STRINGS = [...SAMPLE_NAMES, ...SAMPLE_EMAILS, ...SAMPLE_CITIES];
 
-function randomValue(colType: string | { VarChar: number } | { Blob: number }, nullable: boolean): unknown {
+function randomHex(len: number): string {
+	const bytes = Array.from({ length: len }, () => Math.floor(Math.random() * 256));
+	return bytes.map(b => b.toString(16).padStart(2, '0')).join('');
+}
+
+function randomValue(colType: ColumnType, nullable: boolean): unknown {
 	if (nullable && Math.random() < 0.1) return null;
 
 	if (typeof colType === 'object') {
-		if ('VarChar' in colType) {
+		if ('VarChar' in colType || 'Char' in colType) {
+			const max = 'VarChar' in colType ? colType.VarChar : colType.Char;
 			const s = SAMPLE_STRINGS[Math.floor(Math.random() * SAMPLE_STRINGS.length)];
-			return s.length > colType.VarChar ? s.slice(0, colType.VarChar) : s;
+			return s.length > max ? s.slice(0, max) : s;
+		}
+		if ('Decimal' in colType) {
+			// Decimals travel as strings so no digits are lost
+			const [precision, scale] = colType.Decimal;
+			const unscaled = Math.floor(Math.random() * 10 ** Math.min(precision, 9));
+			return (unscaled / 10 ** scale).toFixed(scale);
 		}
-		// Blob — small random hex string
-		const len = Math.min(8, ('Blob' in colType ? colType.Blob : 8));
-		const bytes = Array.from({ length: len }, () => Math.floor(Math.random() * 256));
-		return bytes.map(b => b.toString(16).padStart(2, '0')).join('');
+		// Blob — a few random bytes as "0x…" hex
+		return '0x' + randomHex(Math.min(8, colType.Blob));
 	}
 
 	switch (colType) {
@@ -88,16 +121,30 @@ function randomValue(colType: string | { VarChar: number } | { Blob: number }, n
 		case 'UInt32': return Math.floor(Math.random() * 10001);
 		case 'Float64': return Math.round(Math.random() * 10000) / 100;
 		case 'Bool':   return Math.random() > 0.5;
+		case 'Int16':  return Math.floor(Math.random() * 2001) - 1000;
+		case 'Int64':  return Math.floor(Math.random() * 2_000_000_001) - 1_000_000_000;
+		case 'Float32': return Math.round(Math.random() * 1000) / 8;
+		case 'Date':
+		case 'Timestamp': {
+			// Somewhere between 2000 and 2030
+			const ms = Date.UTC(2000, 0, 1) + Math.random() * 30 * 365 * 86_400_000;
+			const iso = new Date(Math.floor(ms / 1000) * 1000).toISOString().replace('.000Z', 'Z');
+			return colType === 'Date' ? iso.slice(0, 10) : iso;
+		}
+		case 'Uuid': {
+			const h = randomHex(16);
+			return `${h.slice(0, 8)}-${h.slice(8, 12)}-${h.slice(12, 16)}-${h.slice(16, 20)}-${h.slice(20)}`;
+		}
 		default:       return 0;
 	}
 }
 
-function coerceValue(raw: string, colType: string | { VarChar: number } | { Blob: number }): unknown {
+function coerceValue(raw: string, colType: ColumnType): unknown {
 	if (raw === '') return null;
-	if (typeof colType === 'object') return raw; // VarChar / Blob → string as-is
+	if (typeof colType === 'object') return raw; // VarChar / Blob / Char / Decimal → string as-is
 	switch (colType) {
-		case 'Int32': case 'UInt32': return parseInt(raw, 10);
-		case 'Float64': return parseFloat(raw);
+		case 'Int32': case 'UInt32': case 'Int16': return parseInt(raw, 10);
+		case 'Float64': case 'Float32': return parseFloat(raw);
 		case 'Bool': {
 			const lower = raw.toLowerCase();
 			return lower === 'true' || lower === '1';
@@ -134,26 +181,31 @@ export const storageState = {
 	},
 
 	initEngine(config: EngineConfig) {
-		if (engine) {
-			engine.destroy();
-		}
-		engine = new StorageEngineWrapper(config);
-		engineConfig = engine.config();
-		engineReady = true;
-		tables = [];
-		selectedTable = null;
-		scanResults = [];
-		scanColumns = [];
-		tableInfo = null;
-		selectedSlotId = null;
-		selectedCell = null;
-		getRowResult = null;
-		selectedPageId = null;
-		pageSnap = null;
-		refreshSnapshots();
+		adoptEngine(new StorageEngineWrapper(config));
 		setStatus('Engine initialized', 'success');
 	},
 
+	/** Replace the engine with one reopened from a saved image. */
+	loadImage(bytes: Uint8Array) {
+		try {
+			adoptEngine(StorageEngineWrapper.fromImage(bytes));
+			setStatus(`Loaded image with ${tables.length} table(s)`, 'success');
+		} catch (e: unknown) {
+			setStatus(describeError(e), 'error');
+		}
+	},
+
+	/** The current database as bytes, or null if saving isn't possible. */
+	exportImage(): Uint8Array | null {
+		if (!engine) return null;
+		try {
+			return engine.exportImage();
+		} catch (e: unknown) {
+			setStatus(describeError(e), 'error');
+			return null;
+		}
+	},
+
 	resetEngine() {
 		if (engine) {
 			engine.destroy();
@@ -185,13 +237,18 @@ export const storageState = {
 			refreshSnapshots();
 			setStatus(`Table '${name}' created`, 'success');
 		} catch (e: unknown) {
-			setStatus(String(e), 'error');
+			setStatus(describeError(e), 'error');
 		}
 	},
 
 	dropTable(name: string) {
 		if (!engine) return;
-		engine.dropTable(name);
+		try {
+			engine.dropTable(name);
+		} catch (e: unknown) {
+			setStatus(describeError(e), 'error');
+			return;
+		}
 		tables = engine.listTables();
 		if (selectedTable === name) {
 			selectedTable = tables.length > 0 ? tables[0] : null;
@@ -233,7 +290,7 @@ export const storageState = {
 				setStatus(`Inserted row ${rowId}`, 'success');
 			}
 		} catch (e: unknown) {
-			setStatus(String(e), 'error');
+			setStatus(describeError(e), 'error');
 		}
 	},
 
@@ -247,7 +304,7 @@ export const storageState = {
 				ok ? 'success' : 'error'
 			);
 		} catch (e: unknown) {
-			setStatus(String(e), 'error');
+			setStatus(describeError(e), 'error');
 		}
 	},
 
@@ -276,7 +333,7 @@ export const storageState = {
 			setStatus(`Got row ${rowId}`, 'success');
 		} catch (e: unknown) {
 			getRowResult = null;
-			setStatus(String(e), 'error');
+			setStatus(describeError(e), 'error');
 		}
 	},
 
@@ -315,7 +372,7 @@ export const storageState = {
 			refreshSnapshots();
 			setStatus(`Scanned ${scanResults.length} rows`, 'success');
 		} catch (e: unknown) {
-			setStatus(String(e), 'error');
+			setStatus(describeError(e), 'error');
 		}
 	},
 
@@ -362,6 +419,32 @@ export const storageState = {
 		setStatus('Flushed all dirty pages', 'success');
 	},
 
+	setFaultSeed(seed: number) {
+		if (!engine) return;
+		engine.setFaultSeed(seed);
+		refreshSnapshots();
+		setStatus(`Fault seed set to ${seed}`, 'info');
+	},
+
+	armFault(kind: FaultKind, trigger: { nth: number } | { pageId: number }) {
+		if (!engine) return;
+		try {
+			const id = engine.armFault(kind, trigger);
+			refreshSnapshots();
+			const when = 'nth' in trigger ? `write/read #${trigger.nth}` : `page ${trigger.pageId}`;
+			setStatus(`Armed fault #${id}: ${kind} on ${when}`, 'info');
+		} catch (e: unknown) {
+			setStatus(describeError(e), 'error');
+		}
+	},
+
+	disarmFaults() {
+		if (!engine) return;
+		engine.disarmFaults();
+		refreshSnapshots();
+		setStatus('Disarmed all faults', 'info');
+	},
+
 	bootstrapTable(name: string, columns: ColumnDef[], rowCount: number) {
 		if (!engine) return;
 		try {
@@ -378,7 +461,7 @@ export const storageState = {
 			refreshSnapshots();
 			setStatus(`Created '${name}' with ${rowCount} rows`, 'success');
 		} catch (e: unknown) {
-			setStatus(String(e), 'error');
+			setStatus(describeError(e), 'error');
 		}
 	},
 
@@ -400,7 +483,7 @@ export const storageState = {
 			refreshSnapshots();
 			setStatus(`Loaded '${tableName}' with ${rows.length} rows from CSV`, 'success');
 		} catch (e: unknown) {
-			setStatus(String(e), 'error');
+			setStatus(describeError(e), 'error');
 		}
 	},
 
@@ -4,14 +4,27 @@ import {
 	type EngineConfig,
 	type BufferPoolSnapshot,
 	type DiskSnapshot,
+	type FaultKind,
 	type PageSnapshot,
 	type TableInfo,
 	type ScanRow,
+	type ScanPage,
 	type ColumnDef,
+	type RecoveryReport,
+	type VacuumReport,
+	type QueryResult,
+	type JoinAlgorithm,
+	type SortResult,
+	type IndexSnapshot,
+	type HashIndexSnapshot,
+	type FsmSnapshot,
 	decodeBufferPool,
 	decodeDisk,
 	decodePage,
 	decodeTable,
+	decodeIndex,
+	decodeHashIndex,
+	decodeFsm,
 } from './storage-types.js';
 
 let initialized = false;
@@ -26,8 +39,21 @@ export async function initStorageWasm(): Promise<void> {
 export class StorageEngineWrapper {
 	private inner: WasmStorageEngine;
 
-	constructor(config: EngineConfig) {
-		this.inner = new WasmStorageEngine(JSON.stringify(config));
+	constructor(config: EngineConfig | WasmStorageEngine) {
+		this.inner =
+			config instanceof WasmStorageEngine
+				? config
+				: new WasmStorageEngine(JSON.stringify(config));
+	}
+
+	/** Reopen a database from bytes produced by `exportImage`. */
+	static fromImage(bytes: Uint8Array): StorageEngineWrapper {
+		return new StorageEngineWrapper(WasmStorageEngine.from_image(bytes));
+	}
+
+	/** The whole disk (superblock, catalog and every page) as bytes. */
+	exportImage(): Uint8Array {
+		return this.inner.export_image();
 	}
 
 	config(): EngineConfig {
@@ -47,22 +73,75 @@ export class StorageEngineWrapper {
 		return JSON.parse(this.inner.list_tables());
 	}
 
-	insert(tableName: string, values: unknown[]): string {
-		return this.inner.insert(tableName, JSON.stringify(values));
+	insert(tableName: string, values: unknown[], txn?: number): string {
+		return this.inner.insert(tableName, JSON.stringify(values), txn);
+	}
+
+	/**
+	 * Insert with raw bytes for Blob columns: one entry of `blobs` per Blob
+	 * column, in column order, and `null` at those positions in `values`.
+	 */
+	insertWithBlobs(
+		tableName: string,
+		values: unknown[],
+		blobs: (Uint8Array | null)[],
+		txn?: number,
+	): string {
+		return this.inner.insert_with_blobs(tableName, JSON.stringify(values), blobs, txn);
 	}
 
 	get(tableName: string, rowId: string): unknown[] {
 		return JSON.parse(this.inner.get(tableName, rowId));
 	}
 
-	delete(tableName: string, rowId: string): boolean {
-		return this.inner.delete(tableName, rowId);
+	delete(tableName: string, rowId: string, txn?: number): boolean {
+		return this.inner.delete(tableName, rowId, txn);
+	}
+
+	/** Replace a row's values; its row id stays the same. */
+	update(tableName: string, rowId: string, values: unknown[], txn?: number): boolean {
+		return this.inner.update(tableName, rowId, JSON.stringify(values), txn);
 	}
 
 	scan(tableName: string): ScanRow[] {
 		return JSON.parse(this.inner.scan(tableName));
 	}
 
+	/** Up to `limit` rows starting at cursor `from` (omit for the first page). */
+	scanFrom(tableName: string, limit: number, from?: string): ScanPage {
+		return JSON.parse(this.inner.scan_from(tableName, from ?? '', limit));
+	}
+
+	/** Compact pages and free empty ones; fails while transactions are open. */
+	vacuum(tableName: string): VacuumReport {
+		return JSON.parse(this.inner.vacuum(tableName));
+	}
+
+	// ── Indexes ────────────────────────────────────────────────
+
+	createIndex(tableName: string, column: string): number {
+		return this.inner.create_index(tableName, column);
+	}
+
+	indexLookup(tableName: string, column: string, key: unknown): ScanRow[] {
+		return JSON.parse(this.inner.index_lookup(tableName, column, JSON.stringify(key)));
+	}
+
+	/** Range scan; pass `null` for an open bound. */
+	indexRange(tableName: string, column: string, low: unknown, high: unknown): ScanRow[] {
+		return JSON.parse(
+			this.inner.index_range(tableName, column, JSON.stringify(low), JSON.stringify(high)),
+		);
+	}
+
+	createHashIndex(tableName: string, column: string): number {
+		return this.inner.create_hash_index(tableName, column);
+	}
+
+	hashLookup(tableName: string, column: string, key: unknown): ScanRow[] {
+		return JSON.parse(this.inner.hash_lookup(tableName, column, JSON.stringify(key)));
+	}
+
 	flushAll(): void {
 		this.inner.flush_all();
 	}
@@ -71,11 +150,94 @@ export class StorageEngineWrapper {
 		return this.inner.flush_page(pageId);
 	}
 
+	// ── Transactions ───────────────────────────────────────────
+
+	begin(): number {
+		return this.inner.begin();
+	}
+
+	commit(txn: number): boolean {
+		return this.inner.commit(txn);
+	}
+
+	abort(txn: number): boolean {
+		return this.inner.abort(txn);
+	}
+
+	activeTransactions(): number[] {
+		return JSON.parse(this.inner.active_transactions());
+	}
+
+	// ── SQL ────────────────────────────────────────────────────
+
+	executeSql(sql: string, txn?: number): QueryResult {
+		return JSON.parse(this.inner.execute_sql(sql, txn));
+	}
+
+	join(
+		algorithm: JoinAlgorithm,
+		left: [table: string, column: string],
+		right: [table: string, column: string],
+		memoryFrames: number
+	): QueryResult {
+		return JSON.parse(
+			this.inner.join(algorithm, left[0], left[1], right[0], right[1], memoryFrames)
+		);
+	}
+
+	sortTable(table: string, column: string, descending: boolean, memoryFrames: number): SortResult {
+		return JSON.parse(this.inner.sort_table(table, column, descending, memoryFrames));
+	}
+
+	/** `aggregates` are calls like `count(*)`, `sum(price)`; one result row per group. */
+	aggregate(
+		table: string,
+		groupBy: string[],
+		aggregates: string[],
+		memoryFrames: number
+	): QueryResult {
+		return JSON.parse(
+			this.inner.aggregate(
+				table,
+				JSON.stringify(groupBy),
+				JSON.stringify(aggregates),
+				memoryFrames
+			)
+		);
+	}
+
+	// ── Crash recovery ─────────────────────────────────────────
+
+	simulateCrash(): void {
+		this.inner.simulate_crash();
+	}
+
+	recover(): RecoveryReport {
+		return JSON.parse(this.inner.recover());
+	}
+
 	tableSchema(tableName: string): { columns: ColumnDef[] } | null {
 		const json = this.inner.table_schema(tableName);
 		return json ? JSON.parse(json) : null;
 	}
 
+	// ── Fault injection ────────────────────────────────────────
+
+	setFaultSeed(seed: number): void {
+		this.inner.set_fault_seed(seed);
+	}
+
+	/** Arm a one-shot disk fault on the Nth write (or read) from now, or on a page. */
+	armFault(kind: FaultKind, trigger: { nth: number } | { pageId: number }): number {
+		return 'nth' in trigger
+			? this.inner.arm_fault(kind, trigger.nth, undefined)
+			: this.inner.arm_fault(kind, undefined, trigger.pageId);
+	}
+
+	disarmFaults(): void {
+		this.inner.disarm_faults();
+	}
+
 	// ── Snapshots ──────────────────────────────────────────────
 
 	snapshotBufferPool(): BufferPoolSnapshot {
@@ -96,6 +258,21 @@ export class StorageEngineWrapper {
 		return data ? decodeTable(data) : null;
 	}
 
+	snapshotIndex(tableName: string, column: string): IndexSnapshot | null {
+		const data = this.inner.snapshot_index(tableName, column);
+		return data ? decodeIndex(data) : null;
+	}
+
+	snapshotHashIndex(tableName: string, column: string): HashIndexSnapshot | null {
+		const data = this.inner.snapshot_hash_index(tableName, column);
+		return data ? decodeHashIndex(data) : null;
+	}
+
+	snapshotFsm(tableName: string): FsmSnapshot | null {
+		const data = this.inner.snapshot_fsm(tableName);
+		return data ? decodeFsm(data) : null;
+	}
+
 	destroy(): void {
 		this.inner.free();
 	}
@@ -2,11 +2,20 @@
 
 export const INVALID_PAGE = 0xFFFFFFFF;
 
+/** Bytes in the slotted-page header (see `storage/types.rs`). */
+export const PAGE_HEADER_SIZE = 24;
+
 export interface EngineConfig {
 	page_size: number;
 	pool_size: number;
 	disk_capacity: number;
 	overflow_threshold: number;
+	replacement_policy?: string; // LRU, Clock, LRU-k, 2Q, ARC, FIFO
+	// Simulated disk latencies in microseconds
+	seek_us?: number;
+	seq_transfer_us?: number;
+	random_transfer_us?: number;
+	write_penalty_us?: number;
 }
 
 export interface FrameInfo {
@@ -21,7 +30,7 @@ export interface BufferPoolSnapshot {
 	pageSize: number;
 	frames: FrameInfo[];
 	pageTable: Map<number, number>; // pageId → frameId
-	lruOrder: number[];             // frameIds, front=LRU
+	lruOrder: number[];             // frameIds in eviction order, front = next victim
 	hitCount: number;
 	missCount: number;
 	diskReadCount: number;
@@ -29,11 +38,44 @@ export interface BufferPoolSnapshot {
 	diskNumAllocated: number;
 	diskMaxPages: number;
 	diskBasePtr: number;
+	ioElapsedUs: number;  // simulated time spent on disk I/O
+	ioSequential: number; // accesses at or right after the disk head
+	ioRandom: number;     // accesses that needed a seek
+	ioHead: number | null;
+	policy: ReplacerState;
 }
 
+/** Per-policy replacer state, tagged by policy name. */
+export type ReplacerState =
+	| { kind: 'LRU' }
+	| { kind: 'FIFO' }
+	| { kind: 'Clock'; hand: number; refBits: boolean[] }
+	| { kind: 'LRU-k'; k: number; history: { accesses: number; kDistance: number | null }[] }
+	| { kind: '2Q'; a1In: number[]; a1Out: number[]; am: number[] }
+	| { kind: 'ARC'; p: number; t1: number[]; t2: number[]; b1: number[]; b2: number[] };
+
 export interface DiskPageInfo {
 	isAllocated: boolean;
-	pageType: number; // 0=Data, 1=Overflow, 2=Free
+	pageType: number; // 0=Data, 1=Overflow, 2=Free, ... (see pageTypeName)
+	isCorrupt: boolean; // disk bytes fail their checksum
+}
+
+export type FaultKind = 'torn_write' | 'dropped_write' | 'bit_flip' | 'read_error';
+
+export const FAULT_KINDS: FaultKind[] = ['torn_write', 'dropped_write', 'bit_flip', 'read_error'];
+
+export interface ArmedFault {
+	id: number;
+	kind: FaultKind;
+	trigger: { nth: number } | { pageId: number };
+}
+
+export interface FiredFault {
+	id: number;
+	kind: FaultKind;
+	pageId: number;
+	op: number; // which read or write it hit
+	detail: number; // bytes landed (torn write) or bit index (bit flip)
 }
 
 export interface DiskSnapshot {
@@ -42,11 +84,15 @@ export interface DiskSnapshot {
 	numAllocated: number;
 	diskBasePtr: number;
 	pages: DiskPageInfo[];
+	faultSeed: number;
+	armedFaults: ArmedFault[];
+	firedFaults: FiredFault[];
 }
 
 export interface SlotInfo {
 	offset: number;
 	length: number;
+	kind: number; // 0=tuple, 1=moved tuple, 2=forwarding slot
 }
 
 export interface PageSnapshot {
@@ -58,13 +104,26 @@ export interface PageSnapshot {
 	freeEnd: number;
 	nextPageId: number;
 	freeSpace: number;
+	pageLsn: number;
 	slots: SlotInfo[];
 	rawBytes: Uint8Array;
 }
 
+/**
+ * A column type as the engine's schema JSON spells it: a plain name such
+ * as `'Int32'` or `'Timestamp'`, or an object for the parameterised types.
+ * Decimal takes `[precision, scale]`.
+ */
+export type ColumnType =
+	| string
+	| { VarChar: number }
+	| { Blob: number }
+	| { Char: number }
+	| { Decimal: [number, number] };
+
 export interface ColumnDef {
 	name: string;
-	type: string | { VarChar: number } | { Blob: number };
+	type: ColumnType;
 	nullable: boolean;
 }
 
@@ -76,11 +135,155 @@ export interface TableInfo {
 	pageIds: number[];
 }
 
+export interface IndexKey {
+	text: string;
+	rowId: string;
+}
+
+export interface IndexNode {
+	pageId: number;
+	depth: number;
+	isLeaf: boolean;
+	nextLeaf: number | null;
+	keys: IndexKey[];
+	children: number[];
+}
+
+export interface IndexSnapshot {
+	rootPageId: number;
+	keySize: number;
+	nodes: IndexNode[];
+}
+
+export interface HashBucketInfo {
+	pageId: number;
+	localDepth: number;
+	/** The bucket page and its overflow pages, with their entry counts. */
+	chain: { pageId: number; numEntries: number }[];
+}
+
+export interface HashIndexSnapshot {
+	directoryPageId: number;
+	globalDepth: number;
+	keySize: number;
+	bucketCapacity: number;
+	/** Per directory slot: bucket page and that bucket's local depth. */
+	directory: { bucketPageId: number; localDepth: number }[];
+	buckets: HashBucketInfo[];
+}
+
+export interface FsmSnapshot {
+	/** Bytes per category step. */
+	granularity: number;
+	mapPageIds: number[];
+	entries: { pageId: number; category: number; approxFree: number }[];
+}
+
+export interface RecoveryReport {
+	records_scanned: number;
+	redo_start: number;
+	redo_applied: number;
+	undo_applied: number;
+	losers: number[];
+	/** Losers whose rollback stopped at an unreadable page; recover again to finish. */
+	unfinished: number[];
+}
+
+export interface VacuumReport {
+	pages_scanned: number;
+	pages_compacted: number;
+	slots_trimmed: number;
+	pages_freed: number;
+	bytes_reclaimed: number;
+}
+
 export interface ScanRow {
 	row_id: string;
 	values: unknown[];
 }
 
+/** One page of a paginated scan; `next` is the cursor for the following page. */
+export interface ScanPage {
+	rows: ScanRow[];
+	next: string | null;
+}
+
+/** One operator of an executed plan, with the rows and I/O of its own work. */
+export interface PlanProfile {
+	operator: string;
+	detail: string;
+	rows_produced: number;
+	pages_touched: number;
+	page_reads: number;
+	page_writes: number;
+	children: PlanProfile[];
+}
+
+/** Result of `executeSql`, `join` or `aggregate`: output columns, rows and plan of a SELECT, or how many rows a change touched. */
+export interface QueryResult {
+	columns: ColumnDef[];
+	rows: unknown[][];
+	rows_affected: number;
+	plan: PlanProfile | null;
+}
+
+/** Algorithms accepted by `join`. */
+export type JoinAlgorithm = 'nested_loop' | 'block_nested_loop' | 'hash' | 'sort_merge';
+
+/** Stats of a plan node and everything below it, e.g. a join's total page reads and writes. */
+export function planTotals(plan: PlanProfile): Omit<PlanProfile, 'operator' | 'detail' | 'children'> {
+	const total = {
+		rows_produced: plan.rows_produced,
+		pages_touched: plan.pages_touched,
+		page_reads: plan.page_reads,
+		page_writes: plan.page_writes
+	};
+	for (const child of plan.children) {
+		const sub = planTotals(child);
+		total.rows_produced += sub.rows_produced;
+		total.pages_touched += sub.pages_touched;
+		total.page_reads += sub.page_reads;
+		total.page_writes += sub.page_writes;
+	}
+	return total;
+}
+
+/** A sorted run of an external sort; `pass` is 0 for runs cut from the input. */
+export interface SortRun {
+	id: number;
+	pass: number;
+	rows: number;
+	pages: number[];
+}
+
+/** Runs merged into one; the final merge streams out and has a null `output`. */
+export interface SortMerge {
+	inputs: number[];
+	output: number | null;
+}
+
+/** What an external sort did, run by run and pass by pass. `runs` is empty if the rows fit in memory. */
+export interface SortTrace {
+	memory_frames: number;
+	input_rows: number;
+	runs: SortRun[];
+	passes: SortMerge[][];
+}
+
+/** Result of `sortTable`. */
+export interface SortResult {
+	result: QueryResult;
+	trace: SortTrace;
+}
+
+/** Shape of the errors the engine throws (an `Error` with these extras). */
+export interface StorageError extends Error {
+	/** Stable kind, e.g. `ROW_NOT_FOUND`, `DISK_FULL`, `CORRUPT_PAGE`. */
+	code: string;
+	/** Page involved, or null. */
+	pageId: number | null;
+}
+
 // ── Binary decoders ────────────────────────────────────────────────
 
 class BinaryReader {
@@ -129,24 +332,74 @@ export function decodeBufferPool(data: Uint8Array): BufferPoolSnapshot {
 		pageTable.set(r.u32(), r.u32());
 	}
 
-	const lruLen = r.u32();
-	const lruOrder: number[] = [];
-	for (let i = 0; i < lruLen; i++) {
-		lruOrder.push(r.u32());
-	}
+	const lruOrder = readList(r);
+
+	const hitCount = Number(r.u64());
+	const missCount = Number(r.u64());
+	const diskReadCount = Number(r.u64());
+	const diskWriteCount = Number(r.u64());
+	const diskNumAllocated = r.u32();
+	const diskMaxPages = r.u32();
+	const diskBasePtr = r.u32();
+	const ioElapsedUs = Number(r.u64());
+	const ioSequential = Number(r.u64());
+	const ioRandom = Number(r.u64());
+	const head = r.u32();
+	const ioHead = head === INVALID_PAGE ? null : head;
+	const policy = decodeReplacerState(r, poolSize);
 
 	return {
 		poolSize, pageSize, frames, pageTable, lruOrder,
-		hitCount: Number(r.u64()),
-		missCount: Number(r.u64()),
-		diskReadCount: Number(r.u64()),
-		diskWriteCount: Number(r.u64()),
-		diskNumAllocated: r.u32(),
-		diskMaxPages: r.u32(),
-		diskBasePtr: r.u32(),
+		hitCount, missCount, diskReadCount, diskWriteCount,
+		diskNumAllocated, diskMaxPages, diskBasePtr,
+		ioElapsedUs, ioSequential, ioRandom, ioHead, policy,
 	};
 }
 
+function readList(r: BinaryReader): number[] {
+	const len = r.u32();
+	const out: number[] = [];
+	for (let i = 0; i < len; i++) {
+		out.push(r.u32());
+	}
+	return out;
+}
+
+function decodeReplacerState(r: BinaryReader, poolSize: number): ReplacerState {
+	const tag = r.u8();
+	switch (tag) {
+		case 1: {
+			const hand = r.u32();
+			const refBits: boolean[] = [];
+			for (let i = 0; i < poolSize; i++) refBits.push(r.u8() !== 0);
+			return { kind: 'Clock', hand, refBits };
+		}
+		case 2: {
+			const k = r.u32();
+			const history: { accesses: number; kDistance: number | null }[] = [];
+			for (let i = 0; i < poolSize; i++) {
+				const accesses = r.u32();
+				const dist = r.u64();
+				history.push({
+					accesses,
+					kDistance: dist === 0xffffffffffffffffn ? null : Number(dist),
+				});
+			}
+			return { kind: 'LRU-k', k, history };
+		}
+		case 3:
+			return { kind: '2Q', a1In: readList(r), a1Out: readList(r), am: readList(r) };
+		case 4: {
+			const p = r.u32();
+			return { kind: 'ARC', p, t1: readList(r), t2: readList(r), b1: readList(r), b2: readList(r) };
+		}
+		case 5:
+			return { kind: 'FIFO' };
+		default:
+			return { kind: 'LRU' };
+	}
+}
+
 export function decodeDisk(data: Uint8Array): DiskSnapshot {
 	const r = new BinaryReader(data);
 	const maxPages = r.u32();
@@ -159,10 +412,33 @@ export function decodeDisk(data: Uint8Array): DiskSnapshot {
 		pages.push({
 			isAllocated: r.u8() !== 0,
 			pageType: r.u8(),
+			isCorrupt: r.u8() !== 0,
+		});
+	}
+
+	const faultSeed = Number(r.u64());
+	const armedFaults: ArmedFault[] = [];
+	const armedLen = r.u32();
+	for (let i = 0; i < armedLen; i++) {
+		const id = r.u32();
+		const kind = FAULT_KINDS[r.u8()];
+		const byPage = r.u8() === 1;
+		const value = r.u32();
+		armedFaults.push({ id, kind, trigger: byPage ? { pageId: value } : { nth: value } });
+	}
+	const firedFaults: FiredFault[] = [];
+	const firedLen = r.u32();
+	for (let i = 0; i < firedLen; i++) {
+		firedFaults.push({
+			id: r.u32(),
+			kind: FAULT_KINDS[r.u8()],
+			pageId: r.u32(),
+			op: r.u32(),
+			detail: r.u32(),
 		});
 	}
 
-	return { maxPages, pageSize, numAllocated, diskBasePtr, pages };
+	return { maxPages, pageSize, numAllocated, diskBasePtr, pages, faultSeed, armedFaults, firedFaults };
 }
 
 export function decodePage(data: Uint8Array): PageSnapshot {
@@ -175,16 +451,17 @@ export function decodePage(data: Uint8Array): PageSnapshot {
 	const freeEnd = r.u16();
 	const nextPageId = r.u32();
 	const freeSpace = r.u16();
+	const pageLsn = r.u32();
 
 	const numSlots = r.u16();
 	const slots: SlotInfo[] = [];
 	for (let i = 0; i < numSlots; i++) {
-		slots.push({ offset: r.u16(), length: r.u16() });
+		slots.push({ offset: r.u16(), length: r.u16(), kind: r.u8() });
 	}
 
 	const rawBytes = r.bytes(pageSize);
 
-	return { pageSize, pageId, pageType, slotCount, freeStart, freeEnd, nextPageId, freeSpace, slots, rawBytes };
+	return { pageSize, pageId, pageType, slotCount, freeStart, freeEnd, nextPageId, freeSpace, pageLsn, slots, rawBytes };
 }
 
 export function decodeTable(data: Uint8Array): TableInfo {
@@ -205,16 +482,25 @@ export function decodeTable(data: Uint8Array): TableInfo {
 		const colName = new TextDecoder().decode(cnBytes);
 		const typeTag = r.u8();
 		const nullable = r.u8() !== 0;
-		const maxLen = r.u16();
+		// Length for VarChar/Blob/Char, precision << 8 | scale for Decimal
+		const param = r.u16();
 
-		let type_: string | { VarChar: number } | { Blob: number };
+		let type_: ColumnType;
 		switch (typeTag) {
 			case 0: type_ = 'Int32'; break;
 			case 1: type_ = 'UInt32'; break;
 			case 2: type_ = 'Float64'; break;
 			case 3: type_ = 'Bool'; break;
-			case 4: type_ = { VarChar: maxLen }; break;
-			case 5: type_ = { Blob: maxLen }; break;
+			case 4: type_ = { VarChar: param }; break;
+			case 5: type_ = { Blob: param }; break;
+			case 6: type_ = 'Int16'; break;
+			case 7: type_ = 'Int64'; break;
+			case 8: type_ = 'Float32'; break;
+			case 9: type_ = { Decimal: [param >> 8, param & 0xff] }; break;
+			case 10: type_ = 'Date'; break;
+			case 11: type_ = 'Timestamp'; break;
+			case 12: type_ = 'Uuid'; break;
+			case 13: type_ = { Char: param }; break;
 			default: type_ = 'Int32';
 		}
 		columns.push({ name: colName, type: type_, nullable });
@@ -229,6 +515,85 @@ export function decodeTable(data: Uint8Array): TableInfo {
 	return { name, rowCount, firstPageId, columns, pageIds };
 }
 
+export function decodeIndex(data: Uint8Array): IndexSnapshot {
+	const r = new BinaryReader(data);
+	const rootPageId = r.u32();
+	const keySize = r.u16();
+	const nodeCount = r.u32();
+
+	const nodes: IndexNode[] = [];
+	for (let i = 0; i < nodeCount; i++) {
+		const pageId = r.u32();
+		const depth = r.u16();
+		const isLeaf = r.u8() !== 0;
+		const next = r.u32();
+		const numKeys = r.u16();
+		const keys: IndexKey[] = [];
+		for (let k = 0; k < numKeys; k++) {
+			const text = new TextDecoder().decode(r.bytes(r.u16()));
+			keys.push({ text, rowId: `${r.u32()}:${r.u16()}` });
+		}
+		const children: number[] = [];
+		if (!isLeaf) {
+			for (let c = 0; c <= numKeys; c++) children.push(r.u32());
+		}
+		nodes.push({
+			pageId, depth, isLeaf,
+			nextLeaf: next === INVALID_PAGE ? null : next,
+			keys, children,
+		});
+	}
+
+	return { rootPageId, keySize, nodes };
+}
+
+export function decodeHashIndex(data: Uint8Array): HashIndexSnapshot {
+	const r = new BinaryReader(data);
+	const directoryPageId = r.u32();
+	const globalDepth = r.u8();
+	const keySize = r.u16();
+	const bucketCapacity = r.u16();
+
+	const dirSize = r.u32();
+	const directory: { bucketPageId: number; localDepth: number }[] = [];
+	for (let i = 0; i < dirSize; i++) {
+		directory.push({ bucketPageId: r.u32(), localDepth: r.u8() });
+	}
+
+	const bucketCount = r.u32();
+	const buckets: HashBucketInfo[] = [];
+	for (let i = 0; i < bucketCount; i++) {
+		const pageId = r.u32();
+		const localDepth = r.u8();
+		const chainLen = r.u16();
+		const chain: { pageId: number; numEntries: number }[] = [];
+		for (let c = 0; c < chainLen; c++) {
+			chain.push({ pageId: r.u32(), numEntries: r.u16() });
+		}
+		buckets.push({ pageId, localDepth, chain });
+	}
+
+	return { directoryPageId, globalDepth, keySize, bucketCapacity, directory, buckets };
+}
+
+export function decodeFsm(data: Uint8Array): FsmSnapshot {
+	const r = new BinaryReader(data);
+	const granularity = r.u16();
+	const mapPageCount = r.u16();
+	const mapPageIds: number[] = [];
+	for (let i = 0; i < mapPageCount; i++) {
+		mapPageIds.push(r.u32());
+	}
+
+	const entryCount = r.u32();
+	const entries: { pageId: number; category: number; approxFree: number }[] = [];
+	for (let i = 0; i < entryCount; i++) {
+		entries.push({ pageId: r.u32(), category: r.u8(), approxFree: r.u16() });
+	}
+
+	return { granularity, mapPageIds, entries };
+}
+
 // ── Utility ────────────────────────────────────────────────────────
 
 export function pageTypeName(t: number): string {
@@ -236,6 +601,13 @@ export function pageTypeName(t: number): string {
 		case 0: return 'Data';
 		case 1: return 'Overflow';
 		case 2: return 'Free';
+		case 3: return 'Index';
+		case 4: return 'HashDirectory';
+		case 5: return 'HashBucket';
+		case 6: return 'FreeSpaceMap';
+		case 7: return 'Superblock';
+		case 8: return 'Catalog';
+		case 9: return 'Temp';
 		default: return '?';
 	}
 }
@@ -249,6 +621,15 @@ export function formatBytes(n: number): string {
 	return `${(n / 1024).toFixed(1)}KB`;
 }
 
+export function isStorageError(e: unknown): e is StorageError {
+	return e instanceof Error && typeof (e as StorageError).code === 'string';
+}
+
+/** Status-bar text for anything thrown by the engine. */
+export function describeError(e: unknown): string {
+	return isStorageError(e) ? `${e.code}: ${e.message}` : String(e);
+}
+
 // ── CSV utilities ──────────────────────────────────────────────────
 
 export function parseCSV(text: string): { headers: string[]; rows: string[][] } {
@@ -1,61 +1,64 @@
 /* tslint:disable */
 /* eslint-disable */
 
-export class LinkedList {
+/**
+ * JSON adapter over `Database` for the frontend.
+ */
+export class StorageEngine {
     free(): void;
     [Symbol.dispose](): void;
     /**
-     * Clear the entire list.
+     * Abort a transaction, undoing every insert, delete and page allocation
+     * it made.
      */
-    clear(): void;
+    abort(txn: number): boolean;
     /**
-     * Delete the first occurrence of `value`. Returns true if found.
+     * IDs of running transactions as a JSON array.
      */
-    delete(value: number): boolean;
+    active_transactions(): string;
     /**
-     * Generate a random list of `count` elements.
+     * Aggregate `table` without sending its rows over: `group_by` is a
+     * JSON array of column names, `aggregates` a JSON array like
+     * `["count(*)", "sum(price)", "avg(price)"]` of COUNT, SUM, MIN, MAX
+     * and AVG calls.  Returns a query result as from `execute_sql`, one
+     * row per group with the group columns and then the aggregates.  The
+     * hash table holds at most `memory_frames` frames of groups before
+     * spilling partitions to temp pages.
      */
-    generate_random(count: number, max_value: number): void;
+    aggregate(table: string, group_by: string, aggregates: string, memory_frames: number): string;
     /**
-     * Insert a value in sorted (ascending) order.
+     * Arm a one-shot disk fault and return its id.  `kind` is one of
+     * `torn_write`, `dropped_write`, `bit_flip` or `read_error`.  Give
+     * exactly one of `nth` (fire on the Nth write, or read for
+     * `read_error`, from now) and `page_id` (fire on the next write or
+     * read of that page).
      */
-    insert_sorted(value: number): void;
-    length(): number;
-    constructor();
+    arm_fault(kind: string, nth?: number | null, page_id?: number | null): number;
     /**
-     * Push a value to the front of the list.
+     * Start a transaction.  Pass the returned ID to `insert`/`delete`.
+     * Once it has written, other writes fail with `TXNS_ACTIVE` until it
+     * commits or aborts.
      */
-    push_front(value: number): void;
+    begin(): number;
     /**
-     * Search for a value. Returns the arena index or NULL.
+     * Commit a transaction, making its changes durable.
      */
-    search(value: number): number;
+    commit(txn: number): boolean;
     /**
-     * Snapshot the full list state as a flat Uint32Array.
-     *
-     * Layout:
-     *   [total_nodes, head_index, arena_base_ptr, node_size,
-     *    arena_len, (value, next, alive)×arena_len,
-     *    traversal_len, (index, action)×traversal_len ]
-     *
-     * All values are u32.  `alive` is 0 or 1.
-     * `arena_base_ptr` is the real WASM linear memory address of the arena.
-     * `node_size` is `size_of::<Node>()` in bytes.
+     * Get the current engine configuration as JSON.
      */
-    snapshot(): Uint32Array;
+    config(): string;
     /**
-     * Sort the list in ascending order (bottom-up merge sort, O(n log n)).
+     * Build an extendible hash index on `column` for equality lookups.
+     * Returns the directory page id.
      */
-    sort(): void;
-}
-
-export class StorageEngine {
-    free(): void;
-    [Symbol.dispose](): void;
+    create_hash_index(table_name: string, column: string): number;
     /**
-     * Get the current engine configuration as JSON.
+     * Build a B+tree index on `column`.  Existing rows are indexed
+     * immediately; later inserts and deletes keep it up to date.  Returns
+     * the index's root page id.
      */
-    config(): string;
+    create_index(table_name: string, column: string): number;
     /**
      * Create a table. Schema JSON:
      * ```json
@@ -67,13 +70,36 @@ export class StorageEngine {
      */
     create_table(name: string, schema_json: string): boolean;
     /**
-     * Delete a row by "page_id:slot_id".
+     * Delete a row by "page_id:slot_id", inside `txn` if given, otherwise
+     * auto-committed.
      */
-    delete(table_name: string, row_id_str: string): boolean;
+    delete(table_name: string, row_id_str: string, txn?: number | null): boolean;
     /**
-     * Drop a table and free its pages.
+     * Cancel every fault that hasn't fired yet.
+     */
+    disarm_faults(): void;
+    /**
+     * Drop a table and free its pages.  Returns `false` if there was no
+     * such table; refused while transactions are active.
      */
     drop_table(name: string): boolean;
+    /**
+     * Run one SQL statement: CREATE/DROP TABLE, INSERT, SELECT (with
+     * WHERE, ORDER BY, LIMIT and OFFSET), UPDATE or DELETE.  Row changes
+     * join `txn` if given and are undone as a whole if any row fails.
+     * Returns `{ "columns": [...], "rows": [[...]], "rows_affected": n,
+     * "plan": {...} }`; columns are written as in `table_schema`, and only
+     * a SELECT has columns and a plan.  The plan is a tree of `{ "operator",
+     * "detail", "rows_produced", "pages_touched", "page_reads",
+     * "page_writes", "children" }` with each operator's own I/O.
+     */
+    execute_sql(sql: string, txn?: number | null): string;
+    /**
+     * Flush everything and return the whole disk as bytes (header,
+     * allocation bitmap, then every page).  Refused while transactions are
+     * running, since their changes would be saved without their log.
+     */
+    export_image(): Uint8Array;
     /**
      * Flush all dirty pages in the buffer pool.
      */
@@ -82,18 +108,57 @@ export class StorageEngine {
      * Flush a specific page.
      */
     flush_page(page_id: number): boolean;
+    /**
+     * Reopen a database from bytes produced by `export_image`.  The
+     * configuration comes from the image's superblock; the log starts
+     * empty.
+     */
+    static from_image(bytes: Uint8Array): StorageEngine;
     /**
      * Get a row by "page_id:slot_id". Returns values as JSON array.
      */
     get(table_name: string, row_id_str: string): string;
+    /**
+     * Rows whose hash-indexed `column` equals `key_json`.  Returns the
+     * same shape as `scan`.
+     */
+    hash_lookup(table_name: string, column: string, key_json: string): string;
+    /**
+     * Rows whose indexed `column` equals `key_json` (a JSON value).
+     * Returns the same shape as `scan`.
+     */
+    index_lookup(table_name: string, column: string, key_json: string): string;
+    /**
+     * Rows whose indexed `column` lies in `[low_json, high_json]`, in key
+     * order.  Pass `null` for an open bound.  Returns the same shape as
+     * `scan`.
+     */
+    index_range(table_name: string, column: string, low_json: string, high_json: string): string;
     /**
      * Insert a row. Values JSON:
      * ```json
      * [42, "Alice", 3.14, true, null]
      * ```
+     * Runs inside `txn` if given, otherwise in its own auto-committed
+     * transaction.  A failed insert is rolled back without aborting `txn`.
      * Returns RowId as "page_id:slot_id" string.
      */
-    insert(table_name: string, values_json: string): string;
+    insert(table_name: string, values_json: string, txn?: number | null): string;
+    /**
+     * Insert a row whose Blob columns take raw bytes instead of JSON:
+     * `blobs` holds one `Uint8Array` (or `null`) per Blob column, in column
+     * order, and `values_json` has `null` at those positions.
+     */
+    insert_with_blobs(table_name: string, values_json: string, blobs: Array<any>, txn?: number | null): string;
+    /**
+     * Join two tables on `left_table.left_column = right_table.right_column`
+     * with `algorithm` (`nested_loop`, `block_nested_loop`, `hash` or
+     * `sort_merge`), holding at most `memory_frames` frames of rows.
+     * Returns a query result as from `execute_sql`, with columns named
+     * `table.column`; summing the plan's `page_reads` and `page_writes`
+     * gives the join's I/O, to compare algorithms.
+     */
+    join(algorithm: string, left_table: string, left_column: string, right_table: string, right_column: string, memory_frames: number): string;
     /**
      * List table names as JSON array.
      */
@@ -103,14 +168,45 @@ export class StorageEngine {
      *
      * Config JSON format:
      * ```json
-     * { "page_size": 128, "pool_size": 8, "disk_capacity": 64, "overflow_threshold": 64 }
+     * { "page_size": 128, "pool_size": 8, "disk_capacity": 64, "overflow_threshold": 64,
+     *   "replacement_policy": "LRU", "seek_us": 4000, "seq_transfer_us": 20,
+     *   "random_transfer_us": 100, "write_penalty_us": 50 }
      * ```
+     * `replacement_policy` is one of `LRU`, `Clock`, `LRU-K` (or `LRU-<k>`),
+     * `2Q`, `ARC`, `FIFO`; it defaults to `LRU`.  The `*_us` keys set the
+     * simulated disk latencies (see `io_cost`).
      */
     constructor(config_json: string);
+    /**
+     * Run ARIES recovery (analysis, redo, undo) over the durable log.
+     * Returns a JSON report:
+     * ```json
+     * { "records_scanned": 12, "redo_start": 1, "redo_applied": 9,
+     *   "undo_applied": 2, "losers": [4], "unfinished": [] }
+     * ```
+     */
+    recover(): string;
     /**
      * Scan all rows. Returns JSON array of { "row_id": "p:s", "values": [...] }.
      */
     scan(table_name: string): string;
+    /**
+     * Scan one page of results: up to `limit` rows starting at the cursor
+     * `from` ("page_id:slot_id", or empty for the start of the table).
+     * Returns `{ "rows": [...], "next": "p:s" | null }`, where `next` is
+     * the cursor for the following call and `null` means the scan is done.
+     */
+    scan_from(table_name: string, from: string, limit: number): string;
+    /**
+     * Seed the generator that picks where torn writes stop and which bit
+     * flips.
+     */
+    set_fault_seed(seed: number): void;
+    /**
+     * Simulate a crash: all buffer pool frames are lost without being
+     * written back, along with any log records not yet flushed.
+     */
+    simulate_crash(): void;
     /**
      * Snapshot the buffer pool state as binary.
      */
@@ -119,6 +215,18 @@ export class StorageEngine {
      * Snapshot disk overview as binary.
      */
     snapshot_disk(): Uint8Array;
+    /**
+     * Snapshot a table's free space map as binary.
+     */
+    snapshot_fsm(table_name: string): Uint8Array | undefined;
+    /**
+     * Snapshot the hash index on `column` (directory and buckets) as binary.
+     */
+    snapshot_hash_index(table_name: string, column: string): Uint8Array | undefined;
+    /**
+     * Snapshot the B+tree on `column` as binary.
+     */
+    snapshot_index(table_name: string, column: string): Uint8Array | undefined;
     /**
      * Snapshot a single page's details as binary.
      */
@@ -127,51 +235,95 @@ export class StorageEngine {
      * Snapshot table metadata as binary.
      */
     snapshot_table(table_name: string): Uint8Array | undefined;
+    /**
+     * Every row of `table`, ordered by `column`, using an external merge
+     * sort in `memory_frames` frames.  Returns `{ "result": {...},
+     * "trace": {...} }`: a query result as from `execute_sql`, and the
+     * sort's runs — `{ "id", "pass", "rows", "pages" }`, pass 0 for runs
+     * cut from the input — and merge passes, each a list of `{ "inputs":
+     * [run ids], "output": run id }` where the final merge's output is
+     * null.  Runs are empty if the rows fit in memory.
+     */
+    sort_table(table: string, column: string, descending: boolean, memory_frames: number): string;
     /**
      * Get table schema as JSON for the frontend.
      */
     table_schema(table_name: string): string | undefined;
+    /**
+     * Replace the values of the row at "page_id:slot_id", inside `txn` if
+     * given, otherwise auto-committed.  The RowId stays the same even when
+     * the new values no longer fit on the row's page.  Returns `false` if
+     * the row doesn't exist.
+     */
+    update(table_name: string, row_id_str: string, values_json: string, txn?: number | null): boolean;
+    /**
+     * Vacuum a table: compact fragmented pages, trim trailing tombstone
+     * slots, and free pages left empty.  Refused while any transaction is
+     * running, since an abort could need the tombstones back.  Returns a
+     * JSON report:
+     * ```json
+     * { "pages_scanned": 6, "pages_compacted": 2, "slots_trimmed": 3,
+     *   "pages_freed": 1, "bytes_reclaimed": 140 }
+     * ```
+     */
+    vacuum(table_name: string): string;
 }
 
 export type InitInput = RequestInfo | URL | Response | BufferSource | WebAssembly.Module;
 
 export interface InitOutput {
     readonly memory: WebAssembly.Memory;
-    readonly __wbg_linkedlist_free: (a: number, b: number) => void;
     readonly __wbg_storageengine_free: (a: number, b: number) => void;
-    readonly linkedlist_clear: (a: number) => void;
-    readonly linkedlist_delete: (a: number, b: number) => number;
-    readonly linkedlist_generate_random: (a: number, b: number, c: number) => void;
-    readonly linkedlist_insert_sorted: (a: number, b: number) => void;
-    readonly linkedlist_length: (a: number) => number;
-    readonly linkedlist_new: () => number;
-    readonly linkedlist_push_front: (a: number, b: number) => void;
-    readonly linkedlist_search: (a: number, b: number) => number;
-    readonly linkedlist_snapshot: (a: number) => [number, number];
-    readonly linkedlist_sort: (a: number) => void;
+    readonly storageengine_abort: (a: number, b: number) => [number, number, number];
+    readonly storageengine_active_transactions: (a: number) => [number, number];
+    readonly storageengine_aggregate: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number) => [number, number, number, number];
+    readonly storageengine_arm_fault: (a: number, b: number, c: number, d: number, e: number) => [number, number, number];
+    readonly storageengine_begin: (a: number) => number;
+    readonly storageengine_commit: (a: number, b: number) => [number, number, number];
     readonly storageengine_config: (a: number) => [number, number];
+    readonly storageengine_create_hash_index: (a: number, b: number, c: number, d: number, e: number) => [number, number, number];
+    readonly storageengine_create_index: (a: number, b: number, c: number, d: number, e: number) => [number, number, number];
     readonly storageengine_create_table: (a: number, b: number, c: number, d: number, e: number) => [number, number, number];
-    readonly storageengine_delete: (a: number, b: number, c: number, d: number, e: number) => [number, number, number];
-    readonly storageengine_drop_table: (a: number, b: number, c: number) => number;
+    readonly storageengine_delete: (a: number, b: number, c: number, d: number, e: number, f: number) => [number, number, number];
+    readonly storageengine_disarm_faults: (a: number) => void;
+    readonly storageengine_drop_table: (a: number, b: number, c: number) => [number, number, number];
+    readonly storageengine_execute_sql: (a: number, b: number, c: number, d: number) => [number, number, number, number];
+    readonly storageengine_export_image: (a: number) => [number, number, number, number];
     readonly storageengine_flush_all: (a: number) => void;
     readonly storageengine_flush_page: (a: number, b: number) => number;
+    readonly storageengine_from_image: (a: number, b: number) => [number, number, number];
     readonly storageengine_get: (a: number, b: number, c: number, d: number, e: number) => [number, number, number, number];
-    readonly storageengine_insert: (a: number, b: number, c: number, d: number, e: number) => [number, number, number, number];
+    readonly storageengine_hash_lookup: (a: number, b: number, c: number, d: number, e: number, f: number, g: number) => [number, number, number, number];
+    readonly storageengine_index_lookup: (a: number, b: number, c: number, d: number, e: number, f: number, g: number) => [number, number, number, number];
+    readonly storageengine_index_range: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number) => [number, number, number, number];
+    readonly storageengine_insert: (a: number, b: number, c: number, d: number, e: number, f: number) => [number, number, number, number];
+    readonly storageengine_insert_with_blobs: (a: number, b: number, c: number, d: number, e: number, f: any, g: number) => [number, number, number, number];
+    readonly storageengine_join: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number, l: number) => [number, number, number, number];
     readonly storageengine_list_tables: (a: number) => [number, number];
     readonly storageengine_new: (a: number, b: number) => [number, number, number];
+    readonly storageengine_recover: (a: number) => [number, number];
     readonly storageengine_scan: (a: number, b: number, c: number) => [number, number, number, number];
+    readonly storageengine_scan_from: (a: number, b: number, c: number, d: number, e: number, f: number) => [number, number, number, number];
+    readonly storageengine_set_fault_seed: (a: number, b: number) => void;
+    readonly storageengine_simulate_crash: (a: number) => void;
     readonly storageengine_snapshot_buffer_pool: (a: number) => [number, number];
     readonly storageengine_snapshot_disk: (a: number) => [number, number];
+    readonly storageengine_snapshot_fsm: (a: number, b: number, c: number) => [number, number];
+    readonly storageengine_snapshot_hash_index: (a: number, b: number, c: number, d: number, e: number) => [number, number];
+    readonly storageengine_snapshot_index: (a: number, b: number, c: number, d: number, e: number) => [number, number];
     readonly storageengine_snapshot_page: (a: number, b: number) => [number, number];
     readonly storageengine_snapshot_table: (a: number, b: number, c: number) => [number, number];
+    readonly storageengine_sort_table: (a: number, b: number, c: number, d: number, e: number, f: number, g: number) => [number, number, number, number];
     readonly storageengine_table_schema: (a: number, b: number, c: number) => [number, number];
+    readonly storageengine_update: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number) => [number, number, number];
+    readonly storageengine_vacuum: (a: number, b: number, c: number) => [number, number, number, number];
     readonly __wbindgen_exn_store: (a: number) => void;
     readonly __externref_table_alloc: () => number;
     readonly __wbindgen_externrefs: WebAssembly.Table;
+    readonly __externref_table_dealloc: (a: number) => void;
     readonly __wbindgen_free: (a: number, b: number, c: number) => void;
     readonly __wbindgen_malloc: (a: number, b: number) => number;
     readonly __wbindgen_realloc: (a: number, b: number, c: number, d: number) => number;
-    readonly __externref_table_dealloc: (a: number) => void;
     readonly __wbindgen_start: () => void;
 }
 
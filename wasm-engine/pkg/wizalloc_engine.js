@@ -1,113 +1,134 @@
 /* @ts-self-types="./wizalloc_engine.d.ts" */
 
-export class LinkedList {
+/**
+ * JSON adapter over `Database` for the frontend.
+ */
+export class StorageEngine {
+    static __wrap(ptr) {
+        ptr = ptr >>> 0;
+        const obj = Object.create(StorageEngine.prototype);
+        obj.__wbg_ptr = ptr;
+        StorageEngineFinalization.register(obj, obj.__wbg_ptr, obj);
+        return obj;
+    }
     __destroy_into_raw() {
         const ptr = this.__wbg_ptr;
         this.__wbg_ptr = 0;
-        LinkedListFinalization.unregister(this);
+        StorageEngineFinalization.unregister(this);
         return ptr;
     }
     free() {
         const ptr = this.__destroy_into_raw();
-        wasm.__wbg_linkedlist_free(ptr, 0);
-    }
-    /**
-     * Clear the entire list.
-     */
-    clear() {
-        wasm.linkedlist_clear(this.__wbg_ptr);
+        wasm.__wbg_storageengine_free(ptr, 0);
     }
     /**
-     * Delete the first occurrence of `value`. Returns true if found.
-     * @param {number} value
+     * Abort a transaction, undoing every insert, delete and page allocation
+     * it made.
+     * @param {number} txn
      * @returns {boolean}
      */
-    delete(value) {
-        const ret = wasm.linkedlist_delete(this.__wbg_ptr, value);
-        return ret !== 0;
+    abort(txn) {
+        const ret = wasm.storageengine_abort(this.__wbg_ptr, txn);
+        if (ret[2]) {
+            throw takeFromExternrefTable0(ret[1]);
+        }
+        return ret[0] !== 0;
     }
     /**
-     * Generate a random list of `count` elements.
-     * @param {number} count
-     * @param {number} max_value
+     * IDs of running transactions as a JSON array.
+     * @returns {string}
      */
-    generate_random(count, max_value) {
-        wasm.linkedlist_generate_random(this.__wbg_ptr, count, max_value);
+    active_transactions() {
+        let deferred1_0;
+        let deferred1_1;
+        try {
+            const ret = wasm.storageengine_active_transactions(this.__wbg_ptr);
+            deferred1_0 = ret[0];
+            deferred1_1 = ret[1];
+            return getStringFromWasm0(ret[0], ret[1]);
+        } finally {
+            wasm.__wbindgen_free(deferred1_0, deferred1_1, 1);
+        }
     }
     /**
-     * Insert a value in sorted (ascending) order.
-     * @param {number} value
+     * Aggregate `table` without sending its rows over: `group_by` is a
+     * JSON array of column names, `aggregates` a JSON array like
+     * `["count(*)", "sum(price)", "avg(price)"]` of COUNT, SUM, MIN, MAX
+     * and AVG calls.  Returns a query result as from `execute_sql`, one
+     * row per group with the group columns and then the aggregates.  The
+     * hash table holds at most `memory_frames` frames of groups before
+     * spilling partitions to temp pages.
+     * @param {string} table
+     * @param {string} group_by
+     * @param {string} aggregates
+     * @param {number} memory_frames
+     * @returns {string}
      */
-    insert_sorted(value) {
-        wasm.linkedlist_insert_sorted(this.__wbg_ptr, value);
+    aggregate(table, group_by, aggregates, memory_frames) {
+        let deferred5_0;
+        let deferred5_1;
+        try {
+            const ptr0 = passStringToWasm0(table, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+            const len0 = WASM_VECTOR_LEN;
+            const ptr1 = passStringToWasm0(group_by, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+            const len1 = WASM_VECTOR_LEN;
+            const ptr2 = passStringToWasm0(aggregates, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+            const len2 = WASM_VECTOR_LEN;
+            const ret = wasm.storageengine_aggregate(this.__wbg_ptr, ptr0, len0, ptr1, len1, ptr2, len2, memory_frames);
+            var ptr4 = ret[0];
+            var len4 = ret[1];
+            if (ret[3]) {
+                ptr4 = 0; len4 = 0;
+                throw takeFromExternrefTable0(ret[2]);
+            }
+            deferred5_0 = ptr4;
+            deferred5_1 = len4;
+            return getStringFromWasm0(ptr4, len4);
+        } finally {
+            wasm.__wbindgen_free(deferred5_0, deferred5_1, 1);
+        }
     }
     /**
+     * Arm a one-shot disk fault and return its id.  `kind` is one of
+     * `torn_write`, `dropped_write`, `bit_flip` or `read_error`.  Give
+     * exactly one of `nth` (fire on the Nth write, or read for
+     * `read_error`, from now) and `page_id` (fire on the next write or
+     * read of that page).
+     * @param {string} kind
+     * @param {number | null} [nth]
+     * @param {number | null} [page_id]
      * @returns {number}
      */
-    length() {
-        const ret = wasm.linkedlist_length(this.__wbg_ptr);
-        return ret >>> 0;
-    }
-    constructor() {
-        const ret = wasm.linkedlist_new();
-        this.__wbg_ptr = ret >>> 0;
-        LinkedListFinalization.register(this, this.__wbg_ptr, this);
-        return this;
-    }
-    /**
-     * Push a value to the front of the list.
-     * @param {number} value
-     */
-    push_front(value) {
-        wasm.linkedlist_push_front(this.__wbg_ptr, value);
+    arm_fault(kind, nth, page_id) {
+        const ptr0 = passStringToWasm0(kind, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ret = wasm.storageengine_arm_fault(this.__wbg_ptr, ptr0, len0, isLikeNone(nth) ? 0x100000001 : (nth) >>> 0, isLikeNone(page_id) ? 0x100000001 : (page_id) >>> 0);
+        if (ret[2]) {
+            throw takeFromExternrefTable0(ret[1]);
+        }
+        return ret[0] >>> 0;
     }
     /**
-     * Search for a value. Returns the arena index or NULL.
-     * @param {number} value
+     * Start a transaction.  Pass the returned ID to `insert`/`delete`.
+     * Once it has written, other writes fail with `TXNS_ACTIVE` until it
+     * commits or aborts.
      * @returns {number}
      */
-    search(value) {
-        const ret = wasm.linkedlist_search(this.__wbg_ptr, value);
+    begin() {
+        const ret = wasm.storageengine_begin(this.__wbg_ptr);
         return ret >>> 0;
     }
     /**
-     * Snapshot the full list state as a flat Uint32Array.
-     *
-     * Layout:
-     *   [total_nodes, head_index, arena_base_ptr, node_size,
-     *    arena_len, (value, next, alive)×arena_len,
-     *    traversal_len, (index, action)×traversal_len ]
-     *
-     * All values are u32.  `alive` is 0 or 1.
-     * `arena_base_ptr` is the real WASM linear memory address of the arena.
-     * `node_size` is `size_of::<Node>()` in bytes.
-     * @returns {Uint32Array}
-     */
-    snapshot() {
-        const ret = wasm.linkedlist_snapshot(this.__wbg_ptr);
-        var v1 = getArrayU32FromWasm0(ret[0], ret[1]).slice();
-        wasm.__wbindgen_free(ret[0], ret[1] * 4, 4);
-        return v1;
-    }
-    /**
-     * Sort the list in ascending order (bottom-up merge sort, O(n log n)).
+     * Commit a transaction, making its changes durable.
+     * @param {number} txn
+     * @returns {boolean}
      */
-    sort() {
-        wasm.linkedlist_sort(this.__wbg_ptr);
-    }
-}
-if (Symbol.dispose) LinkedList.prototype[Symbol.dispose] = LinkedList.prototype.free;
-
-export class StorageEngine {
-    __destroy_into_raw() {
-        const ptr = this.__wbg_ptr;
-        this.__wbg_ptr = 0;
-        StorageEngineFinalization.unregister(this);
-        return ptr;
-    }
-    free() {
-        const ptr = this.__destroy_into_raw();
-        wasm.__wbg_storageengine_free(ptr, 0);
+    commit(txn) {
+        const ret = wasm.storageengine_commit(this.__wbg_ptr, txn);
+        if (ret[2]) {
+            throw takeFromExternrefTable0(ret[1]);
+        }
+        return ret[0] !== 0;
     }
     /**
      * Get the current engine configuration as JSON.
@@ -125,6 +146,43 @@ export class StorageEngine {
             wasm.__wbindgen_free(deferred1_0, deferred1_1, 1);
         }
     }
+    /**
+     * Build an extendible hash index on `column` for equality lookups.
+     * Returns the directory page id.
+     * @param {string} table_name
+     * @param {string} column
+     * @returns {number}
+     */
+    create_hash_index(table_name, column) {
+        const ptr0 = passStringToWasm0(table_name, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ptr1 = passStringToWasm0(column, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+        const len1 = WASM_VECTOR_LEN;
+        const ret = wasm.storageengine_create_hash_index(this.__wbg_ptr, ptr0, len0, ptr1, len1);
+        if (ret[2]) {
+            throw takeFromExternrefTable0(ret[1]);
+        }
+        return ret[0] >>> 0;
+    }
+    /**
+     * Build a B+tree index on `column`.  Existing rows are indexed
+     * immediately; later inserts and deletes keep it up to date.  Returns
+     * the index's root page id.
+     * @param {string} table_name
+     * @param {string} column
+     * @returns {number}
+     */
+    create_index(table_name, column) {
+        const ptr0 = passStringToWasm0(table_name, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ptr1 = passStringToWasm0(column, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+        const len1 = WASM_VECTOR_LEN;
+        const ret = wasm.storageengine_create_index(this.__wbg_ptr, ptr0, len0, ptr1, len1);
+        if (ret[2]) {
+            throw takeFromExternrefTable0(ret[1]);
+        }
+        return ret[0] >>> 0;
+    }
     /**
      * Create a table. Schema JSON:
      * ```json
@@ -149,24 +207,33 @@ export class StorageEngine {
         return ret[0] !== 0;
     }
     /**
-     * Delete a row by "page_id:slot_id".
+     * Delete a row by "page_id:slot_id", inside `txn` if given, otherwise
+     * auto-committed.
      * @param {string} table_name
      * @param {string} row_id_str
+     * @param {number | null} [txn]
      * @returns {boolean}
      */
-    delete(table_name, row_id_str) {
+    delete(table_name, row_id_str, txn) {
         const ptr0 = passStringToWasm0(table_name, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
         const len0 = WASM_VECTOR_LEN;
         const ptr1 = passStringToWasm0(row_id_str, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
         const len1 = WASM_VECTOR_LEN;
-        const ret = wasm.storageengine_delete(this.__wbg_ptr, ptr0, len0, ptr1, len1);
+        const ret = wasm.storageengine_delete(this.__wbg_ptr, ptr0, len0, ptr1, len1, isLikeNone(txn) ? 0x100000001 : (txn) >>> 0);
         if (ret[2]) {
             throw takeFromExternrefTable0(ret[1]);
         }
         return ret[0] !== 0;
     }
     /**
-     * Drop a table and free its pages.
+     * Cancel every fault that hasn't fired yet.
+     */
+    disarm_faults() {
+        wasm.storageengine_disarm_faults(this.__wbg_ptr);
+    }
+    /**
+     * Drop a table and free its pages.  Returns `false` if there was no
+     * such table; refused while transactions are active.
      * @param {string} name
      * @returns {boolean}
      */
@@ -174,7 +241,58 @@ export class StorageEngine {
         const ptr0 = passStringToWasm0(name, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
         const len0 = WASM_VECTOR_LEN;
         const ret = wasm.storageengine_drop_table(this.__wbg_ptr, ptr0, len0);
-        return ret !== 0;
+        if (ret[2]) {
+            throw takeFromExternrefTable0(ret[1]);
+        }
+        return ret[0] !== 0;
+    }
+    /**
+     * Run one SQL statement: CREATE/DROP TABLE, INSERT, SELECT (with
+     * WHERE, ORDER BY, LIMIT and OFFSET), UPDATE or DELETE.  Row changes
+     * join `txn` if given and are undone as a whole if any row fails.
+     * Returns `{ "columns": [...], "rows": [[...]], "rows_affected": n,
+     * "plan": {...} }`; columns are written as in `table_schema`, and only
+     * a SELECT has columns and a plan.  The plan is a tree of `{ "operator",
+     * "detail", "rows_produced", "pages_touched", "page_reads",
+     * "page_writes", "children" }` with each operator's own I/O.
+     * @param {string} sql
+     * @param {number | null} [txn]
+     * @returns {string}
+     */
+    execute_sql(sql, txn) {
+        let deferred3_0;
+        let deferred3_1;
+        try {
+            const ptr0 = passStringToWasm0(sql, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+            const len0 = WASM_VECTOR_LEN;
+            const ret = wasm.storageengine_execute_sql(this.__wbg_ptr, ptr0, len0, isLikeNone(txn) ? 0x100000001 : (txn) >>> 0);
+            var ptr2 = ret[0];
+            var len2 = ret[1];
+            if (ret[3]) {
+                ptr2 = 0; len2 = 0;
+                throw takeFromExternrefTable0(ret[2]);
+            }
+            deferred3_0 = ptr2;
+            deferred3_1 = len2;
+            return getStringFromWasm0(ptr2, len2);
+        } finally {
+            wasm.__wbindgen_free(deferred3_0, deferred3_1, 1);
+        }
+    }
+    /**
+     * Flush everything and return the whole disk as bytes (header,
+     * allocation bitmap, then every page).  Refused while transactions are
+     * running, since their changes would be saved without their log.
+     * @returns {Uint8Array}
+     */
+    export_image() {
+        const ret = wasm.storageengine_export_image(this.__wbg_ptr);
+        if (ret[3]) {
+            throw takeFromExternrefTable0(ret[2]);
+        }
+        var v1 = getArrayU8FromWasm0(ret[0], ret[1]).slice();
+        wasm.__wbindgen_free(ret[0], ret[1] * 1, 1);
+        return v1;
     }
     /**
      * Flush all dirty pages in the buffer pool.
@@ -191,6 +309,22 @@ export class StorageEngine {
         const ret = wasm.storageengine_flush_page(this.__wbg_ptr, page_id);
         return ret !== 0;
     }
+    /**
+     * Reopen a database from bytes produced by `export_image`.  The
+     * configuration comes from the image's superblock; the log starts
+     * empty.
+     * @param {Uint8Array} bytes
+     * @returns {StorageEngine}
+     */
+    static from_image(bytes) {
+        const ptr0 = passArray8ToWasm0(bytes, wasm.__wbindgen_malloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ret = wasm.storageengine_from_image(ptr0, len0);
+        if (ret[2]) {
+            throw takeFromExternrefTable0(ret[1]);
+        }
+        return StorageEngine.__wrap(ret[0]);
+    }
     /**
      * Get a row by "page_id:slot_id". Returns values as JSON array.
      * @param {string} table_name
@@ -219,17 +353,120 @@ export class StorageEngine {
             wasm.__wbindgen_free(deferred4_0, deferred4_1, 1);
         }
     }
+    /**
+     * Rows whose hash-indexed `column` equals `key_json`.  Returns the
+     * same shape as `scan`.
+     * @param {string} table_name
+     * @param {string} column
+     * @param {string} key_json
+     * @returns {string}
+     */
+    hash_lookup(table_name, column, key_json) {
+        let deferred5_0;
+        let deferred5_1;
+        try {
+            const ptr0 = passStringToWasm0(table_name, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+            const len0 = WASM_VECTOR_LEN;
+            const ptr1 = passStringToWasm0(column, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+            const len1 = WASM_VECTOR_LEN;
+            const ptr2 = passStringToWasm0(key_json, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+            const len2 = WASM_VECTOR_LEN;
+            const ret = wasm.storageengine_hash_lookup(this.__wbg_ptr, ptr0, len0, ptr1, len1, ptr2, len2);
+            var ptr4 = ret[0];
+            var len4 = ret[1];
+            if (ret[3]) {
+                ptr4 = 0; len4 = 0;
+                throw takeFromExternrefTable0(ret[2]);
+            }
+            deferred5_0 = ptr4;
+            deferred5_1 = len4;
+            return getStringFromWasm0(ptr4, len4);
+        } finally {
+            wasm.__wbindgen_free(deferred5_0, deferred5_1, 1);
+        }
+    }
+    /**
+     * Rows whose indexed `column` equals `key_json` (a JSON value).
+     * Returns the same shape as `scan`.
+     * @param {string} table_name
+     * @param {string} column
+     * @param {string} key_json
+     * @returns {string}
+     */
+    index_lookup(table_name, column, key_json) {
+        let deferred5_0;
+        let deferred5_1;
+        try {
+            const ptr0 = passStringToWasm0(table_name, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+            const len0 = WASM_VECTOR_LEN;
+            const ptr1 = passStringToWasm0(column, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+            const len1 = WASM_VECTOR_LEN;
+            const ptr2 = passStringToWasm0(key_json, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+            const len2 = WASM_VECTOR_LEN;
+            const ret = wasm.storageengine_index_lookup(this.__wbg_ptr, ptr0, len0, ptr1, len1, ptr2, len2);
+            var ptr4 = ret[0];
+            var len4 = ret[1];
+            if (ret[3]) {
+                ptr4 = 0; len4 = 0;
+                throw takeFromExternrefTable0(ret[2]);
+            }
+            deferred5_0 = ptr4;
+            deferred5_1 = len4;
+            return getStringFromWasm0(ptr4, len4);
+        } finally {
+            wasm.__wbindgen_free(deferred5_0, deferred5_1, 1);
+        }
+    }
+    /**
+     * Rows whose indexed `column` lies in `[low_json, high_json]`, in key
+     * order.  Pass `null` for an open bound.  Returns the same shape as
+     * `scan`.
+     * @param {string} table_name
+     * @param {string} column
+     * @param {string} low_json
+     * @param {string} high_json
+     * @returns {string}
+     */
+    index_range(table_name, column, low_json, high_json) {
+        let deferred6_0;
+        let deferred6_1;
+        try {
+            const ptr0 = passStringToWasm0(table_name, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+            const len0 = WASM_VECTOR_LEN;
+            const ptr1 = passStringToWasm0(column, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+            const len1 = WASM_VECTOR_LEN;
+            const ptr2 = passStringToWasm0(low_json, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+            const len2 = WASM_VECTOR_LEN;
+            const ptr3 = passStringToWasm0(high_json, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+            const len3 = WASM_VECTOR_LEN;
+            const ret = wasm.storageengine_index_range(this.__wbg_ptr, ptr0, len0, ptr1, len1, ptr2, len2, ptr3, len3);
+            var ptr5 = ret[0];
+            var len5 = ret[1];
+            if (ret[3]) {
+                ptr5 = 0; len5 = 0;
+                throw takeFromExternrefTable0(ret[2]);
+            }
+            deferred6_0 = ptr5;
+            deferred6_1 = len5;
+            return getStringFromWasm0(ptr5, len5);
+        } finally {
+            wasm.__wbindgen_free(deferred6_0, deferred6_1, 1);
+        }
+    }
     /**
      * Insert a row. Values JSON:
      * ```json
      * [42, "Alice", 3.14, true, null]
      * ```
+     * Runs inside `txn` if given, otherwise in its own auto-committed
+     * transaction.  A failed insert is rolled back without aborting `txn`.
      * Returns RowId as "page_id:slot_id" string.
      * @param {string} table_name
      * @param {string} values_json
+     * @param {number | null} [txn]
      * @returns {string}
      */
-    insert(table_name, values_json) {
+    insert(table_name, values_json, txn) {
         let deferred4_0;
         let deferred4_1;
         try {
@@ -237,7 +474,7 @@ export class StorageEngine {
             const len0 = WASM_VECTOR_LEN;
             const ptr1 = passStringToWasm0(values_json, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
             const len1 = WASM_VECTOR_LEN;
-            const ret = wasm.storageengine_insert(this.__wbg_ptr, ptr0, len0, ptr1, len1);
+            const ret = wasm.storageengine_insert(this.__wbg_ptr, ptr0, len0, ptr1, len1, isLikeNone(txn) ? 0x100000001 : (txn) >>> 0);
             var ptr3 = ret[0];
             var len3 = ret[1];
             if (ret[3]) {
@@ -251,6 +488,81 @@ export class StorageEngine {
             wasm.__wbindgen_free(deferred4_0, deferred4_1, 1);
         }
     }
+    /**
+     * Insert a row whose Blob columns take raw bytes instead of JSON:
+     * `blobs` holds one `Uint8Array` (or `null`) per Blob column, in column
+     * order, and `values_json` has `null` at those positions.
+     * @param {string} table_name
+     * @param {string} values_json
+     * @param {Array<any>} blobs
+     * @param {number | null} [txn]
+     * @returns {string}
+     */
+    insert_with_blobs(table_name, values_json, blobs, txn) {
+        let deferred4_0;
+        let deferred4_1;
+        try {
+            const ptr0 = passStringToWasm0(table_name, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+            const len0 = WASM_VECTOR_LEN;
+            const ptr1 = passStringToWasm0(values_json, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+            const len1 = WASM_VECTOR_LEN;
+            const ret = wasm.storageengine_insert_with_blobs(this.__wbg_ptr, ptr0, len0, ptr1, len1, blobs, isLikeNone(txn) ? 0x100000001 : (txn) >>> 0);
+            var ptr3 = ret[0];
+            var len3 = ret[1];
+            if (ret[3]) {
+                ptr3 = 0; len3 = 0;
+                throw takeFromExternrefTable0(ret[2]);
+            }
+            deferred4_0 = ptr3;
+            deferred4_1 = len3;
+            return getStringFromWasm0(ptr3, len3);
+        } finally {
+            wasm.__wbindgen_free(deferred4_0, deferred4_1, 1);
+        }
+    }
+    /**
+     * Join two tables on `left_table.left_column = right_table.right_column`
+     * with `algorithm` (`nested_loop`, `block_nested_loop`, `hash` or
+     * `sort_merge`), holding at most `memory_frames` frames of rows.
+     * Returns a query result as from `execute_sql`, with columns named
+     * `table.column`; summing the plan's `page_reads` and `page_writes`
+     * gives the join's I/O, to compare algorithms.
+     * @param {string} algorithm
+     * @param {string} left_table
+     * @param {string} left_column
+     * @param {string} right_table
+     * @param {string} right_column
+     * @param {number} memory_frames
+     * @returns {string}
+     */
+    join(algorithm, left_table, left_column, right_table, right_column, memory_frames) {
+        let deferred7_0;
+        let deferred7_1;
+        try {
+            const ptr0 = passStringToWasm0(algorithm, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+            const len0 = WASM_VECTOR_LEN;
+            const ptr1 = passStringToWasm0(left_table, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+            const len1 = WASM_VECTOR_LEN;
+            const ptr2 = passStringToWasm0(left_column, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+            const len2 = WASM_VECTOR_LEN;
+            const ptr3 = passStringToWasm0(right_table, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+            const len3 = WASM_VECTOR_LEN;
+            const ptr4 = passStringToWasm0(right_column, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+            const len4 = WASM_VECTOR_LEN;
+            const ret = wasm.storageengine_join(this.__wbg_ptr, ptr0, len0, ptr1, len1, ptr2, len2, ptr3, len3, ptr4, len4, memory_frames);
+            var ptr6 = ret[0];
+            var len6 = ret[1];
+            if (ret[3]) {
+                ptr6 = 0; len6 = 0;
+                throw takeFromExternrefTable0(ret[2]);
+            }
+            deferred7_0 = ptr6;
+            deferred7_1 = len6;
+            return getStringFromWasm0(ptr6, len6);
+        } finally {
+            wasm.__wbindgen_free(deferred7_0, deferred7_1, 1);
+        }
+    }
     /**
      * List table names as JSON array.
      * @returns {string}
@@ -272,8 +584,13 @@ export class StorageEngine {
      *
      * Config JSON format:
      * ```json
-     * { "page_size": 128, "pool_size": 8, "disk_capacity": 64, "overflow_threshold": 64 }
+     * { "page_size": 128, "pool_size": 8, "disk_capacity": 64, "overflow_threshold": 64,
+     *   "replacement_policy": "LRU", "seek_us": 4000, "seq_transfer_us": 20,
+     *   "random_transfer_us": 100, "write_penalty_us": 50 }
      * ```
+     * `replacement_policy` is one of `LRU`, `Clock`, `LRU-K` (or `LRU-<k>`),
+     * `2Q`, `ARC`, `FIFO`; it defaults to `LRU`.  The `*_us` keys set the
+     * simulated disk latencies (see `io_cost`).
      * @param {string} config_json
      */
     constructor(config_json) {
@@ -287,6 +604,27 @@ export class StorageEngine {
         StorageEngineFinalization.register(this, this.__wbg_ptr, this);
         return this;
     }
+    /**
+     * Run ARIES recovery (analysis, redo, undo) over the durable log.
+     * Returns a JSON report:
+     * ```json
+     * { "records_scanned": 12, "redo_start": 1, "redo_applied": 9,
+     *   "undo_applied": 2, "losers": [4], "unfinished": [] }
+     * ```
+     * @returns {string}
+     */
+    recover() {
+        let deferred1_0;
+        let deferred1_1;
+        try {
+            const ret = wasm.storageengine_recover(this.__wbg_ptr);
+            deferred1_0 = ret[0];
+            deferred1_1 = ret[1];
+            return getStringFromWasm0(ret[0], ret[1]);
+        } finally {
+            wasm.__wbindgen_free(deferred1_0, deferred1_1, 1);
+        }
+    }
     /**
      * Scan all rows. Returns JSON array of { "row_id": "p:s", "values": [...] }.
      * @param {string} table_name
@@ -312,6 +650,53 @@ export class StorageEngine {
             wasm.__wbindgen_free(deferred3_0, deferred3_1, 1);
         }
     }
+    /**
+     * Scan one page of results: up to `limit` rows starting at the cursor
+     * `from` ("page_id:slot_id", or empty for the start of the table).
+     * Returns `{ "rows": [...], "next": "p:s" | null }`, where `next` is
+     * the cursor for the following call and `null` means the scan is done.
+     * @param {string} table_name
+     * @param {string} from
+     * @param {number} limit
+     * @returns {string}
+     */
+    scan_from(table_name, from, limit) {
+        let deferred4_0;
+        let deferred4_1;
+        try {
+            const ptr0 = passStringToWasm0(table_name, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+            const len0 = WASM_VECTOR_LEN;
+            const ptr1 = passStringToWasm0(from, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+            const len1 = WASM_VECTOR_LEN;
+            const ret = wasm.storageengine_scan_from(this.__wbg_ptr, ptr0, len0, ptr1, len1, limit);
+            var ptr3 = ret[0];
+            var len3 = ret[1];
+            if (ret[3]) {
+                ptr3 = 0; len3 = 0;
+                throw takeFromExternrefTable0(ret[2]);
+            }
+            deferred4_0 = ptr3;
+            deferred4_1 = len3;
+            return getStringFromWasm0(ptr3, len3);
+        } finally {
+            wasm.__wbindgen_free(deferred4_0, deferred4_1, 1);
+        }
+    }
+    /**
+     * Seed the generator that picks where torn writes stop and which bit
+     * flips.
+     * @param {number} seed
+     */
+    set_fault_seed(seed) {
+        wasm.storageengine_set_fault_seed(this.__wbg_ptr, seed);
+    }
+    /**
+     * Simulate a crash: all buffer pool frames are lost without being
+     * written back, along with any log records not yet flushed.
+     */
+    simulate_crash() {
+        wasm.storageengine_simulate_crash(this.__wbg_ptr);
+    }
     /**
      * Snapshot the buffer pool state as binary.
      * @returns {Uint8Array}
@@ -332,6 +717,60 @@ export class StorageEngine {
         wasm.__wbindgen_free(ret[0], ret[1] * 1, 1);
         return v1;
     }
+    /**
+     * Snapshot a table's free space map as binary.
+     * @param {string} table_name
+     * @returns {Uint8Array | undefined}
+     */
+    snapshot_fsm(table_name) {
+        const ptr0 = passStringToWasm0(table_name, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ret = wasm.storageengine_snapshot_fsm(this.__wbg_ptr, ptr0, len0);
+        let v2;
+        if (ret[0] !== 0) {
+            v2 = getArrayU8FromWasm0(ret[0], ret[1]).slice();
+            wasm.__wbindgen_free(ret[0], ret[1] * 1, 1);
+        }
+        return v2;
+    }
+    /**
+     * Snapshot the hash index on `column` (directory and buckets) as binary.
+     * @param {string} table_name
+     * @param {string} column
+     * @returns {Uint8Array | undefined}
+     */
+    snapshot_hash_index(table_name, column) {
+        const ptr0 = passStringToWasm0(table_name, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ptr1 = passStringToWasm0(column, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+        const len1 = WASM_VECTOR_LEN;
+        const ret = wasm.storageengine_snapshot_hash_index(this.__wbg_ptr, ptr0, len0, ptr1, len1);
+        let v3;
+        if (ret[0] !== 0) {
+            v3 = getArrayU8FromWasm0(ret[0], ret[1]).slice();
+            wasm.__wbindgen_free(ret[0], ret[1] * 1, 1);
+        }
+        return v3;
+    }
+    /**
+     * Snapshot the B+tree on `column` as binary.
+     * @param {string} table_name
+     * @param {string} column
+     * @returns {Uint8Array | undefined}
+     */
+    snapshot_index(table_name, column) {
+        const ptr0 = passStringToWasm0(table_name, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ptr1 = passStringToWasm0(column, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+        const len1 = WASM_VECTOR_LEN;
+        const ret = wasm.storageengine_snapshot_index(this.__wbg_ptr, ptr0, len0, ptr1, len1);
+        let v3;
+        if (ret[0] !== 0) {
+            v3 = getArrayU8FromWasm0(ret[0], ret[1]).slice();
+            wasm.__wbindgen_free(ret[0], ret[1] * 1, 1);
+        }
+        return v3;
+    }
     /**
      * Snapshot a single page's details as binary.
      * @param {number} page_id
@@ -362,6 +801,42 @@ export class StorageEngine {
         }
         return v2;
     }
+    /**
+     * Every row of `table`, ordered by `column`, using an external merge
+     * sort in `memory_frames` frames.  Returns `{ "result": {...},
+     * "trace": {...} }`: a query result as from `execute_sql`, and the
+     * sort's runs — `{ "id", "pass", "rows", "pages" }`, pass 0 for runs
+     * cut from the input — and merge passes, each a list of `{ "inputs":
+     * [run ids], "output": run id }` where the final merge's output is
+     * null.  Runs are empty if the rows fit in memory.
+     * @param {string} table
+     * @param {string} column
+     * @param {boolean} descending
+     * @param {number} memory_frames
+     * @returns {string}
+     */
+    sort_table(table, column, descending, memory_frames) {
+        let deferred4_0;
+        let deferred4_1;
+        try {
+            const ptr0 = passStringToWasm0(table, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+            const len0 = WASM_VECTOR_LEN;
+            const ptr1 = passStringToWasm0(column, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+            const len1 = WASM_VECTOR_LEN;
+            const ret = wasm.storageengine_sort_table(this.__wbg_ptr, ptr0, len0, ptr1, len1, descending, memory_frames);
+            var ptr3 = ret[0];
+            var len3 = ret[1];
+            if (ret[3]) {
+                ptr3 = 0; len3 = 0;
+                throw takeFromExternrefTable0(ret[2]);
+            }
+            deferred4_0 = ptr3;
+            deferred4_1 = len3;
+            return getStringFromWasm0(ptr3, len3);
+        } finally {
+            wasm.__wbindgen_free(deferred4_0, deferred4_1, 1);
+        }
+    }
     /**
      * Get table schema as JSON for the frontend.
      * @param {string} table_name
@@ -378,19 +853,118 @@ export class StorageEngine {
         }
         return v2;
     }
+    /**
+     * Replace the values of the row at "page_id:slot_id", inside `txn` if
+     * given, otherwise auto-committed.  The RowId stays the same even when
+     * the new values no longer fit on the row's page.  Returns `false` if
+     * the row doesn't exist.
+     * @param {string} table_name
+     * @param {string} row_id_str
+     * @param {string} values_json
+     * @param {number | null} [txn]
+     * @returns {boolean}
+     */
+    update(table_name, row_id_str, values_json, txn) {
+        const ptr0 = passStringToWasm0(table_name, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ptr1 = passStringToWasm0(row_id_str, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+        const len1 = WASM_VECTOR_LEN;
+        const ptr2 = passStringToWasm0(values_json, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+        const len2 = WASM_VECTOR_LEN;
+        const ret = wasm.storageengine_update(this.__wbg_ptr, ptr0, len0, ptr1, len1, ptr2, len2, isLikeNone(txn) ? 0x100000001 : (txn) >>> 0);
+        if (ret[2]) {
+            throw takeFromExternrefTable0(ret[1]);
+        }
+        return ret[0] !== 0;
+    }
+    /**
+     * Vacuum a table: compact fragmented pages, trim trailing tombstone
+     * slots, and free pages left empty.  Refused while any transaction is
+     * running, since an abort could need the tombstones back.  Returns a
+     * JSON report:
+     * ```json
+     * { "pages_scanned": 6, "pages_compacted": 2, "slots_trimmed": 3,
+     *   "pages_freed": 1, "bytes_reclaimed": 140 }
+     * ```
+     * @param {string} table_name
+     * @returns {string}
+     */
+    vacuum(table_name) {
+        let deferred3_0;
+        let deferred3_1;
+        try {
+            const ptr0 = passStringToWasm0(table_name, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+            const len0 = WASM_VECTOR_LEN;
+            const ret = wasm.storageengine_vacuum(this.__wbg_ptr, ptr0, len0);
+            var ptr2 = ret[0];
+            var len2 = ret[1];
+            if (ret[3]) {
+                ptr2 = 0; len2 = 0;
+                throw takeFromExternrefTable0(ret[2]);
+            }
+            deferred3_0 = ptr2;
+            deferred3_1 = len2;
+            return getStringFromWasm0(ptr2, len2);
+        } finally {
+            wasm.__wbindgen_free(deferred3_0, deferred3_1, 1);
+        }
+    }
 }
 if (Symbol.dispose) StorageEngine.prototype[Symbol.dispose] = StorageEngine.prototype.free;
 
 function __wbg_get_imports() {
     const import0 = {
         __proto__: null,
+        __wbg___wbindgen_is_null_ac34f5003991759a: function(arg0) {
+            const ret = arg0 === null;
+            return ret;
+        },
+        __wbg___wbindgen_is_undefined_9e4d92534c42d778: function(arg0) {
+            const ret = arg0 === undefined;
+            return ret;
+        },
         __wbg___wbindgen_throw_be289d5034ed271b: function(arg0, arg1) {
             throw new Error(getStringFromWasm0(arg0, arg1));
         },
-        __wbg_getRandomValues_1c61fac11405ffdc: function() { return handleError(function (arg0, arg1) {
-            globalThis.crypto.getRandomValues(getArrayU8FromWasm0(arg0, arg1));
+        __wbg_get_9b94d73e6221f75c: function(arg0, arg1) {
+            const ret = arg0[arg1 >>> 0];
+            return ret;
+        },
+        __wbg_instanceof_Uint8Array_9b9075935c74707c: function(arg0) {
+            let result;
+            try {
+                result = arg0 instanceof Uint8Array;
+            } catch (_) {
+                result = false;
+            }
+            const ret = result;
+            return ret;
+        },
+        __wbg_length_32ed9a279acd054c: function(arg0) {
+            const ret = arg0.length;
+            return ret;
+        },
+        __wbg_length_35a7bace40f36eac: function(arg0) {
+            const ret = arg0.length;
+            return ret;
+        },
+        __wbg_new_72b49615380db768: function(arg0, arg1) {
+            const ret = new Error(getStringFromWasm0(arg0, arg1));
+            return ret;
+        },
+        __wbg_prototypesetcall_bdcdcc5842e4d77d: function(arg0, arg1, arg2) {
+            Uint8Array.prototype.set.call(getArrayU8FromWasm0(arg0, arg1), arg2);
+        },
+        __wbg_set_6cb8631f80447a67: function() { return handleError(function (arg0, arg1, arg2) {
+            const ret = Reflect.set(arg0, arg1, arg2);
+            return ret;
         }, arguments); },
-        __wbindgen_cast_0000000000000001: function(arg0, arg1) {
+        __wbindgen_cast_0000000000000001: function(arg0) {
+            // Cast intrinsic for `F64 -> Externref`.
+            const ret = arg0;
+            return ret;
+        },
+        __wbindgen_cast_0000000000000002: function(arg0, arg1) {
             // Cast intrinsic for `Ref(String) -> Externref`.
             const ret = getStringFromWasm0(arg0, arg1);
             return ret;
@@ -411,9 +985,6 @@ function __wbg_get_imports() {
     };
 }
 
-const LinkedListFinalization = (typeof FinalizationRegistry === 'undefined')
-    ? { register: () => {}, unregister: () => {} }
-    : new FinalizationRegistry(ptr => wasm.__wbg_linkedlist_free(ptr >>> 0, 1));
 const StorageEngineFinalization = (typeof FinalizationRegistry === 'undefined')
     ? { register: () => {}, unregister: () => {} }
     : new FinalizationRegistry(ptr => wasm.__wbg_storageengine_free(ptr >>> 0, 1));
@@ -424,11 +995,6 @@ function addToExternrefTable0(obj) {
     return idx;
 }
 
-function getArrayU32FromWasm0(ptr, len) {
-    ptr = ptr >>> 0;
-    return getUint32ArrayMemory0().subarray(ptr / 4, ptr / 4 + len);
-}
-
 function getArrayU8FromWasm0(ptr, len) {
     ptr = ptr >>> 0;
     return getUint8ArrayMemory0().subarray(ptr / 1, ptr / 1 + len);
@@ -439,14 +1005,6 @@ function getStringFromWasm0(ptr, len) {
     return decodeText(ptr, len);
 }
 
-let cachedUint32ArrayMemory0 = null;
-function getUint32ArrayMemory0() {
-    if (cachedUint32ArrayMemory0 === null || cachedUint32ArrayMemory0.byteLength === 0) {
-        cachedUint32ArrayMemory0 = new Uint32Array(wasm.memory.buffer);
-    }
-    return cachedUint32ArrayMemory0;
-}
-
 let cachedUint8ArrayMemory0 = null;
 function getUint8ArrayMemory0() {
     if (cachedUint8ArrayMemory0 === null || cachedUint8ArrayMemory0.byteLength === 0) {
@@ -464,6 +1022,17 @@ function handleError(f, args) {
     }
 }
 
+function isLikeNone(x) {
+    return x === undefined || x === null;
+}
+
+function passArray8ToWasm0(arg, malloc) {
+    const ptr = malloc(arg.length * 1, 1) >>> 0;
+    getUint8ArrayMemory0().set(arg, ptr / 1);
+    WASM_VECTOR_LEN = arg.length;
+    return ptr;
+}
+
 function passStringToWasm0(arg, malloc, realloc) {
     if (realloc === undefined) {
         const buf = cachedTextEncoder.encode(arg);
@@ -540,7 +1109,6 @@ let wasmModule, wasm;
 function __wbg_finalize_init(instance, module) {
     wasm = instance.exports;
     wasmModule = module;
-    cachedUint32ArrayMemory0 = null;
     cachedUint8ArrayMemory0 = null;
     wasm.__wbindgen_start();
     return wasm;
@@ -0,0 +1,108 @@
+//! `wizalloc` — drive the storage engine from a terminal.
+//!
+//! ```text
+//! wizalloc [--config <json>] [script]
+//! ```
+//! With a script, runs its commands in order, echoing each one, and exits
+//! non-zero if any failed.  Without one, reads commands from stdin.
+
+use std::io::{self, BufRead, Write};
+use std::process::ExitCode;
+
+use wizalloc_engine::Database;
+use wizalloc_engine::repl::Repl;
+use wizalloc_engine::storage::config::EngineConfig;
+
+const USAGE: &str = "usage: wizalloc [--config <json>] [script]";
+
+fn main() -> ExitCode {
+    let mut config_json = None;
+    let mut script = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--config" => match args.next() {
+                Some(json) => config_json = Some(json),
+                None => return usage(),
+            },
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            _ if script.is_none() => script = Some(arg),
+            _ => return usage(),
+        }
+    }
+
+    let mut repl = Repl::new(Database::new(EngineConfig::default_config()).unwrap());
+    if let Some(json) = config_json
+        && let Err(e) = repl.execute(&format!("reset {}", json))
+    {
+        eprintln!("error: {}", e);
+        return ExitCode::FAILURE;
+    }
+
+    match script {
+        Some(path) => match std::fs::read_to_string(&path) {
+            Ok(text) => run_script(&mut repl, &text),
+            Err(e) => {
+                eprintln!("error: cannot read {}: {}", path, e);
+                ExitCode::FAILURE
+            }
+        },
+        None => run_interactive(&mut repl),
+    }
+}
+
+fn usage() -> ExitCode {
+    eprintln!("{}", USAGE);
+    ExitCode::FAILURE
+}
+
+fn run_script(repl: &mut Repl, text: &str) -> ExitCode {
+    let mut failed = false;
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if is_quit(line) {
+            break;
+        }
+        println!("> {}", line);
+        match repl.execute(line) {
+            Ok(out) if out.is_empty() => {}
+            Ok(out) => println!("{}", out),
+            Err(e) => {
+                eprintln!("error (line {}): {}", n + 1, e);
+                failed = true;
+            }
+        }
+    }
+    if failed { ExitCode::FAILURE } else { ExitCode::SUCCESS }
+}
+
+fn run_interactive(repl: &mut Repl) -> ExitCode {
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("wizalloc> ");
+        let _ = io::stdout().flush();
+        let Some(Ok(line)) = lines.next() else {
+            break;
+        };
+        if is_quit(line.trim()) {
+            break;
+        }
+        match repl.execute(&line) {
+            Ok(out) if out.is_empty() => {}
+            Ok(out) => println!("{}", out),
+            Err(e) => eprintln!("error: {}", e),
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+fn is_quit(line: &str) -> bool {
+    matches!(line, "quit" | "exit")
+}
@@ -0,0 +1,1019 @@
+//! Database — the engine's native Rust API.
+//!
+//! Owns the buffer pool, the tables and the transaction manager, and works
+//! in typed `Schema`, `Value` and `RowId` terms.  The `StorageEngine` wasm
+//! class is a thin JSON adapter over it; native code and tests use it
+//! directly.
+//!
+//! Row operations take an optional transaction: given one, the change joins
+//! it; otherwise it runs in its own auto-committed transaction.  A row
+//! change that fails part-way is undone either way, without aborting the
+//! caller's transaction.
+
+use std::collections::HashMap;
+
+use crate::exec::{self, Operator};
+use crate::exec::aggregate::{AggregateExpr, AggregateFunction, HashAggregate};
+use crate::exec::join::{self, JoinAlgorithm, JoinKeys};
+use crate::exec::operators::SeqScan;
+use crate::exec::sort::{ExternalSort, SortTrace};
+use crate::sql::expr::BoundExpr;
+use crate::sql::{self, QueryResult};
+use crate::storage::btree;
+use crate::storage::buffer_pool::BufferPoolManager;
+use crate::storage::catalog;
+use crate::storage::config::EngineConfig;
+use crate::storage::disk::DiskManager;
+use crate::storage::error::{StorageError, StorageResult};
+use crate::storage::fault::{FaultKind, Trigger};
+use crate::storage::fsm;
+use crate::storage::hash_index;
+use crate::storage::io_cost::IoClock;
+use crate::storage::page;
+use crate::storage::recovery::{self, RecoveryReport};
+use crate::storage::schema::*;
+use crate::storage::table::{TableHeap, VacuumReport};
+use crate::storage::txn::TransactionManager;
+use crate::storage::types::*;
+
+/// Rows as returned by scans and lookups.
+pub type Rows = Vec<(RowId, Vec<Value>)>;
+
+pub struct Database {
+    config: EngineConfig,
+    bpm: BufferPoolManager,
+    tables: HashMap<String, TableHeap>,
+    txns: TransactionManager,
+    /// First page of the on-disk catalog (see `catalog`).
+    catalog_root: PageId,
+}
+
+impl Database {
+    /// Format a fresh in-memory disk.  `config` is clamped to legal ranges
+    /// first (see `EngineConfig::validate`).
+    pub fn new(mut config: EngineConfig) -> StorageResult<Self> {
+        config.validate().map_err(StorageError::Parse)?;
+
+        let mut disk = DiskManager::new(config.page_size, config.disk_capacity);
+        disk.clock = IoClock::new(config.io_cost);
+        let mut bpm =
+            BufferPoolManager::with_policy(config.pool_size, disk, config.replacement_policy);
+        let mut txns = TransactionManager::new();
+
+        // Page 0 is the superblock, followed by the catalog root.
+        let txn = txns.begin(&mut bpm);
+        let catalog_root = catalog::format(&mut bpm, txn, &config)?;
+        txns.commit(&mut bpm, txn);
+
+        Ok(Self {
+            config,
+            bpm,
+            tables: HashMap::new(),
+            txns,
+            catalog_root,
+        })
+    }
+
+    /// Reopen a database from bytes produced by `export_image`.  The
+    /// configuration comes from the image's superblock; the log starts
+    /// empty.
+    pub fn from_image(bytes: &[u8]) -> StorageResult<Self> {
+        let invalid = |e: std::io::Error| StorageError::Catalog(format!("Invalid image: {}", e));
+        let disk = DiskManager::from_image(bytes).map_err(invalid)?;
+        let superblock = disk.peek_page(catalog::SUPERBLOCK_PAGE).map_err(invalid)?;
+        if !page::verify_checksum(&superblock) {
+            return Err(StorageError::Corrupt {
+                page_id: catalog::SUPERBLOCK_PAGE,
+                stored: page::checksum(&superblock),
+                computed: page::compute_checksum(&superblock),
+            });
+        }
+        let (config, catalog_root) = catalog::decode_superblock(&superblock)?;
+        if config.page_size != disk.page_size() || config.disk_capacity != disk.max_pages() {
+            return Err(StorageError::Catalog(
+                "Superblock does not match the image geometry".into(),
+            ));
+        }
+
+        let mut bpm =
+            BufferPoolManager::with_policy(config.pool_size, disk, config.replacement_policy);
+        // Page LSNs point into the old log, which isn't part of the image.
+        for pid in 0..config.disk_capacity {
+            if bpm.disk.is_allocated(pid)
+                && let Ok(fid) = bpm.fetch_page(pid)
+            {
+                page::set_page_lsn(bpm.frame_data_mut(fid), INVALID_LSN);
+                bpm.unpin_page(pid, true);
+            }
+        }
+        bpm.flush_all();
+
+        let tables = catalog::load(&mut bpm, catalog_root, config.overflow_threshold)?;
+        Ok(Self {
+            config,
+            bpm,
+            tables: tables.into_iter().map(|t| (t.name.clone(), t)).collect(),
+            txns: TransactionManager::new(),
+            catalog_root,
+        })
+    }
+
+    /// Flush everything and return the whole disk as bytes (header,
+    /// allocation bitmap, then every page).  Refused while transactions are
+    /// running, since their changes would be saved without their log.
+    pub fn export_image(&mut self) -> StorageResult<Vec<u8>> {
+        if !self.txns.active().is_empty() {
+            return Err(StorageError::TxnsActive(
+                "Cannot export while transactions are active".into(),
+            ));
+        }
+        self.bpm.flush_all();
+        self.bpm.disk.export_image()
+            .map_err(|e| StorageError::Catalog(format!("Export failed: {}", e)))
+    }
+
+    pub fn config(&self) -> &EngineConfig {
+        &self.config
+    }
+
+    /// The buffer pool, for snapshots and inspection.
+    pub fn buffer_pool(&self) -> &BufferPoolManager {
+        &self.bpm
+    }
+
+    pub fn buffer_pool_mut(&mut self) -> &mut BufferPoolManager {
+        &mut self.bpm
+    }
+
+    // ── Tables ──────────────────────────────────────────────────────
+
+    pub fn create_table(&mut self, name: &str, schema: Schema) -> StorageResult<()> {
+        if self.tables.contains_key(name) {
+            return Err(StorageError::TableExists(name.into()));
+        }
+        if schema.columns.is_empty() {
+            return Err(StorageError::SchemaMismatch(
+                "Schema must have at least one column".into(),
+            ));
+        }
+        schema.validate()?;
+
+        let txn = self.txns.begin_write(&mut self.bpm)?;
+        self.bpm.take_error();
+        let table = match TableHeap::create(
+            name.to_string(),
+            schema,
+            self.config.overflow_threshold,
+            &mut self.bpm,
+            txn,
+        ) {
+            Ok(table) => table,
+            Err(e) => {
+                self.txns.abort(&mut self.bpm, txn)?;
+                return Err(e);
+            }
+        };
+        self.tables.insert(name.to_string(), table);
+        if let Err(e) = self.save_catalog(txn) {
+            self.tables.remove(name);
+            self.txns.abort(&mut self.bpm, txn)?;
+            return Err(e);
+        }
+        self.txns.commit(&mut self.bpm, txn);
+        Ok(())
+    }
+
+    /// Drop a table and free its pages.  Returns `false` if there was no
+    /// such table.  Refused while transactions are running, since one could
+    /// still hold changes to the table's pages.
+    pub fn drop_table(&mut self, name: &str) -> StorageResult<bool> {
+        if !self.tables.contains_key(name) {
+            return Ok(false);
+        }
+        if !self.txns.active().is_empty() {
+            return Err(StorageError::TxnsActive(
+                "Cannot drop a table while transactions are active".into(),
+            ));
+        }
+        let mut page_ids = self.table_page_ids(name)?;
+        let table = self.tables.remove(name).unwrap();
+        for index in &table.indexes {
+            page_ids.extend(btree::page_ids(&mut self.bpm, index.root_page_id));
+        }
+        for index in &table.hash_indexes {
+            page_ids.extend(hash_index::page_ids(&mut self.bpm, index.directory_page_id));
+        }
+        page_ids.extend(fsm::page_ids(&mut self.bpm, table.fsm_page_id));
+        let txn = self.txns.begin(&mut self.bpm);
+        for pid in page_ids {
+            self.bpm.free_page_logged(txn, pid);
+        }
+        if let Err(e) = self.save_catalog(txn) {
+            self.tables.insert(name.to_string(), table);
+            self.txns.abort(&mut self.bpm, txn)?;
+            return Err(e);
+        }
+        self.txns.commit(&mut self.bpm, txn);
+        Ok(true)
+    }
+
+    /// Table names, sorted.
+    pub fn table_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.tables.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    pub fn table(&self, name: &str) -> StorageResult<&TableHeap> {
+        self.tables.get(name).ok_or_else(|| StorageError::TableNotFound(name.into()))
+    }
+
+    /// Pages in a table's heap chain.
+    pub fn table_page_ids(&mut self, name: &str) -> StorageResult<Vec<PageId>> {
+        let table = self.tables.get(name)
+            .ok_or_else(|| StorageError::TableNotFound(name.into()))?;
+        table.page_ids(&mut self.bpm)
+    }
+
+    // ── Rows ────────────────────────────────────────────────────────
+
+    /// Insert a row and return its RowId.
+    pub fn insert(
+        &mut self,
+        table_name: &str,
+        values: &[Value],
+        txn: Option<TxnId>,
+    ) -> StorageResult<RowId> {
+        self.table(table_name)?;
+        let (txn, auto) = self.resolve_txn(txn)?;
+        let savepoint = self.bpm.log.last_lsn(txn);
+        self.bpm.take_error();
+        let table = self.tables.get_mut(table_name).unwrap();
+        match table.insert(&mut self.bpm, txn, values) {
+            Ok(row_id) => {
+                if auto {
+                    self.txns.commit(&mut self.bpm, txn);
+                }
+                Ok(row_id)
+            }
+            Err(e) => {
+                self.undo_failed(table_name, txn, auto, savepoint)?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Get a row by RowId.
+    pub fn get(&mut self, table_name: &str, row_id: RowId) -> StorageResult<Vec<Value>> {
+        self.check_row_id(table_name, row_id)?;
+        let table = &self.tables[table_name];
+        self.bpm.take_error();
+        table.get(&mut self.bpm, row_id)
+    }
+
+    /// Delete a row.  Returns `false` if there was no row at `row_id`, and
+    /// fails with `RowNotFound` if `row_id` isn't on one of the table's
+    /// pages.
+    pub fn delete(
+        &mut self,
+        table_name: &str,
+        row_id: RowId,
+        txn: Option<TxnId>,
+    ) -> StorageResult<bool> {
+        self.check_row_id(table_name, row_id)?;
+        let (txn, auto) = self.resolve_txn(txn)?;
+        let savepoint = self.bpm.log.last_lsn(txn);
+        self.bpm.take_error();
+        let table = self.tables.get_mut(table_name).unwrap();
+        let deleted = match table.delete(&mut self.bpm, txn, row_id) {
+            Ok(ptrs) => {
+                self.txns.defer_free(txn, ptrs);
+                true
+            }
+            Err(StorageError::RowNotFound(_)) => false,
+            Err(e) => {
+                self.undo_failed(table_name, txn, auto, savepoint)?;
+                return Err(e);
+            }
+        };
+        if auto {
+            self.txns.commit(&mut self.bpm, txn);
+        }
+        Ok(deleted)
+    }
+
+    /// Replace the values of the row at `row_id`.  The RowId stays the same
+    /// even when the new values no longer fit on the row's page.  Returns
+    /// `false` if the row doesn't exist, like `delete`.
+    pub fn update(
+        &mut self,
+        table_name: &str,
+        row_id: RowId,
+        values: &[Value],
+        txn: Option<TxnId>,
+    ) -> StorageResult<bool> {
+        self.check_row_id(table_name, row_id)?;
+        let table = &self.tables[table_name];
+        table.schema.check(values)?;
+        if !table.contains(&mut self.bpm, row_id) {
+            return Ok(false);
+        }
+
+        let (txn, auto) = self.resolve_txn(txn)?;
+        let savepoint = self.bpm.log.last_lsn(txn);
+        self.bpm.take_error();
+        let table = self.tables.get_mut(table_name).unwrap();
+        match table.update(&mut self.bpm, txn, row_id, values) {
+            Ok(ptrs) => self.txns.defer_free(txn, ptrs),
+            Err(e) => {
+                self.undo_failed(table_name, txn, auto, savepoint)?;
+                return Err(e);
+            }
+        }
+        if auto {
+            self.txns.commit(&mut self.bpm, txn);
+        }
+        Ok(true)
+    }
+
+    /// Every live row, in page order.
+    pub fn scan(&mut self, table_name: &str) -> StorageResult<Rows> {
+        let table = self.tables.get(table_name)
+            .ok_or_else(|| StorageError::TableNotFound(table_name.into()))?;
+        self.bpm.take_error();
+        let rows = table.scan(&mut self.bpm);
+        self.check_pages()?;
+        Ok(rows)
+    }
+
+    /// Up to `limit` rows starting at `from` (inclusive), or at the start of
+    /// the table.  Also returns the cursor for the following call, `None`
+    /// once the scan is done.
+    pub fn scan_from(
+        &mut self,
+        table_name: &str,
+        from: Option<RowId>,
+        limit: usize,
+    ) -> StorageResult<(Rows, Option<RowId>)> {
+        let table = self.tables.get(table_name)
+            .ok_or_else(|| StorageError::TableNotFound(table_name.into()))?;
+        let start = match from {
+            None => RowId { page_id: table.first_page_id, slot_id: 0 },
+            Some(rid) => {
+                if !table.owns_page(&mut self.bpm, rid.page_id)? {
+                    return Err(StorageError::Parse(format!(
+                        "Cursor {}:{} is not in table '{}'",
+                        rid.page_id, rid.slot_id, table_name
+                    )));
+                }
+                rid
+            }
+        };
+
+        // Read one row past the limit: its RowId is the next cursor
+        self.bpm.take_error();
+        let mut rows: Rows = table.iter_from(&mut self.bpm, start).take(limit + 1).collect();
+        self.check_pages()?;
+        let next = if rows.len() > limit {
+            rows.pop().map(|(rid, _)| rid)
+        } else {
+            None
+        };
+        Ok((rows, next))
+    }
+
+    /// Compact fragmented pages, trim trailing tombstone slots, and free
+    /// pages left empty.  Refused while any transaction is running, since
+    /// an abort could need the tombstones back.
+    pub fn vacuum(&mut self, table_name: &str) -> StorageResult<VacuumReport> {
+        self.table(table_name)?;
+        if !self.txns.active().is_empty() {
+            return Err(StorageError::TxnsActive(
+                "Cannot vacuum while transactions are active".into(),
+            ));
+        }
+
+        let txn = self.txns.begin(&mut self.bpm);
+        let table = self.tables.get_mut(table_name).unwrap();
+        let report = table.vacuum(&mut self.bpm, txn);
+        self.txns.commit(&mut self.bpm, txn);
+        Ok(report)
+    }
+
+    // ── Indexes ─────────────────────────────────────────────────────
+
+    /// Build a B+tree index on `column`.  Existing rows are indexed
+    /// immediately; later changes keep it up to date.  Returns the index's
+    /// root page.
+    pub fn create_index(&mut self, table_name: &str, column: &str) -> StorageResult<PageId> {
+        let col = self.column(table_name, column)?;
+        if self.tables[table_name].index_on(col).is_some() {
+            return Err(StorageError::AlreadyIndexed(format!(
+                "Column '{}' is already indexed", column
+            )));
+        }
+
+        let txn = self.txns.begin_write(&mut self.bpm)?;
+        self.bpm.take_error();
+        let table = self.tables.get_mut(table_name).unwrap();
+        let root = match table.create_index(&mut self.bpm, txn, col) {
+            Ok(root) => root,
+            Err(e) => {
+                self.txns.abort(&mut self.bpm, txn)?;
+                return Err(e);
+            }
+        };
+        if let Err(e) = self.save_catalog(txn) {
+            self.tables.get_mut(table_name).unwrap().indexes.pop();
+            self.txns.abort(&mut self.bpm, txn)?;
+            return Err(e);
+        }
+        self.txns.commit(&mut self.bpm, txn);
+        Ok(root)
+    }
+
+    /// Build an extendible hash index on `column` for equality lookups.
+    /// Returns the directory page.
+    pub fn create_hash_index(&mut self, table_name: &str, column: &str) -> StorageResult<PageId> {
+        let col = self.column(table_name, column)?;
+        if self.tables[table_name].hash_index_on(col).is_some() {
+            return Err(StorageError::AlreadyIndexed(format!(
+                "Column '{}' already has a hash index", column
+            )));
+        }
+
+        let txn = self.txns.begin_write(&mut self.bpm)?;
+        self.bpm.take_error();
+        let table = self.tables.get_mut(table_name).unwrap();
+        let dir = match table.create_hash_index(&mut self.bpm, txn, col) {
+            Ok(dir) => dir,
+            Err(e) => {
+                self.txns.abort(&mut self.bpm, txn)?;
+                return Err(e);
+            }
+        };
+        if let Err(e) = self.save_catalog(txn) {
+            self.tables.get_mut(table_name).unwrap().hash_indexes.pop();
+            self.txns.abort(&mut self.bpm, txn)?;
+            return Err(e);
+        }
+        self.txns.commit(&mut self.bpm, txn);
+        Ok(dir)
+    }
+
+    /// Rows whose hash-indexed `column` equals `key`.
+    pub fn hash_lookup(&mut self, table_name: &str, column: &str, key: &Value) -> StorageResult<Rows> {
+        let col = self.column(table_name, column)?;
+        let table = &self.tables[table_name];
+        let index = table.hash_index_on(col)
+            .ok_or_else(|| StorageError::NotIndexed(format!("Column '{}' has no hash index", column)))?;
+        let Some(key) = index_key(&table.schema.columns[col], key, index.key_size)? else {
+            return Ok(Vec::new()); // NULLs are never indexed
+        };
+
+        self.bpm.take_error();
+        let rows = hash_index::lookup(&mut self.bpm, index.directory_page_id, &key)
+            .into_iter()
+            .filter_map(|rid| Some((rid, table.get(&mut self.bpm, rid).ok()?)))
+            .collect();
+        self.check_pages()?;
+        Ok(rows)
+    }
+
+    /// Rows whose indexed `column` equals `key`.
+    pub fn index_lookup(&mut self, table_name: &str, column: &str, key: &Value) -> StorageResult<Rows> {
+        self.index_range(table_name, column, Some(key), Some(key))
+    }
+
+    /// Rows whose indexed `column` lies in `[low, high]`, in key order.
+    /// `None` leaves that end open.
+    pub fn index_range(
+        &mut self,
+        table_name: &str,
+        column: &str,
+        low: Option<&Value>,
+        high: Option<&Value>,
+    ) -> StorageResult<Rows> {
+        let col = self.column(table_name, column)?;
+        let table = &self.tables[table_name];
+        let index = table.index_on(col)
+            .ok_or_else(|| StorageError::NotIndexed(format!("Column '{}' is not indexed", column)))?;
+        let bound = |v: Option<&Value>| match v {
+            Some(v) => index_key(&table.schema.columns[col], v, index.key_size),
+            None => Ok(None),
+        };
+        let (low, high) = (bound(low)?, bound(high)?);
+
+        self.bpm.take_error();
+        let hits = btree::range(&mut self.bpm, index.root_page_id, low.as_deref(), high.as_deref());
+        let rows = hits
+            .into_iter()
+            .filter_map(|(_, rid)| Some((rid, table.get(&mut self.bpm, rid).ok()?)))
+            .collect();
+        self.check_pages()?;
+        Ok(rows)
+    }
+
+    // ── Transactions ────────────────────────────────────────────────
+
+    pub fn begin(&mut self) -> TxnId {
+        self.txns.begin(&mut self.bpm)
+    }
+
+    /// Commit a transaction, making its changes durable.
+    pub fn commit(&mut self, txn: TxnId) -> StorageResult<()> {
+        if !self.txns.commit(&mut self.bpm, txn) {
+            return Err(StorageError::TxnNotActive(txn));
+        }
+        Ok(())
+    }
+
+    /// Abort a transaction, undoing every change and page allocation it
+    /// made.
+    pub fn abort(&mut self, txn: TxnId) -> StorageResult<()> {
+        if !self.txns.abort(&mut self.bpm, txn)? {
+            return Err(StorageError::TxnNotActive(txn));
+        }
+        for table in self.tables.values_mut() {
+            table.recount(&mut self.bpm);
+        }
+        Ok(())
+    }
+
+    pub fn active_transactions(&self) -> Vec<TxnId> {
+        self.txns.active()
+    }
+
+    /// Mark the current end of `txn`'s changes, for `rollback_to`.
+    pub fn savepoint(&self, txn: TxnId) -> StorageResult<Lsn> {
+        if !self.txns.is_active(txn) {
+            return Err(StorageError::TxnNotActive(txn));
+        }
+        Ok(self.bpm.log.last_lsn(txn))
+    }
+
+    /// Undo the changes `txn` made after `savepoint`, keeping the
+    /// transaction and its earlier work.
+    pub fn rollback_to(&mut self, txn: TxnId, savepoint: Lsn) -> StorageResult<()> {
+        if !self.txns.is_active(txn) {
+            return Err(StorageError::TxnNotActive(txn));
+        }
+        recovery::rollback_to(&mut self.bpm, txn, savepoint)?;
+        for table in self.tables.values_mut() {
+            table.recount(&mut self.bpm);
+        }
+        Ok(())
+    }
+
+    // ── SQL ─────────────────────────────────────────────────────────
+
+    /// Run one SQL statement (see `sql` for the dialect).  INSERT, UPDATE
+    /// and DELETE join `txn` if given and are undone as a whole if any row
+    /// fails.
+    pub fn execute_sql(&mut self, text: &str, txn: Option<TxnId>) -> StorageResult<QueryResult> {
+        sql::execute(self, text, txn)
+    }
+
+    /// Join two tables on equal values of one column each, with `algorithm`
+    /// holding at most `memory_frames` buffer pool frames of rows.  The
+    /// result has every column of both tables, named `table.column`, and
+    /// the plan; `plan.total()` is the join's page reads and writes.
+    pub fn join(
+        &mut self,
+        algorithm: JoinAlgorithm,
+        left: (&str, &str),
+        right: (&str, &str),
+        memory_frames: usize,
+    ) -> StorageResult<QueryResult> {
+        let keys = JoinKeys { left: self.column(left.0, left.1)?, right: self.column(right.0, right.1)? };
+        let mut columns = Vec::new();
+        for table_name in [left.0, right.0] {
+            columns.extend(self.table(table_name)?.schema.columns.iter().map(|c| Column {
+                name: format!("{}.{}", table_name, c.name),
+                ..c.clone()
+            }));
+        }
+        let mut plan = join::join(
+            algorithm,
+            Box::new(SeqScan::new(self.table(left.0)?)),
+            Box::new(SeqScan::new(self.table(right.0)?)),
+            keys,
+            memory_frames,
+        );
+        let rows = exec::run(&mut *plan, &mut self.bpm)?;
+        Ok(QueryResult { columns, rows, rows_affected: 0, plan: Some(plan.profile()) })
+    }
+
+    /// Every row of `table_name`, ordered by `(column, descending)` keys
+    /// with an external merge sort in `memory_frames` frames.  Returns the
+    /// rows with the plan, and the sort's runs and merge passes.
+    pub fn sort(
+        &mut self,
+        table_name: &str,
+        order_by: &[(&str, bool)],
+        memory_frames: usize,
+    ) -> StorageResult<(QueryResult, SortTrace)> {
+        let keys = order_by
+            .iter()
+            .map(|&(column, desc)| Ok((BoundExpr::Column(self.column(table_name, column)?), desc)))
+            .collect::<StorageResult<Vec<_>>>()?;
+        let table = self.table(table_name)?;
+        let columns = table.schema.columns.clone();
+        let mut plan = ExternalSort::new(Box::new(SeqScan::new(table)), keys, memory_frames);
+        let rows = exec::run(&mut plan, &mut self.bpm)?;
+        let result = QueryResult { columns, rows, rows_affected: 0, plan: Some(plan.profile()) };
+        Ok((result, plan.trace().clone()))
+    }
+
+    /// Aggregate `table_name`: one row per distinct combination of the
+    /// `group_by` columns (a single row without any), holding those columns
+    /// and then each `(function, column)` aggregate — COUNT(*) when the
+    /// column is `None`.  The hash table holds at most `memory_frames`
+    /// frames of groups before spilling partitions to temp pages.
+    pub fn aggregate(
+        &mut self,
+        table_name: &str,
+        group_by: &[&str],
+        aggregates: &[(AggregateFunction, Option<&str>)],
+        memory_frames: usize,
+    ) -> StorageResult<QueryResult> {
+        let schema = &self.table(table_name)?.schema;
+        let mut columns = Vec::new();
+        let mut keys = Vec::new();
+        for &name in group_by {
+            let i = self.column(table_name, name)?;
+            columns.push(schema.columns[i].clone());
+            keys.push(BoundExpr::Column(i));
+        }
+        let mut calls = Vec::new();
+        for &(function, column) in aggregates {
+            let arg = column.map(|name| self.column(table_name, name)).transpose()?;
+            let arg_type = arg.map(|i| &schema.columns[i].col_type);
+            let col_type = function.result_type(arg_type).ok_or_else(|| {
+                StorageError::Query(match arg_type {
+                    Some(t) => format!("Can't take {} of {} column '{}'", function.name().to_uppercase(), t, column.unwrap()),
+                    None => format!("{} needs a column", function.name().to_uppercase()),
+                })
+            })?;
+            columns.push(Column {
+                name: format!("{}({})", function.name(), column.unwrap_or("*")),
+                col_type,
+                nullable: function != AggregateFunction::Count,
+            });
+            calls.push(AggregateExpr { function, arg: arg.map(BoundExpr::Column) });
+        }
+        let scan = Box::new(SeqScan::new(self.table(table_name)?));
+        let mut plan = HashAggregate::new(scan, keys, calls, memory_frames);
+        let rows = exec::run(&mut plan, &mut self.bpm)?;
+        Ok(QueryResult { columns, rows, rows_affected: 0, plan: Some(plan.profile()) })
+    }
+
+    // ── Buffer pool and disk ────────────────────────────────────────
+
+    /// Flush all dirty pages in the buffer pool.
+    pub fn flush_all(&mut self) {
+        self.bpm.flush_all();
+    }
+
+    /// Flush a specific page.  Returns `false` if it isn't in the pool.
+    pub fn flush_page(&mut self, page_id: PageId) -> bool {
+        self.bpm.flush_page(page_id)
+    }
+
+    /// Seed the generator that picks where torn writes stop and which bit
+    /// flips.
+    pub fn set_fault_seed(&mut self, seed: u64) {
+        self.bpm.disk.faults.reseed(seed);
+    }
+
+    /// Arm a one-shot disk fault and return its id.
+    pub fn arm_fault(&mut self, kind: FaultKind, trigger: Trigger) -> StorageResult<u32> {
+        match trigger {
+            Trigger::Nth(0) => {
+                return Err(StorageError::Parse("A fault's nth must be positive".into()));
+            }
+            Trigger::Page(pid) if pid >= self.bpm.disk.max_pages() => {
+                return Err(StorageError::Parse(format!("Page {} is out of range", pid)));
+            }
+            _ => {}
+        }
+        Ok(self.bpm.disk.faults.arm(kind, trigger))
+    }
+
+    /// Cancel every fault that hasn't fired yet.
+    pub fn disarm_faults(&mut self) {
+        self.bpm.disk.faults.disarm_all();
+    }
+
+    // ── Crash recovery ──────────────────────────────────────────────
+
+    /// Simulate a crash: all buffer pool frames are lost without being
+    /// written back, along with any log records not yet flushed.
+    pub fn simulate_crash(&mut self) {
+        self.bpm.crash();
+        self.txns.crash();
+    }
+
+    /// Run ARIES recovery (analysis, redo, undo) over the durable log.
+    pub fn recover(&mut self) -> RecoveryReport {
+        let report = recovery::recover(&mut self.bpm);
+        // The catalog on disk is now authoritative: DDL that didn't commit
+        // has been rolled back with everything else.
+        match catalog::load(&mut self.bpm, self.catalog_root, self.config.overflow_threshold) {
+            Ok(tables) => {
+                self.tables = tables.into_iter().map(|t| (t.name.clone(), t)).collect();
+            }
+            Err(_) => {
+                for table in self.tables.values_mut() {
+                    table.recount(&mut self.bpm);
+                }
+            }
+        }
+        report
+    }
+
+    // ── Internal ───────────────────────────────────────────────────
+
+    /// Position of `column` in `table_name`'s schema.
+    fn column(&self, table_name: &str, column: &str) -> StorageResult<usize> {
+        self.table(table_name)?
+            .schema
+            .columns
+            .iter()
+            .position(|c| c.name == column)
+            .ok_or_else(|| StorageError::ColumnNotFound(column.into()))
+    }
+
+    /// Fail with `RowNotFound` unless `row_id` is on one of the table's
+    /// heap pages, so a RowId from another table (or no table at all) is
+    /// never decoded with the wrong schema.
+    fn check_row_id(&mut self, table_name: &str, row_id: RowId) -> StorageResult<()> {
+        let table = self.tables.get(table_name)
+            .ok_or_else(|| StorageError::TableNotFound(table_name.into()))?;
+        if !table.owns_page(&mut self.bpm, row_id.page_id)? {
+            return Err(StorageError::RowNotFound(row_id));
+        }
+        Ok(())
+    }
+
+    /// Undo a row change that failed part-way: abort an auto-commit
+    /// transaction, or roll the caller's back to `savepoint`.  Fails if the
+    /// undo itself couldn't read a page.
+    fn undo_failed(
+        &mut self,
+        table_name: &str,
+        txn: TxnId,
+        auto: bool,
+        savepoint: Lsn,
+    ) -> StorageResult<()> {
+        if auto {
+            self.txns.abort(&mut self.bpm, txn)?;
+        } else {
+            recovery::rollback_to(&mut self.bpm, txn, savepoint)?;
+        }
+        self.tables.get_mut(table_name).unwrap().recount(&mut self.bpm);
+        Ok(())
+    }
+
+    /// Fail if a page couldn't be read since the last `take_error`, rather
+    /// than return results with that page's rows silently missing.
+    fn check_pages(&mut self) -> StorageResult<()> {
+        match self.bpm.take_error() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// Rewrite the on-disk catalog from `tables` on behalf of `txn`.
+    fn save_catalog(&mut self, txn: TxnId) -> StorageResult<()> {
+        let mut tables: Vec<&TableHeap> = self.tables.values().collect();
+        tables.sort_by(|a, b| a.name.cmp(&b.name));
+        catalog::save(&mut self.bpm, txn, self.catalog_root, &tables)
+    }
+
+    /// Use the caller's transaction if it is running, or start an
+    /// auto-commit one, and claim the right to write for it.  Returns
+    /// (txn, is_auto_commit).
+    fn resolve_txn(&mut self, txn: Option<TxnId>) -> StorageResult<(TxnId, bool)> {
+        match txn {
+            Some(t) if self.txns.is_active(t) => {
+                self.txns.claim_write(t)?;
+                Ok((t, false))
+            }
+            Some(t) => Err(StorageError::TxnNotActive(t)),
+            None => Ok((self.txns.begin_write(&mut self.bpm)?, true)),
+        }
+    }
+}
+
+/// Encode `value` as a key for an index on `col`; `None` for NULL, which
+/// is never indexed.
+fn index_key(col: &Column, value: &Value, key_size: usize) -> StorageResult<Option<Vec<u8>>> {
+    if *value == Value::Null {
+        return Ok(None);
+    }
+    Schema::new(vec![col.clone()]).check(std::slice::from_ref(value))?;
+    encode_key(value, key_size).map(Some).ok_or_else(|| StorageError::KeyTooLarge {
+        size: key_bytes(value).map_or(0, |key| key.len()),
+        max: key_size,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn db() -> Database {
+        let mut config = EngineConfig::default_config();
+        config.page_size = 128;
+        Database::new(config).unwrap()
+    }
+
+    fn users() -> Schema {
+        Schema::new(vec![
+            Column { name: "id".into(), col_type: ColumnType::Int32, nullable: false },
+            Column { name: "name".into(), col_type: ColumnType::VarChar(40), nullable: true },
+        ])
+    }
+
+    fn row(id: i32, name: &str) -> Vec<Value> {
+        vec![Value::Int32(id), Value::VarChar(name.into())]
+    }
+
+    #[test]
+    fn typed_row_round_trip() {
+        let mut db = db();
+        db.create_table("users", users()).unwrap();
+        assert_eq!(db.create_table("users", users()), Err(StorageError::TableExists("users".into())));
+
+        let a = db.insert("users", &row(1, "ann"), None).unwrap();
+        let b = db.insert("users", &row(2, "bob"), None).unwrap();
+        assert_eq!(db.get("users", a).unwrap(), row(1, "ann"));
+        assert!(db.update("users", b, &row(2, "bea"), None).unwrap());
+        assert!(db.delete("users", a, None).unwrap());
+        assert!(!db.delete("users", a, None).unwrap());
+        assert_eq!(db.scan("users").unwrap(), vec![(b, row(2, "bea"))]);
+        assert!(matches!(
+            db.insert("users", &[Value::Null, Value::Null], None),
+            Err(StorageError::SchemaMismatch(_))
+        ));
+        assert_eq!(db.table("nope").err(), Some(StorageError::TableNotFound("nope".into())));
+    }
+
+    #[test]
+    fn transactions_and_indexes() {
+        let mut db = db();
+        db.create_table("users", users()).unwrap();
+        db.create_index("users", "id").unwrap();
+        db.create_hash_index("users", "name").unwrap();
+
+        let txn = db.begin();
+        let a = db.insert("users", &row(7, "ann"), Some(txn)).unwrap();
+        db.insert("users", &row(3, "cat"), Some(txn)).unwrap();
+        assert_eq!(db.index_lookup("users", "id", &Value::Int32(7)).unwrap(), vec![(a, row(7, "ann"))]);
+        db.abort(txn).unwrap();
+        assert!(db.scan("users").unwrap().is_empty());
+        assert_eq!(db.commit(txn), Err(StorageError::TxnNotActive(txn)));
+
+        for (i, name) in ["a", "b", "c", "d"].iter().enumerate() {
+            db.insert("users", &row(i as i32, name), None).unwrap();
+        }
+        let ids: Vec<Value> = db
+            .index_range("users", "id", Some(&Value::Int32(1)), None)
+            .unwrap()
+            .into_iter()
+            .map(|(_, v)| v[0].clone())
+            .collect();
+        assert_eq!(ids, vec![Value::Int32(1), Value::Int32(2), Value::Int32(3)]);
+        assert_eq!(db.hash_lookup("users", "name", &Value::VarChar("c".into())).unwrap().len(), 1);
+        assert!(matches!(
+            db.hash_lookup("users", "id", &Value::Int32(1)),
+            Err(StorageError::NotIndexed(_))
+        ));
+    }
+
+    #[test]
+    fn row_ids_must_belong_to_the_table() {
+        let mut db = db();
+        db.create_table("users", users()).unwrap();
+        db.create_table("nums", Schema::new(vec![
+            Column { name: "n".into(), col_type: ColumnType::Int64, nullable: false },
+        ])).unwrap();
+        let a = db.insert("users", &row(1, "ann"), None).unwrap();
+        let n = db.insert("nums", &[Value::Int64(7)], None).unwrap();
+
+        let far = RowId { page_id: 60_000, slot_id: 0 };
+        for rid in [n, far] {
+            assert_eq!(db.get("users", rid), Err(StorageError::RowNotFound(rid)));
+            assert_eq!(db.delete("users", rid, None), Err(StorageError::RowNotFound(rid)));
+            assert_eq!(
+                db.update("users", rid, &row(2, "bob"), None),
+                Err(StorageError::RowNotFound(rid))
+            );
+        }
+        assert_eq!(db.get("nums", n).unwrap(), vec![Value::Int64(7)]);
+
+        // On the table's own page, a slot past the end is just a missing row
+        let past = RowId { slot_id: 9, ..a };
+        assert_eq!(db.get("users", past), Err(StorageError::RowNotFound(past)));
+        assert_eq!(db.delete("users", past, None), Ok(false));
+        assert_eq!(db.update("users", past, &row(2, "bob"), None), Ok(false));
+    }
+
+    #[test]
+    fn row_id_check_does_not_read_the_chain() {
+        let mut config = EngineConfig::default_config();
+        config.page_size = 128;
+        config.pool_size = 4;
+        let mut db = Database::new(config).unwrap();
+        db.create_table("users", users()).unwrap();
+        let rids: Vec<RowId> = (0..40)
+            .map(|i| db.insert("users", &row(i, "someone"), None).unwrap())
+            .collect();
+        let pages = db.table_page_ids("users").unwrap();
+        assert!(pages.len() > 4);
+
+        // An unreadable page elsewhere in the chain doesn't hide the row
+        let last = *rids.last().unwrap();
+        db.arm_fault(FaultKind::ReadError, Trigger::Page(pages[1])).unwrap();
+        assert_eq!(db.get("users", last).unwrap(), row(39, "someone"));
+
+        // An unreadable free space map is an I/O error, not a missing row
+        let fsm_page = db.table("users").unwrap().fsm_page_id;
+        db.bpm.flush_all();
+        db.bpm.crash();
+        db.arm_fault(FaultKind::ReadError, Trigger::Page(fsm_page)).unwrap();
+        assert_eq!(db.get("users", last).unwrap_err().code(), "IO_ERROR");
+    }
+
+    #[test]
+    fn failed_drop_keeps_the_table() {
+        let mut config = EngineConfig::default_config();
+        config.page_size = 128;
+        config.pool_size = 4;
+        let mut db = Database::new(config).unwrap();
+        db.create_table("users", users()).unwrap();
+        for i in 0..20 {
+            db.insert("users", &row(i, "someone"), None).unwrap();
+        }
+
+        let root = db.catalog_root;
+        db.arm_fault(FaultKind::ReadError, Trigger::Page(root)).unwrap();
+        assert_eq!(db.drop_table("users").unwrap_err().code(), "IO_ERROR");
+        assert_eq!(db.table_names(), ["users"]);
+        assert!(db.active_transactions().is_empty());
+        assert_eq!(db.scan("users").unwrap().len(), 20);
+        assert_eq!(db.drop_table("users"), Ok(true));
+    }
+
+    #[test]
+    fn one_writer_at_a_time() {
+        let mut db = db();
+        db.create_table("users", users()).unwrap();
+        let a = db.insert("users", &row(1, "ann"), None).unwrap();
+
+        let t1 = db.begin();
+        let t2 = db.begin();
+        assert!(db.delete("users", a, Some(t1)).unwrap());
+        // Neither t2 nor an auto-commit write may take the slot t1 emptied
+        let busy = db.insert("users", &row(2, "bob"), Some(t2)).unwrap_err();
+        assert_eq!(busy.code(), "TXNS_ACTIVE");
+        assert!(db.insert("users", &row(2, "bob"), None).is_err());
+        assert!(db.create_index("users", "id").is_err());
+        assert!(db.drop_table("users").is_err());
+        assert_eq!(db.scan("users").unwrap(), vec![]);
+
+        db.abort(t1).unwrap();
+        assert_eq!(db.get("users", a).unwrap(), row(1, "ann"));
+        let b = db.insert("users", &row(2, "bob"), Some(t2)).unwrap();
+        db.commit(t2).unwrap();
+        assert_eq!(db.scan("users").unwrap(), vec![(a, row(1, "ann")), (b, row(2, "bob"))]);
+
+        // A transaction that only reads holds nobody up, but still blocks a drop
+        let reader = db.begin();
+        db.insert("users", &row(3, "cat"), None).unwrap();
+        assert_eq!(db.drop_table("users").unwrap_err().code(), "TXNS_ACTIVE");
+        db.commit(reader).unwrap();
+        assert_eq!(db.drop_table("users"), Ok(true));
+        assert_eq!(db.drop_table("users"), Ok(false));
+    }
+
+    #[test]
+    fn image_round_trip_and_paged_scan() {
+        let mut db = db();
+        db.create_table("users", users()).unwrap();
+        for i in 0..10 {
+            db.insert("users", &row(i, "someone"), None).unwrap();
+        }
+        let (first, next) = db.scan_from("users", None, 4).unwrap();
+        assert_eq!(first.len(), 4);
+        let (rest, end) = db.scan_from("users", next, 100).unwrap();
+        assert_eq!(rest.len(), 6);
+        assert_eq!(end, None);
+
+        let image = db.export_image().unwrap();
+        let mut reopened = Database::from_image(&image).unwrap();
+        assert_eq!(reopened.table_names(), vec!["users".to_string()]);
+        assert_eq!(reopened.scan("users").unwrap().len(), 10);
+    }
+}
@@ -0,0 +1,502 @@
+//! Hash aggregation — COUNT, SUM, MIN, MAX and AVG, optionally per group.
+//!
+//! Each input row is reduced to its group values and aggregate arguments,
+//! and folded into a hash table of groups.  The table may hold
+//! `memory_frames - 1` pages' worth of groups, each costing its group
+//! values' spilled size plus `STATE_SIZE` per aggregate.  Once it is full,
+//! rows of groups already in the table are still folded in, but rows of
+//! new groups are written to `memory_frames - 1` partitions on temp pages,
+//! split by group hash.  When the input is done the table is emitted, and
+//! each partition is then aggregated the same way — partitioning again,
+//! with a different hash, if it overflows too.
+//!
+//! NULLs are skipped by every aggregate but COUNT(*), and form a group of
+//! their own in GROUP BY.  Without GROUP BY there is exactly one output
+//! row, even for an empty input.  Groups come out in no particular order.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+use crate::exec::join::hash_key;
+use crate::exec::operators::{join, node};
+use crate::exec::spill::{SpillFile, page_capacity, partition_of, row_size};
+use crate::exec::{IoMark, Operator, OperatorStats, PlanProfile, Row};
+use crate::sql::expr::{BoundExpr, add, cast, compare};
+use crate::storage::buffer_pool::BufferPoolManager;
+use crate::storage::error::{StorageError, StorageResult};
+use crate::storage::schema::{ColumnType, Value};
+
+/// Memory charged per aggregate per group.
+const STATE_SIZE: usize = 16;
+
+/// Partitioning levels after which a table is let grow past its budget
+/// rather than split yet again.
+const MAX_LEVEL: u64 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggregateFunction {
+    Count,
+    Sum,
+    Min,
+    Max,
+    Avg,
+}
+
+impl AggregateFunction {
+    pub fn name(self) -> &'static str {
+        match self {
+            AggregateFunction::Count => "count",
+            AggregateFunction::Sum => "sum",
+            AggregateFunction::Min => "min",
+            AggregateFunction::Max => "max",
+            AggregateFunction::Avg => "avg",
+        }
+    }
+
+    /// Case-insensitive.
+    pub fn parse(name: &str) -> Option<Self> {
+        [
+            AggregateFunction::Count,
+            AggregateFunction::Sum,
+            AggregateFunction::Min,
+            AggregateFunction::Max,
+            AggregateFunction::Avg,
+        ]
+        .into_iter()
+        .find(|f| f.name().eq_ignore_ascii_case(name))
+    }
+
+    /// The type of the result for an argument of type `arg` (`None` for
+    /// COUNT(*)), or `None` if the function doesn't apply to it.
+    pub fn result_type(self, arg: Option<&ColumnType>) -> Option<ColumnType> {
+        use ColumnType::*;
+        match (self, arg) {
+            (AggregateFunction::Count, _) => Some(Int64),
+            (_, None) => None,
+            (AggregateFunction::Min | AggregateFunction::Max, Some(t)) => Some(t.clone()),
+            (AggregateFunction::Sum, Some(Int16 | Int32 | UInt32 | Int64)) => Some(Int64),
+            (AggregateFunction::Sum, Some(Decimal(_, scale))) => Some(Decimal(18, *scale)),
+            (AggregateFunction::Sum | AggregateFunction::Avg, Some(Float32 | Float64)) => Some(Float64),
+            (AggregateFunction::Avg, Some(Int16 | Int32 | UInt32 | Int64 | Decimal(..))) => Some(Float64),
+            _ => None,
+        }
+    }
+}
+
+/// One aggregate of the output: a function of an argument, or COUNT(*)
+/// when `arg` is `None`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AggregateExpr {
+    pub function: AggregateFunction,
+    pub arg: Option<BoundExpr>,
+}
+
+/// `count(*)`, `sum($2)`
+impl fmt::Display for AggregateExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.arg {
+            Some(arg) => write!(f, "{}({})", self.function.name(), arg),
+            None => write!(f, "{}(*)", self.function.name()),
+        }
+    }
+}
+
+/// A running aggregate.
+#[derive(Debug, Clone)]
+enum State {
+    Count(i64),
+    /// The sum and how many values went into it; `None` before the first.
+    Sum(Option<Value>, i64),
+    Min(Option<Value>),
+    Max(Option<Value>),
+}
+
+impl State {
+    fn new(function: AggregateFunction) -> Self {
+        match function {
+            AggregateFunction::Count => State::Count(0),
+            AggregateFunction::Sum | AggregateFunction::Avg => State::Sum(None, 0),
+            AggregateFunction::Min => State::Min(None),
+            AggregateFunction::Max => State::Max(None),
+        }
+    }
+
+    /// Fold in one value; `None` for a row of COUNT(*).
+    fn update(&mut self, v: Option<&Value>) -> StorageResult<()> {
+        let v = match v {
+            None => {
+                if let State::Count(n) = self {
+                    *n += 1;
+                }
+                return Ok(());
+            }
+            Some(Value::Null) => return Ok(()),
+            Some(v) => v,
+        };
+        let keep = |best: &mut Option<Value>, wanted| {
+            if best.as_ref().is_none_or(|b| compare(v, b) == Some(wanted)) {
+                *best = Some(v.clone());
+            }
+        };
+        match self {
+            State::Count(n) => *n += 1,
+            State::Sum(sum, n) => {
+                *sum = Some(match sum.take() {
+                    Some(s) => add(s, v.clone())?,
+                    None => v.clone(),
+                });
+                *n += 1;
+            }
+            State::Min(best) => keep(best, std::cmp::Ordering::Less),
+            State::Max(best) => keep(best, std::cmp::Ordering::Greater),
+        }
+        Ok(())
+    }
+
+    fn finish(&self, function: AggregateFunction) -> StorageResult<Value> {
+        Ok(match self {
+            State::Count(n) => Value::Int64(*n),
+            State::Sum(None, _) | State::Min(None) | State::Max(None) => Value::Null,
+            State::Sum(Some(sum), n) if function == AggregateFunction::Avg => {
+                match cast(sum, &ColumnType::Float64)? {
+                    Value::Float64(total) => Value::Float64(total / *n as f64),
+                    _ => Value::Null,
+                }
+            }
+            // A sum of one value keeps its type; widen it like `+` would
+            State::Sum(Some(sum), _) => add(sum.clone(), Value::Int64(0))?,
+            State::Min(Some(v)) | State::Max(Some(v)) => v.clone(),
+        })
+    }
+}
+
+/// Groups and their running aggregates, in the order first seen.
+#[derive(Default)]
+struct GroupTable {
+    groups: Vec<(Row, Vec<State>)>,
+    /// Group hash key to positions in `groups`.
+    index: HashMap<Vec<u8>, Vec<usize>>,
+    /// Bytes charged against the budget.
+    used: usize,
+}
+
+impl GroupTable {
+    fn find(&self, key: &[u8], values: &[Value]) -> Option<usize> {
+        let same = |a: &Value, b: &Value| {
+            (*a == Value::Null && *b == Value::Null) || compare(a, b) == Some(std::cmp::Ordering::Equal)
+        };
+        self.index
+            .get(key)?
+            .iter()
+            .copied()
+            .find(|&i| self.groups[i].0.iter().zip(values).all(|(a, b)| same(a, b)))
+    }
+}
+
+/// Hash key of a group: its values' keys one after another, NULL as `n`.
+fn group_key(values: &[Value]) -> Vec<u8> {
+    values.iter().flat_map(|v| hash_key(v).unwrap_or_else(|| vec![b'n'])).collect()
+}
+
+pub struct HashAggregate {
+    child: Box<dyn Operator>,
+    group_by: Vec<BoundExpr>,
+    aggregates: Vec<AggregateExpr>,
+    memory_frames: usize,
+    budget: usize,
+    table: GroupTable,
+    /// Partitions being written while the table is full.
+    filling: Vec<SpillFile>,
+    /// Partitions written but not yet aggregated, with their level.
+    partitions: VecDeque<(SpillFile, u64)>,
+    output: VecDeque<Row>,
+    /// Partitions written by the last run.
+    spilled: usize,
+    stats: OperatorStats,
+}
+
+impl HashAggregate {
+    pub fn new(
+        child: Box<dyn Operator>,
+        group_by: Vec<BoundExpr>,
+        aggregates: Vec<AggregateExpr>,
+        memory_frames: usize,
+    ) -> Self {
+        Self {
+            child,
+            group_by,
+            aggregates,
+            memory_frames,
+            budget: 0,
+            table: GroupTable::default(),
+            filling: Vec::new(),
+            partitions: VecDeque::new(),
+            output: VecDeque::new(),
+            spilled: 0,
+            stats: OperatorStats::default(),
+        }
+    }
+
+    /// The group values followed by each aggregate's argument.
+    fn reduce(&self, row: &[Value]) -> StorageResult<Row> {
+        let args = self.aggregates.iter().filter_map(|a| a.arg.as_ref());
+        self.group_by.iter().chain(args).map(|e| e.eval(row)).collect()
+    }
+
+    /// Fold a reduced row into the table, or spill it if its group is new
+    /// and the table is full.  `level` is how many times its rows have
+    /// been partitioned.
+    fn consume(&mut self, bpm: &mut BufferPoolManager, reduced: Row, level: u64) -> StorageResult<()> {
+        let groups = self.group_by.len();
+        let key = group_key(&reduced[..groups]);
+        let index = match self.table.find(&key, &reduced[..groups]) {
+            Some(i) => i,
+            None => {
+                let cost = row_size(&reduced[..groups]) + STATE_SIZE * self.aggregates.len();
+                let full = self.table.used + cost > self.budget;
+                if full && !self.table.groups.is_empty() && level < MAX_LEVEL {
+                    if self.filling.is_empty() {
+                        self.filling = (0..self.memory_frames - 1).map(|_| SpillFile::new()).collect();
+                    }
+                    let part = partition_of(&key, level, self.filling.len());
+                    let mark = IoMark::new(bpm);
+                    let result = self.filling[part].append(bpm, &reduced);
+                    self.stats.charge(bpm, mark);
+                    return result;
+                }
+                let states = self.aggregates.iter().map(|a| State::new(a.function)).collect();
+                self.table.groups.push((reduced[..groups].to_vec(), states));
+                self.table.index.entry(key).or_default().push(self.table.groups.len() - 1);
+                self.table.used += cost;
+                self.table.groups.len() - 1
+            }
+        };
+        let mut args = reduced[groups..].iter();
+        for (state, aggregate) in self.table.groups[index].1.iter_mut().zip(&self.aggregates) {
+            let arg = if aggregate.arg.is_some() { args.next() } else { None };
+            state.update(arg)?;
+        }
+        Ok(())
+    }
+
+    /// Move the table's groups to the output, and the partitions written
+    /// meanwhile to the queue.
+    fn emit(&mut self, bpm: &mut BufferPoolManager, level: u64) -> StorageResult<()> {
+        for (values, states) in std::mem::take(&mut self.table).groups {
+            let mut row = values;
+            for (state, aggregate) in states.iter().zip(&self.aggregates) {
+                row.push(state.finish(aggregate.function)?);
+            }
+            self.output.push_back(row);
+        }
+        for mut file in self.filling.drain(..) {
+            file.finish(bpm);
+            if !file.is_empty() {
+                self.spilled += 1;
+                self.partitions.push_back((file, level + 1));
+            }
+        }
+        Ok(())
+    }
+
+    /// Aggregate the next queued partition.  `false` if there is none.
+    fn next_partition(&mut self, bpm: &mut BufferPoolManager) -> StorageResult<bool> {
+        let Some((mut file, level)) = self.partitions.pop_front() else {
+            return Ok(false);
+        };
+        let result = (|| {
+            for page in 0..file.pages().len() {
+                let mark = IoMark::new(bpm);
+                let rows = file.read_page(bpm, page);
+                self.stats.charge(bpm, mark);
+                for row in rows? {
+                    self.consume(bpm, row, level)?;
+                }
+            }
+            self.emit(bpm, level)
+        })();
+        file.free(bpm);
+        result.map(|()| true)
+    }
+
+    fn free_partitions(&mut self, bpm: &mut BufferPoolManager) {
+        for mut file in self.filling.drain(..).chain(self.partitions.drain(..).map(|(f, _)| f)) {
+            file.free(bpm);
+        }
+    }
+}
+
+impl Operator for HashAggregate {
+    fn open(&mut self, bpm: &mut BufferPoolManager) -> StorageResult<()> {
+        let most = bpm.pool_size().saturating_sub(1);
+        if self.memory_frames < 2 || self.memory_frames > most {
+            return Err(StorageError::Query(format!(
+                "An aggregate needs between 2 and {} frames of memory, not {}",
+                most, self.memory_frames
+            )));
+        }
+        self.budget = (self.memory_frames - 1) * page_capacity(bpm);
+        self.free_partitions(bpm);
+        self.table = GroupTable::default();
+        self.output.clear();
+        self.spilled = 0;
+
+        self.child.open(bpm)?;
+        while let Some(row) = self.child.next(bpm)? {
+            let reduced = self.reduce(&row)?;
+            self.consume(bpm, reduced, 0)?;
+        }
+        if self.group_by.is_empty() && self.table.groups.is_empty() {
+            // No rows: still one result, COUNT 0 and everything else NULL
+            let states = self.aggregates.iter().map(|a| State::new(a.function)).collect();
+            self.table.groups.push((Vec::new(), states));
+        }
+        self.emit(bpm, 0)
+    }
+
+    fn next(&mut self, bpm: &mut BufferPoolManager) -> StorageResult<Option<Row>> {
+        loop {
+            if let Some(row) = self.output.pop_front() {
+                self.stats.rows_produced += 1;
+                return Ok(Some(row));
+            }
+            if !self.next_partition(bpm)? {
+                return Ok(None);
+            }
+        }
+    }
+
+    fn close(&mut self, bpm: &mut BufferPoolManager) {
+        self.free_partitions(bpm);
+        self.table = GroupTable::default();
+        self.output.clear();
+        self.child.close(bpm);
+    }
+
+    fn profile(&self) -> PlanProfile {
+        let mut parts = Vec::new();
+        if !self.group_by.is_empty() {
+            parts.push(format!("group by {}", join(self.group_by.iter().map(|e| e.to_string()))));
+        }
+        parts.push(join(self.aggregates.iter().map(|a| a.to_string())));
+        parts.push(format!("{} frames", self.memory_frames));
+        if self.spilled > 0 {
+            parts.push(format!("{} partitions", self.spilled));
+        }
+        node("HashAggregate", parts.join(", "), self.stats, [&*self.child])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::config::EngineConfig;
+    use crate::storage::schema::{Column, Schema};
+    use crate::Database;
+
+    /// `t(k, n, price)` with 13 groups of `k`, NULL among them.
+    fn setup(rows: i64) -> Database {
+        let mut config = EngineConfig::default_config();
+        config.page_size = 128;
+        config.disk_capacity = 256;
+        let mut db = Database::new(config).unwrap();
+        let column = |name: &str, col_type| Column { name: name.into(), col_type, nullable: true };
+        db.create_table("t", Schema::new(vec![
+            column("k", ColumnType::VarChar(8)),
+            column("n", ColumnType::Int32),
+            column("price", ColumnType::Decimal(8, 2)),
+        ]))
+        .unwrap();
+        for i in 0..rows {
+            let k = if i % 13 == 0 { Value::Null } else { Value::VarChar(format!("g{}", i % 12)) };
+            let n = if i % 5 == 0 { Value::Null } else { Value::Int32(i as i32) };
+            db.insert("t", &[k, n, Value::Decimal(i * 25, 2)], None).unwrap();
+        }
+        db
+    }
+
+    const CALLS: [(AggregateFunction, Option<&str>); 6] = [
+        (AggregateFunction::Count, None),
+        (AggregateFunction::Count, Some("n")),
+        (AggregateFunction::Sum, Some("n")),
+        (AggregateFunction::Sum, Some("price")),
+        (AggregateFunction::Max, Some("n")),
+        (AggregateFunction::Avg, Some("n")),
+    ];
+
+    /// The same aggregates, worked out from the scanned rows.
+    fn expected(db: &mut Database) -> Vec<Row> {
+        let mut groups: Vec<(Value, Vec<Row>)> = Vec::new();
+        for (_, row) in db.scan("t").unwrap() {
+            match groups.iter_mut().find(|(k, _)| *k == row[0]) {
+                Some((_, rows)) => rows.push(row),
+                None => groups.push((row[0].clone(), vec![row])),
+            }
+        }
+        let mut out: Vec<Row> = groups
+            .into_iter()
+            .map(|(k, rows)| {
+                let ns: Vec<i64> = rows.iter().filter_map(|r| match r[1] { Value::Int32(n) => Some(n as i64), _ => None }).collect();
+                let cents: i64 = rows.iter().map(|r| match r[2] { Value::Decimal(c, _) => c, _ => 0 }).sum();
+                let sum: i64 = ns.iter().sum();
+                vec![
+                    k,
+                    Value::Int64(rows.len() as i64),
+                    Value::Int64(ns.len() as i64),
+                    if ns.is_empty() { Value::Null } else { Value::Int64(sum) },
+                    Value::Decimal(cents, 2),
+                    ns.iter().max().map_or(Value::Null, |&m| Value::Int32(m as i32)),
+                    if ns.is_empty() { Value::Null } else { Value::Float64(sum as f64 / ns.len() as f64) },
+                ]
+            })
+            .collect();
+        out.sort_by(|a, b| crate::exec::operators::cmp_keys(a, b, &[false]));
+        out
+    }
+
+    #[test]
+    fn groups_fit_or_spill_alike() {
+        let mut db = setup(150);
+        let want = expected(&mut db);
+        assert_eq!(want.len(), 13);
+        let allocated = db.buffer_pool().disk.num_allocated();
+        for frames in [2, 3, 7] {
+            let result = db.aggregate("t", &["k"], &CALLS, frames).unwrap();
+            let mut rows = result.rows;
+            rows.sort_by(|a, b| crate::exec::operators::cmp_keys(a, b, &[false]));
+            assert_eq!(rows, want, "{} frames", frames);
+            assert_eq!(db.buffer_pool().disk.num_allocated(), allocated, "temp pages are freed");
+            let plan = result.plan.unwrap();
+            if frames < 7 {
+                assert!(plan.detail.contains("partitions"), "{}", plan.detail);
+                assert!(plan.stats.pages_touched > 0);
+            }
+        }
+        // Fewer aggregates per group: the table fits
+        let plan = db.aggregate("t", &["k"], &CALLS[..1], 7).unwrap().plan.unwrap();
+        assert_eq!(plan.detail, "group by $0, count(*), 7 frames");
+        assert_eq!(plan.stats.pages_touched, 0);
+        let names: Vec<_> = db.aggregate("t", &["k"], &CALLS, 7).unwrap().columns.into_iter().map(|c| c.name).collect();
+        assert_eq!(names, ["k", "count(*)", "count(n)", "sum(n)", "sum(price)", "max(n)", "avg(n)"]);
+    }
+
+    #[test]
+    fn without_group_by_there_is_one_row() {
+        let mut db = setup(0);
+        let calls = [(AggregateFunction::Count, None), (AggregateFunction::Min, Some("price"))];
+        let result = db.aggregate("t", &[], &calls, 2).unwrap();
+        assert_eq!(result.rows, vec![vec![Value::Int64(0), Value::Null]]);
+
+        let mut db = setup(10);
+        let result = db.aggregate("t", &[], &calls, 2).unwrap();
+        assert_eq!(result.rows, vec![vec![Value::Int64(10), Value::Decimal(0, 2)]]);
+        assert_eq!(result.columns[1].col_type, ColumnType::Decimal(8, 2));
+
+        let err = db.aggregate("t", &[], &[(AggregateFunction::Avg, Some("k"))], 2).unwrap_err();
+        assert_eq!(err, StorageError::Query("Can't take AVG of VarChar(8) column 'k'".into()));
+        let err = db.aggregate("t", &[], &[(AggregateFunction::Sum, None)], 2).unwrap_err();
+        assert_eq!(err, StorageError::Query("SUM needs a column".into()));
+        let err = db.aggregate("t", &[], &calls, 8).unwrap_err();
+        assert_eq!(err.code(), "QUERY_ERROR");
+    }
+}
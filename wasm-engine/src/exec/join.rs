@@ -0,0 +1,797 @@
+//! Join operators — the four classic equi-join algorithms.
+//!
+//! Each joins `left` and `right` on `left[keys.left] = right[keys.right]`
+//! (NULL keys never match) and emits the left row followed by the right
+//! one.  They differ in how often they read their inputs and how much they
+//! keep in memory, which is the point: run the same join four ways and
+//! compare the page reads and writes in their profiles.
+//!
+//! * `NestedLoopJoin` rescans `right` once per left row.
+//! * `BlockNestedLoopJoin` fills `memory_frames - 1` pages' worth of left
+//!   rows, then rescans `right` once per block.
+//! * `HashJoin` builds a hash table on `right` and probes it with `left`.
+//!   If the table outgrows `memory_frames - 1` pages, both inputs are
+//!   split by key hash into `memory_frames - 1` partitions on temp pages
+//!   (Grace hash join) and each partition pair is joined on its own.
+//! * `SortMergeJoin` sorts both inputs on the key with `ExternalSort`,
+//!   one after the other and each in `memory_frames`, then merges them.
+//!
+//! The memory budget counts rows by their spilled size (`spill::row_size`)
+//! against whole pages, and has to leave the pool at least one frame.
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, VecDeque};
+
+use crate::exec::operators::{node, sort_order};
+use crate::exec::sort::ExternalSort;
+use crate::exec::spill::{SpillFile, encode_row, page_capacity, partition_of, row_size};
+use crate::exec::{IoMark, Operator, OperatorStats, PlanProfile, Row};
+use crate::sql::expr::{BoundExpr, compare};
+use crate::storage::buffer_pool::BufferPoolManager;
+use crate::storage::error::{StorageError, StorageResult};
+use crate::storage::schema::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinAlgorithm {
+    NestedLoop,
+    BlockNestedLoop,
+    Hash,
+    SortMerge,
+}
+
+impl JoinAlgorithm {
+    pub const ALL: [JoinAlgorithm; 4] = [
+        JoinAlgorithm::NestedLoop,
+        JoinAlgorithm::BlockNestedLoop,
+        JoinAlgorithm::Hash,
+        JoinAlgorithm::SortMerge,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            JoinAlgorithm::NestedLoop => "nested_loop",
+            JoinAlgorithm::BlockNestedLoop => "block_nested_loop",
+            JoinAlgorithm::Hash => "hash",
+            JoinAlgorithm::SortMerge => "sort_merge",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|a| a.name() == name)
+    }
+}
+
+/// Which column of each input the join matches on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JoinKeys {
+    pub left: usize,
+    pub right: usize,
+}
+
+/// A join of `left` and `right` using `algorithm`.
+pub fn join(
+    algorithm: JoinAlgorithm,
+    left: Box<dyn Operator>,
+    right: Box<dyn Operator>,
+    keys: JoinKeys,
+    memory_frames: usize,
+) -> Box<dyn Operator> {
+    match algorithm {
+        JoinAlgorithm::NestedLoop => Box::new(NestedLoopJoin::new(left, right, keys, memory_frames)),
+        JoinAlgorithm::BlockNestedLoop => {
+            Box::new(BlockNestedLoopJoin::new(left, right, keys, memory_frames))
+        }
+        JoinAlgorithm::Hash => Box::new(HashJoin::new(left, right, keys, memory_frames)),
+        JoinAlgorithm::SortMerge => Box::new(SortMergeJoin::new(left, right, keys, memory_frames)),
+    }
+}
+
+/// Bytes of rows a join may keep: `memory_frames - 1` pages, the last
+/// frame being for reading its input.  Fails unless the pool can spare the
+/// frames.
+fn row_budget(bpm: &BufferPoolManager, memory_frames: usize) -> StorageResult<usize> {
+    let most = bpm.pool_size().saturating_sub(1);
+    if memory_frames < 2 || memory_frames > most {
+        return Err(StorageError::Query(format!(
+            "A join needs between 2 and {} frames of memory, not {}",
+            most, memory_frames
+        )));
+    }
+    Ok((memory_frames - 1) * page_capacity(bpm))
+}
+
+fn keys_match(a: &Value, b: &Value) -> bool {
+    compare(a, b) == Some(Ordering::Equal)
+}
+
+fn concat(left: &[Value], right: &[Value]) -> Row {
+    left.iter().chain(right).cloned().collect()
+}
+
+fn rescan(op: &mut dyn Operator, bpm: &mut BufferPoolManager) -> StorageResult<()> {
+    op.close(bpm);
+    op.open(bpm)
+}
+
+fn keys_detail(keys: JoinKeys, memory_frames: usize) -> String {
+    format!("left ${} = right ${}, {} frames", keys.left, keys.right, memory_frames)
+}
+
+// ── Nested loop ────────────────────────────────────────────────────
+
+pub struct NestedLoopJoin {
+    left: Box<dyn Operator>,
+    right: Box<dyn Operator>,
+    keys: JoinKeys,
+    memory_frames: usize,
+    /// The left row being matched against a scan of `right`.
+    outer: Option<Row>,
+    stats: OperatorStats,
+}
+
+impl NestedLoopJoin {
+    pub fn new(left: Box<dyn Operator>, right: Box<dyn Operator>, keys: JoinKeys, memory_frames: usize) -> Self {
+        Self { left, right, keys, memory_frames, outer: None, stats: OperatorStats::default() }
+    }
+}
+
+impl Operator for NestedLoopJoin {
+    fn open(&mut self, bpm: &mut BufferPoolManager) -> StorageResult<()> {
+        row_budget(bpm, self.memory_frames)?;
+        self.outer = None;
+        self.left.open(bpm)
+    }
+
+    fn next(&mut self, bpm: &mut BufferPoolManager) -> StorageResult<Option<Row>> {
+        loop {
+            let outer = match &self.outer {
+                Some(outer) => outer,
+                None => {
+                    let Some(row) = self.left.next(bpm)? else {
+                        return Ok(None);
+                    };
+                    rescan(&mut *self.right, bpm)?;
+                    self.outer.insert(row)
+                }
+            };
+            match self.right.next(bpm)? {
+                Some(inner) if keys_match(&outer[self.keys.left], &inner[self.keys.right]) => {
+                    self.stats.rows_produced += 1;
+                    return Ok(Some(concat(outer, &inner)));
+                }
+                Some(_) => {}
+                None => self.outer = None,
+            }
+        }
+    }
+
+    fn close(&mut self, bpm: &mut BufferPoolManager) {
+        self.outer = None;
+        self.left.close(bpm);
+        self.right.close(bpm);
+    }
+
+    fn profile(&self) -> PlanProfile {
+        let detail = keys_detail(self.keys, self.memory_frames);
+        node("NestedLoopJoin", detail, self.stats, [&*self.left, &*self.right])
+    }
+}
+
+// ── Block nested loop ──────────────────────────────────────────────
+
+pub struct BlockNestedLoopJoin {
+    left: Box<dyn Operator>,
+    right: Box<dyn Operator>,
+    keys: JoinKeys,
+    memory_frames: usize,
+    budget: usize,
+    /// Left rows being matched against a scan of `right`.
+    block: Vec<Row>,
+    /// A left row that didn't fit in the last block.
+    carry: Option<Row>,
+    left_done: bool,
+    /// Whether `right` is being scanned for the current block.
+    scanning: bool,
+    pending: VecDeque<Row>,
+    blocks: u64,
+    stats: OperatorStats,
+}
+
+impl BlockNestedLoopJoin {
+    pub fn new(left: Box<dyn Operator>, right: Box<dyn Operator>, keys: JoinKeys, memory_frames: usize) -> Self {
+        Self {
+            left,
+            right,
+            keys,
+            memory_frames,
+            budget: 0,
+            block: Vec::new(),
+            carry: None,
+            left_done: false,
+            scanning: false,
+            pending: VecDeque::new(),
+            blocks: 0,
+            stats: OperatorStats::default(),
+        }
+    }
+
+    /// Fill the next block from `left`.  `false` once `left` is used up.
+    fn load_block(&mut self, bpm: &mut BufferPoolManager) -> StorageResult<bool> {
+        self.block.clear();
+        let mut used = 0;
+        if let Some(row) = self.carry.take() {
+            used += row_size(&row);
+            self.block.push(row);
+        }
+        while !self.left_done {
+            let Some(row) = self.left.next(bpm)? else {
+                self.left_done = true;
+                break;
+            };
+            let size = row_size(&row);
+            if !self.block.is_empty() && used + size > self.budget {
+                self.carry = Some(row);
+                break;
+            }
+            used += size;
+            self.block.push(row);
+        }
+        Ok(!self.block.is_empty())
+    }
+}
+
+impl Operator for BlockNestedLoopJoin {
+    fn open(&mut self, bpm: &mut BufferPoolManager) -> StorageResult<()> {
+        self.budget = row_budget(bpm, self.memory_frames)?;
+        self.block.clear();
+        self.carry = None;
+        self.left_done = false;
+        self.scanning = false;
+        self.pending.clear();
+        self.left.open(bpm)
+    }
+
+    fn next(&mut self, bpm: &mut BufferPoolManager) -> StorageResult<Option<Row>> {
+        loop {
+            if let Some(row) = self.pending.pop_front() {
+                self.stats.rows_produced += 1;
+                return Ok(Some(row));
+            }
+            if self.scanning {
+                match self.right.next(bpm)? {
+                    Some(inner) => {
+                        let key = &inner[self.keys.right];
+                        for outer in &self.block {
+                            if keys_match(&outer[self.keys.left], key) {
+                                self.pending.push_back(concat(outer, &inner));
+                            }
+                        }
+                    }
+                    None => self.scanning = false,
+                }
+                continue;
+            }
+            if !self.load_block(bpm)? {
+                return Ok(None);
+            }
+            self.blocks += 1;
+            rescan(&mut *self.right, bpm)?;
+            self.scanning = true;
+        }
+    }
+
+    fn close(&mut self, bpm: &mut BufferPoolManager) {
+        self.block.clear();
+        self.pending.clear();
+        self.left.close(bpm);
+        self.right.close(bpm);
+    }
+
+    fn profile(&self) -> PlanProfile {
+        let detail = format!("{}, {} blocks", keys_detail(self.keys, self.memory_frames), self.blocks);
+        node("BlockNestedLoopJoin", detail, self.stats, [&*self.left, &*self.right])
+    }
+}
+
+// ── Hash ───────────────────────────────────────────────────────────
+
+/// Key bytes for hashing: values `compare` finds equal hash alike, so
+/// `5`, `5.0` and `5.00` share a bucket.  Buckets are still checked with
+/// `compare`.  `None` for NULL.
+pub(crate) fn hash_key(v: &Value) -> Option<Vec<u8>> {
+    let int = |n: i128| [&[b'i'][..], &n.to_le_bytes()].concat();
+    let float = |f: f64| {
+        if f.fract() == 0.0 && f.abs() < 1e18 {
+            int(f as i128)
+        } else {
+            [&[b'f'][..], &f.to_bits().to_le_bytes()].concat()
+        }
+    };
+    Some(match v {
+        Value::Null => return None,
+        Value::Int16(n) => int(*n as i128),
+        Value::Int32(n) => int(*n as i128),
+        Value::UInt32(n) => int(*n as i128),
+        Value::Int64(n) => int(*n as i128),
+        Value::Float32(f) => float(*f as f64),
+        Value::Float64(f) => float(*f),
+        Value::Decimal(n, s) => float(*n as f64 / 10f64.powi(*s as i32)),
+        v => encode_row(std::slice::from_ref(v)),
+    })
+}
+
+/// Append to a partition, counting the I/O as the join's own.
+fn spill(stats: &mut OperatorStats, bpm: &mut BufferPoolManager, file: &mut SpillFile, row: &[Value]) -> StorageResult<()> {
+    let mark = IoMark::new(bpm);
+    let result = file.append(bpm, row);
+    stats.charge(bpm, mark);
+    result
+}
+
+enum HashPhase {
+    /// The build side fit: stream `left` past the table.
+    Probe,
+    /// Join partition pairs from temp pages, loading `build[part]` a chunk
+    /// of pages at a time and reading `probe[part]` once per chunk.
+    Partitioned {
+        build: Vec<SpillFile>,
+        probe: Vec<SpillFile>,
+        part: usize,
+        /// Next build page to load.
+        next_build_page: usize,
+        /// Next probe page to read against the loaded chunk; `None` when
+        /// no chunk is loaded.
+        next_probe_page: Option<usize>,
+    },
+    Done,
+}
+
+pub struct HashJoin {
+    left: Box<dyn Operator>,
+    right: Box<dyn Operator>,
+    keys: JoinKeys,
+    memory_frames: usize,
+    table: HashMap<Vec<u8>, Vec<Row>>,
+    phase: HashPhase,
+    pending: VecDeque<Row>,
+    /// Partitions used by the last run, 0 if the build side fit.
+    partitions: usize,
+    stats: OperatorStats,
+}
+
+impl HashJoin {
+    pub fn new(left: Box<dyn Operator>, right: Box<dyn Operator>, keys: JoinKeys, memory_frames: usize) -> Self {
+        Self {
+            left,
+            right,
+            keys,
+            memory_frames,
+            table: HashMap::new(),
+            phase: HashPhase::Done,
+            pending: VecDeque::new(),
+            partitions: 0,
+            stats: OperatorStats::default(),
+        }
+    }
+
+    /// Queue `row` joined with its matches in the table.
+    fn probe(&mut self, row: &[Value]) {
+        let key = &row[self.keys.left];
+        let Some(bucket) = hash_key(key).and_then(|k| self.table.get(&k)) else {
+            return;
+        };
+        for inner in bucket {
+            if keys_match(key, &inner[self.keys.right]) {
+                self.pending.push_back(concat(row, inner));
+            }
+        }
+    }
+
+    /// Build the table from `right`, switching to partitions if it
+    /// outgrows the budget.  Returns the build partitions if it did.
+    fn build(&mut self, bpm: &mut BufferPoolManager, budget: usize) -> StorageResult<Option<Vec<SpillFile>>> {
+        let mut parts = None;
+        let result = self.fill(bpm, budget, &mut parts);
+        if let Some(files) = &mut parts {
+            for file in files.iter_mut() {
+                if result.is_ok() {
+                    file.finish(bpm);
+                } else {
+                    file.free(bpm);
+                }
+            }
+        }
+        result.map(|()| parts)
+    }
+
+    fn fill(
+        &mut self,
+        bpm: &mut BufferPoolManager,
+        budget: usize,
+        parts: &mut Option<Vec<SpillFile>>,
+    ) -> StorageResult<()> {
+        let n = self.memory_frames - 1;
+        let mut used = 0;
+        self.right.open(bpm)?;
+        while let Some(row) = self.right.next(bpm)? {
+            let Some(key) = hash_key(&row[self.keys.right]) else {
+                continue;
+            };
+            if let Some(files) = parts {
+                spill(&mut self.stats, bpm, &mut files[partition_of(&key, 0, n)], &row)?;
+                continue;
+            }
+            used += row_size(&row);
+            self.table.entry(key).or_default().push(row);
+            if used > budget {
+                // Out of memory: move what's built so far out to partitions
+                let files = parts.insert((0..n).map(|_| SpillFile::new()).collect());
+                for (key, rows) in std::mem::take(&mut self.table) {
+                    for row in rows {
+                        spill(&mut self.stats, bpm, &mut files[partition_of(&key, 0, n)], &row)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Split `left` into partitions matching the build side's.
+    fn partition_probe(&mut self, bpm: &mut BufferPoolManager, files: &mut [SpillFile]) -> StorageResult<()> {
+        self.left.open(bpm)?;
+        while let Some(row) = self.left.next(bpm)? {
+            if let Some(key) = hash_key(&row[self.keys.left]) {
+                let part = partition_of(&key, 0, files.len());
+                spill(&mut self.stats, bpm, &mut files[part], &row)?;
+            }
+        }
+        files.iter_mut().for_each(|f| f.finish(bpm));
+        Ok(())
+    }
+
+    fn free_partitions(&mut self, bpm: &mut BufferPoolManager) {
+        if let HashPhase::Partitioned { build, probe, .. } = &mut self.phase {
+            build.iter_mut().chain(probe.iter_mut()).for_each(|f| f.free(bpm));
+        }
+    }
+
+    /// Produce the next batch of matches from the partitions.  `false`
+    /// once every partition is done.
+    fn next_partitioned(&mut self, bpm: &mut BufferPoolManager) -> StorageResult<bool> {
+        let chunk_pages = self.memory_frames - 1;
+        let HashPhase::Partitioned { build, probe, part, next_build_page, next_probe_page } = &mut self.phase else {
+            return Ok(false);
+        };
+        let mark = IoMark::new(bpm);
+        let result: StorageResult<Option<Vec<Row>>> = (|| {
+            loop {
+                if *part == build.len() {
+                    return Ok(None);
+                }
+                if let Some(page) = *next_probe_page
+                    && page < probe[*part].pages().len()
+                {
+                    *next_probe_page = Some(page + 1);
+                    return Ok(Some(probe[*part].read_page(bpm, page)?));
+                }
+                // Load the next chunk of this partition's build side, or
+                // move on to the next partition
+                self.table.clear();
+                *next_probe_page = None;
+                let pages = build[*part].pages().len();
+                if *next_build_page >= pages || probe[*part].is_empty() {
+                    *part += 1;
+                    *next_build_page = 0;
+                    continue;
+                }
+                let end = (*next_build_page + chunk_pages).min(pages);
+                for page in *next_build_page..end {
+                    for row in build[*part].read_page(bpm, page)? {
+                        let key = hash_key(&row[self.keys.right]).expect("NULL keys are never spilled");
+                        self.table.entry(key).or_default().push(row);
+                    }
+                }
+                *next_build_page = end;
+                *next_probe_page = Some(0);
+            }
+        })();
+        self.stats.charge(bpm, mark);
+        match result? {
+            Some(rows) => {
+                for row in rows {
+                    self.probe(&row);
+                }
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+impl Operator for HashJoin {
+    fn open(&mut self, bpm: &mut BufferPoolManager) -> StorageResult<()> {
+        let budget = row_budget(bpm, self.memory_frames)?;
+        self.free_partitions(bpm);
+        self.table.clear();
+        self.pending.clear();
+        self.phase = HashPhase::Done;
+        self.partitions = 0;
+        match self.build(bpm, budget)? {
+            None => {
+                self.left.open(bpm)?;
+                self.phase = HashPhase::Probe;
+            }
+            Some(build) => {
+                let mut probe: Vec<SpillFile> = (0..build.len()).map(|_| SpillFile::new()).collect();
+                self.partitions = build.len();
+                let result = self.partition_probe(bpm, &mut probe);
+                self.phase = HashPhase::Partitioned {
+                    build,
+                    probe,
+                    part: 0,
+                    next_build_page: 0,
+                    next_probe_page: None,
+                };
+                result?;
+            }
+        }
+        Ok(())
+    }
+
+    fn next(&mut self, bpm: &mut BufferPoolManager) -> StorageResult<Option<Row>> {
+        loop {
+            if let Some(row) = self.pending.pop_front() {
+                self.stats.rows_produced += 1;
+                return Ok(Some(row));
+            }
+            match self.phase {
+                HashPhase::Probe => {
+                    let Some(row) = self.left.next(bpm)? else {
+                        self.phase = HashPhase::Done;
+                        return Ok(None);
+                    };
+                    self.probe(&row);
+                }
+                HashPhase::Partitioned { .. } => {
+                    if !self.next_partitioned(bpm)? {
+                        self.free_partitions(bpm);
+                        self.phase = HashPhase::Done;
+                    }
+                }
+                HashPhase::Done => return Ok(None),
+            }
+        }
+    }
+
+    fn close(&mut self, bpm: &mut BufferPoolManager) {
+        self.free_partitions(bpm);
+        self.phase = HashPhase::Done;
+        self.table.clear();
+        self.pending.clear();
+        self.left.close(bpm);
+        self.right.close(bpm);
+    }
+
+    fn profile(&self) -> PlanProfile {
+        let mut detail = keys_detail(self.keys, self.memory_frames);
+        if self.partitions > 0 {
+            detail += &format!(", {} partitions", self.partitions);
+        }
+        node("HashJoin", detail, self.stats, [&*self.left, &*self.right])
+    }
+}
+
+// ── Sort-merge ─────────────────────────────────────────────────────
+
+pub struct SortMergeJoin {
+    left: Box<dyn Operator>,
+    right: Box<dyn Operator>,
+    keys: JoinKeys,
+    memory_frames: usize,
+    /// Consecutive right rows sharing one key, and that key.
+    group: Vec<Row>,
+    group_key: Option<Value>,
+    /// The right row after the group.
+    lookahead: Option<Row>,
+    pending: VecDeque<Row>,
+    stats: OperatorStats,
+}
+
+impl SortMergeJoin {
+    /// Sort both inputs on their key, then merge.
+    pub fn new(left: Box<dyn Operator>, right: Box<dyn Operator>, keys: JoinKeys, memory_frames: usize) -> Self {
+        let sorted = |input, key| -> Box<dyn Operator> {
+            Box::new(ExternalSort::new(input, vec![(BoundExpr::Column(key), false)], memory_frames))
+        };
+        Self {
+            left: sorted(left, keys.left),
+            right: sorted(right, keys.right),
+            keys,
+            memory_frames,
+            group: Vec::new(),
+            group_key: None,
+            lookahead: None,
+            pending: VecDeque::new(),
+            stats: OperatorStats::default(),
+        }
+    }
+
+    /// Read the next group of equal, non-NULL keys from `right`.  `false`
+    /// once `right` is used up.
+    fn next_group(&mut self, bpm: &mut BufferPoolManager) -> StorageResult<bool> {
+        self.group.clear();
+        let first = loop {
+            let row = match self.lookahead.take() {
+                Some(row) => row,
+                None => match self.right.next(bpm)? {
+                    Some(row) => row,
+                    None => return Ok(false),
+                },
+            };
+            if row[self.keys.right] != Value::Null {
+                break row;
+            }
+        };
+        let key = first[self.keys.right].clone();
+        self.group.push(first);
+        while let Some(row) = self.right.next(bpm)? {
+            if !keys_match(&row[self.keys.right], &key) {
+                self.lookahead = Some(row);
+                break;
+            }
+            self.group.push(row);
+        }
+        self.group_key = Some(key);
+        Ok(true)
+    }
+}
+
+impl Operator for SortMergeJoin {
+    fn open(&mut self, bpm: &mut BufferPoolManager) -> StorageResult<()> {
+        row_budget(bpm, self.memory_frames)?;
+        self.group.clear();
+        self.group_key = None;
+        self.lookahead = None;
+        self.pending.clear();
+        self.left.open(bpm)?;
+        self.right.open(bpm)
+    }
+
+    fn next(&mut self, bpm: &mut BufferPoolManager) -> StorageResult<Option<Row>> {
+        loop {
+            if let Some(row) = self.pending.pop_front() {
+                self.stats.rows_produced += 1;
+                return Ok(Some(row));
+            }
+            let Some(row) = self.left.next(bpm)? else {
+                return Ok(None);
+            };
+            let key = &row[self.keys.left];
+            if *key == Value::Null {
+                continue;
+            }
+            // Move the right side up to the first group not below `key`
+            while self.group_key.as_ref().is_none_or(|g| sort_order(g, key) == Ordering::Less) {
+                if !self.next_group(bpm)? {
+                    return Ok(None);
+                }
+            }
+            if keys_match(self.group_key.as_ref().unwrap(), key) {
+                self.pending.extend(self.group.iter().map(|inner| concat(&row, inner)));
+            }
+        }
+    }
+
+    fn close(&mut self, bpm: &mut BufferPoolManager) {
+        self.group.clear();
+        self.pending.clear();
+        self.left.close(bpm);
+        self.right.close(bpm);
+    }
+
+    fn profile(&self) -> PlanProfile {
+        let detail = keys_detail(self.keys, self.memory_frames);
+        node("SortMergeJoin", detail, self.stats, [&*self.left, &*self.right])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::config::EngineConfig;
+    use crate::storage::schema::*;
+    use crate::Database;
+
+    /// `a(id, k)` and `b(k, tag)` over several 128-byte pages, with some
+    /// NULL keys and keys of different integer types on each side.
+    fn setup() -> (Database, Vec<Row>) {
+        let mut config = EngineConfig::default_config();
+        config.page_size = 128;
+        config.disk_capacity = 256;
+        let mut db = Database::new(config).unwrap();
+        let column = |name: &str, col_type| Column { name: name.into(), col_type, nullable: true };
+        db.create_table("a", Schema::new(vec![column("id", ColumnType::Int32), column("k", ColumnType::Int32)]))
+            .unwrap();
+        db.create_table("b", Schema::new(vec![column("k", ColumnType::Int64), column("tag", ColumnType::VarChar(12))]))
+            .unwrap();
+        let a: Vec<Row> = (0..40)
+            .map(|i| vec![Value::Int32(i), if i % 7 == 0 { Value::Null } else { Value::Int32(i % 10) }])
+            .collect();
+        let b: Vec<Row> = (0..120)
+            .map(|i| vec![if i == 3 { Value::Null } else { Value::Int64(i % 12) }, Value::VarChar(format!("tag {}", i))])
+            .collect();
+        for row in &a {
+            db.insert("a", row, None).unwrap();
+        }
+        for row in &b {
+            db.insert("b", row, None).unwrap();
+        }
+        let mut expected = Vec::new();
+        for l in &a {
+            for r in &b {
+                if keys_match(&l[1], &r[0]) {
+                    expected.push(concat(l, r));
+                }
+            }
+        }
+        expected.sort_by(|x, y| crate::exec::operators::cmp_keys(x, y, &[false; 4]));
+        (db, expected)
+    }
+
+    fn sorted(mut rows: Vec<Row>) -> Vec<Row> {
+        rows.sort_by(|x, y| crate::exec::operators::cmp_keys(x, y, &[false; 4]));
+        rows
+    }
+
+    #[test]
+    fn algorithms_agree() {
+        let (mut db, expected) = setup();
+        assert!(expected.len() > 40);
+        let allocated = db.buffer_pool().disk.num_allocated();
+        for algorithm in JoinAlgorithm::ALL {
+            let least = if algorithm == JoinAlgorithm::SortMerge { 3 } else { 2 };
+            for frames in [least, 3, 7] {
+                let result = db.join(algorithm, ("a", "k"), ("b", "k"), frames).unwrap();
+                assert_eq!(sorted(result.rows), expected, "{} with {} frames", algorithm.name(), frames);
+                assert_eq!(db.buffer_pool().disk.num_allocated(), allocated, "temp pages are freed");
+                let names: Vec<_> = result.columns.iter().map(|c| c.name.as_str()).collect();
+                assert_eq!(names, ["a.id", "a.k", "b.k", "b.tag"]);
+            }
+        }
+        let plan = db.join(JoinAlgorithm::Hash, ("b", "k"), ("a", "k"), 3).unwrap().plan.unwrap();
+        assert_eq!(plan.detail, "left $0 = right $1, 3 frames, 2 partitions");
+        assert!(plan.stats.pages_touched > 0, "partitions are charged to the join");
+        let plan = db.join(JoinAlgorithm::Hash, ("b", "k"), ("a", "k"), 7).unwrap().plan.unwrap();
+        assert_eq!(plan.detail, "left $0 = right $1, 7 frames");
+        assert_eq!(plan.stats.pages_touched, 0);
+    }
+
+    #[test]
+    fn io_depends_on_algorithm_and_memory() {
+        let (mut db, _) = setup();
+        let mut io = |algorithm, frames| {
+            db.flush_all();
+            // `b` is the larger table: scan it once, as the outer or probe side
+            let plan = db.join(algorithm, ("b", "k"), ("a", "k"), frames).unwrap().plan.unwrap();
+            plan.total()
+        };
+        let nested = io(JoinAlgorithm::NestedLoop, 2);
+        let block_small = io(JoinAlgorithm::BlockNestedLoop, 2);
+        let block_large = io(JoinAlgorithm::BlockNestedLoop, 7);
+        let hash = io(JoinAlgorithm::Hash, 7);
+        assert!(nested.pages_touched > block_small.pages_touched, "{:?} vs {:?}", nested, block_small);
+        assert!(block_small.pages_touched > block_large.pages_touched, "{:?} vs {:?}", block_small, block_large);
+        assert!(block_large.pages_touched > hash.pages_touched, "{:?} vs {:?}", block_large, hash);
+    }
+
+    #[test]
+    fn memory_budget_is_checked() {
+        let (mut db, _) = setup();
+        for frames in [0, 1, 8] {
+            let err = db.join(JoinAlgorithm::Hash, ("a", "k"), ("b", "k"), frames).unwrap_err();
+            assert_eq!(err, StorageError::Query(format!("A join needs between 2 and 7 frames of memory, not {}", frames)));
+        }
+        assert_eq!(JoinAlgorithm::parse("sort_merge"), Some(JoinAlgorithm::SortMerge));
+        assert_eq!(JoinAlgorithm::parse("merge"), None);
+    }
+}
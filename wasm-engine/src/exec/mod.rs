@@ -0,0 +1,169 @@
+//! Query executor — iterator-model ("Volcano") physical operators.
+//!
+//! A plan is a tree of `Operator`s.  The consumer calls `open` on the root,
+//! then `next` until it returns `None`, then `close`; each operator pulls
+//! rows from its children the same way, one at a time.  Operators hold no
+//! borrow of the buffer pool between calls: it is passed to every call, so
+//! a plan can read several tables at once.
+//!
+//! Every operator keeps `OperatorStats` for its own work — rows it
+//! produced, and the page requests, disk reads and disk writes made while
+//! it (not its children) was running.  `profile` collects them into a tree
+//! so a plan's I/O can be shown node by node; a node's total including its
+//! inputs is the sum over its subtree.
+
+pub mod aggregate;
+pub mod join;
+pub mod operators;
+pub mod sort;
+pub mod spill;
+
+use std::fmt;
+
+use crate::storage::buffer_pool::BufferPoolManager;
+use crate::storage::error::StorageResult;
+use crate::storage::schema::Value;
+
+/// A row flowing between operators.
+pub type Row = Vec<Value>;
+
+pub trait Operator {
+    /// Start producing rows from the beginning.  Opening an operator again
+    /// restarts it; its stats keep accumulating.
+    fn open(&mut self, bpm: &mut BufferPoolManager) -> StorageResult<()>;
+
+    /// The next row, or `None` once the operator is exhausted.
+    fn next(&mut self, bpm: &mut BufferPoolManager) -> StorageResult<Option<Row>>;
+
+    /// Release anything held since `open`.
+    fn close(&mut self, bpm: &mut BufferPoolManager);
+
+    /// This operator's stats and its children's, as a tree.
+    fn profile(&self) -> PlanProfile;
+}
+
+/// Work done by one operator, not counting its children.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OperatorStats {
+    pub rows_produced: u64,
+    /// Buffer pool page requests, hits and misses alike.
+    pub pages_touched: u64,
+    /// Pages read from disk (buffer pool misses).
+    pub page_reads: u64,
+    /// Pages written to disk, by evictions or the operator's own spills.
+    pub page_writes: u64,
+}
+
+impl OperatorStats {
+    /// Count the I/O done since `mark`.
+    pub fn charge(&mut self, bpm: &BufferPoolManager, mark: IoMark) {
+        let now = IoMark::new(bpm);
+        self.pages_touched += now.requests - mark.requests;
+        self.page_reads += now.reads - mark.reads;
+        self.page_writes += now.writes - mark.writes;
+    }
+}
+
+impl std::ops::AddAssign for OperatorStats {
+    fn add_assign(&mut self, other: Self) {
+        self.rows_produced += other.rows_produced;
+        self.pages_touched += other.pages_touched;
+        self.page_reads += other.page_reads;
+        self.page_writes += other.page_writes;
+    }
+}
+
+/// The buffer pool and disk counters at some moment, to measure the I/O
+/// of a stretch of work.
+#[derive(Debug, Clone, Copy)]
+pub struct IoMark {
+    requests: u64,
+    reads: u64,
+    writes: u64,
+}
+
+impl IoMark {
+    pub fn new(bpm: &BufferPoolManager) -> Self {
+        Self {
+            requests: bpm.hit_count + bpm.miss_count,
+            reads: bpm.disk.read_count,
+            writes: bpm.disk.write_count,
+        }
+    }
+}
+
+/// One node of an executed plan: what the operator is, what it did, and
+/// its inputs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanProfile {
+    /// Operator name: `SeqScan`, `Filter`, `Sort`, ...
+    pub operator: &'static str,
+    /// Its arguments: a table name, a predicate, sort keys.
+    pub detail: String,
+    pub stats: OperatorStats,
+    pub children: Vec<PlanProfile>,
+}
+
+impl PlanProfile {
+    /// Stats of this node and everything below it.
+    pub fn total(&self) -> OperatorStats {
+        let mut total = self.stats;
+        for child in &self.children {
+            total += child.total();
+        }
+        total
+    }
+}
+
+/// An indented tree, one operator per line:
+///
+/// ```text
+/// Projection $1  rows=2 pages=0 reads=0 writes=0
+///   SeqScan items  rows=2 pages=1 reads=1 writes=0
+/// ```
+impl fmt::Display for PlanProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn node(f: &mut fmt::Formatter<'_>, p: &PlanProfile, depth: usize) -> fmt::Result {
+            let s = &p.stats;
+            let label = if p.detail.is_empty() {
+                p.operator.to_string()
+            } else {
+                format!("{} {}", p.operator, p.detail)
+            };
+            write!(
+                f,
+                "{:indent$}{}  rows={} pages={} reads={} writes={}",
+                "",
+                label,
+                s.rows_produced,
+                s.pages_touched,
+                s.page_reads,
+                s.page_writes,
+                indent = depth * 2
+            )?;
+            for child in &p.children {
+                writeln!(f)?;
+                node(f, child, depth + 1)?;
+            }
+            Ok(())
+        }
+        node(f, self, 0)
+    }
+}
+
+/// Run a plan to completion and collect its rows.  The plan is closed
+/// whether or not it fails.
+pub fn run(plan: &mut dyn Operator, bpm: &mut BufferPoolManager) -> StorageResult<Vec<Row>> {
+    let result = drain(plan, bpm);
+    plan.close(bpm);
+    result
+}
+
+fn drain(plan: &mut dyn Operator, bpm: &mut BufferPoolManager) -> StorageResult<Vec<Row>> {
+    plan.open(bpm)?;
+    let mut rows = Vec::new();
+    while let Some(row) = plan.next(bpm)? {
+        rows.push(row);
+    }
+    Ok(rows)
+}
@@ -0,0 +1,451 @@
+//! The basic operators: scanning a table or a list of rows, filtering,
+//! computing output columns, sorting, and LIMIT/OFFSET.
+
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+
+use crate::exec::{IoMark, Operator, OperatorStats, PlanProfile, Row};
+use crate::sql::expr::{BoundExpr, compare, is_true};
+use crate::storage::buffer_pool::BufferPoolManager;
+use crate::storage::error::StorageResult;
+use crate::storage::schema::Value;
+use crate::storage::table::TableHeap;
+use crate::storage::types::*;
+
+// ── SeqScan ────────────────────────────────────────────────────────
+
+/// Every live row of a table, in page order.  Reads one page at a time:
+/// the page's rows are decoded while it is pinned once, then handed out.
+pub struct SeqScan {
+    table: TableHeap,
+    /// Where the next page starts; `None` once the chain is done.
+    cursor: Option<RowId>,
+    page: VecDeque<Row>,
+    stats: OperatorStats,
+}
+
+impl SeqScan {
+    /// Scan `table` as it is now: rows appended to the chain later may or
+    /// may not be seen.
+    pub fn new(table: &TableHeap) -> Self {
+        Self { table: table.clone(), cursor: None, page: VecDeque::new(), stats: OperatorStats::default() }
+    }
+}
+
+impl Operator for SeqScan {
+    fn open(&mut self, bpm: &mut BufferPoolManager) -> StorageResult<()> {
+        bpm.take_error();
+        self.cursor = Some(RowId { page_id: self.table.first_page_id, slot_id: 0 });
+        self.page.clear();
+        Ok(())
+    }
+
+    fn next(&mut self, bpm: &mut BufferPoolManager) -> StorageResult<Option<Row>> {
+        loop {
+            if let Some(row) = self.page.pop_front() {
+                self.stats.rows_produced += 1;
+                return Ok(Some(row));
+            }
+            let Some(start) = self.cursor else {
+                return Ok(None);
+            };
+            let mark = IoMark::new(bpm);
+            let mut rows = self.table.iter_from(bpm, start);
+            while let Some((_, row)) = rows.next_in_page() {
+                self.page.push_back(row);
+            }
+            self.cursor = rows.position();
+            drop(rows);
+            self.stats.charge(bpm, mark);
+            // A page that couldn't be read ends the chain early; don't
+            // pass that off as the end of the table
+            if let Some(e) = bpm.take_error() {
+                self.cursor = None;
+                return Err(e);
+            }
+        }
+    }
+
+    fn close(&mut self, _bpm: &mut BufferPoolManager) {
+        self.cursor = None;
+        self.page.clear();
+    }
+
+    fn profile(&self) -> PlanProfile {
+        leaf("SeqScan", self.table.name.clone(), self.stats)
+    }
+}
+
+// ── Values ─────────────────────────────────────────────────────────
+
+/// A fixed list of rows, such as the single empty row a SELECT without
+/// FROM evaluates its expressions against.
+pub struct Values {
+    rows: Vec<Row>,
+    pos: usize,
+    stats: OperatorStats,
+}
+
+impl Values {
+    pub fn new(rows: Vec<Row>) -> Self {
+        Self { rows, pos: 0, stats: OperatorStats::default() }
+    }
+}
+
+impl Operator for Values {
+    fn open(&mut self, _bpm: &mut BufferPoolManager) -> StorageResult<()> {
+        self.pos = 0;
+        Ok(())
+    }
+
+    fn next(&mut self, _bpm: &mut BufferPoolManager) -> StorageResult<Option<Row>> {
+        let Some(row) = self.rows.get(self.pos) else {
+            return Ok(None);
+        };
+        self.pos += 1;
+        self.stats.rows_produced += 1;
+        Ok(Some(row.clone()))
+    }
+
+    fn close(&mut self, _bpm: &mut BufferPoolManager) {}
+
+    fn profile(&self) -> PlanProfile {
+        leaf("Values", format!("{} rows", self.rows.len()), self.stats)
+    }
+}
+
+// ── Filter ─────────────────────────────────────────────────────────
+
+/// The child's rows for which `predicate` is true (not false or NULL).
+pub struct Filter {
+    child: Box<dyn Operator>,
+    predicate: BoundExpr,
+    stats: OperatorStats,
+}
+
+impl Filter {
+    pub fn new(child: Box<dyn Operator>, predicate: BoundExpr) -> Self {
+        Self { child, predicate, stats: OperatorStats::default() }
+    }
+}
+
+impl Operator for Filter {
+    fn open(&mut self, bpm: &mut BufferPoolManager) -> StorageResult<()> {
+        self.child.open(bpm)
+    }
+
+    fn next(&mut self, bpm: &mut BufferPoolManager) -> StorageResult<Option<Row>> {
+        while let Some(row) = self.child.next(bpm)? {
+            if is_true(&self.predicate.eval(&row)?) {
+                self.stats.rows_produced += 1;
+                return Ok(Some(row));
+            }
+        }
+        Ok(None)
+    }
+
+    fn close(&mut self, bpm: &mut BufferPoolManager) {
+        self.child.close(bpm);
+    }
+
+    fn profile(&self) -> PlanProfile {
+        node("Filter", self.predicate.to_string(), self.stats, [&*self.child])
+    }
+}
+
+// ── Projection ─────────────────────────────────────────────────────
+
+/// One output row per input row, computed by `exprs`.
+pub struct Projection {
+    child: Box<dyn Operator>,
+    exprs: Vec<BoundExpr>,
+    stats: OperatorStats,
+}
+
+impl Projection {
+    pub fn new(child: Box<dyn Operator>, exprs: Vec<BoundExpr>) -> Self {
+        Self { child, exprs, stats: OperatorStats::default() }
+    }
+}
+
+impl Operator for Projection {
+    fn open(&mut self, bpm: &mut BufferPoolManager) -> StorageResult<()> {
+        self.child.open(bpm)
+    }
+
+    fn next(&mut self, bpm: &mut BufferPoolManager) -> StorageResult<Option<Row>> {
+        let Some(row) = self.child.next(bpm)? else {
+            return Ok(None);
+        };
+        let out = self.exprs.iter().map(|e| e.eval(&row)).collect::<StorageResult<Row>>()?;
+        self.stats.rows_produced += 1;
+        Ok(Some(out))
+    }
+
+    fn close(&mut self, bpm: &mut BufferPoolManager) {
+        self.child.close(bpm);
+    }
+
+    fn profile(&self) -> PlanProfile {
+        node("Projection", join(self.exprs.iter().map(ToString::to_string)), self.stats, [&*self.child])
+    }
+}
+
+// ── Limit ──────────────────────────────────────────────────────────
+
+/// Skip `offset` rows, then pass on at most `limit`.  Stops pulling from
+/// its child as soon as the limit is reached.
+pub struct Limit {
+    child: Box<dyn Operator>,
+    limit: Option<u64>,
+    offset: u64,
+    /// Rows passed on since `open`.
+    emitted: u64,
+    skipped: bool,
+    stats: OperatorStats,
+}
+
+impl Limit {
+    pub fn new(child: Box<dyn Operator>, limit: Option<u64>, offset: u64) -> Self {
+        Self { child, limit, offset, emitted: 0, skipped: false, stats: OperatorStats::default() }
+    }
+}
+
+impl Operator for Limit {
+    fn open(&mut self, bpm: &mut BufferPoolManager) -> StorageResult<()> {
+        self.emitted = 0;
+        self.skipped = false;
+        self.child.open(bpm)
+    }
+
+    fn next(&mut self, bpm: &mut BufferPoolManager) -> StorageResult<Option<Row>> {
+        if self.limit.is_some_and(|n| self.emitted >= n) {
+            return Ok(None);
+        }
+        if !self.skipped {
+            self.skipped = true;
+            for _ in 0..self.offset {
+                if self.child.next(bpm)?.is_none() {
+                    return Ok(None);
+                }
+            }
+        }
+        let row = self.child.next(bpm)?;
+        if row.is_some() {
+            self.emitted += 1;
+            self.stats.rows_produced += 1;
+        }
+        Ok(row)
+    }
+
+    fn close(&mut self, bpm: &mut BufferPoolManager) {
+        self.child.close(bpm);
+    }
+
+    fn profile(&self) -> PlanProfile {
+        let mut detail = self.limit.map_or("all".to_string(), |n| n.to_string());
+        if self.offset > 0 {
+            detail += &format!(" offset {}", self.offset);
+        }
+        node("Limit", detail, self.stats, [&*self.child])
+    }
+}
+
+// ── Sort ───────────────────────────────────────────────────────────
+
+/// The child's rows ordered by `keys` (each ascending, or descending when
+/// its flag is set), NULLs first.  Reads its whole input into memory on
+/// `open`; the sort is stable.
+pub struct Sort {
+    child: Box<dyn Operator>,
+    keys: Vec<(BoundExpr, bool)>,
+    sorted: VecDeque<Row>,
+    stats: OperatorStats,
+}
+
+impl Sort {
+    pub fn new(child: Box<dyn Operator>, keys: Vec<(BoundExpr, bool)>) -> Self {
+        Self { child, keys, sorted: VecDeque::new(), stats: OperatorStats::default() }
+    }
+}
+
+impl Operator for Sort {
+    fn open(&mut self, bpm: &mut BufferPoolManager) -> StorageResult<()> {
+        self.child.open(bpm)?;
+        let mut keyed = Vec::new();
+        while let Some(row) = self.child.next(bpm)? {
+            let key = self.keys.iter().map(|(e, _)| e.eval(&row)).collect::<StorageResult<Row>>()?;
+            keyed.push((key, row));
+        }
+        let descending: Vec<bool> = self.keys.iter().map(|(_, desc)| *desc).collect();
+        keyed.sort_by(|(a, _), (b, _)| cmp_keys(a, b, &descending));
+        self.sorted = keyed.into_iter().map(|(_, row)| row).collect();
+        Ok(())
+    }
+
+    fn next(&mut self, _bpm: &mut BufferPoolManager) -> StorageResult<Option<Row>> {
+        let row = self.sorted.pop_front();
+        if row.is_some() {
+            self.stats.rows_produced += 1;
+        }
+        Ok(row)
+    }
+
+    fn close(&mut self, bpm: &mut BufferPoolManager) {
+        self.sorted.clear();
+        self.child.close(bpm);
+    }
+
+    fn profile(&self) -> PlanProfile {
+        node("Sort", sort_keys_detail(&self.keys), self.stats, [&*self.child])
+    }
+}
+
+/// Compare two sort keys column by column.
+pub fn cmp_keys(a: &[Value], b: &[Value], descending: &[bool]) -> Ordering {
+    a.iter()
+        .zip(b)
+        .zip(descending)
+        .map(|((a, b), desc)| {
+            let ord = sort_order(a, b);
+            if *desc { ord.reverse() } else { ord }
+        })
+        .find(|o| o.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// ORDER BY order for one value: NULLs first, then by value.
+pub fn sort_order(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Null, _) => Ordering::Less,
+        (_, Value::Null) => Ordering::Greater,
+        _ => compare(a, b).unwrap_or(Ordering::Equal),
+    }
+}
+
+/// `$1 DESC, $0`
+pub fn sort_keys_detail(keys: &[(BoundExpr, bool)]) -> String {
+    join(keys.iter().map(|(e, desc)| if *desc { format!("{} DESC", e) } else { e.to_string() }))
+}
+
+// ── Helpers ────────────────────────────────────────────────────────
+
+fn leaf(operator: &'static str, detail: String, stats: OperatorStats) -> PlanProfile {
+    PlanProfile { operator, detail, stats, children: Vec::new() }
+}
+
+pub(crate) fn node<'a>(
+    operator: &'static str,
+    detail: String,
+    stats: OperatorStats,
+    children: impl IntoIterator<Item = &'a dyn Operator>,
+) -> PlanProfile {
+    PlanProfile { operator, detail, stats, children: children.into_iter().map(|c| c.profile()).collect() }
+}
+
+pub(crate) fn join(items: impl Iterator<Item = String>) -> String {
+    items.collect::<Vec<_>>().join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exec::run;
+    use crate::sql::ast::BinaryOp;
+    use crate::storage::config::EngineConfig;
+    use crate::storage::schema::*;
+    use crate::Database;
+
+    /// A table of (id, name) rows spread over several 128-byte pages.
+    fn setup(rows: i32) -> (Database, TableHeap) {
+        let mut config = EngineConfig::default_config();
+        config.page_size = 128;
+        let mut db = Database::new(config).unwrap();
+        db.create_table("t", Schema::new(vec![
+            Column { name: "id".into(), col_type: ColumnType::Int32, nullable: false },
+            Column { name: "name".into(), col_type: ColumnType::VarChar(20), nullable: true },
+        ]))
+        .unwrap();
+        for i in 0..rows {
+            let name = if i % 3 == 0 { Value::Null } else { Value::VarChar(format!("row {}", i)) };
+            db.insert("t", &[Value::Int32(i), name], None).unwrap();
+        }
+        let table = db.table("t").unwrap().clone();
+        (db, table)
+    }
+
+    fn col(i: usize) -> Box<BoundExpr> {
+        Box::new(BoundExpr::Column(i))
+    }
+
+    fn int(n: i64) -> Box<BoundExpr> {
+        Box::new(BoundExpr::Literal(Value::Int64(n)))
+    }
+
+    #[test]
+    fn scan_reads_each_page_once() {
+        let (mut db, table) = setup(30);
+        let pages = db.table_page_ids("t").unwrap().len() as u64;
+        assert!(pages > 2);
+        let mut scan = SeqScan::new(&table);
+        let rows = run(&mut scan, db.buffer_pool_mut()).unwrap();
+        assert_eq!(rows.len(), 30);
+        assert_eq!(rows[7], vec![Value::Int32(7), Value::VarChar("row 7".into())]);
+        let stats = scan.profile().stats;
+        assert_eq!(stats.rows_produced, 30);
+        assert_eq!(stats.pages_touched, pages);
+
+        // Reopening rescans, and the stats keep counting
+        run(&mut scan, db.buffer_pool_mut()).unwrap();
+        assert_eq!(scan.profile().stats.pages_touched, 2 * pages);
+    }
+
+    #[test]
+    fn filter_sort_limit_project() {
+        let (mut db, table) = setup(30);
+        // SELECT id * 10 FROM t WHERE id >= 10 AND name IS NOT NULL
+        //     ORDER BY id DESC LIMIT 3 OFFSET 1
+        let predicate = BoundExpr::Binary(
+            BinaryOp::And,
+            Box::new(BoundExpr::Binary(BinaryOp::GtEq, col(0), int(10))),
+            Box::new(BoundExpr::IsNull { expr: col(1), negated: true }),
+        );
+        let scan = Box::new(SeqScan::new(&table));
+        let filter = Box::new(Filter::new(scan, predicate));
+        let sort = Box::new(Sort::new(filter, vec![(BoundExpr::Column(0), true)]));
+        let limit = Box::new(Limit::new(sort, Some(3), 1));
+        let mut plan = Projection::new(limit, vec![BoundExpr::Binary(BinaryOp::Mul, col(0), int(10))]);
+
+        let rows = run(&mut plan, db.buffer_pool_mut()).unwrap();
+        assert_eq!(rows, vec![vec![Value::Int64(280)], vec![Value::Int64(260)], vec![Value::Int64(250)]]);
+
+        let profile = plan.profile();
+        let counts: Vec<(&str, u64)> = std::iter::successors(Some(&profile), |p| p.children.first())
+            .map(|p| (p.operator, p.stats.rows_produced))
+            .collect();
+        assert_eq!(counts, [("Projection", 3), ("Limit", 3), ("Sort", 4), ("Filter", 14), ("SeqScan", 30)]);
+        assert_eq!(profile.total().pages_touched, db.table_page_ids("t").unwrap().len() as u64);
+        assert_eq!(
+            profile.to_string().lines().take(3).collect::<Vec<_>>(),
+            [
+                "Projection ($0 * 10)  rows=3 pages=0 reads=0 writes=0",
+                "  Limit 3 offset 1  rows=3 pages=0 reads=0 writes=0",
+                "    Sort $0 DESC  rows=4 pages=0 reads=0 writes=0",
+            ]
+        );
+    }
+
+    #[test]
+    fn limit_stops_pulling_early() {
+        let (mut db, table) = setup(30);
+        let mut plan = Limit::new(Box::new(SeqScan::new(&table)), Some(2), 0);
+        assert_eq!(run(&mut plan, db.buffer_pool_mut()).unwrap().len(), 2);
+        assert_eq!(plan.profile().children[0].stats.pages_touched, 1);
+
+        let mut values = Values::new(vec![vec![Value::Int32(1)], vec![Value::Null]]);
+        assert_eq!(run(&mut values, db.buffer_pool_mut()).unwrap().len(), 2);
+        assert_eq!(values.profile().detail, "2 rows");
+        assert_eq!(sort_order(&Value::Null, &Value::Int32(i32::MIN)), Ordering::Less);
+    }
+}
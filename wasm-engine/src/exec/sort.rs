@@ -0,0 +1,404 @@
+//! External merge sort — ORDER BY for inputs larger than memory.
+//!
+//! With a budget of `B` frames, the sort reads `B` pages' worth of rows
+//! (measured by `spill::row_size`), sorts them in memory and writes them
+//! out as a sorted run on temp pages.  Runs are then merged `B - 1` at a
+//! time — one page of each run in memory, plus one for output — into
+//! longer runs, pass after pass, until at most `B - 1` remain.  The last
+//! pass streams straight to the consumer instead of writing a run.  An
+//! input that fits in `B` pages is sorted in memory and never spilled.
+//!
+//! The sort is stable: runs hold rows in input order, and ties between
+//! runs go to the earlier one.  `trace` records every run and merge so the
+//! passes can be replayed.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::ops::Range;
+
+use crate::exec::operators::{cmp_keys, node, sort_keys_detail};
+use crate::exec::spill::{SpillFile, page_capacity, row_size};
+use crate::exec::{IoMark, Operator, OperatorStats, PlanProfile, Row};
+use crate::sql::expr::BoundExpr;
+use crate::storage::buffer_pool::BufferPoolManager;
+use crate::storage::error::{StorageError, StorageResult};
+use crate::storage::schema::Value;
+use crate::storage::types::PageId;
+
+/// One sorted run, as written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortRun {
+    pub id: usize,
+    /// 0 for runs made from the input, `n` for the output of merge pass `n`.
+    pub pass: usize,
+    pub rows: u64,
+    /// Its temp pages, in order.  They are freed once the run is merged,
+    /// so later runs may reuse them.
+    pub pages: Vec<PageId>,
+}
+
+/// Runs merged into one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortMerge {
+    pub inputs: Vec<usize>,
+    /// The run written, or `None` for the final merge, which streams its
+    /// rows out.
+    pub output: Option<usize>,
+}
+
+/// What the last `open` of an `ExternalSort` did.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SortTrace {
+    pub memory_frames: usize,
+    pub input_rows: u64,
+    /// Every run, by id.  Empty if the input was sorted in memory.
+    pub runs: Vec<SortRun>,
+    /// Merge passes in order, each a list of merges.  A run left over at
+    /// the end of a pass is carried to the next one unmerged.
+    pub passes: Vec<Vec<SortMerge>>,
+}
+
+/// One line per run and per pass:
+///
+/// ```text
+/// run 0: 9 rows on pages 20 21
+/// run 1: 3 rows on pages 22
+/// pass 1: 0 1 -> out
+/// ```
+impl fmt::Display for SortTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.runs.is_empty() {
+            return write!(f, "{} rows sorted in memory", self.input_rows);
+        }
+        let ids = |ids: &[usize]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(" ");
+        let mut lines = Vec::new();
+        for run in &self.runs {
+            let pages: Vec<usize> = run.pages.iter().map(|&p| p as usize).collect();
+            lines.push(format!("run {}: {} rows on pages {}", run.id, run.rows, ids(&pages)));
+        }
+        for (i, pass) in self.passes.iter().enumerate() {
+            let merges: Vec<String> = pass
+                .iter()
+                .map(|m| match m.output {
+                    Some(out) => format!("{} -> {}", ids(&m.inputs), out),
+                    None => format!("{} -> out", ids(&m.inputs)),
+                })
+                .collect();
+            lines.push(format!("pass {}: {}", i + 1, merges.join(", ")));
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// A run being merged: its rows come in one page at a time.
+struct RunCursor {
+    id: usize,
+    file: SpillFile,
+    next_page: usize,
+    /// Rows of the page last read, with their sort keys.
+    page: VecDeque<(Row, Row)>,
+}
+
+impl RunCursor {
+    fn new(id: usize, file: SpillFile) -> Self {
+        Self { id, file, next_page: 0, page: VecDeque::new() }
+    }
+
+    /// Read the next page if the last one is used up.
+    fn fill(
+        &mut self,
+        bpm: &mut BufferPoolManager,
+        keys: &[(BoundExpr, bool)],
+        stats: &mut OperatorStats,
+    ) -> StorageResult<()> {
+        if !self.page.is_empty() || self.next_page == self.file.pages().len() {
+            return Ok(());
+        }
+        let mark = IoMark::new(bpm);
+        let rows = self.file.read_page(bpm, self.next_page);
+        stats.charge(bpm, mark);
+        self.next_page += 1;
+        for row in rows? {
+            self.page.push_back((sort_key(keys, &row)?, row));
+        }
+        Ok(())
+    }
+}
+
+fn sort_key(keys: &[(BoundExpr, bool)], row: &[Value]) -> StorageResult<Row> {
+    keys.iter().map(|(e, _)| e.eval(row)).collect()
+}
+
+/// Take the smallest next row among `cursors`, the earliest run winning
+/// ties.  `None` once they are all used up.
+fn pop_min(
+    cursors: &mut [RunCursor],
+    bpm: &mut BufferPoolManager,
+    keys: &[(BoundExpr, bool)],
+    descending: &[bool],
+    stats: &mut OperatorStats,
+) -> StorageResult<Option<Row>> {
+    let mut best: Option<usize> = None;
+    for i in 0..cursors.len() {
+        cursors[i].fill(bpm, keys, stats)?;
+        let Some((key, _)) = cursors[i].page.front() else {
+            continue;
+        };
+        if best.is_none_or(|b| cmp_keys(key, &cursors[b].page[0].0, descending).is_lt()) {
+            best = Some(i);
+        }
+    }
+    Ok(best.and_then(|b| cursors[b].page.pop_front()).map(|(_, row)| row))
+}
+
+pub struct ExternalSort {
+    child: Box<dyn Operator>,
+    keys: Vec<(BoundExpr, bool)>,
+    descending: Vec<bool>,
+    memory_frames: usize,
+    /// The whole input, when it fit in memory.
+    sorted: VecDeque<Row>,
+    /// Runs to merge in the current pass, or in the final merge.
+    runs: Vec<RunCursor>,
+    /// Runs written by the current pass.
+    merged: Vec<RunCursor>,
+    trace: SortTrace,
+    stats: OperatorStats,
+}
+
+impl ExternalSort {
+    pub fn new(child: Box<dyn Operator>, keys: Vec<(BoundExpr, bool)>, memory_frames: usize) -> Self {
+        let descending = keys.iter().map(|(_, desc)| *desc).collect();
+        Self {
+            child,
+            keys,
+            descending,
+            memory_frames,
+            sorted: VecDeque::new(),
+            runs: Vec::new(),
+            merged: Vec::new(),
+            trace: SortTrace::default(),
+            stats: OperatorStats::default(),
+        }
+    }
+
+    pub fn trace(&self) -> &SortTrace {
+        &self.trace
+    }
+
+    /// Sort `batch` and write it out as a new run.
+    fn write_run(&mut self, bpm: &mut BufferPoolManager, batch: &mut Vec<(Row, Row)>) -> StorageResult<()> {
+        batch.sort_by(|(a, _), (b, _)| cmp_keys(a, b, &self.descending));
+        let id = self.trace.runs.len();
+        self.runs.push(RunCursor::new(id, SpillFile::new()));
+        let file = &mut self.runs.last_mut().unwrap().file;
+        let mark = IoMark::new(bpm);
+        let result = batch.drain(..).try_for_each(|(_, row)| file.append(bpm, &row));
+        file.finish(bpm);
+        self.stats.charge(bpm, mark);
+        result?;
+        self.record_run(id, 0);
+        Ok(())
+    }
+
+    fn record_run(&mut self, id: usize, pass: usize) {
+        let cursor = self.runs.iter().chain(&self.merged).find(|c| c.id == id).unwrap();
+        let (rows, pages) = (cursor.file.len(), cursor.file.pages().to_vec());
+        self.trace.runs.push(SortRun { id, pass, rows, pages });
+    }
+
+    /// Merge `self.runs[range]` into a new run in `self.merged`.
+    fn merge_runs(&mut self, bpm: &mut BufferPoolManager, range: Range<usize>, pass: usize) -> StorageResult<()> {
+        let id = self.trace.runs.len();
+        self.merged.push(RunCursor::new(id, SpillFile::new()));
+        let result = loop {
+            let row = match pop_min(&mut self.runs[range.clone()], bpm, &self.keys, &self.descending, &mut self.stats) {
+                Ok(Some(row)) => row,
+                Ok(None) => break Ok(()),
+                Err(e) => break Err(e),
+            };
+            let mark = IoMark::new(bpm);
+            let appended = self.merged.last_mut().unwrap().file.append(bpm, &row);
+            self.stats.charge(bpm, mark);
+            if let Err(e) = appended {
+                break Err(e);
+            }
+        };
+        self.merged.last_mut().unwrap().file.finish(bpm);
+        result?;
+        self.record_run(id, pass);
+        let inputs = self.runs[range].iter().map(|c| c.id).collect();
+        self.trace.passes.last_mut().unwrap().push(SortMerge { inputs, output: Some(id) });
+        Ok(())
+    }
+
+    /// Merge runs `fan_in` at a time until at most `fan_in` are left.
+    fn merge_passes(&mut self, bpm: &mut BufferPoolManager, fan_in: usize) -> StorageResult<()> {
+        while self.runs.len() > fan_in {
+            let pass = self.trace.passes.len() + 1;
+            self.trace.passes.push(Vec::new());
+            let mut start = 0;
+            while self.runs.len() - start > 1 {
+                let end = (start + fan_in).min(self.runs.len());
+                self.merge_runs(bpm, start..end, pass)?;
+                start = end;
+            }
+            // Free the merged runs, carrying over a lone one at the end
+            let carried = self.runs.split_off(start);
+            for mut cursor in self.runs.drain(..) {
+                cursor.file.free(bpm);
+            }
+            self.runs = std::mem::take(&mut self.merged);
+            self.runs.extend(carried);
+        }
+        let inputs = self.runs.iter().map(|c| c.id).collect();
+        self.trace.passes.push(vec![SortMerge { inputs, output: None }]);
+        Ok(())
+    }
+
+    fn free_runs(&mut self, bpm: &mut BufferPoolManager) {
+        for mut cursor in self.runs.drain(..).chain(self.merged.drain(..)) {
+            cursor.file.free(bpm);
+        }
+    }
+}
+
+impl Operator for ExternalSort {
+    fn open(&mut self, bpm: &mut BufferPoolManager) -> StorageResult<()> {
+        let most = bpm.pool_size().saturating_sub(1);
+        if self.memory_frames < 3 || self.memory_frames > most {
+            return Err(StorageError::Query(format!(
+                "A sort needs between 3 and {} frames of memory, not {}",
+                most, self.memory_frames
+            )));
+        }
+        self.free_runs(bpm);
+        self.sorted.clear();
+        self.trace = SortTrace { memory_frames: self.memory_frames, ..SortTrace::default() };
+        let budget = self.memory_frames * page_capacity(bpm);
+
+        self.child.open(bpm)?;
+        let mut batch = Vec::new();
+        let mut used = 0;
+        while let Some(row) = self.child.next(bpm)? {
+            self.trace.input_rows += 1;
+            let size = row_size(&row);
+            if !batch.is_empty() && used + size > budget {
+                self.write_run(bpm, &mut batch)?;
+                used = 0;
+            }
+            used += size;
+            batch.push((sort_key(&self.keys, &row)?, row));
+        }
+        if self.runs.is_empty() {
+            batch.sort_by(|(a, _), (b, _)| cmp_keys(a, b, &self.descending));
+            self.sorted = batch.into_iter().map(|(_, row)| row).collect();
+            return Ok(());
+        }
+        if !batch.is_empty() {
+            self.write_run(bpm, &mut batch)?;
+        }
+        self.merge_passes(bpm, self.memory_frames - 1)
+    }
+
+    fn next(&mut self, bpm: &mut BufferPoolManager) -> StorageResult<Option<Row>> {
+        let row = match self.sorted.pop_front() {
+            Some(row) => Some(row),
+            None => pop_min(&mut self.runs, bpm, &self.keys, &self.descending, &mut self.stats)?,
+        };
+        if row.is_some() {
+            self.stats.rows_produced += 1;
+        }
+        Ok(row)
+    }
+
+    fn close(&mut self, bpm: &mut BufferPoolManager) {
+        self.sorted.clear();
+        self.free_runs(bpm);
+        self.child.close(bpm);
+    }
+
+    fn profile(&self) -> PlanProfile {
+        let mut detail = format!("{}, {} frames", sort_keys_detail(&self.keys), self.memory_frames);
+        if !self.trace.runs.is_empty() {
+            let runs = self.trace.runs.iter().filter(|r| r.pass == 0).count();
+            detail += &format!(", {} runs, {} passes", runs, self.trace.passes.len());
+        }
+        node("ExternalSort", detail, self.stats, [&*self.child])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::config::EngineConfig;
+    use crate::storage::schema::{Column, ColumnType, Schema};
+    use crate::Database;
+
+    /// `t(k, id)` with `rows` rows, keys repeating out of order.
+    fn setup(rows: i32) -> (Database, Vec<Row>) {
+        let mut config = EngineConfig::default_config();
+        config.page_size = 128;
+        config.disk_capacity = 256;
+        let mut db = Database::new(config).unwrap();
+        let column = |name: &str| Column { name: name.into(), col_type: ColumnType::Int32, nullable: true };
+        db.create_table("t", Schema::new(vec![column("k"), column("id")])).unwrap();
+        let rows: Vec<Row> = (0..rows)
+            .map(|i| vec![if i % 11 == 0 { Value::Null } else { Value::Int32(i * 37 % 50) }, Value::Int32(i)])
+            .collect();
+        for row in &rows {
+            db.insert("t", row, None).unwrap();
+        }
+        (db, rows)
+    }
+
+    #[test]
+    fn runs_are_merged_in_passes() {
+        let (mut db, mut expected) = setup(200);
+        let allocated = db.buffer_pool().disk.num_allocated();
+        let (result, trace) = db.sort("t", &[("k", true)], 3).unwrap();
+        expected.sort_by(|a, b| cmp_keys(&a[..1], &b[..1], &[true]));
+        assert_eq!(result.rows, expected, "sorted, ties in input order");
+
+        let first: Vec<&SortRun> = trace.runs.iter().filter(|r| r.pass == 0).collect();
+        assert!(first.len() > 4, "{}", trace);
+        assert_eq!(first.iter().map(|r| r.rows).sum::<u64>(), 200);
+        assert!(first.iter().all(|r| r.pages.len() <= 4), "{}", trace);
+        assert!(trace.passes.len() > 2, "{}", trace);
+        for (i, pass) in trace.passes.iter().enumerate() {
+            for merge in pass {
+                assert!(merge.inputs.len() <= 2, "{}", trace);
+                assert_eq!(merge.output.is_none(), i == trace.passes.len() - 1);
+                if let Some(out) = merge.output {
+                    let rows: u64 = merge.inputs.iter().map(|&id| trace.runs[id].rows).sum();
+                    assert_eq!(trace.runs[out].rows, rows);
+                    assert_eq!(trace.runs[out].pass, i + 1);
+                }
+            }
+        }
+        assert_eq!(db.buffer_pool().disk.num_allocated(), allocated, "temp pages are freed");
+
+        // More memory: longer runs, fewer passes
+        let (_, wide) = db.sort("t", &[("k", true)], 7).unwrap();
+        assert!(wide.runs.iter().filter(|r| r.pass == 0).count() < first.len());
+        assert!(wide.passes.len() < trace.passes.len());
+        let plan = result.plan.unwrap();
+        assert!(plan.detail.starts_with("$0 DESC, 3 frames"), "{}", plan.detail);
+        assert!(plan.stats.pages_touched > 0);
+    }
+
+    #[test]
+    fn small_inputs_sort_in_memory() {
+        let (mut db, rows) = setup(20);
+        let (result, trace) = db.sort("t", &[("k", false), ("id", true)], 3).unwrap();
+        assert!(trace.runs.is_empty());
+        assert_eq!(trace.to_string(), "20 rows sorted in memory");
+        assert_eq!(result.plan.unwrap().stats.pages_touched, 0);
+        let mut expected = rows;
+        expected.sort_by(|a, b| cmp_keys(a, b, &[false, true]));
+        assert_eq!(result.rows, expected);
+
+        let err = db.sort("t", &[("k", false)], 2).unwrap_err();
+        assert_eq!(err, StorageError::Query("A sort needs between 3 and 7 frames of memory, not 2".into()));
+    }
+}
@@ -0,0 +1,227 @@
+//! Spill files — rows an operator can't keep in memory, written to
+//! temporary pages.
+//!
+//! A spill file is a list of `PageType::Temp` pages from
+//! `BufferPoolManager::new_page`, each a slotted page holding one encoded
+//! row per slot.  The page being filled stays pinned, like an output
+//! buffer; once full it is unpinned and left to the buffer pool, which
+//! writes it to disk if it is evicted.  Temp pages are not logged: they
+//! only live as long as the query, and `free` returns them to the disk.
+//!
+//! Rows are self-describing, so no schema is needed to read them back:
+//! each value is written in its index key form (a type tag, then the
+//! bytes) at its natural width, and NULL as the single byte `NULL_TAG`.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::exec::Row;
+use crate::storage::buffer_pool::BufferPoolManager;
+use crate::storage::error::{StorageError, StorageResult};
+use crate::storage::page;
+use crate::storage::schema::*;
+use crate::storage::types::*;
+
+const NULL_TAG: u8 = 0xFF;
+
+#[derive(Debug, Default)]
+pub struct SpillFile {
+    pages: Vec<PageId>,
+    /// The last page while it is being filled.
+    pinned: Option<(PageId, FrameId)>,
+    rows: u64,
+}
+
+impl SpillFile {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a row, starting a new page when the current one is full.
+    /// Fails with `PageFull` for a row larger than an empty page.
+    pub fn append(&mut self, bpm: &mut BufferPoolManager, row: &[Value]) -> StorageResult<()> {
+        let bytes = encode_row(row);
+        if let Some((_, frame_id)) = self.pinned
+            && page::insert_tuple(bpm.frame_data_mut(frame_id), &bytes).is_ok()
+        {
+            self.rows += 1;
+            return Ok(());
+        }
+        self.finish(bpm);
+        let (page_id, frame_id) = bpm.new_page()?;
+        page::page_init(bpm.frame_data_mut(frame_id), page_id, PageType::Temp);
+        self.pages.push(page_id);
+        self.pinned = Some((page_id, frame_id));
+        page::insert_tuple(bpm.frame_data_mut(frame_id), &bytes)?;
+        self.rows += 1;
+        Ok(())
+    }
+
+    /// Unpin the page being filled.  Appending again starts a new page.
+    pub fn finish(&mut self, bpm: &mut BufferPoolManager) {
+        if let Some((page_id, _)) = self.pinned.take() {
+            bpm.unpin_page(page_id, true);
+        }
+    }
+
+    /// The rows on the file's `index`th page.
+    pub fn read_page(&self, bpm: &mut BufferPoolManager, index: usize) -> StorageResult<Vec<Row>> {
+        let page_id = self.pages[index];
+        let frame_id = match self.pinned {
+            Some((pid, fid)) if pid == page_id => fid,
+            _ => bpm.fetch_page(page_id)?,
+        };
+        let data = bpm.frame_data(frame_id);
+        let rows = (0..page::slot_count(data))
+            .filter_map(|slot| page::get_tuple(data, slot))
+            .map(decode_row)
+            .collect::<Option<Vec<Row>>>();
+        if self.pinned.is_none_or(|(pid, _)| pid != page_id) {
+            bpm.unpin_page(page_id, false);
+        }
+        rows.ok_or_else(|| StorageError::Io { page_id, message: "Unreadable spilled row".into() })
+    }
+
+    /// Every row, in the order written.
+    pub fn read_all(&self, bpm: &mut BufferPoolManager) -> StorageResult<Vec<Row>> {
+        let mut rows = Vec::new();
+        for i in 0..self.pages.len() {
+            rows.extend(self.read_page(bpm, i)?);
+        }
+        Ok(rows)
+    }
+
+    /// Return every page to the disk, written or not.
+    pub fn free(&mut self, bpm: &mut BufferPoolManager) {
+        self.finish(bpm);
+        for page_id in self.pages.drain(..) {
+            bpm.delete_page(page_id);
+        }
+        self.rows = 0;
+    }
+
+    pub fn pages(&self) -> &[PageId] {
+        &self.pages
+    }
+
+    pub fn len(&self) -> u64 {
+        self.rows
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows == 0
+    }
+}
+
+/// Bytes `row` takes in a spill page, its slot included.  Operators use
+/// it to measure their memory against a budget of whole pages.
+pub fn row_size(row: &[Value]) -> usize {
+    SLOT_SIZE + row.iter().map(value_size).sum::<usize>()
+}
+
+/// Which of `partitions` spill files the rows with hash key `key` go to.
+/// A different `seed` splits the same keys differently, for partitioning a
+/// partition again.
+pub fn partition_of(key: &[u8], seed: u64, partitions: usize) -> usize {
+    let mut hasher = DefaultHasher::new();
+    seed.hash(&mut hasher);
+    key.hash(&mut hasher);
+    (hasher.finish() % partitions as u64) as usize
+}
+
+/// Bytes of rows, as `row_size` counts them, one spill page holds.
+pub fn page_capacity(bpm: &BufferPoolManager) -> usize {
+    bpm.page_size() as usize - PAGE_HEADER_SIZE
+}
+
+fn value_size(v: &Value) -> usize {
+    1 + match v {
+        Value::Null => 0,
+        Value::Bool(_) => 1,
+        Value::Int16(_) => 2,
+        Value::Int32(_) | Value::UInt32(_) | Value::Float32(_) | Value::Date(_) => 4,
+        Value::Int64(_) | Value::Float64(_) | Value::Timestamp(_) => 8,
+        Value::Decimal(..) => 9,
+        Value::Uuid(_) => 16,
+        Value::VarChar(s) => 2 + s.len(),
+        Value::Blob(b) => 2 + b.len(),
+    }
+}
+
+pub(crate) fn encode_row(row: &[Value]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(row_size(row) - SLOT_SIZE);
+    for v in row {
+        match encode_key(v, value_size(v)) {
+            Some(key) => out.extend_from_slice(&key),
+            None => out.push(NULL_TAG),
+        }
+    }
+    out
+}
+
+/// `None` if the bytes don't hold a whole row.
+fn decode_row(mut bytes: &[u8]) -> Option<Row> {
+    let mut row = Vec::new();
+    while let Some(&tag) = bytes.first() {
+        let len = match tag {
+            NULL_TAG => 1,
+            // VarChar and Blob: length-prefixed
+            4 | 5 => 3 + u16::from_le_bytes([*bytes.get(1)?, *bytes.get(2)?]) as usize,
+            9 => 10,
+            t => 1 + ColumnType::from_tag(t, 0)?.fixed_size(),
+        };
+        let value = bytes.get(..len)?;
+        row.push(if tag == NULL_TAG { Value::Null } else { decode_key(value) });
+        bytes = &bytes[len..];
+    }
+    Some(row)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::disk::DiskManager;
+
+    #[test]
+    fn rows_round_trip_through_temp_pages() {
+        let mut bpm = BufferPoolManager::new(4, DiskManager::new(128, 64));
+        let mut file = SpillFile::new();
+        let rows: Vec<Row> = (0..40)
+            .map(|i| {
+                vec![
+                    Value::Int32(i),
+                    if i % 2 == 0 { Value::Null } else { Value::VarChar(format!("v{}", i)) },
+                    Value::Decimal(i as i64 * 25, 2),
+                ]
+            })
+            .collect();
+        for row in &rows {
+            file.append(&mut bpm, row).unwrap();
+        }
+        file.finish(&mut bpm);
+        assert_eq!(file.len(), 40);
+        assert!(file.pages().len() > 4, "more pages than frames");
+        assert!(bpm.disk.write_count > 0, "evicted pages were written");
+        let page_id = file.pages()[0];
+        let fid = bpm.fetch_page(page_id).unwrap();
+        assert_eq!(page::page_type(bpm.frame_data(fid)), PageType::Temp);
+        bpm.unpin_page(page_id, false);
+
+        assert_eq!(file.read_all(&mut bpm).unwrap(), rows);
+        let allocated = bpm.disk.num_allocated();
+        let pages = file.pages().len();
+        file.free(&mut bpm);
+        assert_eq!(bpm.disk.num_allocated(), allocated - pages as u32);
+        assert!(file.is_empty());
+    }
+
+    #[test]
+    fn oversized_rows_are_refused() {
+        let mut bpm = BufferPoolManager::new(4, DiskManager::new(64, 16));
+        let mut file = SpillFile::new();
+        let err = file.append(&mut bpm, &[Value::VarChar("x".repeat(60))]).unwrap_err();
+        assert_eq!(err.code(), "PAGE_FULL");
+        file.free(&mut bpm);
+        assert_eq!(row_size(&[Value::Null, Value::Int64(1), Value::VarChar("ab".into())]), 4 + 1 + 9 + 5);
+    }
+}
@@ -1,21 +1,27 @@
 use wasm_bindgen::prelude::*;
 
+pub mod database;
+pub mod exec;
+pub mod json;
+pub mod repl;
+pub mod sql;
 pub mod storage;
+pub mod wire;
 
-use std::collections::HashMap;
-use storage::config::EngineConfig;
-use storage::disk::DiskManager;
-use storage::buffer_pool::BufferPoolManager;
-use storage::table::TableHeap;
+pub use database::Database;
+
+use exec::join::JoinAlgorithm;
+use json::Json;
+use storage::error::{StorageError, StorageResult};
+use storage::fault::{FaultKind, Trigger};
 use storage::schema::*;
-use storage::types::*;
 use storage::snapshot;
+use wire::*;
 
+/// JSON adapter over `Database` for the frontend.
 #[wasm_bindgen]
 pub struct StorageEngine {
-    config: EngineConfig,
-    bpm: BufferPoolManager,
-    tables: HashMap<String, TableHeap>,
+    db: Database,
 }
 
 #[wasm_bindgen]
@@ -24,33 +30,36 @@ impl StorageEngine {
     ///
     /// Config JSON format:
     /// ```json
-    /// { "page_size": 128, "pool_size": 8, "disk_capacity": 64, "overflow_threshold": 64 }
+    /// { "page_size": 128, "pool_size": 8, "disk_capacity": 64, "overflow_threshold": 64,
+    ///   "replacement_policy": "LRU", "seek_us": 4000, "seq_transfer_us": 20,
+    ///   "random_transfer_us": 100, "write_penalty_us": 50 }
     /// ```
+    /// `replacement_policy` is one of `LRU`, `Clock`, `LRU-K` (or `LRU-<k>`),
+    /// `2Q`, `ARC`, `FIFO`; it defaults to `LRU`.  The `*_us` keys set the
+    /// simulated disk latencies (see `io_cost`).
     #[wasm_bindgen(constructor)]
     pub fn new(config_json: &str) -> Result<StorageEngine, JsValue> {
-        let mut config: EngineConfig = parse_config(config_json)
-            .map_err(|e| JsValue::from_str(&e))?;
-        config.validate().map_err(|e| JsValue::from_str(&e))?;
+        let config = parse_config(config_json)?;
+        Ok(Self { db: Database::new(config)? })
+    }
 
-        let disk = DiskManager::new(config.page_size, config.disk_capacity);
-        let bpm = BufferPoolManager::new(config.pool_size, disk);
+    /// Reopen a database from bytes produced by `export_image`.  The
+    /// configuration comes from the image's superblock; the log starts
+    /// empty.
+    pub fn from_image(bytes: &[u8]) -> Result<StorageEngine, JsValue> {
+        Ok(Self { db: Database::from_image(bytes)? })
+    }
 
-        Ok(Self {
-            config,
-            bpm,
-            tables: HashMap::new(),
-        })
+    /// Flush everything and return the whole disk as bytes (header,
+    /// allocation bitmap, then every page).  Refused while transactions are
+    /// running, since their changes would be saved without their log.
+    pub fn export_image(&mut self) -> Result<Vec<u8>, JsValue> {
+        Ok(self.db.export_image()?)
     }
 
     /// Get the current engine configuration as JSON.
     pub fn config(&self) -> String {
-        format!(
-            r#"{{"page_size":{},"pool_size":{},"disk_capacity":{},"overflow_threshold":{}}}"#,
-            self.config.page_size,
-            self.config.pool_size,
-            self.config.disk_capacity,
-            self.config.overflow_threshold
-        )
+        config_to_json(self.db.config()).to_string()
     }
 
     /// Create a table. Schema JSON:
@@ -61,491 +70,482 @@ impl StorageEngine {
     /// ]}
     /// ```
     pub fn create_table(&mut self, name: &str, schema_json: &str) -> Result<bool, JsValue> {
-        if self.tables.contains_key(name) {
-            return Err(JsValue::from_str(&format!("Table '{}' already exists", name)));
+        if self.db.table(name).is_ok() {
+            return Err(StorageError::TableExists(name.into()).into());
         }
-
-        let schema = parse_schema(schema_json)
-            .map_err(|e| JsValue::from_str(&e))?;
-
-        let table = TableHeap::create(
-            name.to_string(),
-            schema,
-            self.config.overflow_threshold,
-            &mut self.bpm,
-        ).ok_or_else(|| JsValue::from_str("Failed to allocate page for table"))?;
-
-        self.tables.insert(name.to_string(), table);
+        let schema = parse_schema(schema_json)?;
+        self.db.create_table(name, schema)?;
         Ok(true)
     }
 
-    /// Drop a table and free its pages.
-    pub fn drop_table(&mut self, name: &str) -> bool {
-        if let Some(table) = self.tables.remove(name) {
-            let page_ids = table.page_ids(&mut self.bpm);
-            for pid in page_ids {
-                self.bpm.delete_page(pid);
-            }
-            true
-        } else {
-            false
-        }
+    /// Drop a table and free its pages.  Returns `false` if there was no
+    /// such table; refused while transactions are active.
+    pub fn drop_table(&mut self, name: &str) -> Result<bool, JsValue> {
+        Ok(self.db.drop_table(name)?)
     }
 
     /// List table names as JSON array.
     pub fn list_tables(&self) -> String {
-        let names: Vec<String> = self.tables.keys()
-            .map(|k| format!("\"{}\"", k))
-            .collect();
-        format!("[{}]", names.join(","))
+        Json::from(self.db.table_names()).to_string()
     }
 
     /// Insert a row. Values JSON:
     /// ```json
     /// [42, "Alice", 3.14, true, null]
     /// ```
+    /// Runs inside `txn` if given, otherwise in its own auto-committed
+    /// transaction.  A failed insert is rolled back without aborting `txn`.
     /// Returns RowId as "page_id:slot_id" string.
-    pub fn insert(&mut self, table_name: &str, values_json: &str) -> Result<String, JsValue> {
-        let table = self.tables.get(table_name)
-            .ok_or_else(|| JsValue::from_str(&format!("Table '{}' not found", table_name)))?;
-        let schema = table.schema.clone();
-
-        let values = parse_values(values_json, &schema)
-            .map_err(|e| JsValue::from_str(&e))?;
-
-        let table = self.tables.get_mut(table_name).unwrap();
-        let row_id = table.insert(&mut self.bpm, &values)
-            .ok_or_else(|| JsValue::from_str("Insert failed: no space"))?;
+    pub fn insert(
+        &mut self,
+        table_name: &str,
+        values_json: &str,
+        txn: Option<u32>,
+    ) -> Result<String, JsValue> {
+        let values = parse_values(values_json, &self.db.table(table_name)?.schema)?;
+        let row_id = self.db.insert(table_name, &values, txn)?;
+        Ok(format!("{}:{}", row_id.page_id, row_id.slot_id))
+    }
 
+    /// Insert a row whose Blob columns take raw bytes instead of JSON:
+    /// `blobs` holds one `Uint8Array` (or `null`) per Blob column, in column
+    /// order, and `values_json` has `null` at those positions.
+    pub fn insert_with_blobs(
+        &mut self,
+        table_name: &str,
+        values_json: &str,
+        blobs: js_sys::Array,
+        txn: Option<u32>,
+    ) -> Result<String, JsValue> {
+        let blobs = blobs
+            .iter()
+            .enumerate()
+            .map(|(i, blob)| {
+                if blob.is_null() || blob.is_undefined() {
+                    return Ok(None);
+                }
+                blob.dyn_ref::<js_sys::Uint8Array>()
+                    .map(|bytes| Some(bytes.to_vec()))
+                    .ok_or_else(|| StorageError::Parse(format!("blobs[{}] must be a Uint8Array or null", i)))
+            })
+            .collect::<StorageResult<Vec<_>>>()?;
+        let values = parse_values_with_blobs(values_json, &self.db.table(table_name)?.schema, blobs)?;
+        let row_id = self.db.insert(table_name, &values, txn)?;
         Ok(format!("{}:{}", row_id.page_id, row_id.slot_id))
     }
 
     /// Get a row by "page_id:slot_id". Returns values as JSON array.
     pub fn get(&mut self, table_name: &str, row_id_str: &str) -> Result<String, JsValue> {
-        let row_id = parse_row_id(row_id_str)
-            .map_err(|e| JsValue::from_str(&e))?;
-        let table = self.tables.get(table_name)
-            .ok_or_else(|| JsValue::from_str(&format!("Table '{}' not found", table_name)))?;
-
-        let values = table.get(&mut self.bpm, row_id)
-            .ok_or_else(|| JsValue::from_str("Row not found"))?;
-
-        Ok(values_to_json(&values))
+        let row_id = parse_row_id(row_id_str)?;
+        let values = self.db.get(table_name, row_id)?;
+        Ok(values_to_json(&values).to_string())
     }
 
-    /// Delete a row by "page_id:slot_id".
-    pub fn delete(&mut self, table_name: &str, row_id_str: &str) -> Result<bool, JsValue> {
-        let row_id = parse_row_id(row_id_str)
-            .map_err(|e| JsValue::from_str(&e))?;
-        let table = self.tables.get_mut(table_name)
-            .ok_or_else(|| JsValue::from_str(&format!("Table '{}' not found", table_name)))?;
+    /// Delete a row by "page_id:slot_id", inside `txn` if given, otherwise
+    /// auto-committed.
+    pub fn delete(
+        &mut self,
+        table_name: &str,
+        row_id_str: &str,
+        txn: Option<u32>,
+    ) -> Result<bool, JsValue> {
+        let row_id = parse_row_id(row_id_str)?;
+        Ok(self.db.delete(table_name, row_id, txn)?)
+    }
 
-        Ok(table.delete(&mut self.bpm, row_id))
+    /// Replace the values of the row at "page_id:slot_id", inside `txn` if
+    /// given, otherwise auto-committed.  The RowId stays the same even when
+    /// the new values no longer fit on the row's page.  Returns `false` if
+    /// the row doesn't exist.
+    pub fn update(
+        &mut self,
+        table_name: &str,
+        row_id_str: &str,
+        values_json: &str,
+        txn: Option<u32>,
+    ) -> Result<bool, JsValue> {
+        let row_id = parse_row_id(row_id_str)?;
+        let values = parse_values(values_json, &self.db.table(table_name)?.schema)?;
+        Ok(self.db.update(table_name, row_id, &values, txn)?)
     }
 
     /// Scan all rows. Returns JSON array of { "row_id": "p:s", "values": [...] }.
     pub fn scan(&mut self, table_name: &str) -> Result<String, JsValue> {
-        let table = self.tables.get(table_name)
-            .ok_or_else(|| JsValue::from_str(&format!("Table '{}' not found", table_name)))?;
+        Ok(rows_to_json(&self.db.scan(table_name)?).to_string())
+    }
 
-        let rows = table.scan(&mut self.bpm);
+    /// Scan one page of results: up to `limit` rows starting at the cursor
+    /// `from` ("page_id:slot_id", or empty for the start of the table).
+    /// Returns `{ "rows": [...], "next": "p:s" | null }`, where `next` is
+    /// the cursor for the following call and `null` means the scan is done.
+    pub fn scan_from(
+        &mut self,
+        table_name: &str,
+        from: &str,
+        limit: u32,
+    ) -> Result<String, JsValue> {
+        let from = if from.is_empty() { None } else { Some(parse_row_id(from)?) };
+        let (rows, next) = self.db.scan_from(table_name, from, limit as usize)?;
+        let next = next.map_or(Json::Null, row_id_to_json);
+        Ok(Json::object([("rows", rows_to_json(&rows)), ("next", next)]).to_string())
+    }
 
-        let mut entries: Vec<String> = Vec::new();
-        for (row_id, values) in &rows {
-            entries.push(format!(
-                r#"{{"row_id":"{}:{}","values":{}}}"#,
-                row_id.page_id, row_id.slot_id, values_to_json(values)
-            ));
-        }
-        Ok(format!("[{}]", entries.join(",")))
+    /// Vacuum a table: compact fragmented pages, trim trailing tombstone
+    /// slots, and free pages left empty.  Refused while any transaction is
+    /// running, since an abort could need the tombstones back.  Returns a
+    /// JSON report:
+    /// ```json
+    /// { "pages_scanned": 6, "pages_compacted": 2, "slots_trimmed": 3,
+    ///   "pages_freed": 1, "bytes_reclaimed": 140 }
+    /// ```
+    pub fn vacuum(&mut self, table_name: &str) -> Result<String, JsValue> {
+        Ok(vacuum_report_to_json(&self.db.vacuum(table_name)?).to_string())
+    }
+
+    // ── Indexes ─────────────────────────────────────────────────────
+
+    /// Build a B+tree index on `column`.  Existing rows are indexed
+    /// immediately; later inserts and deletes keep it up to date.  Returns
+    /// the index's root page id.
+    pub fn create_index(&mut self, table_name: &str, column: &str) -> Result<u32, JsValue> {
+        Ok(self.db.create_index(table_name, column)?)
+    }
+
+    /// Build an extendible hash index on `column` for equality lookups.
+    /// Returns the directory page id.
+    pub fn create_hash_index(&mut self, table_name: &str, column: &str) -> Result<u32, JsValue> {
+        Ok(self.db.create_hash_index(table_name, column)?)
+    }
+
+    /// Rows whose hash-indexed `column` equals `key_json`.  Returns the
+    /// same shape as `scan`.
+    pub fn hash_lookup(
+        &mut self,
+        table_name: &str,
+        column: &str,
+        key_json: &str,
+    ) -> Result<String, JsValue> {
+        let key = self.parse_key(table_name, column, key_json)?.unwrap_or(Value::Null);
+        Ok(rows_to_json(&self.db.hash_lookup(table_name, column, &key)?).to_string())
+    }
+
+    /// Rows whose indexed `column` equals `key_json` (a JSON value).
+    /// Returns the same shape as `scan`.
+    pub fn index_lookup(
+        &mut self,
+        table_name: &str,
+        column: &str,
+        key_json: &str,
+    ) -> Result<String, JsValue> {
+        self.index_range(table_name, column, key_json, key_json)
+    }
+
+    /// Rows whose indexed `column` lies in `[low_json, high_json]`, in key
+    /// order.  Pass `null` for an open bound.  Returns the same shape as
+    /// `scan`.
+    pub fn index_range(
+        &mut self,
+        table_name: &str,
+        column: &str,
+        low_json: &str,
+        high_json: &str,
+    ) -> Result<String, JsValue> {
+        let low = self.parse_key(table_name, column, low_json)?;
+        let high = self.parse_key(table_name, column, high_json)?;
+        let rows = self.db.index_range(table_name, column, low.as_ref(), high.as_ref())?;
+        Ok(rows_to_json(&rows).to_string())
+    }
+
+    // ── Transactions ────────────────────────────────────────────────
+
+    /// Start a transaction.  Pass the returned ID to `insert`/`delete`.
+    /// Once it has written, other writes fail with `TXNS_ACTIVE` until it
+    /// commits or aborts.
+    pub fn begin(&mut self) -> u32 {
+        self.db.begin()
+    }
+
+    /// Commit a transaction, making its changes durable.
+    pub fn commit(&mut self, txn: u32) -> Result<bool, JsValue> {
+        self.db.commit(txn)?;
+        Ok(true)
+    }
+
+    /// Abort a transaction, undoing every insert, delete and page allocation
+    /// it made.
+    pub fn abort(&mut self, txn: u32) -> Result<bool, JsValue> {
+        self.db.abort(txn)?;
+        Ok(true)
+    }
+
+    /// IDs of running transactions as a JSON array.
+    pub fn active_transactions(&self) -> String {
+        Json::from(self.db.active_transactions()).to_string()
+    }
+
+    // ── SQL ─────────────────────────────────────────────────────────
+
+    /// Run one SQL statement: CREATE/DROP TABLE, INSERT, SELECT (with
+    /// WHERE, ORDER BY, LIMIT and OFFSET), UPDATE or DELETE.  Row changes
+    /// join `txn` if given and are undone as a whole if any row fails.
+    /// Returns `{ "columns": [...], "rows": [[...]], "rows_affected": n,
+    /// "plan": {...} }`; columns are written as in `table_schema`, and only
+    /// a SELECT has columns and a plan.  The plan is a tree of `{ "operator",
+    /// "detail", "rows_produced", "pages_touched", "page_reads",
+    /// "page_writes", "children" }` with each operator's own I/O.
+    pub fn execute_sql(&mut self, sql: &str, txn: Option<u32>) -> Result<String, JsValue> {
+        Ok(query_result_to_json(&self.db.execute_sql(sql, txn)?).to_string())
+    }
+
+    /// Join two tables on `left_table.left_column = right_table.right_column`
+    /// with `algorithm` (`nested_loop`, `block_nested_loop`, `hash` or
+    /// `sort_merge`), holding at most `memory_frames` frames of rows.
+    /// Returns a query result as from `execute_sql`, with columns named
+    /// `table.column`; summing the plan's `page_reads` and `page_writes`
+    /// gives the join's I/O, to compare algorithms.
+    pub fn join(
+        &mut self,
+        algorithm: &str,
+        left_table: &str,
+        left_column: &str,
+        right_table: &str,
+        right_column: &str,
+        memory_frames: u32,
+    ) -> Result<String, JsValue> {
+        let algorithm = JoinAlgorithm::parse(algorithm)
+            .ok_or_else(|| StorageError::Parse(format!("Unknown join algorithm: {}", algorithm)))?;
+        let result = self.db.join(
+            algorithm,
+            (left_table, left_column),
+            (right_table, right_column),
+            memory_frames as usize,
+        )?;
+        Ok(query_result_to_json(&result).to_string())
+    }
+
+    /// Every row of `table`, ordered by `column`, using an external merge
+    /// sort in `memory_frames` frames.  Returns `{ "result": {...},
+    /// "trace": {...} }`: a query result as from `execute_sql`, and the
+    /// sort's runs — `{ "id", "pass", "rows", "pages" }`, pass 0 for runs
+    /// cut from the input — and merge passes, each a list of `{ "inputs":
+    /// [run ids], "output": run id }` where the final merge's output is
+    /// null.  Runs are empty if the rows fit in memory.
+    pub fn sort_table(
+        &mut self,
+        table: &str,
+        column: &str,
+        descending: bool,
+        memory_frames: u32,
+    ) -> Result<String, JsValue> {
+        let (result, trace) = self.db.sort(table, &[(column, descending)], memory_frames as usize)?;
+        Ok(Json::object([
+            ("result", query_result_to_json(&result)),
+            ("trace", sort_trace_to_json(&trace)),
+        ])
+        .to_string())
+    }
+
+    /// Aggregate `table` without sending its rows over: `group_by` is a
+    /// JSON array of column names, `aggregates` a JSON array like
+    /// `["count(*)", "sum(price)", "avg(price)"]` of COUNT, SUM, MIN, MAX
+    /// and AVG calls.  Returns a query result as from `execute_sql`, one
+    /// row per group with the group columns and then the aggregates.  The
+    /// hash table holds at most `memory_frames` frames of groups before
+    /// spilling partitions to temp pages.
+    pub fn aggregate(
+        &mut self,
+        table: &str,
+        group_by: &str,
+        aggregates: &str,
+        memory_frames: u32,
+    ) -> Result<String, JsValue> {
+        let group_by = parse_names(group_by)?;
+        let aggregates = parse_names(aggregates)?;
+        let group_by: Vec<&str> = group_by.iter().map(String::as_str).collect();
+        let aggregates = aggregates
+            .iter()
+            .map(|a| parse_aggregate(a))
+            .collect::<StorageResult<Vec<_>>>()?;
+        let result = self.db.aggregate(table, &group_by, &aggregates, memory_frames as usize)?;
+        Ok(query_result_to_json(&result).to_string())
     }
 
     // ── Snapshot methods for visualization ──────────────────────────
 
     /// Snapshot the buffer pool state as binary.
     pub fn snapshot_buffer_pool(&self) -> Vec<u8> {
-        snapshot::snapshot_buffer_pool(&self.bpm)
+        snapshot::snapshot_buffer_pool(self.db.buffer_pool())
     }
 
     /// Snapshot disk overview as binary.
     pub fn snapshot_disk(&self) -> Vec<u8> {
-        snapshot::snapshot_disk(&self.bpm)
+        snapshot::snapshot_disk(self.db.buffer_pool())
     }
 
     /// Snapshot a single page's details as binary.
     pub fn snapshot_page(&mut self, page_id: u32) -> Option<Vec<u8>> {
-        snapshot::snapshot_page(&mut self.bpm, page_id)
+        snapshot::snapshot_page(self.db.buffer_pool_mut(), page_id)
     }
 
     /// Snapshot table metadata as binary.
     pub fn snapshot_table(&mut self, table_name: &str) -> Option<Vec<u8>> {
-        let table = self.tables.get(table_name)?;
-        let page_ids = table.page_ids(&mut self.bpm);
-        let schema = table.schema.clone();
-        let table = self.tables.get(table_name)?;
+        let page_ids = self.db.table_page_ids(table_name).ok()?;
+        let table = self.db.table(table_name).ok()?;
         Some(snapshot::snapshot_table(
             &table.name,
-            &schema,
+            &table.schema,
             table.first_page_id,
             table.row_count,
             &page_ids,
         ))
     }
 
+    /// Snapshot the B+tree on `column` as binary.
+    pub fn snapshot_index(&mut self, table_name: &str, column: &str) -> Option<Vec<u8>> {
+        let table = self.db.table(table_name).ok()?;
+        let col = column_index(&table.schema, column).ok()?;
+        let root = table.index_on(col)?.root_page_id;
+        snapshot::snapshot_index(self.db.buffer_pool_mut(), root)
+    }
+
+    /// Snapshot the hash index on `column` (directory and buckets) as binary.
+    pub fn snapshot_hash_index(&mut self, table_name: &str, column: &str) -> Option<Vec<u8>> {
+        let table = self.db.table(table_name).ok()?;
+        let col = column_index(&table.schema, column).ok()?;
+        let dir = table.hash_index_on(col)?.directory_page_id;
+        snapshot::snapshot_hash_index(self.db.buffer_pool_mut(), dir)
+    }
+
+    /// Snapshot a table's free space map as binary.
+    pub fn snapshot_fsm(&mut self, table_name: &str) -> Option<Vec<u8>> {
+        let root = self.db.table(table_name).ok()?.fsm_page_id;
+        Some(snapshot::snapshot_fsm(self.db.buffer_pool_mut(), root))
+    }
+
     /// Get table schema as JSON for the frontend.
     pub fn table_schema(&self, table_name: &str) -> Option<String> {
-        let table = self.tables.get(table_name)?;
-        Some(schema_to_json(&table.schema))
+        let table = self.db.table(table_name).ok()?;
+        Some(schema_to_json(&table.schema).to_string())
     }
 
     /// Flush all dirty pages in the buffer pool.
     pub fn flush_all(&mut self) {
-        self.bpm.flush_all();
+        self.db.flush_all();
     }
 
     /// Flush a specific page.
     pub fn flush_page(&mut self, page_id: u32) -> bool {
-        self.bpm.flush_page(page_id)
+        self.db.flush_page(page_id)
     }
-}
 
-// ── JSON parsing helpers (minimal, no serde dependency) ────────────
+    // ── Fault injection ─────────────────────────────────────────────
 
-fn parse_config(json: &str) -> Result<EngineConfig, String> {
-    let json = json.trim();
-    let mut config = EngineConfig::default_config();
-
-    if let Some(v) = extract_u32(json, "page_size") { config.page_size = v; }
-    if let Some(v) = extract_u32(json, "pool_size") { config.pool_size = v; }
-    if let Some(v) = extract_u32(json, "disk_capacity") { config.disk_capacity = v; }
-    if let Some(v) = extract_u32(json, "overflow_threshold") { config.overflow_threshold = v; }
-
-    Ok(config)
-}
-
-fn extract_u32(json: &str, key: &str) -> Option<u32> {
-    let pattern = format!("\"{}\"", key);
-    let idx = json.find(&pattern)?;
-    let rest = &json[idx + pattern.len()..];
-    let colon = rest.find(':')?;
-    let after_colon = rest[colon + 1..].trim_start();
-    let end = after_colon.find(|c: char| !c.is_ascii_digit())
-        .unwrap_or(after_colon.len());
-    after_colon[..end].parse().ok()
-}
-
-fn parse_schema(json: &str) -> Result<Schema, String> {
-    // Parse a simple JSON schema format
-    // Look for "columns" array
-    let columns_start = json.find("\"columns\"")
-        .ok_or("Missing 'columns' key")?;
-    let rest = &json[columns_start..];
-    let arr_start = rest.find('[').ok_or("Missing columns array")?;
-    let arr_end = find_matching_bracket(rest, arr_start).ok_or("Malformed columns array")?;
-    let arr = &rest[arr_start + 1..arr_end];
-
-    let mut columns = Vec::new();
-    let mut pos = 0;
-    while pos < arr.len() {
-        if let Some(obj_start) = arr[pos..].find('{') {
-            let abs_start = pos + obj_start;
-            let obj_end = find_matching_brace(arr, abs_start)
-                .ok_or("Malformed column object")?;
-            let obj = &arr[abs_start..=obj_end];
-            columns.push(parse_column(obj)?);
-            pos = obj_end + 1;
-        } else {
-            break;
-        }
+    /// Seed the generator that picks where torn writes stop and which bit
+    /// flips.
+    pub fn set_fault_seed(&mut self, seed: u32) {
+        self.db.set_fault_seed(seed as u64);
     }
 
-    if columns.is_empty() {
-        return Err("Schema must have at least one column".into());
-    }
-    Ok(Schema::new(columns))
-}
-
-fn parse_column(json: &str) -> Result<Column, String> {
-    let name = extract_string(json, "name").ok_or("Missing column name")?;
-    let nullable = extract_bool(json, "nullable").unwrap_or(false);
-
-    // Type can be a string like "Int32" or an object like {"VarChar": 255}
-    let type_idx = json.find("\"type\"").ok_or("Missing column type")?;
-    let rest = &json[type_idx + 6..];
-    let colon = rest.find(':').ok_or("Missing colon after type")?;
-    let after_colon = rest[colon + 1..].trim_start();
-
-    let col_type = if after_colon.starts_with('"') {
-        // Simple type string
-        let end = after_colon[1..].find('"').ok_or("Malformed type string")?;
-        let type_str = &after_colon[1..1 + end];
-        match type_str {
-            "Int32" => ColumnType::Int32,
-            "UInt32" => ColumnType::UInt32,
-            "Float64" => ColumnType::Float64,
-            "Bool" => ColumnType::Bool,
-            _ => return Err(format!("Unknown type: {}", type_str)),
-        }
-    } else if after_colon.starts_with('{') {
-        // Object type like {"VarChar": 255}
-        let end = after_colon.find('}').ok_or("Malformed type object")?;
-        let inner = &after_colon[1..end];
-        if inner.contains("VarChar") {
-            let max = extract_number_after_colon(inner).unwrap_or(255);
-            ColumnType::VarChar(max as u16)
-        } else if inner.contains("Blob") {
-            let max = extract_number_after_colon(inner).unwrap_or(255);
-            ColumnType::Blob(max as u16)
-        } else {
-            return Err("Unknown type object".into());
-        }
-    } else {
-        return Err("Could not parse column type".into());
-    };
-
-    Ok(Column { name, col_type, nullable })
-}
-
-fn parse_values(json: &str, schema: &Schema) -> Result<Vec<Value>, String> {
-    let json = json.trim();
-    if !json.starts_with('[') || !json.ends_with(']') {
-        return Err("Values must be a JSON array".into());
-    }
-    let inner = &json[1..json.len() - 1];
-
-    let parts = split_json_array(inner);
-    if parts.len() != schema.columns.len() {
-        return Err(format!(
-            "Expected {} values, got {}", schema.columns.len(), parts.len()
-        ));
-    }
-
-    let mut values = Vec::new();
-    for (i, part) in parts.iter().enumerate() {
-        let part = part.trim();
-        let col = &schema.columns[i];
-
-        if part == "null" {
-            if !col.nullable {
-                return Err(format!("Column '{}' is not nullable", col.name));
-            }
-            values.push(Value::Null);
-            continue;
-        }
-
-        let val = match &col.col_type {
-            ColumnType::Int32 => {
-                let v: i32 = part.parse().map_err(|_| format!("Invalid Int32: {}", part))?;
-                Value::Int32(v)
-            }
-            ColumnType::UInt32 => {
-                let v: u32 = part.parse().map_err(|_| format!("Invalid UInt32: {}", part))?;
-                Value::UInt32(v)
-            }
-            ColumnType::Float64 => {
-                let v: f64 = part.parse().map_err(|_| format!("Invalid Float64: {}", part))?;
-                Value::Float64(v)
-            }
-            ColumnType::Bool => {
-                let v = match part {
-                    "true" => true,
-                    "false" => false,
-                    _ => return Err(format!("Invalid Bool: {}", part)),
-                };
-                Value::Bool(v)
-            }
-            ColumnType::VarChar(_) => {
-                if part.starts_with('"') && part.ends_with('"') {
-                    Value::VarChar(unescape_json_string(&part[1..part.len() - 1]))
-                } else {
-                    return Err(format!("VarChar must be a string: {}", part));
-                }
-            }
-            ColumnType::Blob(_) => {
-                if part.starts_with('"') && part.ends_with('"') {
-                    Value::Blob(part[1..part.len() - 1].as_bytes().to_vec())
-                } else {
-                    return Err(format!("Blob must be a string: {}", part));
-                }
+    /// Arm a one-shot disk fault and return its id.  `kind` is one of
+    /// `torn_write`, `dropped_write`, `bit_flip` or `read_error`.  Give
+    /// exactly one of `nth` (fire on the Nth write, or read for
+    /// `read_error`, from now) and `page_id` (fire on the next write or
+    /// read of that page).
+    pub fn arm_fault(
+        &mut self,
+        kind: &str,
+        nth: Option<u32>,
+        page_id: Option<u32>,
+    ) -> Result<u32, JsValue> {
+        let kind = FaultKind::parse(kind)
+            .ok_or_else(|| StorageError::Parse(format!("Unknown fault kind: {}", kind)))?;
+        let trigger = match (nth, page_id) {
+            (Some(n), None) if n > 0 => Trigger::Nth(n as u64),
+            (None, Some(pid)) => Trigger::Page(pid),
+            _ => {
+                return Err(StorageError::Parse("Give either a positive nth or a page id".into()).into());
             }
         };
-        values.push(val);
+        Ok(self.db.arm_fault(kind, trigger)?)
     }
 
-    Ok(values)
-}
-
-fn parse_row_id(s: &str) -> Result<RowId, String> {
-    let parts: Vec<&str> = s.split(':').collect();
-    if parts.len() != 2 {
-        return Err("RowId format: page_id:slot_id".into());
+    /// Cancel every fault that hasn't fired yet.
+    pub fn disarm_faults(&mut self) {
+        self.db.disarm_faults();
     }
-    let page_id: PageId = parts[0].parse().map_err(|_| "Invalid page_id")?;
-    let slot_id: SlotId = parts[1].parse().map_err(|_| "Invalid slot_id")?;
-    Ok(RowId { page_id, slot_id })
-}
-
-fn values_to_json(values: &[Value]) -> String {
-    let parts: Vec<String> = values.iter().map(|v| match v {
-        Value::Int32(n) => n.to_string(),
-        Value::UInt32(n) => n.to_string(),
-        Value::Float64(n) => format!("{}", n),
-        Value::Bool(b) => b.to_string(),
-        Value::VarChar(s) => format!("\"{}\"", escape_json_string(s)),
-        Value::Blob(b) => {
-            let hex: String = b.iter().map(|byte| format!("{:02x}", byte)).collect();
-            format!("\"0x{}\"", hex)
-        }
-        Value::Null => "null".into(),
-    }).collect();
-    format!("[{}]", parts.join(","))
-}
 
-fn schema_to_json(schema: &Schema) -> String {
-    let cols: Vec<String> = schema.columns.iter().map(|c| {
-        let type_str = match &c.col_type {
-            ColumnType::Int32 => "\"Int32\"".into(),
-            ColumnType::UInt32 => "\"UInt32\"".into(),
-            ColumnType::Float64 => "\"Float64\"".into(),
-            ColumnType::Bool => "\"Bool\"".into(),
-            ColumnType::VarChar(n) => format!("{{\"VarChar\":{}}}", n),
-            ColumnType::Blob(n) => format!("{{\"Blob\":{}}}", n),
-        };
-        format!(
-            r#"{{"name":"{}","type":{},"nullable":{}}}"#,
-            c.name, type_str, c.nullable
-        )
-    }).collect();
-    format!(r#"{{"columns":[{}]}}"#, cols.join(","))
-}
-
-// ── JSON utility helpers ───────────────────────────────────────────
-
-fn extract_string(json: &str, key: &str) -> Option<String> {
-    let pattern = format!("\"{}\"", key);
-    let idx = json.find(&pattern)?;
-    let rest = &json[idx + pattern.len()..];
-    let colon = rest.find(':')?;
-    let after_colon = rest[colon + 1..].trim_start();
-    if !after_colon.starts_with('"') { return None; }
-    let end = after_colon[1..].find('"')?;
-    Some(unescape_json_string(&after_colon[1..1 + end]))
-}
+    // ── Crash recovery ──────────────────────────────────────────────
 
-fn extract_bool(json: &str, key: &str) -> Option<bool> {
-    let pattern = format!("\"{}\"", key);
-    let idx = json.find(&pattern)?;
-    let rest = &json[idx + pattern.len()..];
-    let colon = rest.find(':')?;
-    let after_colon = rest[colon + 1..].trim_start();
-    if after_colon.starts_with("true") { Some(true) }
-    else if after_colon.starts_with("false") { Some(false) }
-    else { None }
-}
-
-fn extract_number_after_colon(s: &str) -> Option<u32> {
-    let colon = s.find(':')?;
-    let after = s[colon + 1..].trim();
-    let end = after.find(|c: char| !c.is_ascii_digit()).unwrap_or(after.len());
-    after[..end].parse().ok()
-}
+    /// Simulate a crash: all buffer pool frames are lost without being
+    /// written back, along with any log records not yet flushed.
+    pub fn simulate_crash(&mut self) {
+        self.db.simulate_crash();
+    }
 
-fn find_matching_bracket(s: &str, start: usize) -> Option<usize> {
-    let bytes = s.as_bytes();
-    let mut depth = 0;
-    for i in start..bytes.len() {
-        match bytes[i] {
-            b'[' => depth += 1,
-            b']' => { depth -= 1; if depth == 0 { return Some(i); } }
-            _ => {}
-        }
+    /// Run ARIES recovery (analysis, redo, undo) over the durable log.
+    /// Returns a JSON report:
+    /// ```json
+    /// { "records_scanned": 12, "redo_start": 1, "redo_applied": 9,
+    ///   "undo_applied": 2, "losers": [4], "unfinished": [] }
+    /// ```
+    pub fn recover(&mut self) -> String {
+        recovery_report_to_json(&self.db.recover()).to_string()
     }
-    None
 }
 
-fn find_matching_brace(s: &str, start: usize) -> Option<usize> {
-    let bytes = s.as_bytes();
-    let mut depth = 0;
-    for i in start..bytes.len() {
-        match bytes[i] {
-            b'{' => depth += 1,
-            b'}' => { depth -= 1; if depth == 0 { return Some(i); } }
-            _ => {}
-        }
+impl StorageEngine {
+    /// Parse an index key for `column`; `null` gives `None`.
+    fn parse_key(&self, table_name: &str, column: &str, json: &str) -> StorageResult<Option<Value>> {
+        let schema = &self.db.table(table_name)?.schema;
+        let col = column_index(schema, column)?;
+        parse_key(json, &schema.columns[col])
     }
-    None
 }
 
-fn split_json_array(s: &str) -> Vec<String> {
-    let mut parts = Vec::new();
-    let mut depth = 0;
-    let mut current = String::new();
-    let mut in_string = false;
-    let mut escape = false;
-
-    for ch in s.chars() {
-        if escape {
-            current.push(ch);
-            escape = false;
-            continue;
-        }
-        if ch == '\\' && in_string {
-            current.push(ch);
-            escape = true;
-            continue;
-        }
-        if ch == '"' {
-            in_string = !in_string;
-            current.push(ch);
-            continue;
-        }
-        if in_string {
-            current.push(ch);
-            continue;
-        }
-        match ch {
-            '{' | '[' => { depth += 1; current.push(ch); }
-            '}' | ']' => { depth -= 1; current.push(ch); }
-            ',' if depth == 0 => {
-                parts.push(current.trim().to_string());
-                current = String::new();
-            }
-            _ => current.push(ch),
-        }
-    }
-    let trimmed = current.trim().to_string();
-    if !trimmed.is_empty() {
-        parts.push(trimmed);
+/// Errors cross into JS as `Error` objects with `code` and `pageId`
+/// (`null` when no page is involved) alongside the message.
+impl From<StorageError> for JsValue {
+    fn from(e: StorageError) -> Self {
+        let error = js_sys::Error::new(&e.to_string());
+        let page_id = e.page_id().map_or(JsValue::NULL, JsValue::from);
+        let _ = js_sys::Reflect::set(&error, &"code".into(), &e.code().into());
+        let _ = js_sys::Reflect::set(&error, &"pageId".into(), &page_id);
+        error.into()
     }
-    parts
 }
 
-fn escape_json_string(s: &str) -> String {
-    s.replace('\\', "\\\\")
-        .replace('"', "\\\"")
-        .replace('\n', "\\n")
-        .replace('\r', "\\r")
-        .replace('\t', "\\t")
+/// Position of the column named `name`.
+fn column_index(schema: &Schema, name: &str) -> StorageResult<usize> {
+    schema.columns.iter()
+        .position(|c| c.name == name)
+        .ok_or_else(|| StorageError::ColumnNotFound(name.into()))
 }
 
-fn unescape_json_string(s: &str) -> String {
-    let mut result = String::with_capacity(s.len());
-    let mut chars = s.chars();
-    while let Some(ch) = chars.next() {
-        if ch == '\\' {
-            match chars.next() {
-                Some('"') => result.push('"'),
-                Some('\\') => result.push('\\'),
-                Some('n') => result.push('\n'),
-                Some('r') => result.push('\r'),
-                Some('t') => result.push('\t'),
-                Some(c) => { result.push('\\'); result.push(c); }
-                None => result.push('\\'),
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Errors can't become `JsValue`s off wasm, so these drive `db` with
+    // exactly what the adapter methods parse from their arguments.
+    #[test]
+    fn garbage_row_ids_are_row_not_found() {
+        let mut engine = StorageEngine::new(r#"{"page_size": 128}"#).unwrap();
+        let schema = r#"{"columns":[{"name":"id","type":"Int32","nullable":false}]}"#;
+        engine.create_table("a", schema).unwrap();
+        engine.create_table("b", schema).unwrap();
+        let other = engine.insert("b", "[7]", None).unwrap();
+
+        for text in ["4000000000:0", "63:0", other.as_str()] {
+            let rid = parse_row_id(text).unwrap();
+            let values = parse_values("[1]", &engine.db.table("a").unwrap().schema).unwrap();
+            for result in [
+                engine.db.get("a", rid).map(|_| ()),
+                engine.db.delete("a", rid, None).map(|_| ()),
+                engine.db.update("a", rid, &values, None).map(|_| ()),
+            ] {
+                assert_eq!(result.unwrap_err().code(), "ROW_NOT_FOUND", "{}", text);
             }
-        } else {
-            result.push(ch);
         }
+        assert_eq!(engine.get("b", &other).unwrap(), "[7]");
     }
-    result
 }
-
@@ -0,0 +1,504 @@
+//! Text command interpreter behind the `wizalloc` binary.
+//!
+//! One command per line.  Schemas and values are written in the same JSON
+//! the frontend sends, so a `StorageControlPanel` session can be replayed
+//! from a script:
+//!
+//! ```text
+//! create users {"columns":[{"name":"id","type":"Int32","nullable":false}]}
+//! insert users [42]
+//! scan users
+//! page 2
+//! ```
+
+use std::fmt::Write;
+
+use crate::database::Database;
+use crate::exec::join::JoinAlgorithm;
+use crate::sql::QueryResult;
+use crate::storage::buffer_pool::BufferPoolManager;
+use crate::storage::config::EngineConfig;
+use crate::storage::error::{StorageError, StorageResult};
+use crate::storage::page;
+use crate::storage::types::*;
+use crate::wire::{parse_aggregate, parse_config, parse_row_id, parse_schema, parse_values, values_to_json};
+
+pub const HELP: &str = "\
+Commands:
+  tables                       list tables
+  create <table> <schema-json> create a table
+  drop <table>                 drop a table
+  insert <table> <values-json> insert a row, e.g. insert users [1, \"ann\"]
+  get <table> <page:slot>      read a row
+  delete <table> <page:slot>   delete a row
+  scan <table>                 print every row
+  sql <statement>              run SQL, e.g. sql SELECT * FROM users WHERE id > 1
+  explain <select>             run a SELECT and print its plan with rows and I/O
+  join <algorithm> <frames> <table.column> <table.column>
+                               join two tables and print the plan and I/O;
+                               nested_loop, block_nested_loop, hash or sort_merge
+  sort <frames> <table.column> [desc]
+                               external merge sort; print its runs, passes and I/O
+  aggregate <frames> <table> <fn(col),...> [by <col,...>]
+                               hash aggregate, e.g. aggregate 3 t count(*),avg(x) by k
+  flush [page]                 write back one page, or every dirty page
+  pool                         buffer pool frames and eviction order
+  disk                         allocated pages and fault state
+  page <id>                    page header, slot array and hex dump
+  reset [config-json]          start over with a fresh database
+  help                         this text
+  quit                         leave";
+
+pub struct Repl {
+    db: Database,
+}
+
+impl Repl {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    pub fn database(&mut self) -> &mut Database {
+        &mut self.db
+    }
+
+    /// Run one command line and return what it prints.  Blank lines and
+    /// `#` comments print nothing.
+    pub fn execute(&mut self, line: &str) -> StorageResult<String> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(String::new());
+        }
+        let (cmd, rest) = split_word(line);
+        match cmd {
+            "help" => Ok(HELP.into()),
+            "tables" => Ok(self.db.table_names().join("\n")),
+            "create" => {
+                let (name, json) = table_arg(rest)?;
+                self.db.create_table(name, parse_schema(json)?)?;
+                Ok(format!("Created table '{}'", name))
+            }
+            "drop" => {
+                let (name, _) = table_arg(rest)?;
+                if !self.db.drop_table(name)? {
+                    return Err(StorageError::TableNotFound(name.into()));
+                }
+                Ok(format!("Dropped table '{}'", name))
+            }
+            "insert" => {
+                let (name, json) = table_arg(rest)?;
+                let values = parse_values(json, &self.db.table(name)?.schema)?;
+                let rid = self.db.insert(name, &values, None)?;
+                Ok(format!("{}:{}", rid.page_id, rid.slot_id))
+            }
+            "get" => {
+                let (name, rid) = table_arg(rest)?;
+                let values = self.db.get(name, parse_row_id(rid)?)?;
+                Ok(values_to_json(&values).to_string())
+            }
+            "delete" => {
+                let (name, rid) = table_arg(rest)?;
+                let deleted = self.db.delete(name, parse_row_id(rid)?, None)?;
+                Ok(if deleted { "Deleted" } else { "No such row" }.into())
+            }
+            "scan" => {
+                let (name, _) = table_arg(rest)?;
+                let rows = self.db.scan(name)?;
+                let mut out = String::new();
+                for (rid, values) in &rows {
+                    let rid = format!("{}:{}", rid.page_id, rid.slot_id);
+                    let _ = writeln!(out, "{:>7}  {}", rid, values_to_json(values));
+                }
+                let _ = write!(out, "({} rows)", rows.len());
+                Ok(out)
+            }
+            "sql" => {
+                let result = self.db.execute_sql(rest, None)?;
+                if result.columns.is_empty() {
+                    return Ok(format!("({} rows affected)", result.rows_affected));
+                }
+                let mut out = render_rows(&result);
+                let _ = write!(out, "({} rows)", result.rows.len());
+                Ok(out)
+            }
+            "explain" => {
+                let result = self.db.execute_sql(rest, None)?;
+                match result.plan {
+                    Some(plan) => Ok(plan.to_string()),
+                    None => Ok(format!("({} rows affected)", result.rows_affected)),
+                }
+            }
+            "join" => {
+                let (algorithm, rest) = split_word(rest);
+                let (frames, rest) = split_word(rest);
+                let (left, right) = split_word(rest);
+                let algorithm = JoinAlgorithm::parse(algorithm)
+                    .ok_or_else(|| StorageError::Parse(format!("Unknown join algorithm: '{}'", algorithm)))?;
+                let result = self.db.join(algorithm, column_arg(left)?, column_arg(right)?, parse_frames(frames)?)?;
+                let plan = result.plan.expect("joins have a plan");
+                let total = plan.total();
+                Ok(format!(
+                    "{}\n({} rows, {} reads, {} writes)",
+                    plan,
+                    result.rows.len(),
+                    total.page_reads,
+                    total.page_writes
+                ))
+            }
+            "sort" => {
+                let (frames, rest) = split_word(rest);
+                let (column, order) = split_word(rest);
+                let frames = parse_frames(frames)?;
+                let descending = match order {
+                    "" | "asc" => false,
+                    "desc" => true,
+                    other => return Err(StorageError::Parse(format!("Expected asc or desc, found '{}'", other))),
+                };
+                let (table, column) = column_arg(column)?;
+                let (result, trace) = self.db.sort(table, &[(column, descending)], frames)?;
+                let plan = result.plan.expect("sorts have a plan");
+                let total = plan.total();
+                Ok(format!(
+                    "{}\n{}\n({} rows, {} reads, {} writes)",
+                    trace,
+                    plan,
+                    result.rows.len(),
+                    total.page_reads,
+                    total.page_writes
+                ))
+            }
+            "aggregate" => {
+                let (frames, rest) = split_word(rest);
+                let (table, rest) = table_arg(rest)?;
+                let (calls, rest) = split_word(rest);
+                let group_by: Vec<&str> = match split_word(rest) {
+                    ("", _) => Vec::new(),
+                    ("by", columns) => columns.split(',').map(str::trim).collect(),
+                    (other, _) => return Err(StorageError::Parse(format!("Expected by, found '{}'", other))),
+                };
+                let aggregates = calls.split(',').map(parse_aggregate).collect::<StorageResult<Vec<_>>>()?;
+                let result = self.db.aggregate(table, &group_by, &aggregates, parse_frames(frames)?)?;
+                let plan = result.plan.as_ref().expect("aggregates have a plan");
+                let total = plan.total();
+                let mut out = render_rows(&result);
+                let _ = write!(
+                    out,
+                    "{}\n({} rows, {} reads, {} writes)",
+                    plan,
+                    result.rows.len(),
+                    total.page_reads,
+                    total.page_writes
+                );
+                Ok(out)
+            }
+            "flush" => {
+                if rest.is_empty() {
+                    self.db.flush_all();
+                    return Ok("Flushed all pages".into());
+                }
+                let pid = parse_page_id(rest)?;
+                Ok(if self.db.flush_page(pid) {
+                    format!("Flushed page {}", pid)
+                } else {
+                    format!("Page {} is not in the buffer pool", pid)
+                })
+            }
+            "pool" => Ok(render_buffer_pool(self.db.buffer_pool())),
+            "disk" => Ok(render_disk(self.db.buffer_pool())),
+            "page" => render_page(self.db.buffer_pool_mut(), parse_page_id(rest)?),
+            "reset" => {
+                let config = if rest.is_empty() {
+                    EngineConfig::default_config()
+                } else {
+                    parse_config(rest)?
+                };
+                self.db = Database::new(config)?;
+                Ok("Started a fresh database".into())
+            }
+            _ => Err(StorageError::Parse(format!(
+                "Unknown command '{}' (try 'help')", cmd
+            ))),
+        }
+    }
+}
+
+// ── Renderings ─────────────────────────────────────────────────────
+
+/// Frames, page table, eviction order and hit statistics.
+pub fn render_buffer_pool(bpm: &BufferPoolManager) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "Buffer pool: {} frames of {} bytes, policy {}",
+        bpm.pool_size(),
+        bpm.page_size(),
+        bpm.replacer().policy().name()
+    );
+    let _ = writeln!(out, "frame   page  pins  dirty");
+    for (fid, frame) in bpm.frames.iter().enumerate() {
+        match frame.page_id {
+            Some(pid) => {
+                let dirty = if frame.is_dirty { "  yes" } else { "" };
+                let _ = writeln!(out, "{:>5}  {:>5}  {:>4}{}", fid, pid, frame.pin_count, dirty);
+            }
+            None => {
+                let _ = writeln!(out, "{:>5}      -", fid);
+            }
+        }
+    }
+    let order: Vec<String> = bpm.replacer().eviction_order().iter().map(|f| f.to_string()).collect();
+    let _ = writeln!(out, "Eviction order (next victim first): [{}]", order.join(", "));
+    let _ = write!(
+        out,
+        "Hits {}, misses {} ({:.1}% hit rate); disk reads {}, writes {}",
+        bpm.hit_count,
+        bpm.miss_count,
+        bpm.hit_rate() * 100.0,
+        bpm.disk.read_count,
+        bpm.disk.write_count
+    );
+    out
+}
+
+/// Allocated pages with their types, the I/O clock, and disk faults.
+pub fn render_disk(bpm: &BufferPoolManager) -> String {
+    let disk = &bpm.disk;
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "Disk: {} of {} pages allocated, {} bytes each",
+        disk.num_allocated(),
+        disk.max_pages(),
+        bpm.page_size()
+    );
+    let _ = writeln!(out, " page  type");
+    for (pid, &allocated) in disk.allocation_bitmap().iter().enumerate() {
+        if !allocated {
+            continue;
+        }
+        let _ = match disk.peek_page(pid as PageId) {
+            Ok(data) if page::verify_checksum(&data) => {
+                writeln!(out, "{:>5}  {:?}", pid, page::page_type(&data))
+            }
+            Ok(data) => writeln!(out, "{:>5}  {:?} (corrupt)", pid, page::page_type(&data)),
+            Err(e) => writeln!(out, "{:>5}  unreadable: {}", pid, e),
+        };
+    }
+    let clock = &disk.clock;
+    let _ = write!(
+        out,
+        "Simulated I/O: {} us ({} sequential, {} random)",
+        clock.elapsed_us, clock.sequential_count, clock.random_count
+    );
+    for f in disk.faults.armed() {
+        let _ = write!(out, "\nArmed fault {}: {:?} on {:?}", f.id, f.kind, f.trigger);
+    }
+    for f in disk.faults.fired() {
+        let _ = write!(out, "\nFired fault {}: {:?} on page {} (op {})", f.id, f.kind, f.page_id, f.op);
+    }
+    out
+}
+
+/// Header fields, slot array and a hex dump of one page, read through the
+/// buffer pool.
+pub fn render_page(bpm: &mut BufferPoolManager, page_id: PageId) -> StorageResult<String> {
+    if page_id >= bpm.disk.max_pages() {
+        return Err(StorageError::Parse(format!("Page {} is out of range", page_id)));
+    }
+    let fid = bpm.fetch_page(page_id)?;
+    let data = bpm.frame_data(fid).to_vec();
+    bpm.unpin_page(page_id, false);
+
+    let mut out = String::new();
+    let next = match page::next_page(&data) {
+        INVALID_PAGE => "-".to_string(),
+        pid => pid.to_string(),
+    };
+    let _ = writeln!(
+        out,
+        "Page {} ({:?}): lsn {}, next {}, checksum {:08x}",
+        page::page_id(&data),
+        page::page_type(&data),
+        page::page_lsn(&data),
+        next,
+        page::checksum(&data)
+    );
+    let _ = writeln!(
+        out,
+        "{} slots, free space {} bytes [{}..{})",
+        page::slot_count(&data),
+        page::free_space(&data),
+        page::free_start(&data),
+        page::free_end(&data)
+    );
+    if page::slot_count(&data) > 0 {
+        let _ = writeln!(out, "slot  offset  length  kind");
+    }
+    for slot in 0..page::slot_count(&data) {
+        let (offset, len) = page::read_slot(&data, slot);
+        let kind = match page::forward_target(&data, slot) {
+            Some(rid) => format!("forward -> {}:{}", rid.page_id, rid.slot_id),
+            None if len == 0 => "deleted".into(),
+            None if page::tuple_flags(&data, slot) & page::TUPLE_MOVED != 0 => "moved".into(),
+            None => "tuple".into(),
+        };
+        let _ = writeln!(out, "{:>4}  {:>6}  {:>6}  {}", slot, offset, len, kind);
+    }
+    out.push_str(&hex_dump(&data));
+    Ok(out)
+}
+
+/// 16 bytes per line: offset, hex, then printable ASCII.
+pub fn hex_dump(data: &[u8]) -> String {
+    let lines: Vec<String> = data
+        .chunks(16)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+            let ascii: String = chunk
+                .iter()
+                .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+                .collect();
+            format!("{:04x}  {:<47}  |{}|", i * 16, hex.join(" "), ascii)
+        })
+        .collect();
+    lines.join("\n")
+}
+
+// ── Argument helpers ───────────────────────────────────────────────
+
+/// Split off the first whitespace-delimited word.
+fn split_word(s: &str) -> (&str, &str) {
+    match s.split_once(char::is_whitespace) {
+        Some((word, rest)) => (word, rest.trim_start()),
+        None => (s, ""),
+    }
+}
+
+/// A table name followed by the rest of the line.
+fn table_arg(s: &str) -> StorageResult<(&str, &str)> {
+    match split_word(s) {
+        ("", _) => Err(StorageError::Parse("Missing table name".into())),
+        (name, rest) => Ok((name, rest)),
+    }
+}
+
+/// A header of column names, then one JSON row per line.
+fn render_rows(result: &QueryResult) -> String {
+    let names: Vec<&str> = result.columns.iter().map(|c| c.name.as_str()).collect();
+    let mut out = String::new();
+    let _ = writeln!(out, "{}", names.join(" | "));
+    for row in &result.rows {
+        let _ = writeln!(out, "{}", values_to_json(row));
+    }
+    out
+}
+
+/// A `table.column` argument.
+fn column_arg(s: &str) -> StorageResult<(&str, &str)> {
+    s.trim()
+        .split_once('.')
+        .ok_or_else(|| StorageError::Parse(format!("Expected table.column, found '{}'", s.trim())))
+}
+
+fn parse_frames(s: &str) -> StorageResult<usize> {
+    s.parse().map_err(|_| StorageError::Parse(format!("Invalid frame count: '{}'", s)))
+}
+
+fn parse_page_id(s: &str) -> StorageResult<PageId> {
+    s.trim()
+        .parse()
+        .map_err(|_| StorageError::Parse(format!("Invalid page id: '{}'", s.trim())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repl() -> Repl {
+        let mut config = EngineConfig::default_config();
+        config.page_size = 128;
+        Repl::new(Database::new(config).unwrap())
+    }
+
+    #[test]
+    fn script_round_trip() {
+        let mut repl = repl();
+        let schema = r#"{"columns":[{"name":"id","type":"Int32","nullable":false},{"name":"name","type":{"VarChar":20},"nullable":true}]}"#;
+        repl.execute(&format!("create users {}", schema)).unwrap();
+        let rid = repl.execute(r#"insert users [1, "ann"]"#).unwrap();
+        repl.execute("insert users [2, null]").unwrap();
+        assert_eq!(repl.execute(&format!("get users {}", rid)).unwrap(), r#"[1,"ann"]"#);
+        assert_eq!(repl.execute(&format!("delete users {}", rid)).unwrap(), "Deleted");
+        assert!(repl.execute("scan users").unwrap().ends_with("(1 rows)"));
+        assert_eq!(repl.execute("tables").unwrap(), "users");
+        assert_eq!(
+            repl.execute("sql SELECT id, name AS who FROM users").unwrap(),
+            "id | who\n[2,null]\n(1 rows)"
+        );
+        assert_eq!(
+            repl.execute("explain SELECT name FROM users WHERE id = 2").unwrap(),
+            "Projection $1  rows=1 pages=0 reads=0 writes=0\n  \
+             Filter ($0 = 2)  rows=1 pages=0 reads=0 writes=0\n    \
+             SeqScan users  rows=1 pages=1 reads=0 writes=0"
+        );
+        let join = repl.execute("join hash 2 users.id users.id").unwrap();
+        assert!(join.starts_with("HashJoin left $0 = right $0, 2 frames"), "{}", join);
+        assert!(join.ends_with("(1 rows, 0 reads, 0 writes)"), "{}", join);
+        assert_eq!(repl.execute("join merge 2 users.id users.id").unwrap_err().code(), "PARSE_ERROR");
+        assert_eq!(
+            repl.execute("sort 3 users.name desc").unwrap(),
+            "1 rows sorted in memory\n\
+             ExternalSort $1 DESC, 3 frames  rows=1 pages=0 reads=0 writes=0\n  \
+             SeqScan users  rows=1 pages=1 reads=0 writes=0\n\
+             (1 rows, 0 reads, 0 writes)"
+        );
+        assert_eq!(
+            repl.execute("aggregate 2 users count(*),max(name) by id").unwrap(),
+            "id | count(*) | max(name)\n[2,1,null]\n\
+             HashAggregate group by $0, count(*), max($1), 2 frames  rows=1 pages=0 reads=0 writes=0\n  \
+             SeqScan users  rows=1 pages=1 reads=0 writes=0\n\
+             (1 rows, 0 reads, 0 writes)"
+        );
+        assert_eq!(repl.execute("aggregate 2 users sum(name)").unwrap_err().code(), "QUERY_ERROR");
+        assert_eq!(repl.execute("sql DELETE FROM users").unwrap(), "(1 rows affected)");
+        assert_eq!(repl.execute("  # a comment").unwrap(), "");
+        assert_eq!(repl.execute("bogus").unwrap_err().code(), "PARSE_ERROR");
+        assert_eq!(
+            repl.execute("scan nope").unwrap_err(),
+            StorageError::TableNotFound("nope".into())
+        );
+    }
+
+    #[test]
+    fn renderings() {
+        let mut repl = repl();
+        repl.execute(r#"create t {"columns":[{"name":"s","type":{"VarChar":20}}]}"#).unwrap();
+        let rid = repl.execute(r#"insert t ["hi there"]"#).unwrap();
+        let pid = rid.split(':').next().unwrap();
+
+        let pool = repl.execute("pool").unwrap();
+        assert!(pool.contains("policy LRU"));
+        assert!(pool.contains("Eviction order"));
+
+        let page = repl.execute(&format!("page {}", pid)).unwrap();
+        assert!(page.contains("(Data)"));
+        assert!(page.lines().any(|l| l.starts_with("   0") && l.ends_with("tuple")));
+        assert!(repl.execute("page 9999").is_err());
+
+        repl.execute("flush").unwrap();
+        let disk = repl.execute("disk").unwrap();
+        assert!(disk.contains("Superblock"));
+        assert!(disk.contains(&format!("{:>5}  Data", pid)));
+    }
+
+    #[test]
+    fn hex_dump_lines() {
+        let dump = hex_dump(b"ABCDEFGHIJKLMNOPQ\x00");
+        let lines: Vec<&str> = dump.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("0000  41 42"));
+        assert!(lines[0].ends_with("|ABCDEFGHIJKLMNOP|"));
+        assert!(lines[1].ends_with("|Q.|"));
+    }
+}
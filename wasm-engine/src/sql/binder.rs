@@ -0,0 +1,588 @@
+//! Resolves a parsed statement against the catalog.
+//!
+//! Names become row positions, every expression gets a static type and is
+//! checked for operands that can't mix, and literals are converted to the
+//! column types they meet (`d = '2024-01-01'` against a Date column binds
+//! the text as a Date once instead of on every row).  INSERT values are
+//! constants, so they are evaluated and cast here.
+
+use crate::database::Database;
+use crate::sql::ast::*;
+use crate::sql::expr::{BoundExpr, Family, can_cast, cast, query_error};
+use crate::storage::datetime;
+use crate::storage::error::{StorageError, StorageResult};
+use crate::storage::schema::*;
+
+/// Longest text a `||` can produce.
+const MAX_TEXT_LEN: u16 = u16::MAX - 1;
+
+#[derive(Debug, Clone)]
+pub enum BoundStatement {
+    CreateTable { name: String, schema: Schema },
+    DropTable { name: String, if_exists: bool },
+    /// Full rows in schema order, already cast to the column types.
+    Insert { table: String, rows: Vec<Vec<Value>> },
+    Select(BoundSelect),
+    /// Each assignment sets a column (by position) from an expression over
+    /// the old row.
+    Update { table: String, assignments: Vec<(usize, BoundExpr)>, filter: Option<BoundExpr> },
+    Delete { table: String, filter: Option<BoundExpr> },
+}
+
+/// A SELECT whose filter, sort keys and projection all read rows of
+/// `table` (or an empty row when there is no FROM).
+#[derive(Debug, Clone)]
+pub struct BoundSelect {
+    pub table: Option<String>,
+    pub filter: Option<BoundExpr>,
+    /// Sort keys and whether each is descending.
+    pub order_by: Vec<(BoundExpr, bool)>,
+    pub projection: Vec<BoundExpr>,
+    /// Name and type of each output column.
+    pub columns: Vec<Column>,
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+}
+
+pub fn bind(db: &Database, stmt: Statement) -> StorageResult<BoundStatement> {
+    Ok(match stmt {
+        Statement::CreateTable { name, columns } => {
+            for (i, col) in columns.iter().enumerate() {
+                if columns[..i].iter().any(|c| c.name == col.name) {
+                    return Err(StorageError::SchemaMismatch(format!(
+                        "Column '{}' appears more than once",
+                        col.name
+                    )));
+                }
+            }
+            BoundStatement::CreateTable { name, schema: Schema::new(columns) }
+        }
+        Statement::DropTable { name, if_exists } => BoundStatement::DropTable { name, if_exists },
+        Statement::Insert { table, columns, rows } => bind_insert(db, table, columns, rows)?,
+        Statement::Select(select) => BoundStatement::Select(bind_select(db, select)?),
+        Statement::Update { table, assignments, filter } => {
+            let schema = &db.table(&table)?.schema;
+            let scope = Scope::table(&table, None, schema);
+            let mut bound = Vec::new();
+            for (name, expr) in assignments {
+                let (col, _) = scope.resolve(None, &name)?;
+                if bound.iter().any(|(c, _)| *c == col) {
+                    return Err(query_error(format!("Column '{}' is assigned more than once", name)));
+                }
+                let target = &schema.columns[col].col_type;
+                bound.push((col, coerce(scope.bind(&expr)?, target, &name)?));
+            }
+            let filter = scope.bind_filter(filter)?;
+            BoundStatement::Update { table, assignments: bound, filter }
+        }
+        Statement::Delete { table, filter } => {
+            let scope = Scope::table(&table, None, &db.table(&table)?.schema);
+            let filter = scope.bind_filter(filter)?;
+            BoundStatement::Delete { table, filter }
+        }
+    })
+}
+
+fn bind_insert(
+    db: &Database,
+    table: String,
+    columns: Option<Vec<String>>,
+    rows: Vec<Vec<Expr>>,
+) -> StorageResult<BoundStatement> {
+    let schema = &db.table(&table)?.schema;
+    let targets: Vec<usize> = match columns {
+        None => (0..schema.num_columns()).collect(),
+        Some(names) => {
+            let scope = Scope::table(&table, None, schema);
+            let mut targets = Vec::new();
+            for name in &names {
+                let (col, _) = scope.resolve(None, name)?;
+                if targets.contains(&col) {
+                    return Err(query_error(format!("Column '{}' is listed more than once", name)));
+                }
+                targets.push(col);
+            }
+            targets
+        }
+    };
+
+    let empty = Scope::default();
+    let mut bound = Vec::with_capacity(rows.len());
+    for exprs in rows {
+        if exprs.len() != targets.len() {
+            return Err(query_error(format!(
+                "INSERT has {} target columns but a row of {} values",
+                targets.len(),
+                exprs.len()
+            )));
+        }
+        let mut row = vec![Value::Null; schema.num_columns()];
+        for (&col, expr) in targets.iter().zip(&exprs) {
+            let column = &schema.columns[col];
+            let expr = coerce(empty.bind(expr)?, &column.col_type, &column.name)?;
+            row[col] = cast(&expr.eval(&[])?, &column.col_type)?;
+        }
+        bound.push(row);
+    }
+    Ok(BoundStatement::Insert { table, rows: bound })
+}
+
+fn bind_select(db: &Database, select: Select) -> StorageResult<BoundSelect> {
+    let scope = match &select.from {
+        Some(from) => Scope::table(&from.name, from.alias.as_deref(), &db.table(&from.name)?.schema),
+        None => Scope::default(),
+    };
+
+    let mut projection = Vec::new();
+    let mut columns = Vec::new();
+    for item in &select.items {
+        match item {
+            SelectItem::Wildcard(qualifier) => {
+                let before = projection.len();
+                for (i, entry) in scope.entries.iter().enumerate() {
+                    if qualifier.as_ref().is_none_or(|q| *q == entry.qualifier) {
+                        projection.push(BoundExpr::Column(i));
+                        columns.push(entry.column.clone());
+                    }
+                }
+                if projection.len() == before {
+                    return Err(match qualifier {
+                        Some(q) => StorageError::TableNotFound(q.clone()),
+                        None => query_error("SELECT * needs a FROM clause".into()),
+                    });
+                }
+            }
+            SelectItem::Expr { expr, alias } => {
+                let Typed { expr: bound, ty } = scope.bind(expr)?;
+                let column = match (&bound, expr) {
+                    (BoundExpr::Column(i), _) => scope.entries[*i].column.clone(),
+                    (_, expr) => Column {
+                        name: match expr {
+                            Expr::Column { name, .. } => name.clone(),
+                            _ => "?column?".into(),
+                        },
+                        col_type: ty.unwrap_or(ColumnType::VarChar(0)),
+                        nullable: true,
+                    },
+                };
+                let name = alias.clone().unwrap_or(column.name);
+                projection.push(bound);
+                columns.push(Column { name, ..column });
+            }
+        }
+    }
+
+    // ORDER BY may name an output column or give its 1-based position
+    let mut order_by = Vec::new();
+    for item in &select.order_by {
+        let key = match &item.expr {
+            Expr::Literal(Literal::Number(n)) => {
+                let pos = n.parse::<usize>().ok().filter(|p| (1..=projection.len()).contains(p));
+                let pos = pos.ok_or_else(|| {
+                    query_error(format!("ORDER BY position {} is not in the select list", n))
+                })?;
+                projection[pos - 1].clone()
+            }
+            Expr::Column { table: None, name }
+                if scope.find(None, name).is_none() && columns.iter().any(|c| c.name == *name) =>
+            {
+                let pos = columns.iter().position(|c| c.name == *name).unwrap();
+                projection[pos].clone()
+            }
+            expr => scope.bind(expr)?.expr,
+        };
+        order_by.push((key, item.descending));
+    }
+
+    Ok(BoundSelect {
+        table: select.from.map(|f| f.name),
+        filter: scope.bind_filter(select.filter)?,
+        order_by,
+        projection,
+        columns,
+        limit: select.limit,
+        offset: select.offset,
+    })
+}
+
+/// Check that `typed` can be stored in a column of type `target`, casting
+/// a constant right away.
+fn coerce(typed: Typed, target: &ColumnType, column: &str) -> StorageResult<BoundExpr> {
+    if let Some(ty) = &typed.ty
+        && !can_cast(ty, target)
+    {
+        return Err(query_error(format!("Column '{}' is {} but the value is {}", column, target, ty)));
+    }
+    if typed.expr.is_constant() {
+        return Ok(BoundExpr::Literal(cast(&typed.expr.eval(&[])?, target)?));
+    }
+    Ok(typed.expr)
+}
+
+// ── Scope ──────────────────────────────────────────────────────────
+
+/// A column visible to expressions, and the name that qualifies it.
+#[derive(Debug, Clone)]
+struct Entry {
+    qualifier: String,
+    column: Column,
+}
+
+/// The columns an expression can see, in input row order.
+#[derive(Debug, Default)]
+struct Scope {
+    entries: Vec<Entry>,
+}
+
+/// A bound expression and its static type; `None` for a bare NULL.
+struct Typed {
+    expr: BoundExpr,
+    ty: Option<ColumnType>,
+}
+
+impl Scope {
+    /// The columns of one table, qualified by its alias if it has one.
+    fn table(name: &str, alias: Option<&str>, schema: &Schema) -> Self {
+        let qualifier = alias.unwrap_or(name);
+        let entries = schema
+            .columns
+            .iter()
+            .map(|column| Entry { qualifier: qualifier.into(), column: column.clone() })
+            .collect();
+        Self { entries }
+    }
+
+    fn find(&self, table: Option<&str>, name: &str) -> Option<usize> {
+        self.entries
+            .iter()
+            .position(|e| e.column.name == name && table.is_none_or(|t| t == e.qualifier))
+    }
+
+    /// Position and type of a column reference.
+    fn resolve(&self, table: Option<&str>, name: &str) -> StorageResult<(usize, ColumnType)> {
+        if let Some(t) = table
+            && !self.entries.iter().any(|e| e.qualifier == t)
+        {
+            return Err(StorageError::TableNotFound(t.into()));
+        }
+        let matches: Vec<usize> = (0..self.entries.len())
+            .filter(|&i| {
+                let e = &self.entries[i];
+                e.column.name == name && table.is_none_or(|t| t == e.qualifier)
+            })
+            .collect();
+        match matches[..] {
+            [] => Err(StorageError::ColumnNotFound(match table {
+                Some(t) => format!("{}.{}", t, name),
+                None => name.into(),
+            })),
+            [i] => Ok((i, self.entries[i].column.col_type.clone())),
+            _ => Err(query_error(format!("Column '{}' is ambiguous", name))),
+        }
+    }
+
+    fn bind_filter(&self, filter: Option<Expr>) -> StorageResult<Option<BoundExpr>> {
+        let Some(filter) = filter else {
+            return Ok(None);
+        };
+        let typed = self.bind(&filter)?;
+        expect(&typed, Family::Bool, "WHERE")?;
+        Ok(Some(typed.expr))
+    }
+
+    fn bind(&self, expr: &Expr) -> StorageResult<Typed> {
+        Ok(match expr {
+            Expr::Literal(lit) => literal(lit)?,
+            Expr::Column { table, name } => {
+                let (i, ty) = self.resolve(table.as_deref(), name)?;
+                Typed { expr: BoundExpr::Column(i), ty: Some(ty) }
+            }
+            Expr::Unary { op: UnaryOp::Neg, expr } => {
+                let inner = self.bind(expr)?;
+                if inner.ty.as_ref().is_some_and(|t| !Family::of(t).is_numeric()) {
+                    return Err(type_error("-", &inner, None));
+                }
+                let ty = inner.ty.map(|t| match t {
+                    ColumnType::Float32 | ColumnType::Float64 => ColumnType::Float64,
+                    ColumnType::Decimal(_, s) => ColumnType::Decimal(MAX_DECIMAL_PRECISION, s),
+                    _ => ColumnType::Int64,
+                });
+                Typed { expr: BoundExpr::Neg(Box::new(inner.expr)), ty }
+            }
+            Expr::Unary { op: UnaryOp::Not, expr } => {
+                let inner = self.bind(expr)?;
+                expect(&inner, Family::Bool, "NOT")?;
+                Typed { expr: BoundExpr::Not(Box::new(inner.expr)), ty: Some(ColumnType::Bool) }
+            }
+            Expr::Binary { op, left, right } => self.binary(*op, self.bind(left)?, self.bind(right)?)?,
+            Expr::IsNull { expr, negated } => Typed {
+                expr: BoundExpr::IsNull { expr: Box::new(self.bind(expr)?.expr), negated: *negated },
+                ty: Some(ColumnType::Bool),
+            },
+            Expr::Between { expr, low, high, negated } => {
+                let ge = self.binary(BinaryOp::GtEq, self.bind(expr)?, self.bind(low)?)?;
+                let le = self.binary(BinaryOp::LtEq, self.bind(expr)?, self.bind(high)?)?;
+                let both = self.binary(BinaryOp::And, ge, le)?;
+                if *negated {
+                    Typed { expr: BoundExpr::Not(Box::new(both.expr)), ty: both.ty }
+                } else {
+                    both
+                }
+            }
+            Expr::InList { expr, list, negated } => {
+                let probe = self.bind(expr)?;
+                let mut items = Vec::with_capacity(list.len());
+                for item in list {
+                    let (_, item) = comparable(&probe, self.bind(item)?)?;
+                    items.push(item.expr);
+                }
+                Typed {
+                    expr: BoundExpr::InList { expr: Box::new(probe.expr), list: items, negated: *negated },
+                    ty: Some(ColumnType::Bool),
+                }
+            }
+            Expr::Like { expr, pattern, negated } => {
+                let (expr, pattern) = (self.bind(expr)?, self.bind(pattern)?);
+                expect(&expr, Family::Text, "LIKE")?;
+                expect(&pattern, Family::Text, "LIKE")?;
+                Typed {
+                    expr: BoundExpr::Like {
+                        expr: Box::new(expr.expr),
+                        pattern: Box::new(pattern.expr),
+                        negated: *negated,
+                    },
+                    ty: Some(ColumnType::Bool),
+                }
+            }
+        })
+    }
+
+    fn binary(&self, op: BinaryOp, left: Typed, right: Typed) -> StorageResult<Typed> {
+        let families = (left.ty.as_ref().map(Family::of), right.ty.as_ref().map(Family::of));
+        let ty = match op {
+            BinaryOp::And | BinaryOp::Or => {
+                expect(&left, Family::Bool, op.symbol())?;
+                expect(&right, Family::Bool, op.symbol())?;
+                ColumnType::Bool
+            }
+            _ if op.is_comparison() => {
+                let (left, right) = comparable(&left, right)?;
+                return Ok(Typed {
+                    expr: BoundExpr::Binary(op, Box::new(left.expr), Box::new(right.expr)),
+                    ty: Some(ColumnType::Bool),
+                });
+            }
+            BinaryOp::Concat => {
+                if families.0.is_some_and(|f| f != Family::Text) && families.1.is_some_and(|f| f != Family::Text) {
+                    return Err(type_error(op.symbol(), &left, Some(&right)));
+                }
+                ColumnType::VarChar(MAX_TEXT_LEN)
+            }
+            _ => {
+                if families.0.is_some_and(|f| !f.is_numeric()) || families.1.is_some_and(|f| !f.is_numeric()) {
+                    return Err(type_error(op.symbol(), &left, Some(&right)));
+                }
+                arithmetic_type(op, left.ty.as_ref(), right.ty.as_ref())?
+            }
+        };
+        Ok(Typed {
+            expr: BoundExpr::Binary(op, Box::new(left.expr), Box::new(right.expr)),
+            ty: Some(ty),
+        })
+    }
+}
+
+/// Result type of `+ - * / %`: Float64 if a float is involved (or a
+/// Decimal is divided), Decimal if a Decimal is, otherwise Int64.
+fn arithmetic_type(op: BinaryOp, l: Option<&ColumnType>, r: Option<&ColumnType>) -> StorageResult<ColumnType> {
+    let family = |t: Option<&ColumnType>| t.map(Family::of);
+    let scale = |t: Option<&ColumnType>| match t {
+        Some(ColumnType::Decimal(_, s)) => *s,
+        _ => 0,
+    };
+    let (fl, fr) = (family(l), family(r));
+    if fl == Some(Family::Float) || fr == Some(Family::Float) {
+        return Ok(ColumnType::Float64);
+    }
+    if fl != Some(Family::Decimal) && fr != Some(Family::Decimal) {
+        return Ok(ColumnType::Int64);
+    }
+    let (sl, sr) = (scale(l), scale(r));
+    let scale = match op {
+        BinaryOp::Div => return Ok(ColumnType::Float64),
+        BinaryOp::Mul => sl + sr,
+        _ => sl.max(sr),
+    };
+    if scale > MAX_DECIMAL_PRECISION {
+        return Err(query_error(format!(
+            "Decimal result of {} would need {} digits after the point",
+            op.symbol(),
+            scale
+        )));
+    }
+    Ok(ColumnType::Decimal(MAX_DECIMAL_PRECISION, scale))
+}
+
+/// Check that two operands can be compared, casting a text constant to the
+/// other side's Date, Timestamp, Uuid or Decimal type.
+fn comparable(left: &Typed, right: Typed) -> StorageResult<(Typed, Typed)> {
+    let (Some(lt), Some(rt)) = (&left.ty, &right.ty) else {
+        return Ok((Typed { expr: left.expr.clone(), ty: left.ty.clone() }, right));
+    };
+    let (lf, rf) = (Family::of(lt), Family::of(rt));
+    let left = Typed { expr: left.expr.clone(), ty: left.ty.clone() };
+    if lf.comparable(rf) {
+        return Ok((left, right));
+    }
+    let literal_as = |text: &Typed, target: &ColumnType| -> StorageResult<Option<Typed>> {
+        if !text.expr.is_constant() || !can_cast(text.ty.as_ref().unwrap(), target) {
+            return Ok(None);
+        }
+        let value = cast(&text.expr.eval(&[])?, target)?;
+        Ok(Some(Typed { expr: BoundExpr::Literal(value), ty: Some(target.clone()) }))
+    };
+    if lf == Family::Text
+        && let Some(left) = literal_as(&left, rt)?
+    {
+        return Ok((left, right));
+    }
+    if rf == Family::Text
+        && let Some(right) = literal_as(&right, lt)?
+    {
+        return Ok((left, right));
+    }
+    Err(query_error(format!("Can't compare {} with {}", lt, rt)))
+}
+
+fn expect(typed: &Typed, family: Family, context: &str) -> StorageResult<()> {
+    match &typed.ty {
+        Some(t) if Family::of(t) != family => {
+            Err(query_error(format!("{} needs {:?} operands, not {}", context, family, t)))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn type_error(op: &str, left: &Typed, right: Option<&Typed>) -> StorageError {
+    let name = |t: &Typed| t.ty.as_ref().map_or("NULL".to_string(), ColumnType::to_string);
+    match right {
+        Some(right) => query_error(format!("Can't apply {} to {} and {}", op, name(left), name(right))),
+        None => query_error(format!("Can't apply {} to {}", op, name(left))),
+    }
+}
+
+/// Bind a literal.  Numbers are Int64 when they fit, exact Decimals when
+/// written without an exponent, and Float64 otherwise.
+fn literal(lit: &Literal) -> StorageResult<Typed> {
+    let (value, ty) = match lit {
+        Literal::Null => (Value::Null, None),
+        Literal::Bool(b) => (Value::Bool(*b), Some(ColumnType::Bool)),
+        Literal::Number(text) => number(text)?,
+        Literal::String(s) => {
+            let len = u16::try_from(s.len()).unwrap_or(MAX_TEXT_LEN);
+            (Value::VarChar(s.clone()), Some(ColumnType::VarChar(len)))
+        }
+        Literal::Bytes(b) => {
+            let len = u16::try_from(b.len()).unwrap_or(MAX_TEXT_LEN);
+            (Value::Blob(b.clone()), Some(ColumnType::Blob(len)))
+        }
+        Literal::Typed(kind, text) => {
+            let (value, ty) = match kind {
+                TypedLiteral::Date => (datetime::parse_date(text).map(Value::Date), ColumnType::Date),
+                TypedLiteral::Timestamp => {
+                    (datetime::parse_timestamp(text).map(Value::Timestamp), ColumnType::Timestamp)
+                }
+                TypedLiteral::Uuid => (parse_uuid(text).map(Value::Uuid), ColumnType::Uuid),
+            };
+            let value = value.ok_or_else(|| query_error(format!("'{}' is not a valid {}", text, ty)))?;
+            (value, Some(ty))
+        }
+    };
+    Ok(Typed { expr: BoundExpr::Literal(value), ty })
+}
+
+fn number(text: &str) -> StorageResult<(Value, Option<ColumnType>)> {
+    if let Ok(n) = text.parse::<i64>() {
+        return Ok((Value::Int64(n), Some(ColumnType::Int64)));
+    }
+    if !text.contains(['e', 'E']) {
+        let scale = text.split_once('.').map_or(0, |(_, frac)| frac.len());
+        if let Ok(scale) = u8::try_from(scale)
+            && scale <= MAX_DECIMAL_PRECISION
+            && let Some(n) = parse_decimal(text, scale)
+        {
+            return Ok((Value::Decimal(n, scale), Some(ColumnType::Decimal(MAX_DECIMAL_PRECISION, scale))));
+        }
+    }
+    match text.parse::<f64>() {
+        Ok(f) if f.is_finite() => Ok((Value::Float64(f), Some(ColumnType::Float64))),
+        _ => Err(query_error(format!("Number {} is out of range", text))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sql::parser::parse;
+    use crate::storage::config::EngineConfig;
+
+    fn db() -> Database {
+        let mut db = Database::new(EngineConfig::default_config()).unwrap();
+        let col = |name: &str, col_type, nullable| Column { name: name.into(), col_type, nullable };
+        db.create_table("t", Schema::new(vec![
+            col("id", ColumnType::Int32, false),
+            col("name", ColumnType::VarChar(20), true),
+            col("born", ColumnType::Date, true),
+            col("price", ColumnType::Decimal(8, 2), true),
+        ]))
+        .unwrap();
+        db
+    }
+
+    fn bind_sql(db: &Database, sql: &str) -> StorageResult<BoundStatement> {
+        bind(db, parse(sql).unwrap())
+    }
+
+    #[test]
+    fn names_and_literals_resolve() {
+        let db = db();
+        let BoundStatement::Select(s) = bind_sql(&db, "SELECT u.name AS n, id + 1, * FROM t u WHERE born > '2000-01-01' ORDER BY 2 DESC").unwrap() else {
+            panic!("not a select");
+        };
+        let names: Vec<&str> = s.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["n", "?column?", "id", "name", "born", "price"]);
+        assert_eq!(s.columns[1].col_type, ColumnType::Int64);
+        assert_eq!(
+            s.filter,
+            Some(BoundExpr::Binary(
+                BinaryOp::Gt,
+                Box::new(BoundExpr::Column(2)),
+                Box::new(BoundExpr::Literal(Value::Date(10_957)))
+            ))
+        );
+        assert_eq!(s.order_by, vec![(s.projection[1].clone(), true)]);
+
+        let BoundStatement::Insert { rows, .. } = bind_sql(&db, "INSERT INTO t (price, id) VALUES (1.005, 7)").unwrap() else {
+            panic!("not an insert");
+        };
+        assert_eq!(rows, vec![vec![Value::Int32(7), Value::Null, Value::Null, Value::Decimal(101, 2)]]);
+    }
+
+    #[test]
+    fn bind_errors() {
+        let db = db();
+        let err = |sql| bind_sql(&db, sql).unwrap_err();
+        assert_eq!(err("SELECT * FROM nope"), StorageError::TableNotFound("nope".into()));
+        assert_eq!(err("SELECT x FROM t"), StorageError::ColumnNotFound("x".into()));
+        assert_eq!(err("SELECT t.id FROM t u"), StorageError::TableNotFound("t".into()));
+        assert_eq!(err("SELECT id FROM t WHERE name = 1").to_string(), "Can't compare VarChar(20) with Int64");
+        assert_eq!(err("SELECT id FROM t WHERE id"), query_error("WHERE needs Bool operands, not Int32".into()));
+        assert_eq!(err("SELECT name + 1 FROM t").to_string(), "Can't apply + to VarChar(20) and Int64");
+        assert_eq!(err("UPDATE t SET id = 'x'").to_string(), "Column 'id' is Int32 but the value is VarChar(1)");
+        assert_eq!(err("INSERT INTO t VALUES (1)").to_string(), "INSERT has 4 target columns but a row of 1 values");
+        assert_eq!(err("INSERT INTO t (id) VALUES (id)"), StorageError::ColumnNotFound("id".into()));
+        assert_eq!(err("INSERT INTO t (id) VALUES (1e3)").to_string(), "1000 is not a valid Int32");
+        assert_eq!(err("SELECT * FROM t ORDER BY 9").to_string(), "ORDER BY position 9 is not in the select list");
+        assert_eq!(err("CREATE TABLE x (a INT, a INT)").to_string(), "Column 'a' appears more than once");
+    }
+}
@@ -0,0 +1,545 @@
+//! Bound expressions and their evaluation.
+//!
+//! After binding, a column is just its position in the input row and every
+//! operand has been type-checked, so evaluation only has to deal with
+//! NULLs and with runtime failures such as overflow.  NULL follows SQL's
+//! three-valued logic: comparisons with NULL are NULL, `NULL AND false` is
+//! false, and a WHERE clause keeps a row only when it evaluates to true.
+//!
+//! Integer arithmetic is done in i64 and fails on overflow; anything
+//! involving a float is done in f64; Decimals stay exact for `+ - *` and
+//! divide as floats.
+
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::sql::ast::BinaryOp;
+use crate::storage::datetime;
+use crate::storage::error::{StorageError, StorageResult};
+use crate::storage::schema::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BoundExpr {
+    /// Position in the input row.
+    Column(usize),
+    Literal(Value),
+    Neg(Box<BoundExpr>),
+    Not(Box<BoundExpr>),
+    Binary(BinaryOp, Box<BoundExpr>, Box<BoundExpr>),
+    /// `expr IS NULL`, or `IS NOT NULL` when negated.
+    IsNull { expr: Box<BoundExpr>, negated: bool },
+    InList { expr: Box<BoundExpr>, list: Vec<BoundExpr>, negated: bool },
+    Like { expr: Box<BoundExpr>, pattern: Box<BoundExpr>, negated: bool },
+}
+
+impl BoundExpr {
+    pub fn eval(&self, row: &[Value]) -> StorageResult<Value> {
+        Ok(match self {
+            BoundExpr::Column(i) => row[*i].clone(),
+            BoundExpr::Literal(v) => v.clone(),
+            BoundExpr::Neg(e) => negate(e.eval(row)?)?,
+            BoundExpr::Not(e) => match e.eval(row)? {
+                Value::Bool(b) => Value::Bool(!b),
+                _ => Value::Null,
+            },
+            BoundExpr::Binary(BinaryOp::And, l, r) => {
+                let (l, r) = (truth(&l.eval(row)?), truth(&r.eval(row)?));
+                match (l, r) {
+                    (Some(false), _) | (_, Some(false)) => Value::Bool(false),
+                    (Some(true), Some(true)) => Value::Bool(true),
+                    _ => Value::Null,
+                }
+            }
+            BoundExpr::Binary(BinaryOp::Or, l, r) => {
+                let (l, r) = (truth(&l.eval(row)?), truth(&r.eval(row)?));
+                match (l, r) {
+                    (Some(true), _) | (_, Some(true)) => Value::Bool(true),
+                    (Some(false), Some(false)) => Value::Bool(false),
+                    _ => Value::Null,
+                }
+            }
+            BoundExpr::Binary(op, l, r) => binary(*op, l.eval(row)?, r.eval(row)?)?,
+            BoundExpr::IsNull { expr, negated } => {
+                Value::Bool((expr.eval(row)? == Value::Null) != *negated)
+            }
+            BoundExpr::InList { expr, list, negated } => {
+                let v = expr.eval(row)?;
+                if v == Value::Null {
+                    return Ok(Value::Null);
+                }
+                let mut saw_null = false;
+                for item in list {
+                    match compare(&v, &item.eval(row)?) {
+                        Some(Ordering::Equal) => return Ok(Value::Bool(!negated)),
+                        None => saw_null = true,
+                        Some(_) => {}
+                    }
+                }
+                if saw_null { Value::Null } else { Value::Bool(*negated) }
+            }
+            BoundExpr::Like { expr, pattern, negated } => {
+                match (expr.eval(row)?, pattern.eval(row)?) {
+                    (Value::VarChar(s), Value::VarChar(p)) => Value::Bool(like(&s, &p) != *negated),
+                    _ => Value::Null,
+                }
+            }
+        })
+    }
+
+    /// Whether the expression reads no columns.
+    pub fn is_constant(&self) -> bool {
+        match self {
+            BoundExpr::Column(_) => false,
+            BoundExpr::Literal(_) => true,
+            BoundExpr::Neg(e) | BoundExpr::Not(e) | BoundExpr::IsNull { expr: e, .. } => e.is_constant(),
+            BoundExpr::Binary(_, l, r) => l.is_constant() && r.is_constant(),
+            BoundExpr::InList { expr, list, .. } => {
+                expr.is_constant() && list.iter().all(BoundExpr::is_constant)
+            }
+            BoundExpr::Like { expr, pattern, .. } => expr.is_constant() && pattern.is_constant(),
+        }
+    }
+}
+
+/// SQL-like text for plan displays, with columns written by position:
+/// `$2 > 10 AND $0 LIKE 'a%'`.
+impl fmt::Display for BoundExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list = |f: &mut fmt::Formatter<'_>, items: &[BoundExpr]| {
+            for (i, item) in items.iter().enumerate() {
+                write!(f, "{}{}", if i > 0 { ", " } else { "" }, item)?;
+            }
+            Ok(())
+        };
+        let not = |negated: &bool| if *negated { "NOT " } else { "" };
+        match self {
+            BoundExpr::Column(i) => write!(f, "${}", i),
+            BoundExpr::Literal(v @ (Value::VarChar(_) | Value::Date(_) | Value::Timestamp(_) | Value::Uuid(_))) => {
+                write!(f, "'{}'", v.to_string().replace('\'', "''"))
+            }
+            BoundExpr::Literal(v) => write!(f, "{}", v),
+            BoundExpr::Neg(e) => write!(f, "-({})", e),
+            BoundExpr::Not(e) => write!(f, "NOT ({})", e),
+            BoundExpr::Binary(op, l, r) => write!(f, "({} {} {})", l, op.symbol(), r),
+            BoundExpr::IsNull { expr, negated } => write!(f, "{} IS {}NULL", expr, not(negated)),
+            BoundExpr::InList { expr, list: items, negated } => {
+                write!(f, "{} {}IN (", expr, not(negated))?;
+                list(f, items)?;
+                write!(f, ")")
+            }
+            BoundExpr::Like { expr, pattern, negated } => {
+                write!(f, "{} {}LIKE {}", expr, not(negated), pattern)
+            }
+        }
+    }
+}
+
+/// Whether a WHERE clause keeps a row: only a true result does.
+pub fn is_true(v: &Value) -> bool {
+    matches!(v, Value::Bool(true))
+}
+
+fn truth(v: &Value) -> Option<bool> {
+    match v {
+        Value::Bool(b) => Some(*b),
+        _ => None,
+    }
+}
+
+// ── Type families ──────────────────────────────────────────────────
+
+/// Groups of column types that mix in comparisons and arithmetic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Family {
+    Bool,
+    Integer,
+    Float,
+    Decimal,
+    Text,
+    Bytes,
+    Date,
+    Timestamp,
+    Uuid,
+}
+
+impl Family {
+    pub fn of(t: &ColumnType) -> Family {
+        match t {
+            ColumnType::Bool => Family::Bool,
+            ColumnType::Int16 | ColumnType::Int32 | ColumnType::UInt32 | ColumnType::Int64 => {
+                Family::Integer
+            }
+            ColumnType::Float32 | ColumnType::Float64 => Family::Float,
+            ColumnType::Decimal(..) => Family::Decimal,
+            ColumnType::VarChar(_) | ColumnType::Char(_) => Family::Text,
+            ColumnType::Blob(_) => Family::Bytes,
+            ColumnType::Date => Family::Date,
+            ColumnType::Timestamp => Family::Timestamp,
+            ColumnType::Uuid => Family::Uuid,
+        }
+    }
+
+    pub fn is_numeric(self) -> bool {
+        matches!(self, Family::Integer | Family::Float | Family::Decimal)
+    }
+
+    /// Whether values of the two families can be compared.
+    pub fn comparable(self, other: Family) -> bool {
+        self == other || (self.is_numeric() && other.is_numeric())
+    }
+}
+
+// ── Numbers ────────────────────────────────────────────────────────
+
+fn as_i128(v: &Value) -> Option<i128> {
+    match v {
+        Value::Int16(n) => Some(*n as i128),
+        Value::Int32(n) => Some(*n as i128),
+        Value::UInt32(n) => Some(*n as i128),
+        Value::Int64(n) => Some(*n as i128),
+        _ => None,
+    }
+}
+
+fn as_f64(v: &Value) -> Option<f64> {
+    match v {
+        Value::Float32(f) => Some(*f as f64),
+        Value::Float64(f) => Some(*f),
+        Value::Decimal(n, s) => Some(*n as f64 / 10f64.powi(*s as i32)),
+        _ => as_i128(v).map(|n| n as f64),
+    }
+}
+
+/// An integer or Decimal as (unscaled, scale).
+fn as_decimal(v: &Value) -> Option<(i128, u8)> {
+    match v {
+        Value::Decimal(n, s) => Some((*n as i128, *s)),
+        _ => as_i128(v).map(|n| (n, 0)),
+    }
+}
+
+fn pow10(n: u8) -> i128 {
+    10i128.pow(n as u32)
+}
+
+/// Order two non-NULL values, mixing numeric types by value.  `None` if
+/// either is NULL.
+pub fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    if *a == Value::Null || *b == Value::Null {
+        return None;
+    }
+    if let (Some(x), Some(y)) = (as_i128(a), as_i128(b)) {
+        return Some(x.cmp(&y));
+    }
+    let is_float = |v: &Value| matches!(v, Value::Float32(_) | Value::Float64(_));
+    if (is_float(a) || is_float(b))
+        && let (Some(x), Some(y)) = (as_f64(a), as_f64(b))
+    {
+        return Some(x.total_cmp(&y));
+    }
+    if let (Some((x, xs)), Some((y, ys))) = (as_decimal(a), as_decimal(b)) {
+        let scale = xs.max(ys);
+        return Some((x * pow10(scale - xs)).cmp(&(y * pow10(scale - ys))));
+    }
+    Some(cmp_values(a, b))
+}
+
+fn negate(v: Value) -> StorageResult<Value> {
+    Ok(match v {
+        Value::Null => Value::Null,
+        Value::Float32(f) => Value::Float64(-(f as f64)),
+        Value::Float64(f) => Value::Float64(-f),
+        Value::Decimal(n, s) => Value::Decimal(n.checked_neg().ok_or_else(|| overflow("-"))?, s),
+        v => {
+            let n = as_i128(&v).ok_or_else(|| query_error(format!("Can't negate {}", v)))?;
+            Value::Int64(i64::try_from(-n).map_err(|_| overflow("-"))?)
+        }
+    })
+}
+
+/// `a + b`, as the `+` operator computes it: for running sums.
+pub fn add(a: Value, b: Value) -> StorageResult<Value> {
+    binary(BinaryOp::Add, a, b)
+}
+
+fn binary(op: BinaryOp, a: Value, b: Value) -> StorageResult<Value> {
+    if op.is_comparison() {
+        return Ok(match compare(&a, &b) {
+            None => Value::Null,
+            Some(ord) => Value::Bool(match op {
+                BinaryOp::Eq => ord == Ordering::Equal,
+                BinaryOp::NotEq => ord != Ordering::Equal,
+                BinaryOp::Lt => ord == Ordering::Less,
+                BinaryOp::LtEq => ord != Ordering::Greater,
+                BinaryOp::Gt => ord == Ordering::Greater,
+                _ => ord != Ordering::Less,
+            }),
+        });
+    }
+    if a == Value::Null || b == Value::Null {
+        return Ok(Value::Null);
+    }
+    if op == BinaryOp::Concat {
+        let text = |v: Value| match v {
+            Value::VarChar(s) => s,
+            v => v.to_string(),
+        };
+        return Ok(Value::VarChar(text(a) + &text(b)));
+    }
+
+    let zero_divisor = matches!(op, BinaryOp::Div | BinaryOp::Mod) && as_f64(&b) == Some(0.0);
+    if zero_divisor {
+        return Err(query_error("Division by zero".into()));
+    }
+    if let (Some(x), Some(y)) = (as_i128(&a), as_i128(&b)) {
+        let n = match op {
+            BinaryOp::Add => x + y,
+            BinaryOp::Sub => x - y,
+            BinaryOp::Mul => x * y,
+            BinaryOp::Div => x / y,
+            _ => x % y,
+        };
+        return Ok(Value::Int64(i64::try_from(n).map_err(|_| overflow(op.symbol()))?));
+    }
+    let is_float = |v: &Value| matches!(v, Value::Float32(_) | Value::Float64(_));
+    if !is_float(&a) && !is_float(&b) && op != BinaryOp::Div
+        && let (Some((x, xs)), Some((y, ys))) = (as_decimal(&a), as_decimal(&b))
+    {
+        let (n, scale) = match op {
+            BinaryOp::Mul => (x * y, xs + ys),
+            BinaryOp::Mod => {
+                let scale = xs.max(ys);
+                ((x * pow10(scale - xs)) % (y * pow10(scale - ys)), scale)
+            }
+            _ => {
+                let scale = xs.max(ys);
+                let (x, y) = (x * pow10(scale - xs), y * pow10(scale - ys));
+                (if op == BinaryOp::Add { x + y } else { x - y }, scale)
+            }
+        };
+        let n = i64::try_from(n).map_err(|_| overflow(op.symbol()))?;
+        return Ok(Value::Decimal(n, scale));
+    }
+    let (Some(x), Some(y)) = (as_f64(&a), as_f64(&b)) else {
+        return Err(query_error(format!("Can't apply {} to {} and {}", op.symbol(), a, b)));
+    };
+    Ok(Value::Float64(match op {
+        BinaryOp::Add => x + y,
+        BinaryOp::Sub => x - y,
+        BinaryOp::Mul => x * y,
+        BinaryOp::Div => x / y,
+        _ => x % y,
+    }))
+}
+
+/// SQL LIKE: `%` matches any run of characters, `_` any one character.
+fn like(s: &str, pattern: &str) -> bool {
+    let s: Vec<char> = s.chars().collect();
+    let p: Vec<char> = pattern.chars().collect();
+    // Greedy match with backtracking to the last `%`.
+    let (mut i, mut j) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while i < s.len() {
+        if j < p.len() && (p[j] == '_' || p[j] == s[i]) {
+            i += 1;
+            j += 1;
+        } else if j < p.len() && p[j] == '%' {
+            star = Some((j, i));
+            j += 1;
+        } else if let Some((sj, si)) = star {
+            j = sj + 1;
+            i = si + 1;
+            star = Some((sj, si + 1));
+        } else {
+            return false;
+        }
+    }
+    p[j..].iter().all(|&c| c == '%')
+}
+
+// ── Conversions ────────────────────────────────────────────────────
+
+/// Convert a value for storage in a column of type `target`.  Numbers
+/// convert between widths when the value fits (Decimals round to the
+/// target scale), text parses as a Date, Timestamp, Uuid or Decimal, and a
+/// Date widens to a Timestamp at midnight.  NULL stays NULL.
+pub fn cast(v: &Value, target: &ColumnType) -> StorageResult<Value> {
+    let fail = || query_error(format!("{} is not a valid {}", v, target));
+    if *v == Value::Null {
+        return Ok(Value::Null);
+    }
+    let is_float = matches!(v, Value::Float32(_) | Value::Float64(_));
+    let cast = match target {
+        ColumnType::Int16 | ColumnType::Int32 | ColumnType::UInt32 | ColumnType::Int64 => {
+            // Exact values only: integers, and Decimals with no fraction
+            let n = match as_decimal(v) {
+                Some((n, s)) if n % pow10(s) == 0 => n / pow10(s),
+                _ => return Err(fail()),
+            };
+            match target {
+                ColumnType::Int16 => i16::try_from(n).ok().map(Value::Int16),
+                ColumnType::Int32 => i32::try_from(n).ok().map(Value::Int32),
+                ColumnType::UInt32 => u32::try_from(n).ok().map(Value::UInt32),
+                _ => i64::try_from(n).ok().map(Value::Int64),
+            }
+        }
+        ColumnType::Float32 => as_f64(v).map(|f| Value::Float32(f as f32)),
+        ColumnType::Float64 => as_f64(v).map(Value::Float64),
+        ColumnType::Decimal(_, scale) => match v {
+            Value::VarChar(s) => parse_decimal(s.trim(), *scale).map(|n| Value::Decimal(n, *scale)),
+            _ if is_float => as_f64(v)
+                .map(|f| (f * 10f64.powi(*scale as i32)).round())
+                .filter(|f| f.is_finite() && f.abs() < i64::MAX as f64)
+                .map(|f| Value::Decimal(f as i64, *scale)),
+            _ => as_decimal(v).and_then(|(n, s)| {
+                let n = if s <= *scale {
+                    n * pow10(scale - s)
+                } else {
+                    // Round half away from zero
+                    let d = pow10(s - scale);
+                    (n + n.signum() * d / 2) / d
+                };
+                i64::try_from(n).ok().map(|n| Value::Decimal(n, *scale))
+            }),
+        },
+        ColumnType::VarChar(_) => match v {
+            Value::VarChar(_) => Some(v.clone()),
+            _ => None,
+        },
+        // Char values are kept without their padding
+        ColumnType::Char(_) => match v {
+            Value::VarChar(s) => Some(Value::VarChar(s.trim_end_matches(' ').into())),
+            _ => None,
+        },
+        ColumnType::Blob(_) => match v {
+            Value::Blob(_) => Some(v.clone()),
+            _ => None,
+        },
+        ColumnType::Bool => match v {
+            Value::Bool(_) => Some(v.clone()),
+            _ => None,
+        },
+        ColumnType::Date => match v {
+            Value::Date(_) => Some(v.clone()),
+            Value::VarChar(s) => datetime::parse_date(s).map(Value::Date),
+            _ => None,
+        },
+        ColumnType::Timestamp => match v {
+            Value::Timestamp(_) => Some(v.clone()),
+            Value::Date(d) => Some(Value::Timestamp(*d as i64 * 86_400_000_000)),
+            Value::VarChar(s) => datetime::parse_timestamp(s).map(Value::Timestamp),
+            _ => None,
+        },
+        ColumnType::Uuid => match v {
+            Value::Uuid(_) => Some(v.clone()),
+            Value::VarChar(s) => parse_uuid(s).map(Value::Uuid),
+            _ => None,
+        },
+    };
+    cast.ok_or_else(fail)
+}
+
+/// Whether `cast` can ever turn a `from` value into a `to` one.
+pub fn can_cast(from: &ColumnType, to: &ColumnType) -> bool {
+    let (from, to) = (Family::of(from), Family::of(to));
+    from == to
+        || (from.is_numeric() && to.is_numeric())
+        || (from == Family::Text && matches!(to, Family::Date | Family::Timestamp | Family::Uuid | Family::Decimal))
+        || (from == Family::Date && to == Family::Timestamp)
+}
+
+pub fn overflow(op: &str) -> StorageError {
+    query_error(format!("Numeric overflow in {}", op))
+}
+
+pub fn query_error(message: String) -> StorageError {
+    StorageError::Query(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lit(v: Value) -> Box<BoundExpr> {
+        Box::new(BoundExpr::Literal(v))
+    }
+
+    fn eval(op: BinaryOp, a: Value, b: Value) -> StorageResult<Value> {
+        BoundExpr::Binary(op, lit(a), lit(b)).eval(&[])
+    }
+
+    #[test]
+    fn arithmetic_and_comparison() {
+        assert_eq!(eval(BinaryOp::Add, Value::Int32(2), Value::Int16(3)), Ok(Value::Int64(5)));
+        assert_eq!(eval(BinaryOp::Div, Value::Int32(7), Value::Int32(2)), Ok(Value::Int64(3)));
+        assert_eq!(eval(BinaryOp::Div, Value::Int32(7), Value::Int32(0)), Err(query_error("Division by zero".into())));
+        assert_eq!(
+            eval(BinaryOp::Mul, Value::Int64(i64::MAX), Value::Int32(2)),
+            Err(overflow("*"))
+        );
+        assert_eq!(eval(BinaryOp::Add, Value::Decimal(150, 2), Value::Int32(1)), Ok(Value::Decimal(250, 2)));
+        assert_eq!(eval(BinaryOp::Mul, Value::Decimal(15, 1), Value::Decimal(15, 1)), Ok(Value::Decimal(225, 2)));
+        assert_eq!(eval(BinaryOp::Add, Value::Float64(0.5), Value::Decimal(15, 1)), Ok(Value::Float64(2.0)));
+        assert_eq!(eval(BinaryOp::Lt, Value::Int32(-1), Value::UInt32(0)), Ok(Value::Bool(true)));
+        assert_eq!(eval(BinaryOp::Eq, Value::Decimal(10, 1), Value::Int64(1)), Ok(Value::Bool(true)));
+        assert_eq!(eval(BinaryOp::Eq, Value::Null, Value::Int64(1)), Ok(Value::Null));
+        assert_eq!(
+            eval(BinaryOp::Concat, Value::VarChar("id ".into()), Value::Int32(4)),
+            Ok(Value::VarChar("id 4".into()))
+        );
+    }
+
+    #[test]
+    fn three_valued_logic() {
+        let and = |a, b| eval(BinaryOp::And, a, b).unwrap();
+        let or = |a, b| eval(BinaryOp::Or, a, b).unwrap();
+        assert_eq!(and(Value::Null, Value::Bool(false)), Value::Bool(false));
+        assert_eq!(and(Value::Null, Value::Bool(true)), Value::Null);
+        assert_eq!(or(Value::Null, Value::Bool(true)), Value::Bool(true));
+        assert_eq!(or(Value::Null, Value::Bool(false)), Value::Null);
+
+        let in_list = |v, negated| {
+            BoundExpr::InList {
+                expr: lit(v),
+                list: vec![BoundExpr::Literal(Value::Int32(1)), BoundExpr::Literal(Value::Null)],
+                negated,
+            }
+            .eval(&[])
+            .unwrap()
+        };
+        assert_eq!(in_list(Value::Int64(1), false), Value::Bool(true));
+        assert_eq!(in_list(Value::Int64(2), false), Value::Null);
+        assert_eq!(in_list(Value::Int64(2), true), Value::Null);
+    }
+
+    #[test]
+    fn like_patterns() {
+        assert!(like("alice", "a%"));
+        assert!(like("alice", "%c_"));
+        assert!(like("", "%"));
+        assert!(like("a%b", "a%b"));
+        assert!(!like("alice", "b%"));
+        assert!(!like("alice", "_"));
+        assert!(like("mississippi", "%ss%pi"));
+    }
+
+    #[test]
+    fn casts() {
+        assert_eq!(cast(&Value::Int64(300), &ColumnType::Int16), Ok(Value::Int16(300)));
+        assert!(cast(&Value::Int64(70_000), &ColumnType::Int16).is_err());
+        assert!(cast(&Value::Int64(-1), &ColumnType::UInt32).is_err());
+        assert_eq!(cast(&Value::Decimal(1005, 3), &ColumnType::Decimal(10, 2)), Ok(Value::Decimal(101, 2)));
+        assert_eq!(cast(&Value::Decimal(-1005, 3), &ColumnType::Decimal(10, 2)), Ok(Value::Decimal(-101, 2)));
+        assert_eq!(cast(&Value::Decimal(200, 2), &ColumnType::Int32), Ok(Value::Int32(2)));
+        assert!(cast(&Value::Float64(1.5), &ColumnType::Int32).is_err());
+        assert_eq!(cast(&Value::Float64(0.125), &ColumnType::Decimal(4, 2)), Ok(Value::Decimal(13, 2)));
+        assert_eq!(cast(&Value::VarChar("2024-02-29".into()), &ColumnType::Date), Ok(Value::Date(19_782)));
+        assert_eq!(cast(&Value::Date(1), &ColumnType::Timestamp), Ok(Value::Timestamp(86_400_000_000)));
+        assert_eq!(
+            cast(&Value::VarChar("x".into()), &ColumnType::Int32).unwrap_err().to_string(),
+            "x is not a valid Int32"
+        );
+        assert!(can_cast(&ColumnType::VarChar(8), &ColumnType::Uuid));
+        assert!(!can_cast(&ColumnType::Bool, &ColumnType::Int32));
+    }
+}
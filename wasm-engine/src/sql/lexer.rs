@@ -0,0 +1,227 @@
+//! Splits SQL text into tokens, each remembering where it starts so the
+//! parser can point at the offending one.
+
+use std::fmt;
+
+use crate::storage::error::{StorageError, StorageResult};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    /// A bare word: a keyword or an unquoted name, as written.
+    Word(String),
+    /// A `"quoted"` name, never a keyword.
+    QuotedName(String),
+    /// A number exactly as written: `42`, `1.5`, `.5`, `2e10`.
+    Number(String),
+    /// A `'string'` literal, with `''` unescaped.
+    String(String),
+    /// An `X'00ff'` byte literal.
+    Bytes(Vec<u8>),
+    /// Punctuation and operators: `( ) , ; . * + - / % = <> != < <= > >= ||`.
+    Symbol(&'static str),
+    Eof,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Word(w) => write!(f, "'{}'", w),
+            Token::QuotedName(n) => write!(f, "\"{}\"", n),
+            Token::Number(n) => write!(f, "{}", n),
+            Token::String(s) => write!(f, "'{}'", s.replace('\'', "''")),
+            Token::Bytes(_) => write!(f, "a byte literal"),
+            Token::Symbol(s) => write!(f, "'{}'", s),
+            Token::Eof => write!(f, "end of input"),
+        }
+    }
+}
+
+/// A token and the byte offset where it starts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spanned {
+    pub token: Token,
+    pub offset: usize,
+}
+
+const SYMBOLS: [&str; 18] = [
+    "<>", "!=", "<=", ">=", "||", "(", ")", ",", ";", ".", "*", "+", "-", "/", "%", "=", "<", ">",
+];
+
+/// Tokenize `text`; the last token is always `Eof`.  `--` comments run to
+/// the end of the line.
+pub fn tokenize(text: &str) -> StorageResult<Vec<Spanned>> {
+    let bytes = text.as_bytes();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let c = bytes[pos];
+        let start = pos;
+        if c.is_ascii_whitespace() {
+            pos += 1;
+            continue;
+        }
+        if text[pos..].starts_with("--") {
+            pos = text[pos..].find('\n').map_or(bytes.len(), |n| pos + n);
+            continue;
+        }
+
+        let token = if (c == b'x' || c == b'X') && bytes.get(pos + 1) == Some(&b'\'') {
+            let (hex, end) = quoted(text, pos + 1, b'\'')?;
+            pos = end;
+            Token::Bytes(decode_hex(&hex).ok_or_else(|| {
+                syntax_error(text, start, "Byte literals need an even number of hex digits")
+            })?)
+        } else if c.is_ascii_alphabetic() || c == b'_' {
+            while pos < bytes.len() && (bytes[pos].is_ascii_alphanumeric() || bytes[pos] == b'_') {
+                pos += 1;
+            }
+            Token::Word(text[start..pos].to_string())
+        } else if c.is_ascii_digit() || (c == b'.' && bytes.get(pos + 1).is_some_and(u8::is_ascii_digit)) {
+            pos = number_end(bytes, pos);
+            Token::Number(text[start..pos].to_string())
+        } else if c == b'\'' {
+            let (s, end) = quoted(text, pos, b'\'')?;
+            pos = end;
+            Token::String(s)
+        } else if c == b'"' {
+            let (s, end) = quoted(text, pos, b'"')?;
+            pos = end;
+            Token::QuotedName(s)
+        } else if let Some(sym) = SYMBOLS.iter().find(|s| text[pos..].starts_with(*s)) {
+            pos += sym.len();
+            Token::Symbol(sym)
+        } else {
+            let ch = text[pos..].chars().next().unwrap();
+            return Err(syntax_error(text, start, &format!("Unexpected character '{}'", ch)));
+        };
+        tokens.push(Spanned { token, offset: start });
+    }
+    tokens.push(Spanned { token: Token::Eof, offset: text.len() });
+    Ok(tokens)
+}
+
+/// End of the number starting at `pos`: digits, an optional fraction and
+/// an optional exponent.
+fn number_end(bytes: &[u8], mut pos: usize) -> usize {
+    let digits = |pos: &mut usize| {
+        while *pos < bytes.len() && bytes[*pos].is_ascii_digit() {
+            *pos += 1;
+        }
+    };
+    digits(&mut pos);
+    if bytes.get(pos) == Some(&b'.') {
+        pos += 1;
+        digits(&mut pos);
+    }
+    if matches!(bytes.get(pos), Some(b'e' | b'E')) {
+        let mut exp = pos + 1;
+        if matches!(bytes.get(exp), Some(b'+' | b'-')) {
+            exp += 1;
+        }
+        if bytes.get(exp).is_some_and(u8::is_ascii_digit) {
+            pos = exp;
+            digits(&mut pos);
+        }
+    }
+    pos
+}
+
+/// Read a literal enclosed in `quote` starting at `pos`, where a doubled
+/// quote stands for one.  Returns the contents and the offset just past it.
+fn quoted(text: &str, pos: usize, quote: u8) -> StorageResult<(String, usize)> {
+    let bytes = text.as_bytes();
+    let mut out = String::new();
+    let mut i = pos + 1;
+    let mut run = i;
+    loop {
+        match bytes.get(i) {
+            None => return Err(syntax_error(text, pos, "Unterminated quoted text")),
+            Some(&b) if b == quote => {
+                out.push_str(&text[run..i]);
+                if bytes.get(i + 1) == Some(&quote) {
+                    out.push(quote as char);
+                    i += 2;
+                    run = i;
+                } else {
+                    return Ok((out, i + 1));
+                }
+            }
+            Some(_) => i += 1,
+        }
+    }
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// A `Parse` error pointing at byte `offset` of `text`.
+pub fn syntax_error(text: &str, offset: usize, message: &str) -> StorageError {
+    let before = &text[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let column = before[line_start..].chars().count() + 1;
+    StorageError::Parse(format!("{} at line {}, column {}", message, line, column))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(text: &str) -> Vec<Token> {
+        tokenize(text).unwrap().into_iter().map(|s| s.token).collect()
+    }
+
+    #[test]
+    fn tokens_and_literals() {
+        assert_eq!(
+            tokens("SELECT a, \"b c\" FROM t -- note\nWHERE x <= 1.5e3 AND s = 'it''s' || X'0aFF';"),
+            vec![
+                Token::Word("SELECT".into()),
+                Token::Word("a".into()),
+                Token::Symbol(","),
+                Token::QuotedName("b c".into()),
+                Token::Word("FROM".into()),
+                Token::Word("t".into()),
+                Token::Word("WHERE".into()),
+                Token::Word("x".into()),
+                Token::Symbol("<="),
+                Token::Number("1.5e3".into()),
+                Token::Word("AND".into()),
+                Token::Word("s".into()),
+                Token::Symbol("="),
+                Token::String("it's".into()),
+                Token::Symbol("||"),
+                Token::Bytes(vec![0x0a, 0xff]),
+                Token::Symbol(";"),
+                Token::Eof,
+            ]
+        );
+        assert_eq!(tokens(".5 t.a"), vec![
+            Token::Number(".5".into()),
+            Token::Word("t".into()),
+            Token::Symbol("."),
+            Token::Word("a".into()),
+            Token::Eof,
+        ]);
+    }
+
+    #[test]
+    fn errors_point_at_the_input() {
+        assert_eq!(
+            tokenize("SELECT\n  'abc").unwrap_err(),
+            StorageError::Parse("Unterminated quoted text at line 2, column 3".into())
+        );
+        assert_eq!(
+            tokenize("SELECT #").unwrap_err(),
+            StorageError::Parse("Unexpected character '#' at line 1, column 8".into())
+        );
+        assert!(tokenize("SELECT x'abc'").is_err());
+    }
+}
@@ -0,0 +1,273 @@
+//! SQL front end.
+//!
+//! A statement goes through three stages: `lexer` and `parser` turn the
+//! text into a syntax tree, `binder` resolves its names against the
+//! tables' schemas and type-checks it, and `execute` runs the bound
+//! statement: a SELECT as a plan of `exec` operators, anything else
+//! through `Database`.  The dialect is a practical subset:
+//!
+//! ```sql
+//! CREATE TABLE t (id INT NOT NULL, name VARCHAR(40), price DECIMAL(8,2))
+//! DROP TABLE [IF EXISTS] t
+//! INSERT INTO t [(col, …)] VALUES (…), (…)
+//! SELECT expr [AS alias], … | * [FROM t [alias]] [WHERE …]
+//!     [ORDER BY expr|alias|position [ASC|DESC], …] [LIMIT n] [OFFSET n]
+//! UPDATE t SET col = expr, … [WHERE …]
+//! DELETE FROM t [WHERE …]
+//! ```
+//!
+//! Expressions have the usual arithmetic, comparison, `AND`/`OR`/`NOT`,
+//! `||`, `IS [NOT] NULL`, `[NOT] BETWEEN`, `[NOT] IN (…)` and `[NOT] LIKE`.
+//! Syntax errors are `Parse` errors pointing at a line and column; a
+//! statement that parses but can't run fails with `Query` (or the storage
+//! error for a missing table or column).
+//!
+//! ORDER BY sorts in all but one of the buffer pool's frames, spilling
+//! sorted runs to temp pages when the rows don't fit (`exec::sort`).
+//!
+//! An INSERT, UPDATE or DELETE is atomic: if one row fails, the rows it
+//! already changed are undone too.
+
+pub mod ast;
+pub mod binder;
+pub mod expr;
+pub mod lexer;
+pub mod parser;
+
+use crate::database::Database;
+use crate::exec::operators::{Filter, Limit, Projection, SeqScan, Values};
+use crate::exec::sort::ExternalSort;
+use crate::exec::{self, Operator, PlanProfile};
+use crate::storage::error::{StorageError, StorageResult};
+use crate::storage::schema::*;
+use crate::storage::types::TxnId;
+use binder::{BoundSelect, BoundStatement};
+use expr::{BoundExpr, is_true};
+
+/// What a statement returns: the rows of a SELECT, or the number of rows
+/// an INSERT, UPDATE or DELETE changed.
+#[derive(Debug, Clone)]
+pub struct QueryResult {
+    /// Output columns; empty for statements other than SELECT.
+    pub columns: Vec<Column>,
+    pub rows: Vec<Vec<Value>>,
+    pub rows_affected: usize,
+    /// The executed plan with each operator's rows and I/O, for a SELECT.
+    pub plan: Option<PlanProfile>,
+}
+
+impl QueryResult {
+    fn affected(rows_affected: usize) -> Self {
+        Self { columns: Vec::new(), rows: Vec::new(), rows_affected, plan: None }
+    }
+}
+
+/// Parse, bind and run one statement.  Row changes join `txn` if given;
+/// CREATE and DROP always commit on their own.
+pub fn execute(db: &mut Database, text: &str, txn: Option<TxnId>) -> StorageResult<QueryResult> {
+    let stmt = binder::bind(db, parser::parse(text)?)?;
+    match stmt {
+        BoundStatement::CreateTable { name, schema } => {
+            db.create_table(&name, schema)?;
+            Ok(QueryResult::affected(0))
+        }
+        BoundStatement::DropTable { name, if_exists } => {
+            if !db.drop_table(&name)? && !if_exists {
+                return Err(StorageError::TableNotFound(name));
+            }
+            Ok(QueryResult::affected(0))
+        }
+        BoundStatement::Select(select) => run_select(db, &select),
+        stmt => atomically(db, txn, |db, txn| run_change(db, txn, stmt)),
+    }
+}
+
+/// Run `f` in `txn`, or in its own transaction if none is given, undoing
+/// all of its changes if it fails.  If the undo itself fails, its error is
+/// returned instead and the transaction stays active (and listed by
+/// `Database::active_transactions`) for the caller to abort again.
+fn atomically<T>(
+    db: &mut Database,
+    txn: Option<TxnId>,
+    f: impl FnOnce(&mut Database, TxnId) -> StorageResult<T>,
+) -> StorageResult<T> {
+    match txn {
+        Some(txn) => {
+            let savepoint = db.savepoint(txn)?;
+            f(db, txn).or_else(|e| {
+                db.rollback_to(txn, savepoint)?;
+                Err(e)
+            })
+        }
+        None => {
+            let txn = db.begin();
+            match f(db, txn) {
+                Ok(out) => {
+                    db.commit(txn)?;
+                    Ok(out)
+                }
+                Err(e) => {
+                    db.abort(txn)?;
+                    Err(e)
+                }
+            }
+        }
+    }
+}
+
+fn run_change(db: &mut Database, txn: TxnId, stmt: BoundStatement) -> StorageResult<QueryResult> {
+    let mut affected = 0;
+    match stmt {
+        BoundStatement::Insert { table, rows } => {
+            for row in &rows {
+                db.insert(&table, row, Some(txn))?;
+                affected += 1;
+            }
+        }
+        BoundStatement::Update { table, assignments, filter } => {
+            let schema = db.table(&table)?.schema.clone();
+            for (row_id, old) in db.scan(&table)? {
+                if !matches(filter.as_ref(), &old)? {
+                    continue;
+                }
+                let mut new = old.clone();
+                for (col, expr) in &assignments {
+                    new[*col] = expr::cast(&expr.eval(&old)?, &schema.columns[*col].col_type)?;
+                }
+                db.update(&table, row_id, &new, Some(txn))?;
+                affected += 1;
+            }
+        }
+        BoundStatement::Delete { table, filter } => {
+            for (row_id, row) in db.scan(&table)? {
+                if matches(filter.as_ref(), &row)? {
+                    db.delete(&table, row_id, Some(txn))?;
+                    affected += 1;
+                }
+            }
+        }
+        _ => unreachable!("not a row change"),
+    }
+    Ok(QueryResult::affected(affected))
+}
+
+/// Build the SELECT's plan — scan, filter, sort, limit, project — and
+/// run it.
+fn run_select(db: &mut Database, select: &BoundSelect) -> StorageResult<QueryResult> {
+    let mut plan: Box<dyn Operator> = match &select.table {
+        Some(table) => Box::new(SeqScan::new(db.table(table)?)),
+        None => Box::new(Values::new(vec![Vec::new()])),
+    };
+    if let Some(filter) = &select.filter {
+        plan = Box::new(Filter::new(plan, filter.clone()));
+    }
+    if !select.order_by.is_empty() {
+        let frames = db.buffer_pool().pool_size() - 1;
+        plan = Box::new(ExternalSort::new(plan, select.order_by.clone(), frames));
+    }
+    if select.limit.is_some() || select.offset.is_some() {
+        plan = Box::new(Limit::new(plan, select.limit, select.offset.unwrap_or(0)));
+    }
+    let mut plan = Projection::new(plan, select.projection.clone());
+    let rows = exec::run(&mut plan, db.buffer_pool_mut())?;
+    Ok(QueryResult {
+        columns: select.columns.clone(),
+        rows,
+        rows_affected: 0,
+        plan: Some(plan.profile()),
+    })
+}
+
+/// Whether a row passes an optional WHERE clause.
+fn matches(filter: Option<&BoundExpr>, row: &[Value]) -> StorageResult<bool> {
+    match filter {
+        Some(f) => Ok(is_true(&f.eval(row)?)),
+        None => Ok(true),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::config::EngineConfig;
+
+    fn run(db: &mut Database, sql: &str) -> QueryResult {
+        execute(db, sql, None).unwrap()
+    }
+
+    fn rows(db: &mut Database, sql: &str) -> Vec<Vec<Value>> {
+        run(db, sql).rows
+    }
+
+    fn db() -> Database {
+        let mut db = Database::new(EngineConfig::default_config()).unwrap();
+        run(&mut db, "CREATE TABLE items (id INT NOT NULL, name VARCHAR(20), qty SMALLINT, price DECIMAL(6,2))");
+        let n = run(&mut db, "INSERT INTO items VALUES (1, 'apple', 10, 0.5), (2, 'pear', NULL, 1.25), (3, 'plum', 4, 2)");
+        assert_eq!(n.rows_affected, 3);
+        db
+    }
+
+    #[test]
+    fn select_filters_sorts_and_projects() {
+        let mut db = db();
+        let result = run(&mut db, "SELECT name, qty * price AS total FROM items WHERE qty IS NOT NULL ORDER BY total DESC");
+        assert_eq!(result.columns.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(), ["name", "total"]);
+        assert_eq!(result.columns[1].col_type, ColumnType::Decimal(18, 2));
+        assert_eq!(result.rows, vec![
+            vec![Value::VarChar("plum".into()), Value::Decimal(800, 2)],
+            vec![Value::VarChar("apple".into()), Value::Decimal(500, 2)],
+        ]);
+
+        // NULLs sort first
+        assert_eq!(
+            rows(&mut db, "SELECT id FROM items ORDER BY qty LIMIT 2 OFFSET 0"),
+            vec![vec![Value::Int32(2)], vec![Value::Int32(3)]]
+        );
+        assert_eq!(rows(&mut db, "SELECT id FROM items WHERE name LIKE 'p%' ORDER BY 1 DESC LIMIT 1 OFFSET 1"), vec![vec![Value::Int32(2)]]);
+        assert_eq!(
+            rows(&mut db, "SELECT 1 + 2 AS three, 'a' || 'b'"),
+            vec![vec![Value::Int64(3), Value::VarChar("ab".into())]]
+        );
+    }
+
+    #[test]
+    fn update_and_delete() {
+        let mut db = db();
+        assert_eq!(run(&mut db, "UPDATE items SET qty = qty + 1, price = price * 2 WHERE id <> 2").rows_affected, 2);
+        assert_eq!(
+            rows(&mut db, "SELECT qty, price FROM items WHERE id IN (1, 3) ORDER BY id"),
+            vec![
+                vec![Value::Int16(11), Value::Decimal(100, 2)],
+                vec![Value::Int16(5), Value::Decimal(400, 2)],
+            ]
+        );
+        assert_eq!(run(&mut db, "DELETE FROM items WHERE price BETWEEN 1 AND 2").rows_affected, 2);
+        assert_eq!(rows(&mut db, "SELECT id FROM items"), vec![vec![Value::Int32(3)]]);
+        run(&mut db, "DROP TABLE items");
+        assert_eq!(db.table_names(), Vec::<String>::new());
+        assert_eq!(execute(&mut db, "DROP TABLE items", None).unwrap_err(), StorageError::TableNotFound("items".into()));
+        run(&mut db, "DROP TABLE IF EXISTS items");
+    }
+
+    #[test]
+    fn failed_statements_change_nothing() {
+        let mut db = db();
+        // The third row overflows SMALLINT after the first two were updated
+        run(&mut db, "UPDATE items SET qty = 32767 WHERE id = 3");
+        let err = execute(&mut db, "UPDATE items SET qty = qty + 1 WHERE qty IS NOT NULL", None).unwrap_err();
+        assert_eq!(err, StorageError::Query("32768 is not a valid Int16".into()));
+        assert_eq!(rows(&mut db, "SELECT qty FROM items WHERE id = 1"), vec![vec![Value::Int16(10)]]);
+
+        // Inside a transaction only the failed statement is undone
+        let txn = db.begin();
+        execute(&mut db, "INSERT INTO items (id) VALUES (4)", Some(txn)).unwrap();
+        let err = execute(&mut db, "INSERT INTO items (id, name) VALUES (5, 'x'), (NULL, 'y')", Some(txn)).unwrap_err();
+        assert_eq!(err.code(), "SCHEMA_MISMATCH");
+        db.commit(txn).unwrap();
+        assert_eq!(
+            rows(&mut db, "SELECT id FROM items WHERE id > 3"),
+            vec![vec![Value::Int32(4)]]
+        );
+        assert_eq!(execute(&mut db, "SELECT 1 / 0", None).unwrap_err(), StorageError::Query("Division by zero".into()));
+    }
+}
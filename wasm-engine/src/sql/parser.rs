@@ -0,0 +1,631 @@
+//! Recursive-descent parser for the SQL subset:
+//!
+//! ```text
+//! CREATE TABLE t (col TYPE [NOT NULL | NULL], ...)
+//! DROP TABLE [IF EXISTS] t
+//! INSERT INTO t [(col, ...)] VALUES (expr, ...), ...
+//! SELECT items [FROM t [[AS] alias]] [WHERE expr]
+//!     [ORDER BY expr [ASC | DESC], ...] [LIMIT n] [OFFSET n]
+//! UPDATE t SET col = expr, ... [WHERE expr]
+//! DELETE FROM t [WHERE expr]
+//! ```
+//!
+//! Keywords are case-insensitive; names are case-sensitive, and a name
+//! that collides with a keyword can be written in double quotes.  Operator
+//! precedence, loosest first: `OR`, `AND`, `NOT`, comparisons (with `IS`,
+//! `BETWEEN`, `IN`, `LIKE`), `||`, `+ -`, `* / %`, unary minus.
+
+use crate::sql::ast::*;
+use crate::sql::lexer::{syntax_error, tokenize, Spanned, Token};
+use crate::storage::error::{StorageError, StorageResult};
+use crate::storage::schema::{Column, ColumnType};
+
+/// Words that can't be used as bare names.
+const RESERVED: &[&str] = &[
+    "SELECT", "FROM", "WHERE", "ORDER", "BY", "LIMIT", "OFFSET", "INSERT", "INTO", "VALUES",
+    "UPDATE", "SET", "DELETE", "CREATE", "DROP", "TABLE", "AND", "OR", "NOT", "NULL", "IS", "IN",
+    "BETWEEN", "LIKE", "AS", "ASC", "DESC", "TRUE", "FALSE", "JOIN", "ON", "GROUP", "HAVING",
+];
+
+/// Parse one statement, optionally followed by `;`.
+pub fn parse(text: &str) -> StorageResult<Statement> {
+    let mut p = Parser { text, tokens: tokenize(text)?, pos: 0 };
+    let stmt = p.statement()?;
+    p.eat_symbol(";");
+    if p.peek() != &Token::Eof {
+        return Err(p.unexpected("the end of the statement"));
+    }
+    Ok(stmt)
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    tokens: Vec<Spanned>,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    // ── Statements ──────────────────────────────────────────────────
+
+    fn statement(&mut self) -> StorageResult<Statement> {
+        if self.eat_keyword("SELECT") {
+            return Ok(Statement::Select(self.select()?));
+        }
+        if self.eat_keyword("INSERT") {
+            return self.insert();
+        }
+        if self.eat_keyword("UPDATE") {
+            return self.update();
+        }
+        if self.eat_keyword("DELETE") {
+            self.expect_keyword("FROM")?;
+            let table = self.name()?;
+            let filter = self.where_clause()?;
+            return Ok(Statement::Delete { table, filter });
+        }
+        if self.eat_keyword("CREATE") {
+            self.expect_keyword("TABLE")?;
+            return self.create_table();
+        }
+        if self.eat_keyword("DROP") {
+            self.expect_keyword("TABLE")?;
+            let if_exists = self.eat_keyword("IF");
+            if if_exists {
+                self.expect_keyword("EXISTS")?;
+            }
+            return Ok(Statement::DropTable { name: self.name()?, if_exists });
+        }
+        Err(self.unexpected("SELECT, INSERT, UPDATE, DELETE, CREATE or DROP"))
+    }
+
+    fn select(&mut self) -> StorageResult<Select> {
+        let mut items = Vec::new();
+        loop {
+            items.push(self.select_item()?);
+            if !self.eat_symbol(",") {
+                break;
+            }
+        }
+
+        let from = if self.eat_keyword("FROM") {
+            let name = self.name()?;
+            let alias = self.alias()?;
+            Some(TableRef { name, alias })
+        } else {
+            None
+        };
+        let filter = self.where_clause()?;
+
+        let mut order_by = Vec::new();
+        if self.eat_keyword("ORDER") {
+            self.expect_keyword("BY")?;
+            loop {
+                let expr = self.expr()?;
+                let descending = if self.eat_keyword("DESC") {
+                    true
+                } else {
+                    self.eat_keyword("ASC");
+                    false
+                };
+                order_by.push(OrderItem { expr, descending });
+                if !self.eat_symbol(",") {
+                    break;
+                }
+            }
+        }
+
+        let limit = if self.eat_keyword("LIMIT") { Some(self.count()?) } else { None };
+        let offset = if self.eat_keyword("OFFSET") { Some(self.count()?) } else { None };
+        Ok(Select { items, from, filter, order_by, limit, offset })
+    }
+
+    fn select_item(&mut self) -> StorageResult<SelectItem> {
+        if self.eat_symbol("*") {
+            return Ok(SelectItem::Wildcard(None));
+        }
+        // `t.*`
+        if let (Some(name), Token::Symbol("."), Token::Symbol("*")) =
+            (self.peek_name(), self.peek_at(1), self.peek_at(2))
+        {
+            self.pos += 3;
+            return Ok(SelectItem::Wildcard(Some(name)));
+        }
+        let expr = self.expr()?;
+        let alias = self.alias()?;
+        Ok(SelectItem::Expr { expr, alias })
+    }
+
+    /// `AS name`, or a bare name that isn't a keyword.
+    fn alias(&mut self) -> StorageResult<Option<String>> {
+        if self.eat_keyword("AS") {
+            return self.name().map(Some);
+        }
+        match self.peek_name() {
+            Some(name) => {
+                self.pos += 1;
+                Ok(Some(name))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn insert(&mut self) -> StorageResult<Statement> {
+        self.expect_keyword("INTO")?;
+        let table = self.name()?;
+        let columns = if self.eat_symbol("(") {
+            let names = self.comma_separated(Self::name)?;
+            self.expect_symbol(")")?;
+            Some(names)
+        } else {
+            None
+        };
+        self.expect_keyword("VALUES")?;
+        let mut rows = Vec::new();
+        loop {
+            self.expect_symbol("(")?;
+            rows.push(self.comma_separated(Self::expr)?);
+            self.expect_symbol(")")?;
+            if !self.eat_symbol(",") {
+                break;
+            }
+        }
+        Ok(Statement::Insert { table, columns, rows })
+    }
+
+    fn update(&mut self) -> StorageResult<Statement> {
+        let table = self.name()?;
+        self.expect_keyword("SET")?;
+        let assignments = self.comma_separated(|p| {
+            let column = p.name()?;
+            p.expect_symbol("=")?;
+            Ok((column, p.expr()?))
+        })?;
+        let filter = self.where_clause()?;
+        Ok(Statement::Update { table, assignments, filter })
+    }
+
+    fn create_table(&mut self) -> StorageResult<Statement> {
+        let name = self.name()?;
+        self.expect_symbol("(")?;
+        let columns = self.comma_separated(|p| {
+            let name = p.name()?;
+            let col_type = p.column_type()?;
+            let mut nullable = true;
+            if p.eat_keyword("NOT") {
+                p.expect_keyword("NULL")?;
+                nullable = false;
+            } else {
+                p.eat_keyword("NULL");
+            }
+            Ok(Column { name, col_type, nullable })
+        })?;
+        self.expect_symbol(")")?;
+        Ok(Statement::CreateTable { name, columns })
+    }
+
+    fn column_type(&mut self) -> StorageResult<ColumnType> {
+        let offset = self.offset();
+        let Token::Word(word) = self.peek().clone() else {
+            return Err(self.unexpected("a column type"));
+        };
+        self.pos += 1;
+        let col_type = match word.to_ascii_uppercase().as_str() {
+            "INT" | "INTEGER" | "INT4" | "INT32" => ColumnType::Int32,
+            "SMALLINT" | "INT2" | "INT16" => ColumnType::Int16,
+            "BIGINT" | "INT8" | "INT64" => ColumnType::Int64,
+            "UINT32" => ColumnType::UInt32,
+            "REAL" | "FLOAT4" | "FLOAT32" => ColumnType::Float32,
+            "FLOAT" | "FLOAT8" | "FLOAT64" => ColumnType::Float64,
+            "DOUBLE" => {
+                self.eat_keyword("PRECISION");
+                ColumnType::Float64
+            }
+            "BOOL" | "BOOLEAN" => ColumnType::Bool,
+            "DATE" => ColumnType::Date,
+            "TIMESTAMP" => ColumnType::Timestamp,
+            "UUID" => ColumnType::Uuid,
+            "VARCHAR" => ColumnType::VarChar(self.type_length()?),
+            "BLOB" | "VARBINARY" => ColumnType::Blob(self.type_length()?),
+            "CHAR" | "CHARACTER" => {
+                ColumnType::Char(if self.peek() == &Token::Symbol("(") { self.type_length()? } else { 1 })
+            }
+            "DECIMAL" | "NUMERIC" => {
+                self.expect_symbol("(")?;
+                let precision = self.small_number(u8::MAX as u64)? as u8;
+                let scale = if self.eat_symbol(",") { self.small_number(u8::MAX as u64)? as u8 } else { 0 };
+                self.expect_symbol(")")?;
+                ColumnType::Decimal(precision, scale)
+            }
+            _ => return Err(syntax_error(self.text, offset, &format!("Unknown type '{}'", word))),
+        };
+        Ok(col_type)
+    }
+
+    /// `(n)` after VARCHAR, BLOB or CHAR.
+    fn type_length(&mut self) -> StorageResult<u16> {
+        self.expect_symbol("(")?;
+        let n = self.small_number(u16::MAX as u64 - 1)? as u16;
+        self.expect_symbol(")")?;
+        Ok(n)
+    }
+
+    fn where_clause(&mut self) -> StorageResult<Option<Expr>> {
+        if self.eat_keyword("WHERE") { self.expr().map(Some) } else { Ok(None) }
+    }
+
+    /// A non-negative integer for LIMIT or OFFSET.
+    fn count(&mut self) -> StorageResult<u64> {
+        self.small_number(u64::MAX)
+    }
+
+    fn small_number(&mut self, max: u64) -> StorageResult<u64> {
+        match self.peek() {
+            Token::Number(n) => match n.parse::<u64>() {
+                Ok(v) if v <= max => {
+                    self.pos += 1;
+                    Ok(v)
+                }
+                _ => Err(self.unexpected(&format!("an integer from 0 to {}", max))),
+            },
+            _ => Err(self.unexpected("an integer")),
+        }
+    }
+
+    // ── Expressions ─────────────────────────────────────────────────
+
+    fn expr(&mut self) -> StorageResult<Expr> {
+        let mut left = self.and_expr()?;
+        while self.eat_keyword("OR") {
+            left = binary(BinaryOp::Or, left, self.and_expr()?);
+        }
+        Ok(left)
+    }
+
+    fn and_expr(&mut self) -> StorageResult<Expr> {
+        let mut left = self.not_expr()?;
+        while self.eat_keyword("AND") {
+            left = binary(BinaryOp::And, left, self.not_expr()?);
+        }
+        Ok(left)
+    }
+
+    fn not_expr(&mut self) -> StorageResult<Expr> {
+        if self.eat_keyword("NOT") {
+            let expr = self.not_expr()?;
+            return Ok(Expr::Unary { op: UnaryOp::Not, expr: Box::new(expr) });
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> StorageResult<Expr> {
+        let left = self.concat()?;
+        let op = match self.peek() {
+            Token::Symbol("=") => Some(BinaryOp::Eq),
+            Token::Symbol("<>" | "!=") => Some(BinaryOp::NotEq),
+            Token::Symbol("<") => Some(BinaryOp::Lt),
+            Token::Symbol("<=") => Some(BinaryOp::LtEq),
+            Token::Symbol(">") => Some(BinaryOp::Gt),
+            Token::Symbol(">=") => Some(BinaryOp::GtEq),
+            _ => None,
+        };
+        if let Some(op) = op {
+            self.pos += 1;
+            return Ok(binary(op, left, self.concat()?));
+        }
+
+        if self.eat_keyword("IS") {
+            let negated = self.eat_keyword("NOT");
+            self.expect_keyword("NULL")?;
+            return Ok(Expr::IsNull { expr: Box::new(left), negated });
+        }
+        let negated = self.eat_keyword("NOT");
+        if self.eat_keyword("BETWEEN") {
+            let low = self.concat()?;
+            self.expect_keyword("AND")?;
+            let high = self.concat()?;
+            return Ok(Expr::Between {
+                expr: Box::new(left),
+                low: Box::new(low),
+                high: Box::new(high),
+                negated,
+            });
+        }
+        if self.eat_keyword("IN") {
+            self.expect_symbol("(")?;
+            let list = self.comma_separated(Self::expr)?;
+            self.expect_symbol(")")?;
+            return Ok(Expr::InList { expr: Box::new(left), list, negated });
+        }
+        if self.eat_keyword("LIKE") {
+            let pattern = self.concat()?;
+            return Ok(Expr::Like { expr: Box::new(left), pattern: Box::new(pattern), negated });
+        }
+        if negated {
+            return Err(self.unexpected("BETWEEN, IN or LIKE"));
+        }
+        Ok(left)
+    }
+
+    fn concat(&mut self) -> StorageResult<Expr> {
+        let mut left = self.additive()?;
+        while self.eat_symbol("||") {
+            left = binary(BinaryOp::Concat, left, self.additive()?);
+        }
+        Ok(left)
+    }
+
+    fn additive(&mut self) -> StorageResult<Expr> {
+        let mut left = self.multiplicative()?;
+        loop {
+            let op = match self.peek() {
+                Token::Symbol("+") => BinaryOp::Add,
+                Token::Symbol("-") => BinaryOp::Sub,
+                _ => return Ok(left),
+            };
+            self.pos += 1;
+            left = binary(op, left, self.multiplicative()?);
+        }
+    }
+
+    fn multiplicative(&mut self) -> StorageResult<Expr> {
+        let mut left = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Token::Symbol("*") => BinaryOp::Mul,
+                Token::Symbol("/") => BinaryOp::Div,
+                Token::Symbol("%") => BinaryOp::Mod,
+                _ => return Ok(left),
+            };
+            self.pos += 1;
+            left = binary(op, left, self.unary()?);
+        }
+    }
+
+    fn unary(&mut self) -> StorageResult<Expr> {
+        if self.eat_symbol("-") {
+            // Fold the sign into a number so the most negative integers parse
+            if let Token::Number(n) = self.peek().clone() {
+                self.pos += 1;
+                return Ok(Expr::Literal(Literal::Number(format!("-{}", n))));
+            }
+            let expr = self.unary()?;
+            return Ok(Expr::Unary { op: UnaryOp::Neg, expr: Box::new(expr) });
+        }
+        if self.eat_symbol("+") {
+            return self.unary();
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> StorageResult<Expr> {
+        let token = self.peek().clone();
+        let literal = match token {
+            Token::Number(n) => Literal::Number(n),
+            Token::String(s) => Literal::String(s),
+            Token::Bytes(b) => Literal::Bytes(b),
+            Token::Symbol("(") => {
+                self.pos += 1;
+                let expr = self.expr()?;
+                self.expect_symbol(")")?;
+                return Ok(expr);
+            }
+            Token::Word(ref w) => {
+                let typed = match w.to_ascii_uppercase().as_str() {
+                    "NULL" => Some(Literal::Null),
+                    "TRUE" => Some(Literal::Bool(true)),
+                    "FALSE" => Some(Literal::Bool(false)),
+                    kw @ ("DATE" | "TIMESTAMP" | "UUID") => match self.peek_at(1).clone() {
+                        Token::String(s) => {
+                            let kind = match kw {
+                                "DATE" => TypedLiteral::Date,
+                                "TIMESTAMP" => TypedLiteral::Timestamp,
+                                _ => TypedLiteral::Uuid,
+                            };
+                            self.pos += 1;
+                            Some(Literal::Typed(kind, s))
+                        }
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                match typed {
+                    Some(literal) => literal,
+                    None => return self.column_ref(),
+                }
+            }
+            Token::QuotedName(_) => return self.column_ref(),
+            _ => return Err(self.unexpected("an expression")),
+        };
+        self.pos += 1;
+        Ok(Expr::Literal(literal))
+    }
+
+    /// `name` or `table.name`.
+    fn column_ref(&mut self) -> StorageResult<Expr> {
+        if self.peek_name().is_none() {
+            return Err(self.unexpected("an expression"));
+        }
+        let first = self.name()?;
+        if self.eat_symbol(".") {
+            let name = self.name()?;
+            return Ok(Expr::Column { table: Some(first), name });
+        }
+        Ok(Expr::Column { table: None, name: first })
+    }
+
+    // ── Tokens ──────────────────────────────────────────────────────
+
+    fn peek(&self) -> &Token {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, n: usize) -> &Token {
+        let i = (self.pos + n).min(self.tokens.len() - 1);
+        &self.tokens[i].token
+    }
+
+    fn offset(&self) -> usize {
+        self.tokens[self.pos].offset
+    }
+
+    /// The next token as a name, if it is one.
+    fn peek_name(&self) -> Option<String> {
+        match self.peek() {
+            Token::Word(w) if !is_reserved(w) => Some(w.clone()),
+            Token::QuotedName(n) => Some(n.clone()),
+            _ => None,
+        }
+    }
+
+    fn name(&mut self) -> StorageResult<String> {
+        let name = self.peek_name().ok_or_else(|| self.unexpected("a name"))?;
+        self.pos += 1;
+        Ok(name)
+    }
+
+    fn comma_separated<T>(
+        &mut self,
+        mut item: impl FnMut(&mut Self) -> StorageResult<T>,
+    ) -> StorageResult<Vec<T>> {
+        let mut items = vec![item(self)?];
+        while self.eat_symbol(",") {
+            items.push(item(self)?);
+        }
+        Ok(items)
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        match self.peek() {
+            Token::Word(w) if w.eq_ignore_ascii_case(keyword) => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> StorageResult<()> {
+        if self.eat_keyword(keyword) { Ok(()) } else { Err(self.unexpected(keyword)) }
+    }
+
+    fn eat_symbol(&mut self, symbol: &str) -> bool {
+        match self.peek() {
+            Token::Symbol(s) if *s == symbol => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> StorageResult<()> {
+        if self.eat_symbol(symbol) { Ok(()) } else { Err(self.unexpected(&format!("'{}'", symbol))) }
+    }
+
+    fn unexpected(&self, expected: &str) -> StorageError {
+        let message = format!("Expected {}, found {}", expected, self.peek());
+        syntax_error(self.text, self.offset(), &message)
+    }
+}
+
+fn is_reserved(word: &str) -> bool {
+    RESERVED.iter().any(|r| r.eq_ignore_ascii_case(word))
+}
+
+fn binary(op: BinaryOp, left: Expr, right: Expr) -> Expr {
+    Expr::Binary { op, left: Box::new(left), right: Box::new(right) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn col(name: &str) -> Expr {
+        Expr::Column { table: None, name: name.into() }
+    }
+
+    fn num(n: &str) -> Expr {
+        Expr::Literal(Literal::Number(n.into()))
+    }
+
+    #[test]
+    fn select_with_every_clause() {
+        let stmt = parse(
+            "select u.id, name AS n, score * 2 + 1 total from users u \
+             where not active and id between 1 and 10 or name like 'a%' \
+             order by 2 desc, id limit 5 offset 10;",
+        )
+        .unwrap();
+        let Statement::Select(s) = stmt else { panic!("not a select") };
+        assert_eq!(s.from, Some(TableRef { name: "users".into(), alias: Some("u".into()) }));
+        assert_eq!(s.items[0], SelectItem::Expr {
+            expr: Expr::Column { table: Some("u".into()), name: "id".into() },
+            alias: None,
+        });
+        assert_eq!(s.items[2], SelectItem::Expr {
+            expr: binary(BinaryOp::Add, binary(BinaryOp::Mul, col("score"), num("2")), num("1")),
+            alias: Some("total".into()),
+        });
+        let Some(Expr::Binary { op: BinaryOp::Or, left, .. }) = s.filter else { panic!("OR binds loosest") };
+        assert!(matches!(*left, Expr::Binary { op: BinaryOp::And, .. }));
+        assert_eq!(s.order_by, vec![
+            OrderItem { expr: num("2"), descending: true },
+            OrderItem { expr: col("id"), descending: false },
+        ]);
+        assert_eq!((s.limit, s.offset), (Some(5), Some(10)));
+    }
+
+    #[test]
+    fn dml_and_ddl() {
+        assert_eq!(
+            parse("INSERT INTO t (a, \"select\") VALUES (1, -2), (DATE '2024-01-01', NULL)").unwrap(),
+            Statement::Insert {
+                table: "t".into(),
+                columns: Some(vec!["a".into(), "select".into()]),
+                rows: vec![
+                    vec![num("1"), num("-2")],
+                    vec![
+                        Expr::Literal(Literal::Typed(TypedLiteral::Date, "2024-01-01".into())),
+                        Expr::Literal(Literal::Null),
+                    ],
+                ],
+            }
+        );
+        assert_eq!(
+            parse("create table t (id bigint not null, price decimal(10, 2), code char, note varchar(40) null)")
+                .unwrap(),
+            Statement::CreateTable {
+                name: "t".into(),
+                columns: vec![
+                    Column { name: "id".into(), col_type: ColumnType::Int64, nullable: false },
+                    Column { name: "price".into(), col_type: ColumnType::Decimal(10, 2), nullable: true },
+                    Column { name: "code".into(), col_type: ColumnType::Char(1), nullable: true },
+                    Column { name: "note".into(), col_type: ColumnType::VarChar(40), nullable: true },
+                ],
+            }
+        );
+        assert_eq!(
+            parse("UPDATE t SET a = a + 1 WHERE b IS NOT NULL").unwrap(),
+            Statement::Update {
+                table: "t".into(),
+                assignments: vec![("a".into(), binary(BinaryOp::Add, col("a"), num("1")))],
+                filter: Some(Expr::IsNull { expr: Box::new(col("b")), negated: true }),
+            }
+        );
+        assert_eq!(
+            parse("drop table if exists t").unwrap(),
+            Statement::DropTable { name: "t".into(), if_exists: true }
+        );
+    }
+
+    #[test]
+    fn syntax_errors() {
+        let err = |text: &str| parse(text).unwrap_err().to_string();
+        assert_eq!(err("SELECT FROM t"), "Expected an expression, found 'FROM' at line 1, column 8");
+        assert_eq!(err("SELECT a FROM t WHERE"), "Expected an expression, found end of input at line 1, column 22");
+        assert_eq!(err("SELECT 1 2"), "Expected the end of the statement, found 2 at line 1, column 10");
+        assert_eq!(err("CREATE TABLE t (a money)"), "Unknown type 'money' at line 1, column 19");
+        assert_eq!(err("SELECT a NOT = 1"), "Expected BETWEEN, IN or LIKE, found '=' at line 1, column 14");
+        assert_eq!(err("SELECT * FROM t; SELECT 1"), "Expected the end of the statement, found 'SELECT' at line 1, column 18");
+    }
+}
@@ -0,0 +1,708 @@
+//! B+tree index stored in buffer-pool pages.
+//!
+//! Every node is one `PageType::Index` page.  After the common page header:
+//!
+//! ```text
+//! [24]     kind        : u8   (0 = leaf, 1 = internal)
+//! [25..27] num_keys    : u16
+//! [27..29] key_size    : u16
+//! [29..33] first_child : u32  (internal only: subtree left of every key)
+//! [33..]   entries, fixed width:
+//!     leaf:     key[key_size] | rid_page u32 | rid_slot u16
+//!     internal: key[key_size] | rid_page u32 | rid_slot u16 | child u32
+//! ```
+//!
+//! Leaves are chained left to right through the header's `next_page`.
+//! Entries are ordered by (key, rid), so duplicate keys are allowed and every
+//! entry is unique.  An internal entry is the first (key, rid) of its child's
+//! subtree.  The root page never moves: splitting the root copies its
+//! contents into two new children, and a root left with a single child
+//! absorbs it.  The root page id therefore identifies the index.
+//!
+//! Logging: the user transaction logs a logical `IndexInsert`/`IndexDelete`
+//! record, then the page changes run as a short system transaction that logs
+//! full before/after images (`IndexPage`).  The system transaction ends as
+//! soon as the tree is consistent again, so later rollback of the user
+//! transaction undoes the *logical* change — safe even after other
+//! transactions have split or merged the same pages.
+
+use std::cmp::Ordering;
+use crate::storage::types::*;
+use crate::storage::page;
+use crate::storage::error::StorageResult;
+use crate::storage::recovery;
+use crate::storage::schema::cmp_keys;
+use crate::storage::buffer_pool::BufferPoolManager;
+use crate::storage::wal::LogBody;
+
+const OFF_KIND: usize = PAGE_HEADER_SIZE;
+const OFF_NUM_KEYS: usize = PAGE_HEADER_SIZE + 1;
+const OFF_KEY_SIZE: usize = PAGE_HEADER_SIZE + 3;
+const OFF_FIRST_CHILD: usize = PAGE_HEADER_SIZE + 5;
+/// Size of the node header that follows the page header.
+pub const NODE_HEADER_SIZE: usize = 9;
+const ENTRIES_START: usize = PAGE_HEADER_SIZE + NODE_HEADER_SIZE;
+const RID_SIZE: usize = 6;
+
+/// An index over one column of a table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BTreeIndex {
+    /// Column position in the table schema.
+    pub column: usize,
+    /// Root page; fixed for the life of the index.
+    pub root_page_id: PageId,
+    /// Width of every encoded key (see `schema::encode_key`).
+    pub key_size: usize,
+}
+
+// ── Nodes ──────────────────────────────────────────────────────────
+
+/// One key/rid pair.  `child` is only meaningful in internal nodes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub key: Vec<u8>,
+    pub rid: RowId,
+    pub child: PageId,
+}
+
+/// A node decoded from its page.
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub page_id: PageId,
+    pub is_leaf: bool,
+    pub key_size: usize,
+    pub first_child: PageId,
+    /// Right sibling (leaves only).
+    pub next: PageId,
+    pub entries: Vec<Entry>,
+}
+
+impl Node {
+    /// Child `idx`: 0 is `first_child`, `i` is `entries[i - 1].child`.
+    pub fn child_at(&self, idx: usize) -> PageId {
+        if idx == 0 {
+            self.first_child
+        } else {
+            self.entries[idx - 1].child
+        }
+    }
+
+    /// All children of an internal node, left to right.
+    pub fn children(&self) -> Vec<PageId> {
+        if self.is_leaf {
+            return Vec::new();
+        }
+        (0..=self.entries.len()).map(|i| self.child_at(i)).collect()
+    }
+}
+
+/// Maximum entries a node can hold.
+pub fn capacity(page_size: usize, key_size: usize, is_leaf: bool) -> usize {
+    let entry = key_size + RID_SIZE + if is_leaf { 0 } else { 4 };
+    page_size.saturating_sub(ENTRIES_START) / entry
+}
+
+/// Decode a node from page bytes.
+pub fn decode_node(buf: &[u8]) -> Node {
+    let is_leaf = buf[OFF_KIND] == 0;
+    let n = read_u16(buf, OFF_NUM_KEYS) as usize;
+    let key_size = read_u16(buf, OFF_KEY_SIZE) as usize;
+    let width = key_size + RID_SIZE + if is_leaf { 0 } else { 4 };
+
+    let entries = (0..n)
+        .map(|i| {
+            let off = ENTRIES_START + i * width;
+            let key = buf[off..off + key_size].to_vec();
+            let r = off + key_size;
+            let rid = RowId { page_id: read_u32(buf, r), slot_id: read_u16(buf, r + 4) };
+            let child = if is_leaf { INVALID_PAGE } else { read_u32(buf, r + RID_SIZE) };
+            Entry { key, rid, child }
+        })
+        .collect();
+
+    Node {
+        page_id: page::page_id(buf),
+        is_leaf,
+        key_size,
+        first_child: read_u32(buf, OFF_FIRST_CHILD),
+        next: page::next_page(buf),
+        entries,
+    }
+}
+
+/// Encode a node into page bytes (the page header other than `next_page`
+/// is left alone).  Unused space is zeroed.
+pub fn encode_node(node: &Node, buf: &mut [u8]) {
+    buf[OFF_KIND] = if node.is_leaf { 0 } else { 1 };
+    write_u16(buf, OFF_NUM_KEYS, node.entries.len() as u16);
+    write_u16(buf, OFF_KEY_SIZE, node.key_size as u16);
+    write_u32(buf, OFF_FIRST_CHILD, node.first_child);
+    page::set_next_page(buf, node.next);
+
+    let mut off = ENTRIES_START;
+    for e in &node.entries {
+        buf[off..off + node.key_size].copy_from_slice(&e.key);
+        off += node.key_size;
+        write_u32(buf, off, e.rid.page_id);
+        write_u16(buf, off + 4, e.rid.slot_id);
+        off += RID_SIZE;
+        if !node.is_leaf {
+            write_u32(buf, off, e.child);
+            off += 4;
+        }
+    }
+    buf[off..].fill(0);
+}
+
+/// Read and decode a node.
+pub fn read_node(bpm: &mut BufferPoolManager, page_id: PageId) -> Option<Node> {
+    let fid = bpm.fetch_page(page_id).ok()?;
+    let node = decode_node(bpm.frame_data(fid));
+    bpm.unpin_page(page_id, false);
+    Some(node)
+}
+
+// ── Public operations ──────────────────────────────────────────────
+
+/// Allocate an empty index (a single leaf root) on behalf of `txn`.
+/// Returns `None` if a node couldn't hold at least three keys.
+pub fn create(bpm: &mut BufferPoolManager, txn: TxnId, key_size: usize) -> Option<PageId> {
+    let page_size = bpm.page_size() as usize;
+    if capacity(page_size, key_size, false) < 3 {
+        return None;
+    }
+    let (root, fid) = bpm.new_page_logged(txn, PageType::Index).ok()?;
+    let node = Node {
+        page_id: root,
+        is_leaf: true,
+        key_size,
+        first_child: INVALID_PAGE,
+        next: INVALID_PAGE,
+        entries: Vec::new(),
+    };
+    let before = bpm.frame_data(fid).to_vec();
+    encode_node(&node, bpm.frame_data_mut(fid));
+    let after = bpm.frame_data(fid).to_vec();
+    bpm.log_update(txn, fid, LogBody::IndexPage { page_id: root, before, after });
+    bpm.unpin_page(root, true);
+    Some(root)
+}
+
+/// Insert (key, rid) on behalf of `txn`.  Returns `false` if the pair is
+/// already present or a page couldn't be allocated, and fails if the
+/// failed insert couldn't be rolled back.
+pub fn insert(
+    bpm: &mut BufferPoolManager,
+    txn: TxnId,
+    root: PageId,
+    key: &[u8],
+    rid: RowId,
+) -> StorageResult<bool> {
+    // Only log real changes: undoing a no-op insert would remove an entry
+    // someone else owns.
+    if contains(bpm, root, key, rid) {
+        return Ok(false);
+    }
+    bpm.log.append(txn, LogBody::IndexInsert { root, key: key.to_vec(), rid });
+    insert_entry(bpm, root, key, rid)
+}
+
+/// Remove (key, rid) on behalf of `txn`.  Returns `false` if not present,
+/// and fails if a failed delete couldn't be rolled back.
+pub fn delete(
+    bpm: &mut BufferPoolManager,
+    txn: TxnId,
+    root: PageId,
+    key: &[u8],
+    rid: RowId,
+) -> StorageResult<bool> {
+    if !contains(bpm, root, key, rid) {
+        return Ok(false);
+    }
+    bpm.log.append(txn, LogBody::IndexDelete { root, key: key.to_vec(), rid });
+    delete_entry(bpm, root, key, rid)
+}
+
+/// Whether the exact (key, rid) pair is in the tree.
+pub fn contains(bpm: &mut BufferPoolManager, root: PageId, key: &[u8], rid: RowId) -> bool {
+    descend(bpm, root, key, rid).is_some_and(|(leaf, _)| {
+        leaf.entries.iter().any(|e| e.rid == rid && cmp_keys(&e.key, key).is_eq())
+    })
+}
+
+/// Row IDs whose key equals `key`.
+pub fn lookup(bpm: &mut BufferPoolManager, root: PageId, key: &[u8]) -> Vec<RowId> {
+    range(bpm, root, Some(key), Some(key))
+        .into_iter()
+        .map(|(_, rid)| rid)
+        .collect()
+}
+
+/// All (key, rid) pairs with `low <= key <= high`, in key order.  A `None`
+/// bound is unbounded.
+pub fn range(
+    bpm: &mut BufferPoolManager,
+    root: PageId,
+    low: Option<&[u8]>,
+    high: Option<&[u8]>,
+) -> Vec<(Vec<u8>, RowId)> {
+    let mut out = Vec::new();
+    let Some(mut node) = read_node(bpm, root) else { return out };
+
+    // Equal keys may sit left of a matching separator (it carries a rid
+    // too), so descend to the left of any separator equal to `low`.
+    while !node.is_leaf {
+        let idx = match low {
+            Some(l) => node.entries.iter().filter(|e| cmp_keys(&e.key, l).is_lt()).count(),
+            None => 0,
+        };
+        let Some(child) = read_node(bpm, node.child_at(idx)) else { return out };
+        node = child;
+    }
+
+    loop {
+        for e in &node.entries {
+            if low.is_some_and(|l| cmp_keys(&e.key, l).is_lt()) {
+                continue;
+            }
+            if high.is_some_and(|h| cmp_keys(&e.key, h).is_gt()) {
+                return out;
+            }
+            out.push((e.key.clone(), e.rid));
+        }
+        if node.next == INVALID_PAGE {
+            return out;
+        }
+        let Some(next) = read_node(bpm, node.next) else { return out };
+        node = next;
+    }
+}
+
+/// Every page in the tree, breadth-first from the root.
+pub fn page_ids(bpm: &mut BufferPoolManager, root: PageId) -> Vec<PageId> {
+    let mut ids = vec![root];
+    let mut i = 0;
+    while i < ids.len() {
+        if let Some(node) = read_node(bpm, ids[i]) {
+            ids.extend(node.children());
+        }
+        i += 1;
+    }
+    ids
+}
+
+// ── Structural changes (system transactions) ───────────────────────
+
+/// Insert without logging a logical record (used directly by undo).
+/// Page changes run in their own system transaction.
+pub fn insert_entry(
+    bpm: &mut BufferPoolManager,
+    root: PageId,
+    key: &[u8],
+    rid: RowId,
+) -> StorageResult<bool> {
+    let sys = bpm.log.begin();
+    match insert_inner(bpm, sys, root, key, rid) {
+        Some(inserted) => {
+            bpm.log.end(sys);
+            Ok(inserted)
+        }
+        None => {
+            recovery::rollback(bpm, sys)?;
+            Ok(false)
+        }
+    }
+}
+
+/// Delete without logging a logical record (used directly by undo).
+pub fn delete_entry(
+    bpm: &mut BufferPoolManager,
+    root: PageId,
+    key: &[u8],
+    rid: RowId,
+) -> StorageResult<bool> {
+    let sys = bpm.log.begin();
+    match delete_inner(bpm, sys, root, key, rid) {
+        Some(deleted) => {
+            bpm.log.end(sys);
+            Ok(deleted)
+        }
+        None => {
+            recovery::rollback(bpm, sys)?;
+            Ok(false)
+        }
+    }
+}
+
+/// Order of two (key, rid) pairs.
+fn cmp_entry(a_key: &[u8], a_rid: RowId, b_key: &[u8], b_rid: RowId) -> Ordering {
+    cmp_keys(a_key, b_key).then(a_rid.cmp(&b_rid))
+}
+
+/// Walk from the root to the leaf that holds (key, rid).  Returns the leaf
+/// and the internal nodes passed through with the child index taken.
+fn descend(
+    bpm: &mut BufferPoolManager,
+    root: PageId,
+    key: &[u8],
+    rid: RowId,
+) -> Option<(Node, Vec<(Node, usize)>)> {
+    let mut path = Vec::new();
+    let mut node = read_node(bpm, root)?;
+    while !node.is_leaf {
+        let idx = node
+            .entries
+            .iter()
+            .filter(|e| cmp_entry(&e.key, e.rid, key, rid).is_le())
+            .count();
+        let child = read_node(bpm, node.child_at(idx))?;
+        path.push((node, idx));
+        node = child;
+    }
+    Some((node, path))
+}
+
+fn insert_inner(
+    bpm: &mut BufferPoolManager,
+    sys: TxnId,
+    root: PageId,
+    key: &[u8],
+    rid: RowId,
+) -> Option<bool> {
+    let page_size = bpm.page_size() as usize;
+    let (mut node, mut path) = descend(bpm, root, key, rid)?;
+    let pos = match node.entries.binary_search_by(|e| cmp_entry(&e.key, e.rid, key, rid)) {
+        Ok(_) => return Some(false),
+        Err(p) => p,
+    };
+    node.entries.insert(pos, Entry { key: key.to_vec(), rid, child: INVALID_PAGE });
+
+    loop {
+        if node.entries.len() <= capacity(page_size, node.key_size, node.is_leaf) {
+            write_node(bpm, sys, &node)?;
+            return Some(true);
+        }
+        if node.page_id == root {
+            // Move the root's contents into a new left child and split that,
+            // so the root keeps its page id.
+            let left_id = alloc_node(bpm, sys)?;
+            let mut left = Node { page_id: left_id, ..node.clone() };
+            let sep = split(bpm, sys, &mut left)?;
+            let new_root = Node {
+                page_id: root,
+                is_leaf: false,
+                key_size: node.key_size,
+                first_child: left_id,
+                next: INVALID_PAGE,
+                entries: vec![sep],
+            };
+            write_node(bpm, sys, &new_root)?;
+            return Some(true);
+        }
+        let sep = split(bpm, sys, &mut node)?;
+        let (mut parent, idx) = path.pop()?;
+        parent.entries.insert(idx, sep);
+        node = parent;
+    }
+}
+
+/// Split an overfull node in two, writing both halves.  Returns the
+/// separator entry to insert in the parent (pointing at the new right node).
+fn split(bpm: &mut BufferPoolManager, sys: TxnId, node: &mut Node) -> Option<Entry> {
+    let right_id = alloc_node(bpm, sys)?;
+    let mid = node.entries.len() / 2;
+    let mut right = Node {
+        page_id: right_id,
+        is_leaf: node.is_leaf,
+        key_size: node.key_size,
+        first_child: INVALID_PAGE,
+        next: INVALID_PAGE,
+        entries: node.entries.split_off(mid),
+    };
+    let sep = if node.is_leaf {
+        right.next = node.next;
+        node.next = right_id;
+        Entry { key: right.entries[0].key.clone(), rid: right.entries[0].rid, child: right_id }
+    } else {
+        // The middle key moves up; its child becomes the right node's first.
+        let up = right.entries.remove(0);
+        right.first_child = up.child;
+        Entry { key: up.key, rid: up.rid, child: right_id }
+    };
+    write_node(bpm, sys, &right)?;
+    write_node(bpm, sys, node)?;
+    Some(sep)
+}
+
+fn delete_inner(
+    bpm: &mut BufferPoolManager,
+    sys: TxnId,
+    root: PageId,
+    key: &[u8],
+    rid: RowId,
+) -> Option<bool> {
+    let page_size = bpm.page_size() as usize;
+    let (mut node, mut path) = descend(bpm, root, key, rid)?;
+    let Ok(pos) = node.entries.binary_search_by(|e| cmp_entry(&e.key, e.rid, key, rid)) else {
+        return Some(false);
+    };
+    node.entries.remove(pos);
+
+    loop {
+        if node.page_id == root {
+            if !node.is_leaf && node.entries.is_empty() {
+                // Root has a single child left: pull it up into the root.
+                let child = read_node(bpm, node.first_child)?;
+                let child_id = child.page_id;
+                write_node(bpm, sys, &Node { page_id: root, ..child })?;
+                bpm.free_page_logged(sys, child_id).then_some(())?;
+            } else {
+                write_node(bpm, sys, &node)?;
+            }
+            return Some(true);
+        }
+
+        let min = capacity(page_size, node.key_size, node.is_leaf) / 2;
+        if node.entries.len() >= min {
+            write_node(bpm, sys, &node)?;
+            return Some(true);
+        }
+
+        let (mut parent, idx) = path.pop()?;
+        if idx > 0 {
+            let mut left = read_node(bpm, parent.child_at(idx - 1))?;
+            let sep = &mut parent.entries[idx - 1];
+            if left.entries.len() > min {
+                borrow_from_left(&mut left, &mut node, sep);
+                write_node(bpm, sys, &left)?;
+                write_node(bpm, sys, &node)?;
+                write_node(bpm, sys, &parent)?;
+                return Some(true);
+            }
+            let node_id = node.page_id;
+            let sep = parent.entries.remove(idx - 1);
+            merge(&mut left, node, sep);
+            write_node(bpm, sys, &left)?;
+            bpm.free_page_logged(sys, node_id).then_some(())?;
+        } else {
+            let mut right = read_node(bpm, parent.child_at(1))?;
+            let sep = &mut parent.entries[0];
+            if right.entries.len() > min {
+                borrow_from_right(&mut node, &mut right, sep);
+                write_node(bpm, sys, &node)?;
+                write_node(bpm, sys, &right)?;
+                write_node(bpm, sys, &parent)?;
+                return Some(true);
+            }
+            let right_id = right.page_id;
+            let sep = parent.entries.remove(0);
+            merge(&mut node, right, sep);
+            write_node(bpm, sys, &node)?;
+            bpm.free_page_logged(sys, right_id).then_some(())?;
+        }
+        node = parent;
+    }
+}
+
+/// Move the last entry of `left` into the front of `node`.
+fn borrow_from_left(left: &mut Node, node: &mut Node, sep: &mut Entry) {
+    let e = left.entries.pop().expect("left sibling above minimum");
+    if node.is_leaf {
+        node.entries.insert(0, e);
+        sep.key = node.entries[0].key.clone();
+        sep.rid = node.entries[0].rid;
+    } else {
+        node.entries.insert(0, Entry { key: sep.key.clone(), rid: sep.rid, child: node.first_child });
+        node.first_child = e.child;
+        sep.key = e.key;
+        sep.rid = e.rid;
+    }
+}
+
+/// Move the first entry of `right` onto the end of `node`.
+fn borrow_from_right(node: &mut Node, right: &mut Node, sep: &mut Entry) {
+    let e = right.entries.remove(0);
+    if node.is_leaf {
+        node.entries.push(e);
+        sep.key = right.entries[0].key.clone();
+        sep.rid = right.entries[0].rid;
+    } else {
+        node.entries.push(Entry { key: sep.key.clone(), rid: sep.rid, child: right.first_child });
+        right.first_child = e.child;
+        sep.key = e.key;
+        sep.rid = e.rid;
+    }
+}
+
+/// Append `right` (and, for internal nodes, the separator) onto `left`.
+fn merge(left: &mut Node, right: Node, sep: Entry) {
+    if left.is_leaf {
+        left.next = right.next;
+    } else {
+        left.entries.push(Entry { key: sep.key, rid: sep.rid, child: right.first_child });
+    }
+    left.entries.extend(right.entries);
+}
+
+/// Allocate an index page inside the system transaction.
+fn alloc_node(bpm: &mut BufferPoolManager, sys: TxnId) -> Option<PageId> {
+    let (page_id, _) = bpm.new_page_logged(sys, PageType::Index).ok()?;
+    bpm.unpin_page(page_id, true);
+    Some(page_id)
+}
+
+/// Write `node` to its page, logging before and after images.
+fn write_node(bpm: &mut BufferPoolManager, sys: TxnId, node: &Node) -> Option<()> {
+    let fid = bpm.fetch_page(node.page_id).ok()?;
+    let before = bpm.frame_data(fid).to_vec();
+    encode_node(node, bpm.frame_data_mut(fid));
+    let after = bpm.frame_data(fid).to_vec();
+    bpm.log_update(sys, fid, LogBody::IndexPage { page_id: node.page_id, before, after });
+    bpm.unpin_page(node.page_id, true);
+    Some(())
+}
+
+fn read_u16(buf: &[u8], off: usize) -> u16 {
+    u16::from_le_bytes([buf[off], buf[off + 1]])
+}
+
+fn write_u16(buf: &mut [u8], off: usize, v: u16) {
+    buf[off..off + 2].copy_from_slice(&v.to_le_bytes());
+}
+
+fn read_u32(buf: &[u8], off: usize) -> u32 {
+    u32::from_le_bytes([buf[off], buf[off + 1], buf[off + 2], buf[off + 3]])
+}
+
+fn write_u32(buf: &mut [u8], off: usize, v: u32) {
+    buf[off..off + 4].copy_from_slice(&v.to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::disk::DiskManager;
+    use crate::storage::schema::{encode_key, Value};
+
+    const KEY: usize = 5; // Int32 key
+
+    fn setup() -> (BufferPoolManager, PageId) {
+        // 128-byte pages: 7 leaf entries, 5 internal entries per node.
+        let mut bpm = BufferPoolManager::new(8, DiskManager::new(128, 128));
+        let txn = bpm.log.begin();
+        let root = create(&mut bpm, txn, KEY).unwrap();
+        bpm.log.commit(txn);
+        (bpm, root)
+    }
+
+    fn key(v: i32) -> Vec<u8> {
+        encode_key(&Value::Int32(v), KEY).unwrap()
+    }
+
+    fn rid(v: i32) -> RowId {
+        RowId { page_id: 1000 + v as u32, slot_id: 0 }
+    }
+
+    fn keys_in_order(bpm: &mut BufferPoolManager, root: PageId) -> Vec<i32> {
+        range(bpm, root, None, None)
+            .into_iter()
+            .map(|(_, r)| r.page_id as i32 - 1000)
+            .collect()
+    }
+
+    #[test]
+    fn insert_splits_and_stays_sorted() {
+        let (mut bpm, root) = setup();
+        let txn = bpm.log.begin();
+        // Insert in a scrambled order to exercise splits at every position.
+        let order: Vec<i32> = (0..100).map(|i| (i * 37) % 100).collect();
+        for &v in &order {
+            assert!(insert(&mut bpm, txn, root, &key(v), rid(v)).unwrap());
+        }
+        assert!(!insert(&mut bpm, txn, root, &key(5), rid(5)).unwrap()); // duplicate pair
+
+        let root_node = read_node(&mut bpm, root).unwrap();
+        assert!(!root_node.is_leaf);
+        assert!(page_ids(&mut bpm, root).len() > 10);
+        assert_eq!(keys_in_order(&mut bpm, root), (0..100).collect::<Vec<_>>());
+
+        assert_eq!(lookup(&mut bpm, root, &key(42)), vec![rid(42)]);
+        assert!(lookup(&mut bpm, root, &key(500)).is_empty());
+        let hits: Vec<RowId> = range(&mut bpm, root, Some(&key(10)), Some(&key(14)))
+            .into_iter()
+            .map(|(_, r)| r)
+            .collect();
+        assert_eq!(hits, (10..=14).map(rid).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn duplicate_keys_span_leaves() {
+        let (mut bpm, root) = setup();
+        let txn = bpm.log.begin();
+        for slot in 0..20u16 {
+            let r = RowId { page_id: 7, slot_id: slot };
+            assert!(insert(&mut bpm, txn, root, &key(3), r).unwrap());
+        }
+        insert(&mut bpm, txn, root, &key(1), rid(1)).unwrap();
+        insert(&mut bpm, txn, root, &key(9), rid(9)).unwrap();
+        assert_eq!(lookup(&mut bpm, root, &key(3)).len(), 20);
+    }
+
+    #[test]
+    fn delete_merges_and_collapses_root() {
+        let (mut bpm, root) = setup();
+        let allocated = bpm.disk.num_allocated();
+        let txn = bpm.log.begin();
+        for v in 0..60 {
+            insert(&mut bpm, txn, root, &key(v), rid(v)).unwrap();
+        }
+        for v in (0..60).filter(|v| v % 3 != 0) {
+            assert!(delete(&mut bpm, txn, root, &key(v), rid(v)).unwrap());
+        }
+        assert!(!delete(&mut bpm, txn, root, &key(1), rid(1)).unwrap());
+        assert_eq!(keys_in_order(&mut bpm, root), (0..60).step_by(3).collect::<Vec<_>>());
+
+        for v in (0..60).step_by(3) {
+            assert!(delete(&mut bpm, txn, root, &key(v), rid(v)).unwrap());
+        }
+        assert!(read_node(&mut bpm, root).unwrap().is_leaf);
+        assert_eq!(bpm.disk.num_allocated(), allocated);
+    }
+
+    #[test]
+    fn rollback_undoes_logical_changes() {
+        let (mut bpm, root) = setup();
+        let t1 = bpm.log.begin();
+        for v in 0..10 {
+            insert(&mut bpm, t1, root, &key(v), rid(v)).unwrap();
+        }
+        bpm.log.commit(t1);
+
+        let t2 = bpm.log.begin();
+        for v in 10..40 {
+            insert(&mut bpm, t2, root, &key(v), rid(v)).unwrap();
+        }
+        delete(&mut bpm, t2, root, &key(4), rid(4)).unwrap();
+        recovery::rollback(&mut bpm, t2).unwrap();
+        assert_eq!(keys_in_order(&mut bpm, root), (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn tree_survives_crash() {
+        let (mut bpm, root) = setup();
+        let t1 = bpm.log.begin();
+        for v in 0..50 {
+            insert(&mut bpm, t1, root, &key(v), rid(v)).unwrap();
+        }
+        bpm.log.commit(t1);
+        let t2 = bpm.log.begin();
+        for v in 50..80 {
+            insert(&mut bpm, t2, root, &key(v), rid(v)).unwrap();
+        }
+        bpm.flush_all();
+
+        bpm.crash();
+        recovery::recover(&mut bpm);
+        assert_eq!(keys_in_order(&mut bpm, root), (0..50).collect::<Vec<_>>());
+    }
+}
@@ -1,73 +1,25 @@
-//! Buffer Pool Manager + LRU Replacer.
+//! Buffer Pool Manager.
 //!
 //! The buffer pool sits between the "query engine" and the disk manager.
 //! It maintains a fixed number of page-sized frames in memory. When a page
 //! is requested, the BPM either returns it from cache (hit) or reads it
 //! from disk into a frame (miss), possibly evicting another page first.
+//! The victim is chosen by a pluggable `Replacer` (see `replacer.rs`).
+//!
+//! The BPM also owns the write-ahead log and enforces the WAL rule: before
+//! a dirty frame is written back, the log is flushed up to the page's LSN.
+//!
+//! Every write-back stamps the page checksum, and every read verifies it,
+//! so bytes damaged on disk surface as `StorageError::Corrupt` instead of
+//! being decoded as if they were a page.
 
-use std::collections::{HashMap, VecDeque, HashSet};
+use std::collections::HashMap;
 use crate::storage::types::*;
+use crate::storage::error::{StorageError, StorageResult};
 use crate::storage::disk::DiskManager;
 use crate::storage::page;
-
-// ── LRU Replacer ───────────────────────────────────────────────────
-
-/// Tracks which frames are evictable and picks the least-recently-used one.
-pub struct LruReplacer {
-    /// LRU order: front = least recent, back = most recent.
-    order: VecDeque<FrameId>,
-    /// Set of frames that are currently evictable (pin_count == 0).
-    evictable: HashSet<FrameId>,
-}
-
-impl LruReplacer {
-    pub fn new(capacity: usize) -> Self {
-        Self {
-            order: VecDeque::with_capacity(capacity),
-            evictable: HashSet::with_capacity(capacity),
-        }
-    }
-
-    /// Record that a frame was accessed (move to back = most recent).
-    pub fn record_access(&mut self, frame_id: FrameId) {
-        self.order.retain(|&f| f != frame_id);
-        self.order.push_back(frame_id);
-    }
-
-    /// Mark a frame as evictable or not.
-    pub fn set_evictable(&mut self, frame_id: FrameId, evictable: bool) {
-        if evictable {
-            self.evictable.insert(frame_id);
-        } else {
-            self.evictable.remove(&frame_id);
-        }
-    }
-
-    /// Evict the least-recently-used evictable frame.
-    pub fn evict(&mut self) -> Option<FrameId> {
-        // Walk from front (least recent) and find the first evictable frame
-        let pos = self.order.iter().position(|f| self.evictable.contains(f))?;
-        let frame_id = self.order.remove(pos).unwrap();
-        self.evictable.remove(&frame_id);
-        Some(frame_id)
-    }
-
-    /// Remove a frame from the replacer entirely (e.g., when page is deleted).
-    pub fn remove(&mut self, frame_id: FrameId) {
-        self.order.retain(|&f| f != frame_id);
-        self.evictable.remove(&frame_id);
-    }
-
-    /// Number of evictable frames.
-    pub fn size(&self) -> usize {
-        self.evictable.len()
-    }
-
-    /// LRU order (front=least recent) for visualization.
-    pub fn lru_order(&self) -> &VecDeque<FrameId> {
-        &self.order
-    }
-}
+use crate::storage::replacer::{make_replacer, ReplacementPolicy, Replacer};
+use crate::storage::wal::{LogBody, LogManager};
 
 // ── Frame ──────────────────────────────────────────────────────────
 
@@ -92,19 +44,29 @@ pub struct BufferPoolManager {
     page_table: HashMap<PageId, FrameId>,
     /// Indices of empty (unused) frames.
     free_list: Vec<FrameId>,
-    /// LRU eviction policy.
-    replacer: LruReplacer,
+    /// Eviction policy.
+    replacer: Box<dyn Replacer>,
     /// Underlying disk storage.
     pub disk: DiskManager,
+    /// Write-ahead log.
+    pub log: LogManager,
     /// Page size in bytes.
     page_size: u32,
+    /// Most recent failed fetch or allocation, until taken (see
+    /// `take_error`).
+    last_error: Option<StorageError>,
     // ── Stats ──
     pub hit_count: u64,
     pub miss_count: u64,
 }
 
 impl BufferPoolManager {
+    /// A pool using LRU replacement.
     pub fn new(pool_size: u32, disk: DiskManager) -> Self {
+        Self::with_policy(pool_size, disk, ReplacementPolicy::Lru)
+    }
+
+    pub fn with_policy(pool_size: u32, disk: DiskManager, policy: ReplacementPolicy) -> Self {
         let page_size = disk.page_size();
         let mut frames = Vec::with_capacity(pool_size as usize);
         let mut free_list = Vec::with_capacity(pool_size as usize);
@@ -125,9 +87,11 @@ impl BufferPoolManager {
             frames,
             page_table: HashMap::new(),
             free_list,
-            replacer: LruReplacer::new(pool_size as usize),
+            replacer: make_replacer(policy, pool_size as usize),
             disk,
+            log: LogManager::new(),
             page_size,
+            last_error: None,
             hit_count: 0,
             miss_count: 0,
         }
@@ -138,37 +102,75 @@ impl BufferPoolManager {
     /// If the page is already in the pool, returns it (cache hit).
     /// Otherwise, finds a free frame or evicts one (cache miss).
     /// The returned frame is pinned (pin_count incremented).
-    pub fn fetch_page(&mut self, page_id: PageId) -> Option<FrameId> {
+    ///
+    /// A failure is also kept for `take_error`, so callers that can only
+    /// report "not found" don't lose the reason.
+    pub fn fetch_page(&mut self, page_id: PageId) -> StorageResult<FrameId> {
+        self.fetch_page_inner(page_id).inspect_err(|e| self.last_error = Some(e.clone()))
+    }
+
+    fn fetch_page_inner(&mut self, page_id: PageId) -> StorageResult<FrameId> {
         // Cache hit?
         if let Some(&frame_id) = self.page_table.get(&page_id) {
             self.frames[frame_id as usize].pin_count += 1;
             self.replacer.set_evictable(frame_id, false);
-            self.replacer.record_access(frame_id);
+            self.replacer.record_access(frame_id, page_id);
             self.hit_count += 1;
-            return Some(frame_id);
+            return Ok(frame_id);
         }
 
         // Cache miss — need a frame
         self.miss_count += 1;
-        let frame_id = self.get_free_frame()?;
-
-        // Read page from disk into frame
-        self.disk.read_page(page_id, &mut self.frames[frame_id as usize].data);
+        let frame_id = self.get_free_frame().ok_or(StorageError::PoolExhausted)?;
+
+        // Read page from disk into frame and check it
+        let data = &mut self.frames[frame_id as usize].data;
+        let error = match self.disk.read_page(page_id, data) {
+            Err(e) => Some(StorageError::Io { page_id, message: e.to_string() }),
+            Ok(()) if !page::verify_checksum(data) => Some(StorageError::Corrupt {
+                page_id,
+                stored: page::checksum(data),
+                computed: page::compute_checksum(data),
+            }),
+            Ok(()) => None,
+        };
+        if let Some(error) = error {
+            self.free_list.push(frame_id);
+            return Err(error);
+        }
         self.frames[frame_id as usize].page_id = Some(page_id);
         self.frames[frame_id as usize].pin_count = 1;
         self.frames[frame_id as usize].is_dirty = false;
         self.page_table.insert(page_id, frame_id);
-        self.replacer.record_access(frame_id);
+        self.replacer.record_access(frame_id, page_id);
         self.replacer.set_evictable(frame_id, false);
 
-        Some(frame_id)
+        Ok(frame_id)
+    }
+
+    /// The error behind the most recent failed `fetch_page` or `new_page`,
+    /// if any, clearing it.
+    pub fn take_error(&mut self) -> Option<StorageError> {
+        self.last_error.take()
+    }
+
+    /// `take_error`, or `fallback` if nothing failed in the pool.
+    pub fn take_error_or(&mut self, fallback: StorageError) -> StorageError {
+        self.take_error().unwrap_or(fallback)
     }
 
     /// Allocate a new page on disk and bring it into the buffer pool.
     /// Returns (page_id, frame_id).
-    pub fn new_page(&mut self) -> Option<(PageId, FrameId)> {
-        let page_id = self.disk.allocate_page()?;
-        let frame_id = self.get_free_frame()?;
+    pub fn new_page(&mut self) -> StorageResult<(PageId, FrameId)> {
+        self.new_page_inner().inspect_err(|e| self.last_error = Some(e.clone()))
+    }
+
+    fn new_page_inner(&mut self) -> StorageResult<(PageId, FrameId)> {
+        let page_id = self.disk.allocate_page().ok_or(StorageError::DiskFull)?;
+        let Some(frame_id) = self.get_free_frame() else {
+            self.disk.deallocate_page(page_id);
+            return Err(StorageError::PoolExhausted);
+        };
 
         // Initialize the frame
         self.frames[frame_id as usize].data.fill(0);
@@ -181,10 +183,10 @@ impl BufferPoolManager {
         self.frames[frame_id as usize].pin_count = 1;
         self.frames[frame_id as usize].is_dirty = true; // new page needs to be written
         self.page_table.insert(page_id, frame_id);
-        self.replacer.record_access(frame_id);
+        self.replacer.record_access(frame_id, page_id);
         self.replacer.set_evictable(frame_id, false);
 
-        Some((page_id, frame_id))
+        Ok((page_id, frame_id))
     }
 
     /// Decrement pin count. Mark dirty if the caller modified the page.
@@ -207,15 +209,13 @@ impl BufferPoolManager {
         true
     }
 
-    /// Write a dirty page back to disk.
+    /// Write a dirty page back to disk.  Returns `false` if the page isn't
+    /// in the pool or the write failed (the frame then stays dirty).
     pub fn flush_page(&mut self, page_id: PageId) -> bool {
         let Some(&frame_id) = self.page_table.get(&page_id) else {
             return false;
         };
-        let frame = &self.frames[frame_id as usize];
-        self.disk.write_page(page_id, &frame.data);
-        self.frames[frame_id as usize].is_dirty = false;
-        true
+        self.write_back(frame_id, page_id)
     }
 
     /// Delete a page from both the pool and disk.
@@ -245,6 +245,78 @@ impl BufferPoolManager {
                 self.flush_page(page_id);
             }
         }
+        let _ = self.disk.sync();
+    }
+
+    /// Simulate a crash: every frame is dropped without being written back
+    /// and the unflushed log tail is lost.  Disk contents survive.
+    pub fn crash(&mut self) {
+        let pool_size = self.frames.len();
+        for frame in &mut self.frames {
+            frame.data.fill(0);
+            frame.page_id = None;
+            frame.pin_count = 0;
+            frame.is_dirty = false;
+        }
+        self.page_table.clear();
+        self.free_list = (0..pool_size as FrameId).rev().collect();
+        self.replacer = make_replacer(self.replacer.policy(), pool_size);
+        self.log.crash();
+    }
+
+    // ── Logged page operations ─────────────────────────────────────
+
+    /// Append `body` to the log on behalf of `txn` and stamp the frame with
+    /// the new LSN.  Call right after changing the frame's bytes.
+    pub fn log_update(&mut self, txn: TxnId, frame_id: FrameId, body: LogBody) -> Lsn {
+        let lsn = self.log.append(txn, body);
+        page::set_page_lsn(&mut self.frames[frame_id as usize].data, lsn);
+        lsn
+    }
+
+    /// Allocate a page, initialize it as `page_type`, and log the allocation.
+    /// The returned frame is pinned.
+    pub fn new_page_logged(
+        &mut self,
+        txn: TxnId,
+        page_type: PageType,
+    ) -> StorageResult<(PageId, FrameId)> {
+        let (page_id, frame_id) = self.new_page()?;
+        page::page_init(&mut self.frames[frame_id as usize].data, page_id, page_type);
+        self.log_update(txn, frame_id, LogBody::NewPage { page_id, page_type });
+        Ok((page_id, frame_id))
+    }
+
+    /// Free a page on behalf of `txn`, logging its last contents so the
+    /// free can be undone.  The page must not be pinned.  Returns `false`
+    /// if it couldn't be read or is pinned.
+    pub fn free_page_logged(&mut self, txn: TxnId, page_id: PageId) -> bool {
+        let Ok(frame_id) = self.fetch_page(page_id) else {
+            return false;
+        };
+        let image = self.frames[frame_id as usize].data.clone();
+        self.unpin_page(page_id, false);
+        let lsn = self.log.append(txn, LogBody::FreePage { page_id, image });
+        // The free takes effect on disk immediately, so its record must be
+        // durable first.
+        self.log.flush(lsn);
+        self.delete_page(page_id)
+    }
+
+    /// Set a page's `next_page` pointer on behalf of `txn`.
+    pub fn set_next_page_logged(
+        &mut self,
+        txn: TxnId,
+        page_id: PageId,
+        next: PageId,
+    ) -> StorageResult<()> {
+        let frame_id = self.fetch_page(page_id)?;
+        let data = &mut self.frames[frame_id as usize].data;
+        let old_next = page::next_page(data);
+        page::set_next_page(data, next);
+        self.log_update(txn, frame_id, LogBody::SetNext { page_id, old_next, new_next: next });
+        self.unpin_page(page_id, true);
+        Ok(())
     }
 
     // ── Accessors ──────────────────────────────────────────────────
@@ -276,8 +348,8 @@ impl BufferPoolManager {
         &self.page_table
     }
 
-    pub fn replacer(&self) -> &LruReplacer {
-        &self.replacer
+    pub fn replacer(&self) -> &dyn Replacer {
+        self.replacer.as_ref()
     }
 
     pub fn hit_rate(&self) -> f64 {
@@ -287,6 +359,20 @@ impl BufferPoolManager {
 
     // ── Internal ───────────────────────────────────────────────────
 
+    /// Write a frame to disk, flushing the log up to its page LSN first and
+    /// stamping its checksum.  Returns `false` (frame still dirty) if the
+    /// disk write failed.
+    fn write_back(&mut self, frame_id: FrameId, page_id: PageId) -> bool {
+        let lsn = page::page_lsn(&self.frames[frame_id as usize].data);
+        self.log.flush(lsn);
+        page::stamp_checksum(&mut self.frames[frame_id as usize].data);
+        if self.disk.write_page(page_id, &self.frames[frame_id as usize].data).is_err() {
+            return false;
+        }
+        self.frames[frame_id as usize].is_dirty = false;
+        true
+    }
+
     /// Find a free frame, evicting if necessary.
     fn get_free_frame(&mut self) -> Option<FrameId> {
         // Try free list first
@@ -294,15 +380,16 @@ impl BufferPoolManager {
             return Some(frame_id);
         }
 
-        // Evict via LRU
+        // Evict via the replacement policy
         let frame_id = self.replacer.evict()?;
         let frame = &self.frames[frame_id as usize];
         let old_page_id = frame.page_id?;
 
-        // Flush if dirty
-        if frame.is_dirty {
-            self.disk.write_page(old_page_id, &frame.data);
-            self.frames[frame_id as usize].is_dirty = false;
+        // Flush if dirty; a page that can't be written stays put
+        if frame.is_dirty && !self.write_back(frame_id, old_page_id) {
+            self.replacer.record_access(frame_id, old_page_id);
+            self.replacer.set_evictable(frame_id, true);
+            return None;
         }
 
         // Remove old mapping
@@ -393,7 +480,7 @@ mod tests {
         let (p1, _) = bpm.new_page().unwrap();
         // Both pinned — can't evict anything
 
-        assert!(bpm.new_page().is_none());
+        assert_eq!(bpm.new_page(), Err(StorageError::PoolExhausted));
         bpm.unpin_page(p0, false);
         bpm.unpin_page(p1, false);
     }
@@ -411,34 +498,75 @@ mod tests {
     }
 
     #[test]
-    fn lru_replacer_order() {
-        let mut r = LruReplacer::new(4);
-        r.record_access(0);
-        r.record_access(1);
-        r.record_access(2);
-        r.set_evictable(0, true);
-        r.set_evictable(1, true);
-        r.set_evictable(2, true);
-
-        // Should evict 0 (least recent)
-        assert_eq!(r.evict(), Some(0));
-        assert_eq!(r.evict(), Some(1));
-        assert_eq!(r.evict(), Some(2));
-        assert_eq!(r.evict(), None);
+    fn write_back_forces_log() {
+        let mut bpm = make_bpm(4, 16);
+        let txn = bpm.log.begin();
+        let (pid, fid) = bpm.new_page_logged(txn, PageType::Data).unwrap();
+        let lsn = page::page_lsn(bpm.frame_data(fid));
+        assert!(bpm.log.flushed_lsn() < lsn);
+
+        bpm.unpin_page(pid, true);
+        bpm.flush_page(pid);
+        assert!(bpm.log.flushed_lsn() >= lsn);
+    }
+
+    #[test]
+    fn crash_drops_dirty_frames() {
+        let mut bpm = make_bpm(4, 16);
+        let (pid, fid) = bpm.new_page().unwrap();
+        bpm.frame_data_mut(fid)[40] = 0xEE;
+        bpm.unpin_page(pid, true);
+
+        bpm.crash();
+        assert!(bpm.page_to_frame(pid).is_none());
+        let fid = bpm.fetch_page(pid).unwrap();
+        assert_eq!(bpm.frame_data(fid)[40], 0); // change never reached disk
+        bpm.unpin_page(pid, false);
+    }
+
+    #[test]
+    fn corrupt_page_is_rejected() {
+        let mut bpm = make_bpm(2, 16);
+        let (pid, fid) = bpm.new_page().unwrap();
+        bpm.frame_data_mut(fid)[40] = 0x11;
+        bpm.unpin_page(pid, true);
+        bpm.flush_page(pid);
+        bpm.crash();
+
+        // Flip a bit behind the pool's back.
+        let mut raw = bpm.disk.peek_page(pid).unwrap();
+        raw[40] ^= 0x04;
+        bpm.disk.write_page(pid, &raw).unwrap();
+
+        match bpm.fetch_page(pid) {
+            Err(StorageError::Corrupt { page_id, .. }) => assert_eq!(page_id, pid),
+            other => panic!("expected corruption, got {:?}", other),
+        }
+        assert_eq!(bpm.take_error().and_then(|e| e.page_id()), Some(pid));
+        assert!(bpm.take_error().is_none());
+        // The frame went back to the free list.
+        assert!(bpm.new_page().is_ok());
     }
 
     #[test]
-    fn lru_access_reorder() {
-        let mut r = LruReplacer::new(4);
-        r.record_access(0);
-        r.record_access(1);
-        r.record_access(2);
-        r.set_evictable(0, true);
-        r.set_evictable(1, true);
-        r.set_evictable(2, true);
-
-        // Touch 0 again — now 1 is LRU
-        r.record_access(0);
-        assert_eq!(r.evict(), Some(1));
+    fn fifo_policy_ignores_hits() {
+        let dm = DiskManager::new(64, 16);
+        let mut bpm = BufferPoolManager::with_policy(2, dm, ReplacementPolicy::Fifo);
+
+        let (p0, _) = bpm.new_page().unwrap();
+        let (p1, _) = bpm.new_page().unwrap();
+        bpm.unpin_page(p0, false);
+        bpm.unpin_page(p1, false);
+        // A hit on p0 would save it under LRU, but not under FIFO.
+        bpm.fetch_page(p0).unwrap();
+        bpm.unpin_page(p0, false);
+
+        let (p2, _) = bpm.new_page().unwrap();
+        assert!(bpm.page_to_frame(p0).is_none());
+        assert!(bpm.page_to_frame(p1).is_some());
+        bpm.unpin_page(p2, false);
+
+        bpm.crash(); // the policy survives a crash
+        assert_eq!(bpm.replacer().policy(), ReplacementPolicy::Fifo);
     }
 }
@@ -0,0 +1,413 @@
+//! Superblock and system catalog.
+//!
+//! Page 0 of every disk is the superblock.  It records the engine
+//! configuration and where the catalog starts, so a disk image is
+//! self-describing:
+//!
+//! ```text
+//! [24..28] magic "WZSB"
+//! [28..32] page_size          : u32
+//! [32..36] pool_size          : u32
+//! [36..40] disk_capacity      : u32
+//! [40..44] overflow_threshold : u32
+//! [44]     replacement policy : u8   (see `ReplacementPolicy::tag`)
+//! [45..49] policy parameter   : u32  (K for LRU-K)
+//! [49..53] catalog root       : u32
+//! ```
+//!
+//! The I/O cost model is a property of the simulation rather than of the
+//! data, so it isn't stored; a reopened image gets the default model.
+//!
+//! The catalog is a chain of pages holding one serialized byte string, cut
+//! into chunks.  Each catalog page stores `[24..26] used : u16` followed by
+//! its chunk.  The byte string lists every table:
+//!
+//! ```text
+//! table_count : u16
+//! per table:
+//!     name (u16 len + UTF-8) | first_page u32 | fsm_page u32
+//!     column_count u16, per column:
+//!         name (u16 len + UTF-8) | type_tag u8 | type_param u16 | nullable u8
+//!     btree_count u8, per index: column u16 | root u32
+//!     hash_count u8,  per index: column u16 | directory u32
+//! ```
+//!
+//! Everything else about a table (row count, last page, free space) is
+//! recomputed from its pages when the catalog is loaded.  The catalog is
+//! rewritten as a whole by each DDL statement, inside the statement's
+//! transaction, with before/after page images so it rolls back and
+//! recovers like any other page.
+
+use crate::storage::types::*;
+use crate::storage::error::{StorageError, StorageResult};
+use crate::storage::page;
+use crate::storage::config::EngineConfig;
+use crate::storage::replacer::ReplacementPolicy;
+use crate::storage::io_cost::IoCostModel;
+use crate::storage::schema::*;
+use crate::storage::btree::BTreeIndex;
+use crate::storage::hash_index::HashIndex;
+use crate::storage::table::TableHeap;
+use crate::storage::buffer_pool::BufferPoolManager;
+use crate::storage::wal::LogBody;
+
+/// The superblock always lives on the first page.
+pub const SUPERBLOCK_PAGE: PageId = 0;
+
+const MAGIC: &[u8; 4] = b"WZSB";
+const OFF_CATALOG_ROOT: usize = PAGE_HEADER_SIZE + 25;
+const OFF_USED: usize = PAGE_HEADER_SIZE;
+const CHUNK_START: usize = PAGE_HEADER_SIZE + 2;
+
+// ── Superblock ─────────────────────────────────────────────────────
+
+/// Allocate the superblock and an empty catalog on a fresh disk, on behalf
+/// of `txn`.  Returns the catalog root.
+pub fn format(
+    bpm: &mut BufferPoolManager,
+    txn: TxnId,
+    config: &EngineConfig,
+) -> StorageResult<PageId> {
+    let (page_id, fid) = bpm.new_page_logged(txn, PageType::Superblock)?;
+    let root = match bpm.new_page_logged(txn, PageType::Catalog) {
+        Ok((root, _)) if page_id == SUPERBLOCK_PAGE => root,
+        other => {
+            bpm.unpin_page(page_id, true);
+            other?;
+            return Err(StorageError::Catalog("Page 0 is already in use".into()));
+        }
+    };
+    bpm.unpin_page(root, true);
+
+    let before = bpm.frame_data(fid).to_vec();
+    encode_superblock(bpm.frame_data_mut(fid), config, root);
+    let after = bpm.frame_data(fid).to_vec();
+    bpm.log_update(txn, fid, LogBody::IndexPage { page_id, before, after });
+    bpm.unpin_page(page_id, true);
+
+    save(bpm, txn, root, &[])?;
+    Ok(root)
+}
+
+/// Write `config` and the catalog root into a superblock page.
+pub fn encode_superblock(buf: &mut [u8], config: &EngineConfig, catalog_root: PageId) {
+    let k = match config.replacement_policy {
+        ReplacementPolicy::LruK(k) => k,
+        _ => 0,
+    };
+    let base = PAGE_HEADER_SIZE;
+    buf[base..base + 4].copy_from_slice(MAGIC);
+    write_u32(buf, base + 4, config.page_size);
+    write_u32(buf, base + 8, config.pool_size);
+    write_u32(buf, base + 12, config.disk_capacity);
+    write_u32(buf, base + 16, config.overflow_threshold);
+    buf[base + 20] = config.replacement_policy.tag();
+    write_u32(buf, base + 21, k);
+    write_u32(buf, OFF_CATALOG_ROOT, catalog_root);
+}
+
+/// Read the configuration and catalog root back out of a superblock page.
+pub fn decode_superblock(buf: &[u8]) -> StorageResult<(EngineConfig, PageId)> {
+    let base = PAGE_HEADER_SIZE;
+    if buf.len() < OFF_CATALOG_ROOT + 4
+        || PageType::from_u8(buf[4]) != PageType::Superblock
+        || &buf[base..base + 4] != MAGIC
+    {
+        return Err(StorageError::Catalog("Page 0 is not a superblock".into()));
+    }
+    let replacement_policy = ReplacementPolicy::from_tag(buf[base + 20], read_u32(buf, base + 21))
+        .ok_or_else(|| {
+            StorageError::Catalog("Superblock names an unknown replacement policy".into())
+        })?;
+    let config = EngineConfig {
+        page_size: read_u32(buf, base + 4),
+        pool_size: read_u32(buf, base + 8),
+        disk_capacity: read_u32(buf, base + 12),
+        overflow_threshold: read_u32(buf, base + 16),
+        replacement_policy,
+        io_cost: IoCostModel::default(),
+    };
+    Ok((config, read_u32(buf, OFF_CATALOG_ROOT)))
+}
+
+// ── Catalog ────────────────────────────────────────────────────────
+
+/// Rewrite the catalog rooted at `root` to describe `tables`, on behalf of
+/// `txn`.  The chain grows or shrinks to fit.
+pub fn save(
+    bpm: &mut BufferPoolManager,
+    txn: TxnId,
+    root: PageId,
+    tables: &[&TableHeap],
+) -> StorageResult<()> {
+    let bytes = encode_tables(tables);
+    let chunk_size = bpm.page_size() as usize - CHUNK_START;
+    let mut chunks = bytes.chunks(chunk_size);
+    let mut current = root;
+    loop {
+        let chunk = chunks.next().unwrap_or(&[]);
+        let fid = bpm.fetch_page(current)?;
+        let before = bpm.frame_data(fid).to_vec();
+        let buf = bpm.frame_data_mut(fid);
+        write_u16(buf, OFF_USED, chunk.len() as u16);
+        buf[CHUNK_START..CHUNK_START + chunk.len()].copy_from_slice(chunk);
+        buf[CHUNK_START + chunk.len()..].fill(0);
+        let next = page::next_page(buf);
+        let after = buf.to_vec();
+        if after != before {
+            bpm.log_update(txn, fid, LogBody::IndexPage { page_id: current, before, after });
+        }
+        bpm.unpin_page(current, true);
+
+        if chunks.len() == 0 {
+            // Drop pages the catalog no longer needs.
+            if next != INVALID_PAGE {
+                let spare = chain(bpm, next);
+                bpm.set_next_page_logged(txn, current, INVALID_PAGE)?;
+                for pid in spare {
+                    bpm.free_page_logged(txn, pid);
+                }
+            }
+            return Ok(());
+        }
+        current = if next != INVALID_PAGE {
+            next
+        } else {
+            let (new_id, _) = bpm.new_page_logged(txn, PageType::Catalog)?;
+            bpm.unpin_page(new_id, true);
+            bpm.set_next_page_logged(txn, current, new_id)?;
+            new_id
+        };
+    }
+}
+
+/// Reopen every table recorded in the catalog rooted at `root`.
+pub fn load(
+    bpm: &mut BufferPoolManager,
+    root: PageId,
+    overflow_threshold: u32,
+) -> StorageResult<Vec<TableHeap>> {
+    let mut bytes = Vec::new();
+    for pid in chain(bpm, root) {
+        let fid = bpm.fetch_page(pid)?;
+        let buf = bpm.frame_data(fid);
+        let used = read_u16(buf, OFF_USED) as usize;
+        let chunk = buf.get(CHUNK_START..CHUNK_START + used).map(|c| c.to_vec());
+        bpm.unpin_page(pid, false);
+        bytes.extend(chunk.ok_or_else(|| {
+            StorageError::Catalog(format!("Catalog page {} is corrupt", pid))
+        })?);
+    }
+
+    let mut r = Reader { data: &bytes, pos: 0 };
+    let malformed = || StorageError::Catalog("Catalog is malformed".into());
+    let count = r.u16().ok_or_else(malformed)?;
+    let mut tables = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let name = r.string().ok_or_else(malformed)?;
+        let first_page_id = r.u32().ok_or_else(malformed)?;
+        let fsm_page_id = r.u32().ok_or_else(malformed)?;
+        let mut columns = Vec::new();
+        for _ in 0..r.u16().ok_or_else(malformed)? {
+            let name = r.string().ok_or_else(malformed)?;
+            let (tag, param) = (r.u8().ok_or_else(malformed)?, r.u16().ok_or_else(malformed)?);
+            let col_type = ColumnType::from_tag(tag, param).ok_or_else(malformed)?;
+            columns.push(Column { name, col_type, nullable: r.u8().ok_or_else(malformed)? != 0 });
+        }
+        let schema = Schema::new(columns);
+
+        let mut table =
+            TableHeap::open(name, schema, overflow_threshold, first_page_id, fsm_page_id, bpm);
+        for _ in 0..r.u8().ok_or_else(malformed)? {
+            let column = r.u16().ok_or_else(malformed)? as usize;
+            let root_page_id = r.u32().ok_or_else(malformed)?;
+            let col = table.schema.columns.get(column).ok_or_else(malformed)?;
+            let key_size = key_size(&col.col_type);
+            table.indexes.push(BTreeIndex { column, root_page_id, key_size });
+        }
+        for _ in 0..r.u8().ok_or_else(malformed)? {
+            let column = r.u16().ok_or_else(malformed)? as usize;
+            let directory_page_id = r.u32().ok_or_else(malformed)?;
+            let col = table.schema.columns.get(column).ok_or_else(malformed)?;
+            let key_size = key_size(&col.col_type);
+            table.hash_indexes.push(HashIndex { column, directory_page_id, key_size });
+        }
+        tables.push(table);
+    }
+    Ok(tables)
+}
+
+/// Every page in the catalog chain.
+pub fn page_ids(bpm: &mut BufferPoolManager, root: PageId) -> Vec<PageId> {
+    chain(bpm, root)
+}
+
+// ── Internal ───────────────────────────────────────────────────────
+
+fn encode_tables(tables: &[&TableHeap]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&(tables.len() as u16).to_le_bytes());
+    for table in tables {
+        push_str(&mut out, &table.name);
+        out.extend_from_slice(&table.first_page_id.to_le_bytes());
+        out.extend_from_slice(&table.fsm_page_id.to_le_bytes());
+        out.extend_from_slice(&(table.schema.columns.len() as u16).to_le_bytes());
+        for col in &table.schema.columns {
+            push_str(&mut out, &col.name);
+            out.push(col.col_type.type_tag());
+            out.extend_from_slice(&col.col_type.type_param().to_le_bytes());
+            out.push(col.nullable as u8);
+        }
+        out.push(table.indexes.len() as u8);
+        for index in &table.indexes {
+            out.extend_from_slice(&(index.column as u16).to_le_bytes());
+            out.extend_from_slice(&index.root_page_id.to_le_bytes());
+        }
+        out.push(table.hash_indexes.len() as u8);
+        for index in &table.hash_indexes {
+            out.extend_from_slice(&(index.column as u16).to_le_bytes());
+            out.extend_from_slice(&index.directory_page_id.to_le_bytes());
+        }
+    }
+    out
+}
+
+fn chain(bpm: &mut BufferPoolManager, root: PageId) -> Vec<PageId> {
+    let mut ids = Vec::new();
+    let mut current = root;
+    while current != INVALID_PAGE {
+        ids.push(current);
+        let Ok(fid) = bpm.fetch_page(current) else { break };
+        let next = page::next_page(bpm.frame_data(fid));
+        bpm.unpin_page(current, false);
+        current = next;
+    }
+    ids
+}
+
+fn push_str(out: &mut Vec<u8>, s: &str) {
+    out.extend_from_slice(&(s.len() as u16).to_le_bytes());
+    out.extend_from_slice(s.as_bytes());
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, n: usize) -> Option<&[u8]> {
+        let s = self.data.get(self.pos..self.pos + n)?;
+        self.pos += n;
+        Some(s)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        Some(read_u16(self.take(2)?, 0))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(read_u32(self.take(4)?, 0))
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let len = self.u16()? as usize;
+        String::from_utf8(self.take(len)?.to_vec()).ok()
+    }
+}
+
+fn read_u16(buf: &[u8], off: usize) -> u16 {
+    u16::from_le_bytes([buf[off], buf[off + 1]])
+}
+
+fn write_u16(buf: &mut [u8], off: usize, v: u16) {
+    buf[off..off + 2].copy_from_slice(&v.to_le_bytes());
+}
+
+fn read_u32(buf: &[u8], off: usize) -> u32 {
+    u32::from_le_bytes([buf[off], buf[off + 1], buf[off + 2], buf[off + 3]])
+}
+
+fn write_u32(buf: &mut [u8], off: usize, v: u32) {
+    buf[off..off + 4].copy_from_slice(&v.to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::disk::DiskManager;
+    use crate::storage::recovery;
+
+    const TXN: TxnId = 1;
+
+    fn schema() -> Schema {
+        Schema::new(vec![
+            Column { name: "id".into(), col_type: ColumnType::Int32, nullable: false },
+            Column { name: "name".into(), col_type: ColumnType::VarChar(40), nullable: true },
+        ])
+    }
+
+    #[test]
+    fn superblock_round_trip() {
+        let mut config = EngineConfig::default_config();
+        config.replacement_policy = ReplacementPolicy::LruK(3);
+        let mut bpm = BufferPoolManager::new(4, DiskManager::new(128, 16));
+        let root = format(&mut bpm, TXN, &config).unwrap();
+
+        let fid = bpm.fetch_page(SUPERBLOCK_PAGE).unwrap();
+        let (decoded, decoded_root) = decode_superblock(bpm.frame_data(fid)).unwrap();
+        bpm.unpin_page(SUPERBLOCK_PAGE, false);
+        assert_eq!(decoded_root, root);
+        assert_eq!(decoded.replacement_policy, ReplacementPolicy::LruK(3));
+        assert_eq!(decoded.disk_capacity, config.disk_capacity);
+        assert!(decode_superblock(&[0u8; 128]).is_err());
+    }
+
+    #[test]
+    fn tables_survive_save_and_load() {
+        let mut bpm = BufferPoolManager::new(8, DiskManager::new(128, 32));
+        let root = format(&mut bpm, TXN, &EngineConfig::default_config()).unwrap();
+        let mut tables = Vec::new();
+        for name in ["accounts", "customers_with_long_names", "orders"] {
+            let mut t = TableHeap::create(name.into(), schema(), 64, &mut bpm, TXN).unwrap();
+            t.insert(&mut bpm, TXN, &[Value::Int32(1), Value::Null]).unwrap();
+            tables.push(t);
+        }
+        tables[1].create_index(&mut bpm, TXN, 0).unwrap();
+        tables[2].create_hash_index(&mut bpm, TXN, 1).unwrap();
+        save(&mut bpm, TXN, root, &tables.iter().collect::<Vec<_>>()).unwrap();
+        assert!(page_ids(&mut bpm, root).len() > 1);
+
+        let loaded = load(&mut bpm, root, 64).unwrap();
+        assert_eq!(loaded.len(), 3);
+        assert_eq!(loaded[1].name, "customers_with_long_names");
+        assert_eq!(loaded[1].schema.columns[1].col_type, ColumnType::VarChar(40));
+        assert_eq!(loaded[1].indexes[0].root_page_id, tables[1].indexes[0].root_page_id);
+        assert_eq!(loaded[2].hash_indexes[0].column, 1);
+        assert_eq!(loaded[0].row_count, 1);
+
+        // Shrinking the catalog frees the pages it no longer needs.
+        save(&mut bpm, TXN, root, &[&tables[0]]).unwrap();
+        assert_eq!(page_ids(&mut bpm, root), vec![root]);
+        assert_eq!(load(&mut bpm, root, 64).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn rollback_restores_catalog() {
+        let mut bpm = BufferPoolManager::new(8, DiskManager::new(128, 32));
+        let setup = bpm.log.begin();
+        let root = format(&mut bpm, setup, &EngineConfig::default_config()).unwrap();
+        bpm.log.commit(setup);
+
+        let txn = bpm.log.begin();
+        let t = TableHeap::create("t".into(), schema(), 64, &mut bpm, txn).unwrap();
+        save(&mut bpm, txn, root, &[&t]).unwrap();
+        assert_eq!(load(&mut bpm, root, 64).unwrap().len(), 1);
+        recovery::rollback(&mut bpm, txn).unwrap();
+        assert!(load(&mut bpm, root, 64).unwrap().is_empty());
+    }
+}
@@ -1,4 +1,6 @@
 use crate::storage::types::PAGE_HEADER_SIZE;
+use crate::storage::replacer::ReplacementPolicy;
+use crate::storage::io_cost::IoCostModel;
 
 /// Engine configuration — set once at initialization.
 #[derive(Debug, Clone)]
@@ -11,6 +13,10 @@ pub struct EngineConfig {
     pub disk_capacity: u32,
     /// Values larger than this spill to overflow pages.
     pub overflow_threshold: u32,
+    /// Buffer pool page replacement policy.
+    pub replacement_policy: ReplacementPolicy,
+    /// Simulated disk latencies.
+    pub io_cost: IoCostModel,
 }
 
 impl EngineConfig {
@@ -20,6 +26,8 @@ impl EngineConfig {
             pool_size: 8,
             disk_capacity: 64,
             overflow_threshold: 64,
+            replacement_policy: ReplacementPolicy::Lru,
+            io_cost: IoCostModel::default(),
         }
     }
 
@@ -27,12 +35,7 @@ impl EngineConfig {
     /// truly invalid input, otherwise silently clamps.
     pub fn validate(&mut self) -> Result<(), String> {
         // Page size: 64..=8192, multiple of 8
-        if self.page_size < 64 {
-            self.page_size = 64;
-        }
-        if self.page_size > 8192 {
-            self.page_size = 8192;
-        }
+        self.page_size = self.page_size.clamp(64, 8192);
         // Round up to multiple of 8
         self.page_size = (self.page_size + 7) & !7;
 
@@ -46,7 +49,7 @@ impl EngineConfig {
         }
 
         // Overflow threshold: 32..=(page_size - header - one slot)
-        let max_overflow = self.page_size as u32 - PAGE_HEADER_SIZE as u32 - 4 /*slot*/;
+        let max_overflow = self.page_size - PAGE_HEADER_SIZE as u32 - 4 /*slot*/;
         if self.overflow_threshold < 32 {
             self.overflow_threshold = 32;
         }
@@ -54,6 +57,11 @@ impl EngineConfig {
             self.overflow_threshold = max_overflow;
         }
 
+        // LRU-K: K in 1..=8
+        if let ReplacementPolicy::LruK(k) = self.replacement_policy {
+            self.replacement_policy = ReplacementPolicy::LruK(k.clamp(1, 8));
+        }
+
         Ok(())
     }
 
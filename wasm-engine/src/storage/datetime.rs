@@ -0,0 +1,185 @@
+//! Calendar arithmetic for the Date and Timestamp column types.
+//!
+//! Dates are stored as days since 1970-01-01 and timestamps as microseconds
+//! since 1970-01-01 00:00:00 UTC, both in the proleptic Gregorian calendar.
+//! Text forms are ISO 8601: `2024-02-29` and `2024-02-29T13:45:00.25Z`.
+
+const MICROS_PER_SEC: i64 = 1_000_000;
+const MICROS_PER_DAY: i64 = 86_400 * MICROS_PER_SEC;
+
+fn is_leap(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap(year) => 29,
+        2 => 28,
+        _ => 31,
+    }
+}
+
+/// Days from 1970-01-01 to the given date.
+pub fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    // Count years from March so the leap day falls at the end.
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Inverse of `days_from_civil`: (year, month, day).
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = era * 400 + yoe + (month <= 2) as i64;
+    (year, month, day)
+}
+
+/// Parse `YYYY-MM-DD` into days since the epoch.
+pub fn parse_date(s: &str) -> Option<i32> {
+    let b = s.as_bytes();
+    if b.len() != 10 || b[4] != b'-' || b[7] != b'-' {
+        return None;
+    }
+    let year = digits(&s[0..4])? as i64;
+    let month = digits(&s[5..7])?;
+    let day = digits(&s[8..10])?;
+    if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+        return None;
+    }
+    Some(days_from_civil(year, month, day) as i32)
+}
+
+pub fn format_date(days: i32) -> String {
+    let (year, month, day) = civil_from_days(days as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Parse an ISO 8601 timestamp into microseconds since the epoch:
+/// `YYYY-MM-DD[(T| )HH:MM[:SS[.ffffff]]][Z|±HH:MM]`.  Without an offset
+/// the time is taken as UTC.
+pub fn parse_timestamp(s: &str) -> Option<i64> {
+    let days = parse_date(s.get(0..10)?)? as i64;
+    let mut rest = &s[10..];
+    let mut micros = 0i64;
+
+    if let Some(time) = rest.strip_prefix('T').or_else(|| rest.strip_prefix(' ')) {
+        let hour = digits(time.get(0..2)?)? as i64;
+        if time.as_bytes().get(2) != Some(&b':') {
+            return None;
+        }
+        let minute = digits(time.get(3..5)?)? as i64;
+        rest = &time[5..];
+        let mut second = 0;
+        if let Some(sec) = rest.strip_prefix(':') {
+            second = digits(sec.get(0..2)?)? as i64;
+            rest = &sec[2..];
+            if let Some(frac) = rest.strip_prefix('.') {
+                let len = frac.bytes().take_while(u8::is_ascii_digit).count();
+                if len == 0 || len > 6 {
+                    return None;
+                }
+                micros = digits(&frac[..len])? as i64 * 10i64.pow(6 - len as u32);
+                rest = &frac[len..];
+            }
+        }
+        if hour > 23 || minute > 59 || second > 59 {
+            return None;
+        }
+        micros += (hour * 3600 + minute * 60 + second) * MICROS_PER_SEC;
+    }
+
+    let offset = match rest {
+        "" | "Z" => 0,
+        _ => {
+            let sign = match rest.as_bytes()[0] {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return None,
+            };
+            if rest.len() != 6 || rest.as_bytes()[3] != b':' {
+                return None;
+            }
+            let (h, m) = (digits(&rest[1..3])? as i64, digits(&rest[4..6])? as i64);
+            if h > 23 || m > 59 {
+                return None;
+            }
+            sign * (h * 3600 + m * 60) * MICROS_PER_SEC
+        }
+    };
+    Some(days * MICROS_PER_DAY + micros - offset)
+}
+
+/// `YYYY-MM-DDTHH:MM:SSZ`, with as many fractional digits as needed.
+pub fn format_timestamp(micros: i64) -> String {
+    let days = micros.div_euclid(MICROS_PER_DAY);
+    let in_day = micros.rem_euclid(MICROS_PER_DAY);
+    let (year, month, day) = civil_from_days(days);
+    let secs = in_day / MICROS_PER_SEC;
+    let mut out = format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year, month, day, secs / 3600, secs / 60 % 60, secs % 60
+    );
+    let frac = in_day % MICROS_PER_SEC;
+    if frac != 0 {
+        out.push_str(format!(".{:06}", frac).trim_end_matches('0'));
+    }
+    out.push('Z');
+    out
+}
+
+/// An unsigned decimal number made only of ASCII digits.
+fn digits(s: &str) -> Option<u32> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates() {
+        assert_eq!(parse_date("1970-01-01"), Some(0));
+        assert_eq!(parse_date("2000-03-01"), Some(11_017));
+        assert_eq!(parse_date("1969-12-31"), Some(-1));
+        assert_eq!(parse_date("2024-02-29").map(format_date).as_deref(), Some("2024-02-29"));
+        assert_eq!(parse_date("2023-02-29"), None);
+        assert_eq!(parse_date("2023-13-01"), None);
+        assert_eq!(parse_date("2023-1-01"), None);
+        for days in [-800_000, -1, 0, 59, 60, 365, 19_000, 2_000_000] {
+            let (y, m, d) = civil_from_days(days);
+            assert_eq!(days_from_civil(y, m, d), days);
+        }
+    }
+
+    #[test]
+    fn timestamps() {
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(parse_timestamp("1970-01-01"), Some(0));
+        assert_eq!(parse_timestamp("1970-01-01 00:01"), Some(60_000_000));
+        assert_eq!(parse_timestamp("1970-01-01T01:00:00+01:00"), Some(0));
+        assert_eq!(parse_timestamp("1969-12-31T23:59:59.5Z"), Some(-500_000));
+        assert_eq!(format_timestamp(-500_000), "1969-12-31T23:59:59.5Z");
+        assert_eq!(format_timestamp(1_709_214_300_000_000), "2024-02-29T13:45:00Z");
+        assert_eq!(parse_timestamp("2024-02-29T24:00:00Z"), None);
+        assert_eq!(parse_timestamp("2024-02-29T10:00:00.1234567Z"), None);
+        assert_eq!(parse_timestamp("2024-02-29T10:00:00 UTC"), None);
+
+        let t = parse_timestamp("2024-02-29T13:45:00.000250Z").unwrap();
+        assert_eq!(parse_timestamp(&format_timestamp(t)), Some(t));
+    }
+}
@@ -1,137 +1,432 @@
+//! Disk storage.
+//!
+//! `DiskManager` is what the buffer pool talks to: it counts I/O for the UI,
+//! charges it to a simulated clock (see `io_cost`) and forwards page
+//! traffic to a `DiskBackend`.  Two backends exist: the in-memory
+//! `MemoryBackend` used under WASM (the frontend reads its bytes straight
+//! out of linear memory), and a single-file backend for native builds (see
+//! `file_disk`).  It can also inject faults on the way through (see
+//! `fault`).
+//!
+//! A whole disk can also be serialized as one byte string, the "image":
+//!
+//! ```text
+//! [0..8]   magic "WIZALLOC"
+//! [8..12]  format version : u32
+//! [12..16] page_size      : u32
+//! [16..20] max_pages      : u32
+//! [20..]   allocation bitmap, one bit per page (LSB first)
+//! ```
+//!
+//! The header is padded to a whole number of pages, and page `n` follows at
+//! `header_len + n * page_size`.  The file backend uses the same layout, so
+//! an exported image is also a database file.
+
+use std::io;
 use crate::storage::types::*;
+use crate::storage::fault::{FaultInjector, WriteFault};
+use crate::storage::io_cost::IoClock;
 
-/// Manages a contiguous byte array that acts as "disk" storage.
-///
-/// Each page is a fixed-size slice of the storage vector.  A free-list
-/// tracks deallocated pages for reuse.  I/O counters let the UI display
-/// read/write statistics.
-pub struct DiskManager {
-    /// The "disk" — a pre-allocated byte buffer.
-    storage: Vec<u8>,
-    /// Which pages are currently allocated (true = in use).
+// ── Image format ───────────────────────────────────────────────────
+
+const IMAGE_MAGIC: &[u8; 8] = b"WIZALLOC";
+const IMAGE_VERSION: u32 = 1;
+/// Bytes before the allocation bitmap.
+pub const IMAGE_PREFIX_SIZE: usize = 20;
+
+/// Header bytes (prefix plus bitmap) rounded up to a whole number of pages.
+pub fn image_header_len(page_size: u32, max_pages: u32) -> usize {
+    let raw = IMAGE_PREFIX_SIZE + (max_pages as usize).div_ceil(8);
+    raw.div_ceil(page_size as usize) * page_size as usize
+}
+
+/// Encode the full, padded image header.
+pub fn encode_image_header(page_size: u32, max_pages: u32, allocated: &[bool]) -> Vec<u8> {
+    let mut out = vec![0u8; image_header_len(page_size, max_pages)];
+    out[0..8].copy_from_slice(IMAGE_MAGIC);
+    out[8..12].copy_from_slice(&IMAGE_VERSION.to_le_bytes());
+    out[12..16].copy_from_slice(&page_size.to_le_bytes());
+    out[16..20].copy_from_slice(&max_pages.to_le_bytes());
+    for (p, _) in allocated.iter().enumerate().filter(|&(_, &a)| a) {
+        out[IMAGE_PREFIX_SIZE + p / 8] |= 1 << (p % 8);
+    }
+    out
+}
+
+/// Decode the fixed prefix into `(page_size, max_pages)`.
+pub fn decode_image_prefix(bytes: &[u8]) -> io::Result<(u32, u32)> {
+    if bytes.len() < IMAGE_PREFIX_SIZE || &bytes[0..8] != IMAGE_MAGIC {
+        return Err(invalid_data("not a wizalloc database image"));
+    }
+    let field = |off: usize| u32::from_le_bytes(bytes[off..off + 4].try_into().unwrap());
+    if field(8) != IMAGE_VERSION {
+        return Err(invalid_data("unsupported database image version"));
+    }
+    let (page_size, max_pages) = (field(12), field(16));
+    if page_size < PAGE_HEADER_SIZE as u32 {
+        return Err(invalid_data("page size too small"));
+    }
+    Ok((page_size, max_pages))
+}
+
+/// Decode a full header into `(page_size, max_pages, allocation bitmap)`.
+pub fn decode_image_header(bytes: &[u8]) -> io::Result<(u32, u32, Vec<bool>)> {
+    let (page_size, max_pages) = decode_image_prefix(bytes)?;
+    let bits = bytes
+        .get(IMAGE_PREFIX_SIZE..IMAGE_PREFIX_SIZE + (max_pages as usize).div_ceil(8))
+        .ok_or_else(|| invalid_data("truncated allocation bitmap"))?;
+    let allocated = (0..max_pages as usize)
+        .map(|p| bits[p / 8] & (1 << (p % 8)) != 0)
+        .collect();
+    Ok((page_size, max_pages, allocated))
+}
+
+pub(crate) fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+// ── Backend trait ──────────────────────────────────────────────────
+
+/// Where pages physically live.  Page ids run from 0 to `max_pages - 1`;
+/// a backend hands out ids, zeroes them on allocation and stores their
+/// bytes.
+pub trait DiskBackend {
+    fn page_size(&self) -> u32;
+    fn max_pages(&self) -> u32;
+    /// Copy a page's bytes into `buf`.
+    fn read_page(&self, page_id: PageId, buf: &mut [u8]) -> io::Result<()>;
+    /// Overwrite a page with `data`.
+    fn write_page(&mut self, page_id: PageId, data: &[u8]) -> io::Result<()>;
+    /// Reserve a zeroed page, or `None` if the disk is full.
+    fn allocate(&mut self) -> Option<PageId>;
+    /// Reserve a specific page (recovery re-creating a logged page).
+    /// Returns `false` if it is already allocated or out of range.
+    fn allocate_at(&mut self, page_id: PageId) -> bool;
+    /// Return a page to the free list.
+    fn deallocate(&mut self, page_id: PageId);
+    fn is_allocated(&self, page_id: PageId) -> bool;
+    fn num_allocated(&self) -> u32;
+    /// Make every completed write durable.
+    fn sync(&mut self) -> io::Result<()>;
+    /// Address of the page bytes in memory, for backends that keep them
+    /// there (lets the frontend read WASM memory directly).
+    fn base_ptr(&self) -> Option<usize> {
+        None
+    }
+}
+
+// ── Allocation map ─────────────────────────────────────────────────
+
+/// Which pages are in use, plus a free list of released ids.  Shared by the
+/// backends so they hand out ids the same way.
+#[derive(Debug, Clone)]
+pub struct AllocationMap {
     allocated: Vec<bool>,
-    /// Free page IDs available for reuse.
     free_list: Vec<PageId>,
-    /// Total number of pages currently allocated.
     num_allocated: u32,
-    /// Bytes per page.
+}
+
+impl AllocationMap {
+    pub fn new(max_pages: u32) -> Self {
+        Self::from_bitmap(vec![false; max_pages as usize])
+    }
+
+    /// Rebuild from a saved bitmap.  Holes are found again by the linear
+    /// scan in `allocate`.
+    pub fn from_bitmap(allocated: Vec<bool>) -> Self {
+        let num_allocated = allocated.iter().filter(|&&a| a).count() as u32;
+        Self { allocated, free_list: Vec::new(), num_allocated }
+    }
+
+    /// Pick a page id: the free list first, then the lowest unused id.
+    pub fn allocate(&mut self) -> Option<PageId> {
+        let pid = match self.free_list.pop() {
+            Some(pid) => pid,
+            None => self.allocated.iter().position(|&a| !a)? as PageId,
+        };
+        self.allocated[pid as usize] = true;
+        self.num_allocated += 1;
+        Some(pid)
+    }
+
+    pub fn allocate_at(&mut self, page_id: PageId) -> bool {
+        if page_id as usize >= self.allocated.len() || self.allocated[page_id as usize] {
+            return false;
+        }
+        self.free_list.retain(|&p| p != page_id);
+        self.allocated[page_id as usize] = true;
+        self.num_allocated += 1;
+        true
+    }
+
+    /// Returns `false` if the page wasn't allocated.
+    pub fn deallocate(&mut self, page_id: PageId) -> bool {
+        if !self.is_allocated(page_id) {
+            return false;
+        }
+        self.allocated[page_id as usize] = false;
+        self.num_allocated -= 1;
+        self.free_list.push(page_id);
+        true
+    }
+
+    pub fn is_allocated(&self, page_id: PageId) -> bool {
+        (page_id as usize) < self.allocated.len() && self.allocated[page_id as usize]
+    }
+
+    pub fn num_allocated(&self) -> u32 {
+        self.num_allocated
+    }
+
+    pub fn bitmap(&self) -> &[bool] {
+        &self.allocated
+    }
+}
+
+// ── In-memory backend ──────────────────────────────────────────────
+
+/// A contiguous byte array that acts as "disk" storage.  Each page is a
+/// fixed-size slice of the vector.  Gone when the engine is dropped.
+pub struct MemoryBackend {
+    /// The "disk" — a pre-allocated byte buffer.
+    storage: Vec<u8>,
+    map: AllocationMap,
     page_size: u32,
-    /// Maximum number of pages the disk can hold.
     max_pages: u32,
+}
+
+impl MemoryBackend {
+    pub fn new(page_size: u32, max_pages: u32) -> Self {
+        Self {
+            storage: vec![0u8; page_size as usize * max_pages as usize],
+            map: AllocationMap::new(max_pages),
+            page_size,
+            max_pages,
+        }
+    }
+
+    /// Load a disk image (see the module docs).
+    pub fn from_image(bytes: &[u8]) -> io::Result<Self> {
+        let (page_size, max_pages, allocated) = decode_image_header(bytes)?;
+        let start = image_header_len(page_size, max_pages);
+        let len = page_size as usize * max_pages as usize;
+        let storage = bytes
+            .get(start..start + len)
+            .ok_or_else(|| invalid_data("truncated page data"))?
+            .to_vec();
+        Ok(Self { storage, map: AllocationMap::from_bitmap(allocated), page_size, max_pages })
+    }
+
+    fn page_range(&self, page_id: PageId) -> std::ops::Range<usize> {
+        let offset = page_id as usize * self.page_size as usize;
+        offset..offset + self.page_size as usize
+    }
+}
+
+impl DiskBackend for MemoryBackend {
+    fn page_size(&self) -> u32 {
+        self.page_size
+    }
+
+    fn max_pages(&self) -> u32 {
+        self.max_pages
+    }
+
+    fn read_page(&self, page_id: PageId, buf: &mut [u8]) -> io::Result<()> {
+        let range = self.page_range(page_id);
+        buf[..range.len()].copy_from_slice(&self.storage[range]);
+        Ok(())
+    }
+
+    fn write_page(&mut self, page_id: PageId, data: &[u8]) -> io::Result<()> {
+        let range = self.page_range(page_id);
+        let size = range.len();
+        self.storage[range].copy_from_slice(&data[..size]);
+        Ok(())
+    }
+
+    fn allocate(&mut self) -> Option<PageId> {
+        let pid = self.map.allocate()?;
+        let range = self.page_range(pid);
+        self.storage[range].fill(0);
+        Some(pid)
+    }
+
+    fn allocate_at(&mut self, page_id: PageId) -> bool {
+        if !self.map.allocate_at(page_id) {
+            return false;
+        }
+        let range = self.page_range(page_id);
+        self.storage[range].fill(0);
+        true
+    }
+
+    fn deallocate(&mut self, page_id: PageId) {
+        self.map.deallocate(page_id);
+    }
+
+    fn is_allocated(&self, page_id: PageId) -> bool {
+        self.map.is_allocated(page_id)
+    }
+
+    fn num_allocated(&self) -> u32 {
+        self.map.num_allocated()
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn base_ptr(&self) -> Option<usize> {
+        Some(self.storage.as_ptr() as usize)
+    }
+}
+
+// ── Disk manager ───────────────────────────────────────────────────
+
+/// The buffer pool's view of the disk: a backend plus I/O counters and a
+/// simulated clock the UI displays as read/write statistics.
+pub struct DiskManager {
+    backend: Box<dyn DiskBackend>,
     /// Cumulative I/O counters.
     pub read_count: u64,
     pub write_count: u64,
+    /// Simulated time spent on I/O.
+    pub clock: IoClock,
+    /// Armed and fired faults.  Does nothing until a fault is armed.
+    pub faults: FaultInjector,
 }
 
 impl DiskManager {
+    /// An in-memory disk.
     pub fn new(page_size: u32, max_pages: u32) -> Self {
-        let total_bytes = page_size as usize * max_pages as usize;
+        Self::with_backend(Box::new(MemoryBackend::new(page_size, max_pages)))
+    }
+
+    pub fn with_backend(backend: Box<dyn DiskBackend>) -> Self {
         Self {
-            storage: vec![0u8; total_bytes],
-            allocated: vec![false; max_pages as usize],
-            free_list: Vec::new(),
-            num_allocated: 0,
-            page_size,
-            max_pages,
+            backend,
             read_count: 0,
             write_count: 0,
+            clock: IoClock::default(),
+            faults: FaultInjector::new(0),
         }
     }
 
     /// Allocate a fresh page, returning its ID.  Returns `None` if disk is full.
     pub fn allocate_page(&mut self) -> Option<PageId> {
-        // Try free list first
-        if let Some(pid) = self.free_list.pop() {
-            self.allocated[pid as usize] = true;
-            self.num_allocated += 1;
-            // Zero out the page
-            let offset = self.page_offset(pid);
-            self.storage[offset..offset + self.page_size as usize].fill(0);
-            return Some(pid);
-        }
-        // Linear scan for an unallocated page
-        for i in 0..self.max_pages {
-            if !self.allocated[i as usize] {
-                self.allocated[i as usize] = true;
-                self.num_allocated += 1;
-                let offset = self.page_offset(i);
-                self.storage[offset..offset + self.page_size as usize].fill(0);
-                return Some(i);
-            }
-        }
-        None // disk full
+        self.backend.allocate()
+    }
+
+    /// Allocate a specific page ID (used by recovery to re-create a page at
+    /// a logged address).  Returns `false` if it is already allocated or out
+    /// of range.
+    pub fn allocate_specific(&mut self, page_id: PageId) -> bool {
+        self.backend.allocate_at(page_id)
     }
 
     /// Deallocate a page, returning it to the free list.
     pub fn deallocate_page(&mut self, page_id: PageId) {
-        if (page_id as usize) < self.allocated.len() && self.allocated[page_id as usize] {
-            self.allocated[page_id as usize] = false;
-            self.num_allocated -= 1;
-            self.free_list.push(page_id);
-        }
+        self.backend.deallocate(page_id);
     }
 
-    /// Read a page from "disk" into the provided buffer.
-    pub fn read_page(&mut self, page_id: PageId, buf: &mut [u8]) {
-        let offset = self.page_offset(page_id);
-        let size = self.page_size as usize;
-        buf[..size].copy_from_slice(&self.storage[offset..offset + size]);
+    /// Read a page from disk into the provided buffer.
+    pub fn read_page(&mut self, page_id: PageId, buf: &mut [u8]) -> io::Result<()> {
         self.read_count += 1;
+        self.clock.charge(page_id, false);
+        if self.faults.on_read(page_id) {
+            return Err(io::Error::other(format!("injected read error on page {}", page_id)));
+        }
+        self.backend.read_page(page_id, buf)
     }
 
-    /// Write buffer contents to a page on "disk".
-    pub fn write_page(&mut self, page_id: PageId, data: &[u8]) {
-        let offset = self.page_offset(page_id);
-        let size = self.page_size as usize;
-        self.storage[offset..offset + size].copy_from_slice(&data[..size]);
+    /// Write buffer contents to a page on disk.  An armed write fault may
+    /// damage or swallow the write while still reporting success, as a
+    /// crashing or lying disk would.
+    pub fn write_page(&mut self, page_id: PageId, data: &[u8]) -> io::Result<()> {
         self.write_count += 1;
+        self.clock.charge(page_id, true);
+        match self.faults.on_write(page_id, self.page_size() as usize) {
+            WriteFault::None => self.backend.write_page(page_id, data),
+            WriteFault::Dropped => Ok(()),
+            WriteFault::Torn(n) => {
+                let mut landed = self.peek_page(page_id)?;
+                landed[..n].copy_from_slice(&data[..n]);
+                self.backend.write_page(page_id, &landed)
+            }
+            WriteFault::Flip(bit) => {
+                let mut landed = data[..self.page_size() as usize].to_vec();
+                landed[bit / 8] ^= 1 << (bit % 8);
+                self.backend.write_page(page_id, &landed)
+            }
+        }
+    }
+
+    /// Make all writes so far durable.
+    pub fn sync(&mut self) -> io::Result<()> {
+        self.backend.sync()
     }
 
     /// Is the page allocated?
     pub fn is_allocated(&self, page_id: PageId) -> bool {
-        (page_id as usize) < self.allocated.len() && self.allocated[page_id as usize]
+        self.backend.is_allocated(page_id)
+    }
+
+    /// Read a page without counting it as I/O (for snapshots).
+    pub fn peek_page(&self, page_id: PageId) -> io::Result<Vec<u8>> {
+        let mut buf = vec![0u8; self.page_size() as usize];
+        self.backend.read_page(page_id, &mut buf)?;
+        Ok(buf)
     }
 
     // ── Accessors ──────────────────────────────────────────────────
 
     pub fn page_size(&self) -> u32 {
-        self.page_size
+        self.backend.page_size()
     }
 
     pub fn max_pages(&self) -> u32 {
-        self.max_pages
+        self.backend.max_pages()
     }
 
     pub fn num_allocated(&self) -> u32 {
-        self.num_allocated
+        self.backend.num_allocated()
     }
 
-    /// Base pointer of the disk storage in WASM linear memory.
+    /// Base pointer of the disk storage in WASM linear memory (0 when the
+    /// pages don't live in memory).
     pub fn storage_base_ptr(&self) -> usize {
-        self.storage.as_ptr() as usize
+        self.backend.base_ptr().unwrap_or(0)
     }
 
     /// Total storage size in bytes.
     pub fn storage_size(&self) -> usize {
-        self.storage.len()
+        self.page_size() as usize * self.max_pages() as usize
     }
 
-    /// Direct read-only access to a page's bytes on disk (for snapshots).
-    pub fn page_data(&self, page_id: PageId) -> &[u8] {
-        let offset = self.page_offset(page_id);
-        let size = self.page_size as usize;
-        &self.storage[offset..offset + size]
+    /// Allocation flag of every page.
+    pub fn allocation_bitmap(&self) -> Vec<bool> {
+        (0..self.max_pages()).map(|p| self.is_allocated(p)).collect()
     }
 
-    /// Read-only view of allocation bitmap.
-    pub fn allocation_bitmap(&self) -> &[bool] {
-        &self.allocated
+    /// Serialize the whole disk as an image.  Free pages are written as
+    /// zeros.  Not counted as I/O.
+    pub fn export_image(&self) -> io::Result<Vec<u8>> {
+        let bitmap = self.allocation_bitmap();
+        let mut out = encode_image_header(self.page_size(), self.max_pages(), &bitmap);
+        for (pid, &allocated) in bitmap.iter().enumerate() {
+            if allocated {
+                out.extend_from_slice(&self.peek_page(pid as PageId)?);
+            } else {
+                out.resize(out.len() + self.page_size() as usize, 0);
+            }
+        }
+        Ok(out)
     }
 
-    // ── Internal ───────────────────────────────────────────────────
-
-    fn page_offset(&self, page_id: PageId) -> usize {
-        page_id as usize * self.page_size as usize
+    /// An in-memory disk loaded from an image.
+    pub fn from_image(bytes: &[u8]) -> io::Result<Self> {
+        Ok(Self::with_backend(Box::new(MemoryBackend::from_image(bytes)?)))
     }
 }
 
@@ -150,12 +445,12 @@ mod tests {
         let mut data = vec![0u8; 64];
         data[0] = 0xAB;
         data[63] = 0xCD;
-        dm.write_page(pid, &data);
+        dm.write_page(pid, &data).unwrap();
         assert_eq!(dm.write_count, 1);
 
         // Read it back
         let mut buf = vec![0u8; 64];
-        dm.read_page(pid, &mut buf);
+        dm.read_page(pid, &mut buf).unwrap();
         assert_eq!(buf[0], 0xAB);
         assert_eq!(buf[63], 0xCD);
         assert_eq!(dm.read_count, 1);
@@ -165,7 +460,7 @@ mod tests {
     fn deallocate_and_reuse() {
         let mut dm = DiskManager::new(64, 4);
         let p0 = dm.allocate_page().unwrap();
-        let p1 = dm.allocate_page().unwrap();
+        let _p1 = dm.allocate_page().unwrap();
         assert_eq!(dm.num_allocated(), 2);
 
         dm.deallocate_page(p0);
@@ -177,6 +472,24 @@ mod tests {
         assert_eq!(p2, p0); // free list reuse
     }
 
+    #[test]
+    fn image_round_trip() {
+        let mut dm = DiskManager::new(64, 12);
+        let p0 = dm.allocate_page().unwrap();
+        let p1 = dm.allocate_page().unwrap();
+        dm.allocate_page().unwrap();
+        dm.deallocate_page(p1);
+        dm.write_page(p0, &[7u8; 64]).unwrap();
+
+        let image = dm.export_image().unwrap();
+        assert_eq!(image.len(), 64 + 12 * 64);
+        let copy = DiskManager::from_image(&image).unwrap();
+        assert_eq!(copy.allocation_bitmap(), dm.allocation_bitmap());
+        assert_eq!(copy.peek_page(p0).unwrap(), vec![7u8; 64]);
+        assert!(DiskManager::from_image(&image[..100]).is_err());
+        assert!(DiskManager::from_image(b"not an image at all").is_err());
+    }
+
     #[test]
     fn disk_full() {
         let mut dm = DiskManager::new(64, 2);
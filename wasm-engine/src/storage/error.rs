@@ -0,0 +1,156 @@
+//! Storage errors.
+//!
+//! Every fallible storage operation returns `StorageResult`.  Each variant
+//! carries enough context to explain itself, a stable `code` the frontend
+//! can switch on, and the page involved when there is one.
+
+use std::fmt;
+use crate::storage::types::*;
+
+pub type StorageResult<T> = Result<T, StorageError>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StorageError {
+    /// No free page left on disk.
+    DiskFull,
+    /// Every frame is pinned (or its dirty page couldn't be written out).
+    PoolExhausted,
+    /// A disk read or write failed.
+    Io { page_id: PageId, message: String },
+    /// The bytes on disk don't match the page's stored checksum.
+    Corrupt { page_id: PageId, stored: u32, computed: u32 },
+    /// The page hasn't room for a tuple of `needed` bytes.
+    PageFull { page_id: PageId, needed: usize, free: usize },
+    /// No live row at this RowId.
+    RowNotFound(RowId),
+    /// A tuple was to be placed in a slot that is still live.
+    SlotOccupied(RowId),
+    /// Values don't fit the table's schema.
+    SchemaMismatch(String),
+    /// An encoded key is wider than the index allows.
+    KeyTooLarge { size: usize, max: usize },
+    TableNotFound(String),
+    TableExists(String),
+    ColumnNotFound(String),
+    /// The column has no index of the kind asked for.
+    NotIndexed(String),
+    /// The column already has an index of that kind.
+    AlreadyIndexed(String),
+    TxnNotActive(TxnId),
+    /// A logged change couldn't be reversed: its page no longer matches
+    /// what the log says about it.
+    UndoFailed(Lsn),
+    /// Refused because transactions are running.
+    TxnsActive(String),
+    /// Malformed input: JSON, a RowId, a config value, SQL syntax.
+    Parse(String),
+    /// A query that parses but can't run: a type error, an overflow,
+    /// division by zero, a memory budget the buffer pool can't give.
+    Query(String),
+    /// The superblock, catalog or a disk image is unusable.
+    Catalog(String),
+}
+
+impl StorageError {
+    /// Stable identifier for the kind of error.
+    pub fn code(&self) -> &'static str {
+        match self {
+            StorageError::DiskFull => "DISK_FULL",
+            StorageError::PoolExhausted => "POOL_EXHAUSTED",
+            StorageError::Io { .. } => "IO_ERROR",
+            StorageError::Corrupt { .. } => "CORRUPT_PAGE",
+            StorageError::PageFull { .. } => "PAGE_FULL",
+            StorageError::RowNotFound(_) => "ROW_NOT_FOUND",
+            StorageError::SlotOccupied(_) => "SLOT_OCCUPIED",
+            StorageError::SchemaMismatch(_) => "SCHEMA_MISMATCH",
+            StorageError::KeyTooLarge { .. } => "KEY_TOO_LARGE",
+            StorageError::TableNotFound(_) => "TABLE_NOT_FOUND",
+            StorageError::TableExists(_) => "TABLE_EXISTS",
+            StorageError::ColumnNotFound(_) => "COLUMN_NOT_FOUND",
+            StorageError::NotIndexed(_) => "NOT_INDEXED",
+            StorageError::AlreadyIndexed(_) => "ALREADY_INDEXED",
+            StorageError::TxnNotActive(_) => "TXN_NOT_ACTIVE",
+            StorageError::UndoFailed(_) => "UNDO_FAILED",
+            StorageError::TxnsActive(_) => "TXNS_ACTIVE",
+            StorageError::Parse(_) => "PARSE_ERROR",
+            StorageError::Query(_) => "QUERY_ERROR",
+            StorageError::Catalog(_) => "CATALOG_ERROR",
+        }
+    }
+
+    /// The page involved, if the error is about a specific one.
+    pub fn page_id(&self) -> Option<PageId> {
+        match self {
+            StorageError::Io { page_id, .. }
+            | StorageError::Corrupt { page_id, .. }
+            | StorageError::PageFull { page_id, .. } => Some(*page_id),
+            StorageError::RowNotFound(rid) | StorageError::SlotOccupied(rid) => Some(rid.page_id),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::DiskFull => write!(f, "Disk is full"),
+            StorageError::PoolExhausted => write!(f, "Buffer pool exhausted: every frame is pinned"),
+            StorageError::Io { page_id, message } => {
+                write!(f, "I/O error on page {}: {}", page_id, message)
+            }
+            StorageError::Corrupt { page_id, stored, computed } => write!(
+                f,
+                "Page {} is corrupt: stored checksum {:08x}, computed {:08x}",
+                page_id, stored, computed
+            ),
+            StorageError::PageFull { page_id, needed, free } => write!(
+                f,
+                "Page {} is full: {} bytes needed, {} free",
+                page_id, needed, free
+            ),
+            StorageError::RowNotFound(rid) => {
+                write!(f, "Row {}:{} not found", rid.page_id, rid.slot_id)
+            }
+            StorageError::SlotOccupied(rid) => {
+                write!(f, "Slot {}:{} is already in use", rid.page_id, rid.slot_id)
+            }
+            StorageError::SchemaMismatch(msg) => write!(f, "{}", msg),
+            StorageError::KeyTooLarge { size, max } => {
+                write!(f, "Key is {} bytes, the index allows {}", size, max)
+            }
+            StorageError::TableNotFound(name) => write!(f, "Table '{}' not found", name),
+            StorageError::TableExists(name) => write!(f, "Table '{}' already exists", name),
+            StorageError::ColumnNotFound(name) => write!(f, "Column '{}' not found", name),
+            StorageError::NotIndexed(msg)
+            | StorageError::AlreadyIndexed(msg)
+            | StorageError::TxnsActive(msg)
+            | StorageError::Parse(msg)
+            | StorageError::Query(msg)
+            | StorageError::Catalog(msg) => write!(f, "{}", msg),
+            StorageError::TxnNotActive(txn) => write!(f, "Transaction {} is not active", txn),
+            StorageError::UndoFailed(lsn) => {
+                write!(f, "Undo of LSN {} could not be applied to its page", lsn)
+            }
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_and_pages() {
+        let e = StorageError::Corrupt { page_id: 7, stored: 1, computed: 2 };
+        assert_eq!(e.code(), "CORRUPT_PAGE");
+        assert_eq!(e.page_id(), Some(7));
+        assert_eq!(e.to_string(), "Page 7 is corrupt: stored checksum 00000001, computed 00000002");
+
+        let e = StorageError::RowNotFound(RowId { page_id: 3, slot_id: 4 });
+        assert_eq!(e.page_id(), Some(3));
+        assert_eq!(e.to_string(), "Row 3:4 not found");
+        assert_eq!(StorageError::DiskFull.page_id(), None);
+    }
+}
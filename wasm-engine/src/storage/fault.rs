@@ -0,0 +1,282 @@
+//! Deterministic fault injection for the disk.
+//!
+//! `DiskManager` consults a `FaultInjector` on every page read and write.
+//! Faults are armed one at a time and fire once:
+//!
+//! - **torn write**: only a prefix of the page lands; the rest keeps its
+//!   old bytes.
+//! - **dropped write**: the write reports success but nothing lands.
+//! - **bit flip**: the page lands with one bit inverted.
+//! - **read error**: the read fails.
+//!
+//! A fault triggers either on the Nth matching operation after it was armed
+//! (writes for write faults, reads for read errors) or on the next matching
+//! operation against a given page.  Where a torn write stops and which bit
+//! flips are drawn from a seeded generator, so the same seed and workload
+//! always produce the same damage.
+
+use crate::storage::types::*;
+
+/// What goes wrong.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultKind {
+    TornWrite,
+    DroppedWrite,
+    BitFlip,
+    ReadError,
+}
+
+impl FaultKind {
+    /// Numeric tag used in snapshots.
+    pub fn tag(&self) -> u8 {
+        match self {
+            FaultKind::TornWrite => 0,
+            FaultKind::DroppedWrite => 1,
+            FaultKind::BitFlip => 2,
+            FaultKind::ReadError => 3,
+        }
+    }
+
+    /// Parse a kind name (case-insensitive): `torn_write`, `dropped_write`,
+    /// `bit_flip`, `read_error`.
+    pub fn parse(s: &str) -> Option<Self> {
+        Some(match s.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+            "torn_write" | "torn" => FaultKind::TornWrite,
+            "dropped_write" | "drop" => FaultKind::DroppedWrite,
+            "bit_flip" | "flip" => FaultKind::BitFlip,
+            "read_error" => FaultKind::ReadError,
+            _ => return None,
+        })
+    }
+
+    fn on_read(&self) -> bool {
+        *self == FaultKind::ReadError
+    }
+}
+
+/// When an armed fault fires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    /// The Nth matching operation after arming (1 = the very next one).
+    Nth(u64),
+    /// The next matching operation on this page.
+    Page(PageId),
+}
+
+/// A fault waiting to fire.
+#[derive(Debug, Clone)]
+pub struct ArmedFault {
+    pub id: u32,
+    pub kind: FaultKind,
+    pub trigger: Trigger,
+    /// Operation count (reads or writes, by kind) at which an `Nth` trigger
+    /// fires.
+    fire_at: u64,
+}
+
+/// A fault that went off.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FiredFault {
+    pub id: u32,
+    pub kind: FaultKind,
+    pub page_id: PageId,
+    /// Which read or write it hit, counting from the injector's creation.
+    pub op: u64,
+    /// Torn write: bytes that landed.  Bit flip: bit index within the page.
+    /// Otherwise 0.
+    pub detail: u32,
+}
+
+/// How a write should be carried out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteFault {
+    None,
+    /// Write only the first `n` bytes.
+    Torn(usize),
+    Dropped,
+    /// Write the page with this bit inverted.
+    Flip(usize),
+}
+
+pub struct FaultInjector {
+    seed: u64,
+    rng: u64,
+    armed: Vec<ArmedFault>,
+    fired: Vec<FiredFault>,
+    next_id: u32,
+    reads: u64,
+    writes: u64,
+}
+
+impl FaultInjector {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: seed,
+            armed: Vec::new(),
+            fired: Vec::new(),
+            next_id: 1,
+            reads: 0,
+            writes: 0,
+        }
+    }
+
+    /// Restart the generator from `seed`.  Armed and fired faults are kept.
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = seed;
+    }
+
+    /// Arm a fault.  Returns its id.  `Nth(0)` is treated as `Nth(1)`.
+    pub fn arm(&mut self, kind: FaultKind, trigger: Trigger) -> u32 {
+        let done = if kind.on_read() { self.reads } else { self.writes };
+        let fire_at = match trigger {
+            Trigger::Nth(n) => done + n.max(1),
+            Trigger::Page(_) => 0,
+        };
+        let id = self.next_id;
+        self.next_id += 1;
+        self.armed.push(ArmedFault { id, kind, trigger, fire_at });
+        id
+    }
+
+    /// Drop every armed fault.
+    pub fn disarm_all(&mut self) {
+        self.armed.clear();
+    }
+
+    /// Called before each read.  Returns `true` if the read should fail.
+    pub fn on_read(&mut self, page_id: PageId) -> bool {
+        self.reads += 1;
+        let op = self.reads;
+        self.fire(page_id, op, true).is_some()
+    }
+
+    /// Called before each write of a `page_size`-byte page.
+    pub fn on_write(&mut self, page_id: PageId, page_size: usize) -> WriteFault {
+        self.writes += 1;
+        let op = self.writes;
+        let Some(i) = self.fire(page_id, op, false) else {
+            return WriteFault::None;
+        };
+        let (fault, detail) = match self.fired[i].kind {
+            FaultKind::TornWrite => {
+                let n = 1 + self.next_below(page_size.saturating_sub(1).max(1));
+                (WriteFault::Torn(n), n)
+            }
+            FaultKind::BitFlip => {
+                let bit = self.next_below(page_size * 8);
+                (WriteFault::Flip(bit), bit)
+            }
+            _ => (WriteFault::Dropped, 0),
+        };
+        self.fired[i].detail = detail as u32;
+        fault
+    }
+
+    // ── Accessors ──────────────────────────────────────────────────
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn armed(&self) -> &[ArmedFault] {
+        &self.armed
+    }
+
+    pub fn fired(&self) -> &[FiredFault] {
+        &self.fired
+    }
+
+    // ── Internal ───────────────────────────────────────────────────
+
+    /// Fire the first armed fault matching this operation, recording it.
+    /// Returns its index in `fired`.
+    fn fire(&mut self, page_id: PageId, op: u64, is_read: bool) -> Option<usize> {
+        let pos = self.armed.iter().position(|f| {
+            f.kind.on_read() == is_read
+                && match f.trigger {
+                    Trigger::Nth(_) => f.fire_at == op,
+                    Trigger::Page(p) => p == page_id,
+                }
+        })?;
+        let fault = self.armed.remove(pos);
+        self.fired.push(FiredFault { id: fault.id, kind: fault.kind, page_id, op, detail: 0 });
+        Some(self.fired.len() - 1)
+    }
+
+    /// Uniform-ish value in `0..n` (SplitMix64).
+    fn next_below(&mut self, n: usize) -> usize {
+        self.rng = self.rng.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.rng;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        (z % n.max(1) as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nth_write_fires_once() {
+        let mut inj = FaultInjector::new(7);
+        inj.on_write(0, 64);
+        let id = inj.arm(FaultKind::DroppedWrite, Trigger::Nth(2));
+        assert_eq!(inj.on_write(3, 64), WriteFault::None);
+        assert_eq!(inj.on_write(4, 64), WriteFault::Dropped);
+        assert_eq!(inj.on_write(4, 64), WriteFault::None);
+        assert_eq!(
+            inj.fired(),
+            &[FiredFault { id, kind: FaultKind::DroppedWrite, page_id: 4, op: 3, detail: 0 }]
+        );
+        assert!(inj.armed().is_empty());
+    }
+
+    #[test]
+    fn page_triggers_match_direction() {
+        let mut inj = FaultInjector::new(7);
+        inj.arm(FaultKind::ReadError, Trigger::Page(5));
+        inj.arm(FaultKind::BitFlip, Trigger::Page(5));
+        assert!(!inj.on_read(4));
+        assert!(matches!(inj.on_write(5, 64), WriteFault::Flip(bit) if bit < 64 * 8));
+        assert!(inj.on_read(5));
+        assert!(!inj.on_read(5));
+    }
+
+    #[test]
+    fn same_seed_same_damage() {
+        let run = |seed| {
+            let mut inj = FaultInjector::new(seed);
+            inj.arm(FaultKind::TornWrite, Trigger::Nth(1));
+            inj.arm(FaultKind::BitFlip, Trigger::Nth(2));
+            (inj.on_write(1, 128), inj.on_write(2, 128))
+        };
+        assert_eq!(run(42), run(42));
+        let (torn, _) = run(42);
+        assert!(matches!(torn, WriteFault::Torn(n) if (1..128).contains(&n)));
+    }
+
+    #[test]
+    fn damaged_pages_fail_their_checksum() {
+        use crate::storage::buffer_pool::BufferPoolManager;
+        use crate::storage::error::StorageError;
+        use crate::storage::disk::DiskManager;
+
+        let mut bpm = BufferPoolManager::new(1, DiskManager::new(128, 8));
+        let (p0, f0) = bpm.new_page().unwrap();
+        bpm.frame_data_mut(f0)[100] = 0x5A;
+        bpm.unpin_page(p0, true);
+        bpm.disk.faults.arm(FaultKind::TornWrite, Trigger::Page(p0));
+        // Allocating a second page evicts the first through the torn write.
+        let (p1, _) = bpm.new_page().unwrap();
+        bpm.unpin_page(p1, false);
+        assert!(matches!(bpm.fetch_page(p0), Err(StorageError::Corrupt { .. })));
+
+        bpm.disk.faults.arm(FaultKind::ReadError, Trigger::Nth(1));
+        assert!(matches!(bpm.fetch_page(p0), Err(StorageError::Io { .. })));
+        assert_eq!(bpm.disk.faults.fired().len(), 2);
+    }
+}
@@ -0,0 +1,223 @@
+//! Single-file disk backend for native builds.
+//!
+//! The file has the disk image layout described in `disk`: a header with
+//! the geometry and allocation bitmap, padded to a whole page, followed by
+//! every page in order.  Bitmap changes are written through as they happen,
+//! so reopening the file sees exactly the pages that were allocated; page
+//! bytes are only as durable as the last `sync`.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use crate::storage::types::*;
+use crate::storage::disk::{
+    decode_image_header, decode_image_prefix, encode_image_header, image_header_len,
+    invalid_data, AllocationMap, DiskBackend, IMAGE_PREFIX_SIZE,
+};
+
+pub struct FileBackend {
+    file: File,
+    map: AllocationMap,
+    page_size: u32,
+    max_pages: u32,
+    /// Byte offset of page 0.
+    header_len: u64,
+}
+
+impl FileBackend {
+    /// Create (or truncate) a database file with every page unallocated.
+    pub fn create(path: impl AsRef<Path>, page_size: u32, max_pages: u32) -> io::Result<Self> {
+        if page_size < PAGE_HEADER_SIZE as u32 {
+            return Err(invalid_data("page size too small"));
+        }
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        let header = encode_image_header(page_size, max_pages, &[]);
+        file.set_len(header.len() as u64 + page_size as u64 * max_pages as u64)?;
+        (&file).seek(SeekFrom::Start(0))?;
+        (&file).write_all(&header)?;
+
+        Ok(Self {
+            file,
+            map: AllocationMap::new(max_pages),
+            page_size,
+            max_pages,
+            header_len: header.len() as u64,
+        })
+    }
+
+    /// Open an existing database file (or an exported image saved to disk).
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        let mut prefix = [0u8; IMAGE_PREFIX_SIZE];
+        (&file).seek(SeekFrom::Start(0))?;
+        (&file).read_exact(&mut prefix)?;
+        let (page_size, max_pages) = decode_image_prefix(&prefix)?;
+
+        let mut header = vec![0u8; image_header_len(page_size, max_pages)];
+        (&file).seek(SeekFrom::Start(0))?;
+        (&file).read_exact(&mut header)?;
+        let (_, _, bitmap) = decode_image_header(&header)?;
+
+        Ok(Self {
+            file,
+            map: AllocationMap::from_bitmap(bitmap),
+            page_size,
+            max_pages,
+            header_len: header.len() as u64,
+        })
+    }
+
+    fn page_offset(&self, page_id: PageId) -> io::Result<u64> {
+        if page_id >= self.max_pages {
+            return Err(invalid_data("page id out of range"));
+        }
+        Ok(self.header_len + page_id as u64 * self.page_size as u64)
+    }
+
+    /// Write the bitmap byte holding `page_id`'s bit.
+    fn store_bit(&self, page_id: PageId) -> io::Result<()> {
+        let first = page_id as usize / 8 * 8;
+        let byte = (first..(first + 8).min(self.max_pages as usize))
+            .filter(|&p| self.map.is_allocated(p as PageId))
+            .fold(0u8, |b, p| b | 1 << (p % 8));
+        (&self.file).seek(SeekFrom::Start((IMAGE_PREFIX_SIZE + page_id as usize / 8) as u64))?;
+        (&self.file).write_all(&[byte])
+    }
+
+    fn zero_page(&self, page_id: PageId) -> io::Result<()> {
+        (&self.file).seek(SeekFrom::Start(self.page_offset(page_id)?))?;
+        (&self.file).write_all(&vec![0u8; self.page_size as usize])
+    }
+}
+
+impl DiskBackend for FileBackend {
+    fn page_size(&self) -> u32 {
+        self.page_size
+    }
+
+    fn max_pages(&self) -> u32 {
+        self.max_pages
+    }
+
+    fn read_page(&self, page_id: PageId, buf: &mut [u8]) -> io::Result<()> {
+        (&self.file).seek(SeekFrom::Start(self.page_offset(page_id)?))?;
+        (&self.file).read_exact(&mut buf[..self.page_size as usize])
+    }
+
+    fn write_page(&mut self, page_id: PageId, data: &[u8]) -> io::Result<()> {
+        (&self.file).seek(SeekFrom::Start(self.page_offset(page_id)?))?;
+        (&self.file).write_all(&data[..self.page_size as usize])
+    }
+
+    fn allocate(&mut self) -> Option<PageId> {
+        let pid = self.map.allocate()?;
+        if self.zero_page(pid).and_then(|_| self.store_bit(pid)).is_err() {
+            self.map.deallocate(pid);
+            return None;
+        }
+        Some(pid)
+    }
+
+    fn allocate_at(&mut self, page_id: PageId) -> bool {
+        if !self.map.allocate_at(page_id) {
+            return false;
+        }
+        if self.zero_page(page_id).and_then(|_| self.store_bit(page_id)).is_err() {
+            self.map.deallocate(page_id);
+            return false;
+        }
+        true
+    }
+
+    fn deallocate(&mut self, page_id: PageId) {
+        if self.map.deallocate(page_id) {
+            let _ = self.store_bit(page_id);
+        }
+    }
+
+    fn is_allocated(&self, page_id: PageId) -> bool {
+        self.map.is_allocated(page_id)
+    }
+
+    fn num_allocated(&self) -> u32 {
+        self.map.num_allocated()
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        self.file.sync_all()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::buffer_pool::BufferPoolManager;
+    use crate::storage::disk::DiskManager;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("wizalloc-{}-{}.db", name, std::process::id()))
+    }
+
+    #[test]
+    fn pages_survive_reopen() {
+        let path = temp_path("reopen");
+        {
+            let mut fb = FileBackend::create(&path, 64, 20).unwrap();
+            let p0 = fb.allocate().unwrap();
+            let p1 = fb.allocate().unwrap();
+            let _p2 = fb.allocate().unwrap();
+            fb.deallocate(p1);
+            let mut data = vec![0u8; 64];
+            data[0] = 0xAB;
+            data[63] = 0xCD;
+            fb.write_page(p0, &data).unwrap();
+            fb.sync().unwrap();
+        }
+
+        let fb = FileBackend::open(&path).unwrap();
+        assert_eq!(fb.page_size(), 64);
+        assert_eq!(fb.max_pages(), 20);
+        assert_eq!(fb.num_allocated(), 2);
+        assert!(fb.is_allocated(0) && !fb.is_allocated(1) && fb.is_allocated(2));
+        let mut buf = vec![0u8; 64];
+        fb.read_page(0, &mut buf).unwrap();
+        assert_eq!((buf[0], buf[63]), (0xAB, 0xCD));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn buffer_pool_over_file() {
+        let path = temp_path("bpm");
+        let pid = {
+            let dm = DiskManager::with_backend(Box::new(FileBackend::create(&path, 128, 8).unwrap()));
+            let mut bpm = BufferPoolManager::new(2, dm);
+            let (pid, fid) = bpm.new_page().unwrap();
+            bpm.frame_data_mut(fid)[100] = 0x5A;
+            bpm.unpin_page(pid, true);
+            bpm.flush_all();
+            pid
+        };
+
+        let dm = DiskManager::with_backend(Box::new(FileBackend::open(&path).unwrap()));
+        let mut bpm = BufferPoolManager::new(2, dm);
+        let fid = bpm.fetch_page(pid).unwrap();
+        assert_eq!(bpm.frame_data(fid)[100], 0x5A);
+        bpm.unpin_page(pid, false);
+        // The next allocation skips the page that's still in use.
+        assert_ne!(bpm.disk.allocate_page(), Some(pid));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rejects_foreign_files() {
+        let path = temp_path("foreign");
+        std::fs::write(&path, b"definitely not a database").unwrap();
+        assert!(FileBackend::open(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
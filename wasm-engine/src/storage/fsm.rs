@@ -0,0 +1,286 @@
+//! Free space map.
+//!
+//! Records roughly how many bytes each of a table's data pages could still
+//! take, so an insert can go straight to a page with room instead of
+//! walking the whole page chain.  The map lives in its own chain of pages:
+//!
+//! ```text
+//! [24..26] num_entries : u16
+//! [26..]   entries of: page_id u32 | category u8
+//! ```
+//!
+//! A page's category is its total free space (see
+//! `page::total_free_space`) divided by the granularity and rounded down,
+//! so the map never overstates the room on a page.  Map pages are allocated
+//! under the table's transaction, but their entries are hints and are not
+//! logged: callers re-check a page before using it, and `rebuild` brings
+//! the map back in line after a rollback or recovery.
+
+use crate::storage::types::*;
+use crate::storage::page;
+use crate::storage::error::StorageResult;
+use crate::storage::buffer_pool::BufferPoolManager;
+
+const OFF_NUM_ENTRIES: usize = PAGE_HEADER_SIZE;
+const ENTRIES_START: usize = PAGE_HEADER_SIZE + 2;
+const ENTRY_SIZE: usize = 5;
+
+/// Bytes per category step: the smallest that fits any page's free space
+/// into a `u8`.
+pub fn granularity(page_size: usize) -> usize {
+    page_size.div_ceil(256).max(1)
+}
+
+/// Category recorded for a page with `free` bytes available.
+pub fn category(page_size: usize, free: usize) -> u8 {
+    (free / granularity(page_size)).min(u8::MAX as usize) as u8
+}
+
+/// Free bytes a category guarantees.
+pub fn approx_free(page_size: usize, category: u8) -> usize {
+    category as usize * granularity(page_size)
+}
+
+/// Entries per map page.
+pub fn capacity(page_size: usize) -> usize {
+    page_size.saturating_sub(ENTRIES_START) / ENTRY_SIZE
+}
+
+/// Allocate an empty map page on behalf of `txn`.
+pub fn create(bpm: &mut BufferPoolManager, txn: TxnId) -> Option<PageId> {
+    let (page_id, _) = bpm.new_page_logged(txn, PageType::FreeSpaceMap).ok()?;
+    bpm.unpin_page(page_id, true);
+    Some(page_id)
+}
+
+/// Start tracking `page_id`, appending a map page on behalf of `txn` when
+/// the last one is full.
+pub fn add_page(
+    bpm: &mut BufferPoolManager,
+    txn: TxnId,
+    root: PageId,
+    page_id: PageId,
+    free: usize,
+) -> bool {
+    let cap = capacity(bpm.page_size() as usize);
+    let cat = category(bpm.page_size() as usize, free);
+    let mut current = root;
+    loop {
+        let Ok(fid) = bpm.fetch_page(current) else { return false };
+        let buf = bpm.frame_data_mut(fid);
+        let n = read_u16(buf, OFF_NUM_ENTRIES) as usize;
+        if n < cap {
+            write_entry(buf, n, page_id, cat);
+            write_u16(buf, OFF_NUM_ENTRIES, n as u16 + 1);
+            bpm.unpin_page(current, true);
+            return true;
+        }
+        let next = page::next_page(buf);
+        bpm.unpin_page(current, false);
+        if next == INVALID_PAGE {
+            break;
+        }
+        current = next;
+    }
+
+    let Ok((new_id, fid)) = bpm.new_page_logged(txn, PageType::FreeSpaceMap) else {
+        return false;
+    };
+    let buf = bpm.frame_data_mut(fid);
+    write_entry(buf, 0, page_id, cat);
+    write_u16(buf, OFF_NUM_ENTRIES, 1);
+    bpm.unpin_page(new_id, true);
+    bpm.set_next_page_logged(txn, current, new_id).is_ok()
+}
+
+/// Record that `page_id` now has `free` bytes available.  Does nothing if
+/// the page isn't tracked or its category hasn't changed.
+pub fn record(bpm: &mut BufferPoolManager, root: PageId, page_id: PageId, free: usize) {
+    let cat = category(bpm.page_size() as usize, free);
+    let mut current = root;
+    while current != INVALID_PAGE {
+        let Ok(fid) = bpm.fetch_page(current) else { return };
+        let buf = bpm.frame_data_mut(fid);
+        let n = read_u16(buf, OFF_NUM_ENTRIES) as usize;
+        if let Some(i) = (0..n).find(|&i| read_entry(buf, i).0 == page_id) {
+            let changed = read_entry(buf, i).1 != cat;
+            if changed {
+                write_entry(buf, i, page_id, cat);
+            }
+            bpm.unpin_page(current, changed);
+            return;
+        }
+        let next = page::next_page(buf);
+        bpm.unpin_page(current, false);
+        current = next;
+    }
+}
+
+/// First tracked page whose category guarantees `needed` bytes.
+pub fn find(bpm: &mut BufferPoolManager, root: PageId, needed: usize) -> Option<PageId> {
+    let page_size = bpm.page_size() as usize;
+    let mut current = root;
+    while current != INVALID_PAGE {
+        let fid = bpm.fetch_page(current).ok()?;
+        let buf = bpm.frame_data(fid);
+        let n = read_u16(buf, OFF_NUM_ENTRIES) as usize;
+        let hit = (0..n)
+            .map(|i| read_entry(buf, i))
+            .find(|&(_, cat)| approx_free(page_size, cat) >= needed)
+            .map(|(pid, _)| pid);
+        let next = page::next_page(buf);
+        bpm.unpin_page(current, false);
+        if hit.is_some() {
+            return hit;
+        }
+        current = next;
+    }
+    None
+}
+
+/// Whether the map tracks `page_id`, i.e. whether it is one of the
+/// table's data pages.  A map page holds `capacity` entries, so this is
+/// usually a single fetch.
+pub fn contains(bpm: &mut BufferPoolManager, root: PageId, page_id: PageId) -> StorageResult<bool> {
+    let mut current = root;
+    while current != INVALID_PAGE {
+        let fid = bpm.fetch_page(current)?;
+        let buf = bpm.frame_data(fid);
+        let n = read_u16(buf, OFF_NUM_ENTRIES) as usize;
+        let hit = (0..n).any(|i| read_entry(buf, i).0 == page_id);
+        let next = page::next_page(buf);
+        bpm.unpin_page(current, false);
+        if hit {
+            return Ok(true);
+        }
+        current = next;
+    }
+    Ok(false)
+}
+
+/// Every `(page_id, category)` entry, in map order.
+pub fn entries(bpm: &mut BufferPoolManager, root: PageId) -> Vec<(PageId, u8)> {
+    let mut out = Vec::new();
+    let mut current = root;
+    while current != INVALID_PAGE {
+        let Ok(fid) = bpm.fetch_page(current) else { break };
+        let buf = bpm.frame_data(fid);
+        let n = read_u16(buf, OFF_NUM_ENTRIES) as usize;
+        out.extend((0..n).map(|i| read_entry(buf, i)));
+        let next = page::next_page(buf);
+        bpm.unpin_page(current, false);
+        current = next;
+    }
+    out
+}
+
+/// Rewrite the map from scratch as `(page_id, free)` pairs, filling the
+/// existing map pages in order.  Pages beyond the chain's capacity go
+/// untracked until the next rebuild.
+pub fn rebuild(bpm: &mut BufferPoolManager, root: PageId, pages: &[(PageId, usize)]) {
+    let page_size = bpm.page_size() as usize;
+    let cap = capacity(page_size);
+    let mut rest = pages;
+    for pid in page_ids(bpm, root) {
+        let Ok(fid) = bpm.fetch_page(pid) else { return };
+        let take = rest.len().min(cap);
+        let buf = bpm.frame_data_mut(fid);
+        for (i, &(page_id, free)) in rest[..take].iter().enumerate() {
+            write_entry(buf, i, page_id, category(page_size, free));
+        }
+        write_u16(buf, OFF_NUM_ENTRIES, take as u16);
+        bpm.unpin_page(pid, true);
+        rest = &rest[take..];
+    }
+}
+
+/// Every page in the map's chain.
+pub fn page_ids(bpm: &mut BufferPoolManager, root: PageId) -> Vec<PageId> {
+    let mut ids = Vec::new();
+    let mut current = root;
+    while current != INVALID_PAGE {
+        ids.push(current);
+        let Ok(fid) = bpm.fetch_page(current) else { break };
+        let next = page::next_page(bpm.frame_data(fid));
+        bpm.unpin_page(current, false);
+        current = next;
+    }
+    ids
+}
+
+// ── Internal ───────────────────────────────────────────────────────
+
+fn read_entry(buf: &[u8], i: usize) -> (PageId, u8) {
+    let off = ENTRIES_START + i * ENTRY_SIZE;
+    (read_u32(buf, off), buf[off + 4])
+}
+
+fn write_entry(buf: &mut [u8], i: usize, page_id: PageId, cat: u8) {
+    let off = ENTRIES_START + i * ENTRY_SIZE;
+    write_u32(buf, off, page_id);
+    buf[off + 4] = cat;
+}
+
+fn read_u16(buf: &[u8], off: usize) -> u16 {
+    u16::from_le_bytes([buf[off], buf[off + 1]])
+}
+
+fn write_u16(buf: &mut [u8], off: usize, v: u16) {
+    buf[off..off + 2].copy_from_slice(&v.to_le_bytes());
+}
+
+fn read_u32(buf: &[u8], off: usize) -> u32 {
+    u32::from_le_bytes([buf[off], buf[off + 1], buf[off + 2], buf[off + 3]])
+}
+
+fn write_u32(buf: &mut [u8], off: usize, v: u32) {
+    buf[off..off + 4].copy_from_slice(&v.to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::disk::DiskManager;
+
+    const TXN: TxnId = 1;
+
+    #[test]
+    fn record_and_find() {
+        let mut bpm = BufferPoolManager::new(4, DiskManager::new(128, 16));
+        let root = create(&mut bpm, TXN).unwrap();
+        assert!(add_page(&mut bpm, TXN, root, 5, 10));
+        assert!(add_page(&mut bpm, TXN, root, 6, 80));
+
+        assert_eq!(find(&mut bpm, root, 50), Some(6));
+        assert_eq!(find(&mut bpm, root, 8), Some(5));
+        record(&mut bpm, root, 6, 20);
+        assert_eq!(find(&mut bpm, root, 50), None);
+        assert_eq!(entries(&mut bpm, root), vec![(5, 10), (6, 20)]);
+    }
+
+    #[test]
+    fn chain_grows_and_rebuilds() {
+        let mut bpm = BufferPoolManager::new(4, DiskManager::new(64, 16));
+        let root = create(&mut bpm, TXN).unwrap();
+        let cap = capacity(64) as u32;
+        for pid in 0..cap + 2 {
+            assert!(add_page(&mut bpm, TXN, root, 100 + pid, pid as usize));
+        }
+        assert_eq!(page_ids(&mut bpm, root).len(), 2);
+        assert_eq!(find(&mut bpm, root, cap as usize + 1), Some(100 + cap + 1));
+
+        rebuild(&mut bpm, root, &[(7, 30), (8, 0)]);
+        assert_eq!(entries(&mut bpm, root), vec![(7, 30), (8, 0)]);
+    }
+
+    #[test]
+    fn categories_never_overstate() {
+        for page_size in [64, 128, 1024, 8192] {
+            for free in [0, 1, 31, 32, 33, page_size - PAGE_HEADER_SIZE] {
+                let cat = category(page_size, free);
+                assert!(approx_free(page_size, cat) <= free);
+                assert!(free - approx_free(page_size, cat) < granularity(page_size));
+            }
+        }
+    }
+}
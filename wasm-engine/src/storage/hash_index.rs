@@ -0,0 +1,622 @@
+//! Extendible hash index stored in buffer-pool pages.
+//!
+//! A directory page maps the low `global_depth` bits of a key's hash to a
+//! bucket page.  Several directory slots may share a bucket; the bucket's
+//! `local_depth` says how many hash bits its entries actually agree on.
+//!
+//! Directory page (after the common page header):
+//!
+//! ```text
+//! [24]     global_depth : u8
+//! [25..27] key_size     : u16
+//! [27..]   2^global_depth slots of: bucket_page u32 | local_depth u8
+//! ```
+//!
+//! Bucket page:
+//!
+//! ```text
+//! [24..26] num_entries : u16
+//! [26..28] key_size    : u16
+//! [28..]   entries of: key[key_size] | rid_page u32 | rid_slot u16
+//! ```
+//!
+//! A full bucket splits on its next hash bit, doubling the directory first
+//! when its local depth already equals the global depth.  If splitting can't
+//! separate the entries (they all share one hash) or the directory page is
+//! full, an overflow bucket is chained through the header's `next_page`.
+//! An emptied bucket merges back into its split image, and the directory
+//! halves once no bucket needs the top bit.
+//!
+//! Logging follows `btree`: logical `HashInsert`/`HashDelete` records under
+//! the user transaction, page images under a short system transaction.
+
+use crate::storage::types::*;
+use crate::storage::page;
+use crate::storage::error::StorageResult;
+use crate::storage::recovery;
+use crate::storage::schema::cmp_keys;
+use crate::storage::buffer_pool::BufferPoolManager;
+use crate::storage::wal::LogBody;
+
+const OFF_GLOBAL_DEPTH: usize = PAGE_HEADER_SIZE;
+const OFF_DIR_KEY_SIZE: usize = PAGE_HEADER_SIZE + 1;
+const DIR_SLOTS_START: usize = PAGE_HEADER_SIZE + 3;
+const DIR_SLOT_SIZE: usize = 5;
+
+const OFF_NUM_ENTRIES: usize = PAGE_HEADER_SIZE;
+const OFF_BUCKET_KEY_SIZE: usize = PAGE_HEADER_SIZE + 2;
+const BUCKET_ENTRIES_START: usize = PAGE_HEADER_SIZE + 4;
+const RID_SIZE: usize = 6;
+
+/// An equality index over one column of a table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HashIndex {
+    /// Column position in the table schema.
+    pub column: usize,
+    /// Directory page; fixed for the life of the index.
+    pub directory_page_id: PageId,
+    /// Width of every encoded key (see `schema::encode_key`).
+    pub key_size: usize,
+}
+
+// ── Pages ──────────────────────────────────────────────────────────
+
+/// A decoded directory page.
+#[derive(Debug, Clone)]
+pub struct Directory {
+    pub page_id: PageId,
+    pub global_depth: u8,
+    pub key_size: usize,
+    /// Bucket page per slot (`2^global_depth` slots).
+    pub buckets: Vec<PageId>,
+    /// Local depth of the bucket in each slot.
+    pub local_depths: Vec<u8>,
+}
+
+impl Directory {
+    fn slot_of(&self, hash: u32) -> usize {
+        (hash as usize) & ((1usize << self.global_depth) - 1)
+    }
+
+    /// Double the directory: every new slot mirrors its lower twin.
+    fn grow(&mut self) {
+        self.buckets.extend_from_within(..);
+        self.local_depths.extend_from_within(..);
+        self.global_depth += 1;
+    }
+
+    /// Halve the directory while no bucket depends on the top bit.
+    fn shrink(&mut self) {
+        while self.global_depth > 0 && self.local_depths.iter().all(|&d| d < self.global_depth) {
+            let half = self.buckets.len() / 2;
+            self.buckets.truncate(half);
+            self.local_depths.truncate(half);
+            self.global_depth -= 1;
+        }
+    }
+}
+
+/// A decoded bucket page.
+#[derive(Debug, Clone)]
+pub struct Bucket {
+    pub page_id: PageId,
+    pub key_size: usize,
+    /// Next overflow bucket in the chain.
+    pub next: PageId,
+    pub entries: Vec<(Vec<u8>, RowId)>,
+}
+
+/// Largest global depth whose slots fit on one directory page.
+pub fn max_global_depth(page_size: usize) -> u8 {
+    let slots = page_size.saturating_sub(DIR_SLOTS_START) / DIR_SLOT_SIZE;
+    if slots == 0 { 0 } else { slots.ilog2() as u8 }
+}
+
+/// Entries per bucket page.
+pub fn bucket_capacity(page_size: usize, key_size: usize) -> usize {
+    page_size.saturating_sub(BUCKET_ENTRIES_START) / (key_size + RID_SIZE)
+}
+
+/// FNV-1a over the encoded key.
+pub fn hash_key(key: &[u8]) -> u32 {
+    key.iter().fold(0x811c_9dc5u32, |h, &b| (h ^ b as u32).wrapping_mul(0x0100_0193))
+}
+
+pub fn decode_directory(buf: &[u8]) -> Directory {
+    let global_depth = buf[OFF_GLOBAL_DEPTH];
+    let n = 1usize << global_depth;
+    let mut buckets = Vec::with_capacity(n);
+    let mut local_depths = Vec::with_capacity(n);
+    for i in 0..n {
+        let off = DIR_SLOTS_START + i * DIR_SLOT_SIZE;
+        buckets.push(read_u32(buf, off));
+        local_depths.push(buf[off + 4]);
+    }
+    Directory {
+        page_id: page::page_id(buf),
+        global_depth,
+        key_size: read_u16(buf, OFF_DIR_KEY_SIZE) as usize,
+        buckets,
+        local_depths,
+    }
+}
+
+pub fn encode_directory(dir: &Directory, buf: &mut [u8]) {
+    buf[OFF_GLOBAL_DEPTH] = dir.global_depth;
+    write_u16(buf, OFF_DIR_KEY_SIZE, dir.key_size as u16);
+    let mut off = DIR_SLOTS_START;
+    for (&bucket, &depth) in dir.buckets.iter().zip(&dir.local_depths) {
+        write_u32(buf, off, bucket);
+        buf[off + 4] = depth;
+        off += DIR_SLOT_SIZE;
+    }
+    buf[off..].fill(0);
+}
+
+pub fn decode_bucket(buf: &[u8]) -> Bucket {
+    let n = read_u16(buf, OFF_NUM_ENTRIES) as usize;
+    let key_size = read_u16(buf, OFF_BUCKET_KEY_SIZE) as usize;
+    let entries = (0..n)
+        .map(|i| {
+            let off = BUCKET_ENTRIES_START + i * (key_size + RID_SIZE);
+            let r = off + key_size;
+            let rid = RowId { page_id: read_u32(buf, r), slot_id: read_u16(buf, r + 4) };
+            (buf[off..r].to_vec(), rid)
+        })
+        .collect();
+    Bucket { page_id: page::page_id(buf), key_size, next: page::next_page(buf), entries }
+}
+
+pub fn encode_bucket(bucket: &Bucket, buf: &mut [u8]) {
+    write_u16(buf, OFF_NUM_ENTRIES, bucket.entries.len() as u16);
+    write_u16(buf, OFF_BUCKET_KEY_SIZE, bucket.key_size as u16);
+    page::set_next_page(buf, bucket.next);
+    let mut off = BUCKET_ENTRIES_START;
+    for (key, rid) in &bucket.entries {
+        buf[off..off + bucket.key_size].copy_from_slice(key);
+        off += bucket.key_size;
+        write_u32(buf, off, rid.page_id);
+        write_u16(buf, off + 4, rid.slot_id);
+        off += RID_SIZE;
+    }
+    buf[off..].fill(0);
+}
+
+pub fn read_directory(bpm: &mut BufferPoolManager, page_id: PageId) -> Option<Directory> {
+    let fid = bpm.fetch_page(page_id).ok()?;
+    let dir = decode_directory(bpm.frame_data(fid));
+    bpm.unpin_page(page_id, false);
+    Some(dir)
+}
+
+pub fn read_bucket(bpm: &mut BufferPoolManager, page_id: PageId) -> Option<Bucket> {
+    let fid = bpm.fetch_page(page_id).ok()?;
+    let bucket = decode_bucket(bpm.frame_data(fid));
+    bpm.unpin_page(page_id, false);
+    Some(bucket)
+}
+
+/// A bucket followed by its overflow chain.
+pub fn read_chain(bpm: &mut BufferPoolManager, head: PageId) -> Option<Vec<Bucket>> {
+    let mut chain = vec![read_bucket(bpm, head)?];
+    while let Some(next) = chain.last().map(|b| b.next).filter(|&n| n != INVALID_PAGE) {
+        chain.push(read_bucket(bpm, next)?);
+    }
+    Some(chain)
+}
+
+// ── Public operations ──────────────────────────────────────────────
+
+/// Allocate an empty index (global depth 0, one bucket) on behalf of `txn`.
+/// Returns the directory page, or `None` if a bucket couldn't hold two keys.
+pub fn create(bpm: &mut BufferPoolManager, txn: TxnId, key_size: usize) -> Option<PageId> {
+    if bucket_capacity(bpm.page_size() as usize, key_size) < 2 {
+        return None;
+    }
+    let (dir_id, _) = bpm.new_page_logged(txn, PageType::HashDirectory).ok()?;
+    bpm.unpin_page(dir_id, true);
+    let (bucket_id, _) = bpm.new_page_logged(txn, PageType::HashBucket).ok()?;
+    bpm.unpin_page(bucket_id, true);
+
+    let bucket = Bucket { page_id: bucket_id, key_size, next: INVALID_PAGE, entries: Vec::new() };
+    write_bucket(bpm, txn, &bucket)?;
+    let dir = Directory {
+        page_id: dir_id,
+        global_depth: 0,
+        key_size,
+        buckets: vec![bucket_id],
+        local_depths: vec![0],
+    };
+    write_directory(bpm, txn, &dir)?;
+    Some(dir_id)
+}
+
+/// Insert (key, rid) on behalf of `txn`.  Returns `false` if the pair is
+/// already present or a page couldn't be allocated, and fails if the
+/// failed insert couldn't be rolled back.
+pub fn insert(
+    bpm: &mut BufferPoolManager,
+    txn: TxnId,
+    dir: PageId,
+    key: &[u8],
+    rid: RowId,
+) -> StorageResult<bool> {
+    if contains(bpm, dir, key, rid) {
+        return Ok(false);
+    }
+    bpm.log.append(txn, LogBody::HashInsert { dir, key: key.to_vec(), rid });
+    insert_entry(bpm, dir, key, rid)
+}
+
+/// Remove (key, rid) on behalf of `txn`.  Returns `false` if not present,
+/// and fails if a failed delete couldn't be rolled back.
+pub fn delete(
+    bpm: &mut BufferPoolManager,
+    txn: TxnId,
+    dir: PageId,
+    key: &[u8],
+    rid: RowId,
+) -> StorageResult<bool> {
+    if !contains(bpm, dir, key, rid) {
+        return Ok(false);
+    }
+    bpm.log.append(txn, LogBody::HashDelete { dir, key: key.to_vec(), rid });
+    delete_entry(bpm, dir, key, rid)
+}
+
+/// Row IDs whose key equals `key`.
+pub fn lookup(bpm: &mut BufferPoolManager, dir: PageId, key: &[u8]) -> Vec<RowId> {
+    let Some(d) = read_directory(bpm, dir) else { return Vec::new() };
+    let head = d.buckets[d.slot_of(hash_key(key))];
+    read_chain(bpm, head)
+        .unwrap_or_default()
+        .into_iter()
+        .flat_map(|b| b.entries)
+        .filter(|(k, _)| cmp_keys(k, key).is_eq())
+        .map(|(_, rid)| rid)
+        .collect()
+}
+
+/// Whether the exact (key, rid) pair is in the index.
+pub fn contains(bpm: &mut BufferPoolManager, dir: PageId, key: &[u8], rid: RowId) -> bool {
+    lookup(bpm, dir, key).contains(&rid)
+}
+
+/// The directory page followed by every bucket page (chains included).
+pub fn page_ids(bpm: &mut BufferPoolManager, dir: PageId) -> Vec<PageId> {
+    let mut ids = vec![dir];
+    let Some(d) = read_directory(bpm, dir) else { return ids };
+    for &head in &d.buckets {
+        if ids.contains(&head) {
+            continue;
+        }
+        let chain = read_chain(bpm, head).unwrap_or_default();
+        ids.extend(chain.iter().map(|b| b.page_id));
+    }
+    ids
+}
+
+// ── Structural changes (system transactions) ───────────────────────
+
+/// Insert without logging a logical record (used directly by undo).
+pub fn insert_entry(
+    bpm: &mut BufferPoolManager,
+    dir: PageId,
+    key: &[u8],
+    rid: RowId,
+) -> StorageResult<bool> {
+    let sys = bpm.log.begin();
+    match insert_inner(bpm, sys, dir, key, rid) {
+        Some(inserted) => {
+            bpm.log.end(sys);
+            Ok(inserted)
+        }
+        None => {
+            recovery::rollback(bpm, sys)?;
+            Ok(false)
+        }
+    }
+}
+
+/// Delete without logging a logical record (used directly by undo).
+pub fn delete_entry(
+    bpm: &mut BufferPoolManager,
+    dir: PageId,
+    key: &[u8],
+    rid: RowId,
+) -> StorageResult<bool> {
+    let sys = bpm.log.begin();
+    match delete_inner(bpm, sys, dir, key, rid) {
+        Some(deleted) => {
+            bpm.log.end(sys);
+            Ok(deleted)
+        }
+        None => {
+            recovery::rollback(bpm, sys)?;
+            Ok(false)
+        }
+    }
+}
+
+fn insert_inner(
+    bpm: &mut BufferPoolManager,
+    sys: TxnId,
+    dir_id: PageId,
+    key: &[u8],
+    rid: RowId,
+) -> Option<bool> {
+    let page_size = bpm.page_size() as usize;
+    let max_depth = max_global_depth(page_size);
+    let hash = hash_key(key);
+    let mut dir = read_directory(bpm, dir_id)?;
+    let capacity = bucket_capacity(page_size, dir.key_size);
+
+    loop {
+        let slot = dir.slot_of(hash);
+        let head = dir.buckets[slot];
+        let mut chain = read_chain(bpm, head)?;
+        if chain.iter().any(|b| b.entries.iter().any(|(k, r)| *r == rid && cmp_keys(k, key).is_eq())) {
+            return Some(false);
+        }
+        if let Some(b) = chain.iter_mut().find(|b| b.entries.len() < capacity) {
+            b.entries.push((key.to_vec(), rid));
+            write_bucket(bpm, sys, b)?;
+            return Some(true);
+        }
+
+        // Splitting only helps a single page whose entries disagree on some
+        // hash bit, and only while the directory can still grow.
+        let depth = dir.local_depths[slot];
+        let distinct = chain[0].entries.iter().any(|(k, _)| hash_key(k) != hash);
+        if chain.len() > 1 || !distinct || (depth == dir.global_depth && depth >= max_depth) {
+            let (new_id, _) = bpm.new_page_logged(sys, PageType::HashBucket).ok()?;
+            bpm.unpin_page(new_id, true);
+            let overflow = Bucket {
+                page_id: new_id,
+                key_size: dir.key_size,
+                next: INVALID_PAGE,
+                entries: vec![(key.to_vec(), rid)],
+            };
+            write_bucket(bpm, sys, &overflow)?;
+            let last = chain.last_mut()?;
+            last.next = new_id;
+            write_bucket(bpm, sys, last)?;
+            return Some(true);
+        }
+
+        if depth == dir.global_depth {
+            dir.grow();
+        }
+        let (image_id, _) = bpm.new_page_logged(sys, PageType::HashBucket).ok()?;
+        bpm.unpin_page(image_id, true);
+        let bit = 1u32 << depth;
+        let old = &mut chain[0];
+        let (moved, kept): (Vec<_>, Vec<_>) =
+            old.entries.drain(..).partition(|(k, _)| hash_key(k) & bit != 0);
+        old.entries = kept;
+        let image = Bucket { page_id: image_id, key_size: dir.key_size, next: INVALID_PAGE, entries: moved };
+        for i in 0..dir.buckets.len() {
+            if dir.buckets[i] == head {
+                dir.local_depths[i] = depth + 1;
+                if i as u32 & bit != 0 {
+                    dir.buckets[i] = image_id;
+                }
+            }
+        }
+        write_bucket(bpm, sys, old)?;
+        write_bucket(bpm, sys, &image)?;
+        write_directory(bpm, sys, &dir)?;
+    }
+}
+
+fn delete_inner(
+    bpm: &mut BufferPoolManager,
+    sys: TxnId,
+    dir_id: PageId,
+    key: &[u8],
+    rid: RowId,
+) -> Option<bool> {
+    let mut dir = read_directory(bpm, dir_id)?;
+    let slot = dir.slot_of(hash_key(key));
+    let head = dir.buckets[slot];
+    let mut chain = read_chain(bpm, head)?;
+
+    let found = chain.iter().enumerate().find_map(|(ci, b)| {
+        let pos = b.entries.iter().position(|(k, r)| *r == rid && cmp_keys(k, key).is_eq())?;
+        Some((ci, pos))
+    });
+    let Some((ci, pos)) = found else { return Some(false) };
+    chain[ci].entries.swap_remove(pos);
+
+    if !chain[ci].entries.is_empty() {
+        write_bucket(bpm, sys, &chain[ci])?;
+        return Some(true);
+    }
+
+    if ci > 0 {
+        // Unlink an emptied overflow bucket.
+        let gone = chain.remove(ci);
+        chain[ci - 1].next = gone.next;
+        write_bucket(bpm, sys, &chain[ci - 1])?;
+        bpm.free_page_logged(sys, gone.page_id).then_some(())?;
+        return Some(true);
+    }
+    if chain.len() > 1 {
+        // Empty head with a chain: pull the next bucket forward.
+        let next = chain.remove(1);
+        let merged = Bucket { page_id: head, ..next.clone() };
+        write_bucket(bpm, sys, &merged)?;
+        bpm.free_page_logged(sys, next.page_id).then_some(())?;
+        return Some(true);
+    }
+
+    // Empty head bucket: merge it into its split image.
+    let depth = dir.local_depths[slot];
+    if depth == 0 {
+        write_bucket(bpm, sys, &chain[0])?;
+        return Some(true);
+    }
+    let image_slot = slot ^ (1usize << (depth - 1));
+    let image = dir.buckets[image_slot];
+    if dir.local_depths[image_slot] != depth || image == head {
+        write_bucket(bpm, sys, &chain[0])?;
+        return Some(true);
+    }
+    for i in 0..dir.buckets.len() {
+        if dir.buckets[i] == head || dir.buckets[i] == image {
+            dir.buckets[i] = image;
+            dir.local_depths[i] = depth - 1;
+        }
+    }
+    dir.shrink();
+    write_directory(bpm, sys, &dir)?;
+    bpm.free_page_logged(sys, head).then_some(())?;
+    Some(true)
+}
+
+/// Write a bucket to its page, logging before and after images.
+fn write_bucket(bpm: &mut BufferPoolManager, txn: TxnId, bucket: &Bucket) -> Option<()> {
+    write_page(bpm, txn, bucket.page_id, |buf| encode_bucket(bucket, buf))
+}
+
+/// Write the directory to its page, logging before and after images.
+fn write_directory(bpm: &mut BufferPoolManager, txn: TxnId, dir: &Directory) -> Option<()> {
+    write_page(bpm, txn, dir.page_id, |buf| encode_directory(dir, buf))
+}
+
+fn write_page(
+    bpm: &mut BufferPoolManager,
+    txn: TxnId,
+    page_id: PageId,
+    encode: impl FnOnce(&mut [u8]),
+) -> Option<()> {
+    let fid = bpm.fetch_page(page_id).ok()?;
+    let before = bpm.frame_data(fid).to_vec();
+    encode(bpm.frame_data_mut(fid));
+    let after = bpm.frame_data(fid).to_vec();
+    bpm.log_update(txn, fid, LogBody::IndexPage { page_id, before, after });
+    bpm.unpin_page(page_id, true);
+    Some(())
+}
+
+fn read_u16(buf: &[u8], off: usize) -> u16 {
+    u16::from_le_bytes([buf[off], buf[off + 1]])
+}
+
+fn write_u16(buf: &mut [u8], off: usize, v: u16) {
+    buf[off..off + 2].copy_from_slice(&v.to_le_bytes());
+}
+
+fn read_u32(buf: &[u8], off: usize) -> u32 {
+    u32::from_le_bytes([buf[off], buf[off + 1], buf[off + 2], buf[off + 3]])
+}
+
+fn write_u32(buf: &mut [u8], off: usize, v: u32) {
+    buf[off..off + 4].copy_from_slice(&v.to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::disk::DiskManager;
+    use crate::storage::schema::{encode_key, Value};
+
+    const KEY: usize = 5; // Int32 key
+
+    fn setup() -> (BufferPoolManager, PageId) {
+        // 64-byte pages: 3 entries per bucket, global depth up to 3.
+        let mut bpm = BufferPoolManager::new(8, DiskManager::new(64, 256));
+        let txn = bpm.log.begin();
+        let dir = create(&mut bpm, txn, KEY).unwrap();
+        bpm.log.commit(txn);
+        (bpm, dir)
+    }
+
+    fn key(v: i32) -> Vec<u8> {
+        encode_key(&Value::Int32(v), KEY).unwrap()
+    }
+
+    fn rid(v: i32) -> RowId {
+        RowId { page_id: 1000 + v as u32, slot_id: 0 }
+    }
+
+    #[test]
+    fn splits_double_the_directory() {
+        let (mut bpm, dir) = setup();
+        let txn = bpm.log.begin();
+        for v in 0..12 {
+            assert!(insert(&mut bpm, txn, dir, &key(v), rid(v)).unwrap());
+        }
+        assert!(!insert(&mut bpm, txn, dir, &key(3), rid(3)).unwrap());
+
+        let d = read_directory(&mut bpm, dir).unwrap();
+        assert!(d.global_depth >= 2);
+        assert!(d.local_depths.iter().all(|&l| l <= d.global_depth));
+        for v in 0..12 {
+            assert_eq!(lookup(&mut bpm, dir, &key(v)), vec![rid(v)]);
+        }
+        assert!(lookup(&mut bpm, dir, &key(99)).is_empty());
+    }
+
+    #[test]
+    fn duplicate_keys_chain_overflow_buckets() {
+        let (mut bpm, dir) = setup();
+        let txn = bpm.log.begin();
+        let rids: Vec<RowId> = (0..10).map(|s| RowId { page_id: 3, slot_id: s }).collect();
+        for &r in &rids {
+            assert!(insert(&mut bpm, txn, dir, &key(7), r).unwrap());
+        }
+        assert_eq!(lookup(&mut bpm, dir, &key(7)).len(), 10);
+        let d = read_directory(&mut bpm, dir).unwrap();
+        let head = d.buckets[d.slot_of(hash_key(&key(7)))];
+        assert!(read_chain(&mut bpm, head).unwrap().len() >= 3);
+
+        for &r in &rids {
+            assert!(delete(&mut bpm, txn, dir, &key(7), r).unwrap());
+        }
+        assert_eq!(read_chain(&mut bpm, head).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn deletes_merge_buckets_and_shrink() {
+        let (mut bpm, dir) = setup();
+        let allocated = bpm.disk.num_allocated();
+        let txn = bpm.log.begin();
+        for v in 0..12 {
+            insert(&mut bpm, txn, dir, &key(v), rid(v)).unwrap();
+        }
+        for v in 0..12 {
+            assert!(delete(&mut bpm, txn, dir, &key(v), rid(v)).unwrap());
+        }
+        let d = read_directory(&mut bpm, dir).unwrap();
+        assert!(d.global_depth < 3);
+        assert!(page_ids(&mut bpm, dir).len() < 6);
+        assert!(bpm.disk.num_allocated() < allocated + 5);
+    }
+
+    #[test]
+    fn rollback_and_recovery() {
+        let (mut bpm, dir) = setup();
+        let t1 = bpm.log.begin();
+        for v in 0..6 {
+            insert(&mut bpm, t1, dir, &key(v), rid(v)).unwrap();
+        }
+        bpm.log.commit(t1);
+
+        let t2 = bpm.log.begin();
+        for v in 6..20 {
+            insert(&mut bpm, t2, dir, &key(v), rid(v)).unwrap();
+        }
+        delete(&mut bpm, t2, dir, &key(2), rid(2)).unwrap();
+        bpm.flush_all();
+        bpm.crash();
+        recovery::recover(&mut bpm);
+
+        for v in 0..6 {
+            assert_eq!(lookup(&mut bpm, dir, &key(v)), vec![rid(v)]);
+        }
+        for v in 6..20 {
+            assert!(lookup(&mut bpm, dir, &key(v)).is_empty());
+        }
+    }
+}
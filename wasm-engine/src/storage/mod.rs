@@ -1,9 +1,23 @@
 pub mod types;
+pub mod error;
 pub mod config;
 pub mod disk;
+pub mod fault;
+pub mod io_cost;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod file_disk;
 pub mod page;
 pub mod schema;
+pub mod datetime;
+pub mod replacer;
 pub mod buffer_pool;
 pub mod table;
 pub mod overflow;
+pub mod fsm;
+pub mod catalog;
+pub mod btree;
+pub mod hash_index;
 pub mod snapshot;
+pub mod wal;
+pub mod recovery;
+pub mod txn;
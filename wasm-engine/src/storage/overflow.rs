@@ -5,20 +5,24 @@
 //!
 //! ```text
 //! ┌──────────────────────────────────────────────┐
-//! │ PAGE HEADER (16 bytes, page_type = Overflow)  │
+//! │ PAGE HEADER (24 bytes, page_type = Overflow)  │
 //! ├──────────────────────────────────────────────┤
 //! │ data_length : u16  (bytes used in this page)  │
 //! ├──────────────────────────────────────────────┤
-//! │ payload data (up to page_size - 18 bytes)     │
+//! │ payload data (up to page_size - 26 bytes)     │
 //! └──────────────────────────────────────────────┘
 //! ```
 //!
 //! The `next_page_id` field in the standard page header chains overflow pages.
+//! Allocation, payload writes, chaining and frees are all logged under the
+//! caller's transaction, so a failed or aborted write can be rolled back.
 
 use crate::storage::types::*;
+use crate::storage::error::StorageResult;
 use crate::storage::page;
 use crate::storage::schema::OverflowPointer;
 use crate::storage::buffer_pool::BufferPoolManager;
+use crate::storage::wal::LogBody;
 
 /// Bytes reserved for the overflow-specific header (just the data_length u16).
 const OVERFLOW_DATA_OFFSET: usize = PAGE_HEADER_SIZE + 2;
@@ -28,9 +32,23 @@ fn overflow_payload_capacity(page_size: u32) -> usize {
     page_size as usize - OVERFLOW_DATA_OFFSET
 }
 
+/// Write one chunk of payload (length + bytes) into an overflow page buffer.
+pub fn write_chunk(buf: &mut [u8], chunk: &[u8]) {
+    buf[PAGE_HEADER_SIZE..OVERFLOW_DATA_OFFSET]
+        .copy_from_slice(&(chunk.len() as u16).to_le_bytes());
+    buf[OVERFLOW_DATA_OFFSET..OVERFLOW_DATA_OFFSET + chunk.len()].copy_from_slice(chunk);
+}
+
 /// Write a large value across one or more overflow pages.
 /// Returns the overflow pointer to embed in the tuple.
-pub fn write_overflow(bpm: &mut BufferPoolManager, data: &[u8]) -> Option<OverflowPointer> {
+///
+/// On failure, pages already written remain allocated until `txn` is
+/// rolled back.
+pub fn write_overflow(
+    bpm: &mut BufferPoolManager,
+    txn: TxnId,
+    data: &[u8],
+) -> StorageResult<OverflowPointer> {
     let total_len = data.len() as u32;
     let cap = overflow_payload_capacity(bpm.page_size());
     let mut remaining = data;
@@ -38,27 +56,13 @@ pub fn write_overflow(bpm: &mut BufferPoolManager, data: &[u8]) -> Option<Overfl
     let mut prev_page_id: Option<PageId> = None;
 
     while !remaining.is_empty() {
-        let (page_id, frame_id) = bpm.new_page()?;
-
-        // Initialize as overflow page
-        page::page_init(
-            &mut bpm.frames[frame_id as usize].data,
-            page_id,
-            PageType::Overflow,
-        );
+        let (page_id, frame_id) = bpm.new_page_logged(txn, PageType::Overflow)?;
 
         let chunk_len = remaining.len().min(cap);
         let chunk = &remaining[..chunk_len];
 
-        // Write data_length
-        let frame_data = &mut bpm.frames[frame_id as usize].data;
-        frame_data[PAGE_HEADER_SIZE] = (chunk_len as u16).to_le_bytes()[0];
-        frame_data[PAGE_HEADER_SIZE + 1] = (chunk_len as u16).to_le_bytes()[1];
-
-        // Write payload
-        frame_data[OVERFLOW_DATA_OFFSET..OVERFLOW_DATA_OFFSET + chunk_len]
-            .copy_from_slice(chunk);
-
+        write_chunk(&mut bpm.frames[frame_id as usize].data, chunk);
+        bpm.log_update(txn, frame_id, LogBody::OverflowData { page_id, data: chunk.to_vec() });
         bpm.unpin_page(page_id, true);
 
         if first_page_id.is_none() {
@@ -67,23 +71,21 @@ pub fn write_overflow(bpm: &mut BufferPoolManager, data: &[u8]) -> Option<Overfl
 
         // Link previous overflow page to this one
         if let Some(prev) = prev_page_id {
-            let prev_fid = bpm.fetch_page(prev)?;
-            page::set_next_page(&mut bpm.frames[prev_fid as usize].data, page_id);
-            bpm.unpin_page(prev, true);
+            bpm.set_next_page_logged(txn, prev, page_id)?;
         }
 
         prev_page_id = Some(page_id);
         remaining = &remaining[chunk_len..];
     }
 
-    first_page_id.map(|pid| OverflowPointer {
-        page_id: pid,
-        total_len: total_len,
+    Ok(OverflowPointer {
+        page_id: first_page_id.unwrap_or(INVALID_PAGE),
+        total_len,
     })
 }
 
 /// Read an overflow value by following the chain.
-pub fn read_overflow(bpm: &mut BufferPoolManager, ptr: &OverflowPointer) -> Option<Vec<u8>> {
+pub fn read_overflow(bpm: &mut BufferPoolManager, ptr: &OverflowPointer) -> StorageResult<Vec<u8>> {
     let mut result = Vec::with_capacity(ptr.total_len as usize);
     let mut current_page = ptr.page_id;
 
@@ -103,17 +105,17 @@ pub fn read_overflow(bpm: &mut BufferPoolManager, ptr: &OverflowPointer) -> Opti
         current_page = next;
     }
 
-    Some(result)
+    Ok(result)
 }
 
-/// Delete all overflow pages in a chain.
-pub fn delete_overflow(bpm: &mut BufferPoolManager, ptr: &OverflowPointer) {
+/// Delete all overflow pages in a chain on behalf of `txn`.
+pub fn delete_overflow(bpm: &mut BufferPoolManager, txn: TxnId, ptr: &OverflowPointer) {
     let mut current_page = ptr.page_id;
     while current_page != INVALID_PAGE {
-        if let Some(frame_id) = bpm.fetch_page(current_page) {
+        if let Ok(frame_id) = bpm.fetch_page(current_page) {
             let next = page::next_page(bpm.frame_data(frame_id));
             bpm.unpin_page(current_page, false);
-            bpm.delete_page(current_page);
+            bpm.free_page_logged(txn, current_page);
             current_page = next;
         } else {
             break;
@@ -135,7 +137,7 @@ mod tests {
     fn write_and_read_single_page() {
         let mut bpm = make_bpm(128);
         let data = vec![0xAB; 50]; // fits in one overflow page
-        let ptr = write_overflow(&mut bpm, &data).unwrap();
+        let ptr = write_overflow(&mut bpm, 1, &data).unwrap();
         assert_eq!(ptr.total_len, 50);
 
         let read_back = read_overflow(&mut bpm, &ptr).unwrap();
@@ -145,10 +147,10 @@ mod tests {
     #[test]
     fn write_and_read_multi_page() {
         let mut bpm = make_bpm(64);
-        // Overflow payload capacity per page: 64 - 18 = 46 bytes
-        // 100 bytes → needs 3 pages (46 + 46 + 8)
+        // Overflow payload capacity per page: 64 - 26 = 38 bytes
+        // 100 bytes → needs 3 pages (38 + 38 + 24)
         let data: Vec<u8> = (0..100).collect();
-        let ptr = write_overflow(&mut bpm, &data).unwrap();
+        let ptr = write_overflow(&mut bpm, 1, &data).unwrap();
         assert_eq!(ptr.total_len, 100);
 
         let read_back = read_overflow(&mut bpm, &ptr).unwrap();
@@ -159,10 +161,10 @@ mod tests {
     fn delete_overflow_frees_pages() {
         let mut bpm = make_bpm(64);
         let data = vec![0u8; 100];
-        let ptr = write_overflow(&mut bpm, &data).unwrap();
+        let ptr = write_overflow(&mut bpm, 1, &data).unwrap();
 
         let allocated_before = bpm.disk.num_allocated();
-        delete_overflow(&mut bpm, &ptr);
+        delete_overflow(&mut bpm, 1, &ptr);
         let allocated_after = bpm.disk.num_allocated();
         assert!(allocated_after < allocated_before);
     }
@@ -6,7 +6,7 @@
 //! Layout:
 //! ```text
 //! ┌─────────────────────────────────────────────┐
-//! │ PAGE HEADER (16 bytes)                      │
+//! │ PAGE HEADER (24 bytes)                      │
 //! ├─────────────────────────────────────────────┤
 //! │ SLOT ARRAY  (grows →)                       │
 //! │   slot 0 | slot 1 | slot 2 | ...            │
@@ -17,8 +17,19 @@
 //! │   tuple 2 | tuple 1 | tuple 0              │
 //! └─────────────────────────────────────────────┘
 //! ```
+//!
+//! A slot's length field carries two flag bits above the 14-bit length
+//! (page sizes top out at 8 KiB).  `TUPLE_FORWARD` marks a redirect: the
+//! slot's bytes are the `RowId` the row moved to when an update outgrew its
+//! page.  `TUPLE_MOVED` marks the relocated tuple itself, which is reached
+//! only through its forwarding slot and skipped by scans.
+//!
+//! The header ends with a CRC-32 of the whole page (computed with the
+//! checksum field itself taken as zero).  The buffer pool stamps it when a
+//! page is written to disk and checks it when a page is read back.
 
 use crate::storage::types::*;
+use crate::storage::error::{StorageError, StorageResult};
 
 // ── Header field offsets ───────────────────────────────────────────
 
@@ -28,10 +39,44 @@ const OFF_SLOT_COUNT: usize = 5;  // u16  [5..7]
 const OFF_FREE_START: usize = 7;  // u16  [7..9]
 const OFF_FREE_END: usize = 9;    // u16  [9..11]
 const OFF_NEXT_PAGE: usize = 11;  // u32  [11..15]
-// byte 15 is reserved
+const OFF_PAGE_LSN: usize = 15;   // u32  [15..19]
+// byte 19 is reserved
+const OFF_CHECKSUM: usize = 20;   // u32  [20..24]
+
+// ── Slot flags ─────────────────────────────────────────────────────
+
+/// The slot holds a forwarding `RowId` instead of a tuple.
+pub const TUPLE_FORWARD: u16 = 0x8000;
+/// The slot holds a tuple that lives here on behalf of a forwarding slot.
+pub const TUPLE_MOVED: u16 = 0x4000;
+const LEN_MASK: u16 = 0x3FFF;
+
+/// Size of a forwarding slot's payload (page id u32 + slot id u16).
+pub const FORWARD_SIZE: usize = 6;
 
 // ── Helper read/write for little-endian values ─────────────────────
 
+/// Lookup table for the reflected CRC-32 polynomial (IEEE 802.3).
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 { 0xEDB8_8320 ^ (c >> 1) } else { c >> 1 };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+};
+
+fn crc32_update(crc: u32, bytes: &[u8]) -> u32 {
+    bytes.iter().fold(crc, |c, &b| CRC_TABLE[((c ^ b as u32) & 0xFF) as usize] ^ (c >> 8))
+}
+
 fn read_u16(buf: &[u8], off: usize) -> u16 {
     u16::from_le_bytes([buf[off], buf[off + 1]])
 }
@@ -61,13 +106,32 @@ fn slot_offset(n: u16) -> usize {
     PAGE_HEADER_SIZE + n as usize * SLOT_SIZE
 }
 
-/// Read a slot entry: (tuple_offset, tuple_length).
+/// Read a slot entry: (tuple_offset, tuple_length), flags stripped.
 pub fn read_slot(buf: &[u8], slot_id: SlotId) -> (u16, u16) {
+    let (offset, raw) = read_slot_raw(buf, slot_id);
+    (offset, raw & LEN_MASK)
+}
+
+/// Flag bits (`TUPLE_FORWARD` / `TUPLE_MOVED`) of a slot.
+pub fn tuple_flags(buf: &[u8], slot_id: SlotId) -> u16 {
+    if slot_id >= slot_count(buf) {
+        return 0;
+    }
+    read_slot_raw(buf, slot_id).1 & !LEN_MASK
+}
+
+/// Set the flag bits of a live slot.
+pub fn set_tuple_flags(buf: &mut [u8], slot_id: SlotId, flags: u16) {
+    let (offset, len) = read_slot(buf, slot_id);
+    write_slot(buf, slot_id, offset, len | flags);
+}
+
+fn read_slot_raw(buf: &[u8], slot_id: SlotId) -> (u16, u16) {
     let base = slot_offset(slot_id);
     (read_u16(buf, base), read_u16(buf, base + 2))
 }
 
-/// Write a slot entry.
+/// Write a slot entry.  `length` may carry flag bits.
 fn write_slot(buf: &mut [u8], slot_id: SlotId, offset: u16, length: u16) {
     let base = slot_offset(slot_id);
     write_u16(buf, base, offset);
@@ -117,7 +181,13 @@ pub fn free_end(buf: &[u8]) -> u16 {
 pub fn free_space(buf: &[u8]) -> usize {
     let fs = free_start(buf) as usize;
     let fe = free_end(buf) as usize;
-    if fe > fs { fe - fs } else { 0 }
+    fe.saturating_sub(fs)
+}
+
+/// Free gap plus the dead tuple bytes that `compact` would reclaim.
+pub fn total_free_space(buf: &[u8]) -> usize {
+    let live: usize = (0..slot_count(buf)).map(|i| read_slot(buf, i).1 as usize).sum();
+    (buf.len() - free_start(buf) as usize).saturating_sub(live)
 }
 
 /// Next page pointer (linked list of pages in a table).
@@ -130,9 +200,44 @@ pub fn set_next_page(buf: &mut [u8], next: PageId) {
     write_u32(buf, OFF_NEXT_PAGE, next);
 }
 
-/// Insert a tuple into the page.  Returns the slot ID, or `None` if there
-/// isn't enough space (even after considering a deleted slot for reuse).
-pub fn insert_tuple(buf: &mut [u8], tuple_data: &[u8]) -> Option<SlotId> {
+/// LSN of the last logged change applied to this page.
+pub fn page_lsn(buf: &[u8]) -> Lsn {
+    read_u32(buf, OFF_PAGE_LSN)
+}
+
+/// Stamp the page with the LSN of the change just applied to it.
+pub fn set_page_lsn(buf: &mut [u8], lsn: Lsn) {
+    write_u32(buf, OFF_PAGE_LSN, lsn);
+}
+
+/// Checksum stored in the header.
+pub fn checksum(buf: &[u8]) -> u32 {
+    read_u32(buf, OFF_CHECKSUM)
+}
+
+/// CRC-32 of the page contents, treating the checksum field as zero.
+pub fn compute_checksum(buf: &[u8]) -> u32 {
+    let crc = crc32_update(!0, &buf[..OFF_CHECKSUM]);
+    let crc = crc32_update(crc, &[0; 4]);
+    !crc32_update(crc, &buf[OFF_CHECKSUM + 4..])
+}
+
+/// Store the page's current checksum in its header.
+pub fn stamp_checksum(buf: &mut [u8]) {
+    let crc = compute_checksum(buf);
+    write_u32(buf, OFF_CHECKSUM, crc);
+}
+
+/// Does the stored checksum match the contents?  A page of all zeros (never
+/// written since it was allocated) is accepted as well.
+pub fn verify_checksum(buf: &[u8]) -> bool {
+    checksum(buf) == compute_checksum(buf) || buf.iter().all(|&b| b == 0)
+}
+
+/// Insert a tuple into the page.  Returns the slot ID, or `PageFull` if
+/// there isn't enough space (even after considering a deleted slot for
+/// reuse).
+pub fn insert_tuple(buf: &mut [u8], tuple_data: &[u8]) -> StorageResult<SlotId> {
     let data_len = tuple_data.len() as u16;
 
     // Check: do we have room for the tuple data + possibly a new slot?
@@ -152,7 +257,11 @@ pub fn insert_tuple(buf: &mut [u8], tuple_data: &[u8]) -> Option<SlotId> {
     let extra_slot_bytes = if reuse_slot.is_some() { 0 } else { SLOT_SIZE };
     let available = free_space(buf);
     if available < need_for_data + extra_slot_bytes {
-        return None;
+        return Err(StorageError::PageFull {
+            page_id: page_id(buf),
+            needed: need_for_data + extra_slot_bytes,
+            free: available,
+        });
     }
 
     // Write tuple data at the bottom (grow upward)
@@ -173,22 +282,140 @@ pub fn insert_tuple(buf: &mut [u8], tuple_data: &[u8]) -> Option<SlotId> {
         sid
     };
 
-    Some(sid)
+    Ok(sid)
 }
 
-/// Delete a tuple by marking its slot as a tombstone (length = 0).
-/// Does NOT reclaim the tuple bytes immediately — call `compact` for that.
-pub fn delete_tuple(buf: &mut [u8], slot_id: SlotId) -> bool {
+/// Place a tuple at a specific slot so it keeps a known RowId.
+///
+/// Used when replaying an insert or undoing a delete.  The slot must be a
+/// tombstone or lie at/after the end of the slot array; any slots skipped
+/// over are created as tombstones.  Compacts the page first if the free gap
+/// is too small.  Fails with `SlotOccupied` if the slot is live, or
+/// `PageFull` if the tuple can't fit.
+pub fn insert_tuple_at(
+    buf: &mut [u8],
+    slot_id: SlotId,
+    flags: u16,
+    tuple_data: &[u8],
+) -> StorageResult<()> {
     let sc = slot_count(buf);
+    if slot_id < sc && read_slot(buf, slot_id).1 != 0 {
+        return Err(StorageError::SlotOccupied(RowId { page_id: page_id(buf), slot_id }));
+    }
+
+    let new_slots = if slot_id >= sc { (slot_id - sc + 1) as usize } else { 0 };
+    let needed = tuple_data.len() + new_slots * SLOT_SIZE;
+    if free_space(buf) < needed {
+        compact(buf);
+        if free_space(buf) < needed {
+            return Err(StorageError::PageFull {
+                page_id: page_id(buf),
+                needed,
+                free: free_space(buf),
+            });
+        }
+    }
+
     if slot_id >= sc {
-        return false;
+        for i in sc..slot_id {
+            write_slot(buf, i, 0, 0);
+        }
+        write_u16(buf, OFF_SLOT_COUNT, slot_id + 1);
+        write_u16(buf, OFF_FREE_START, slot_offset(slot_id + 1) as u16);
     }
-    let (_, len) = read_slot(buf, slot_id);
+
+    let data_len = tuple_data.len() as u16;
+    let fe = free_end(buf);
+    let new_free_end = fe - data_len;
+    buf[new_free_end as usize..fe as usize].copy_from_slice(tuple_data);
+    write_u16(buf, OFF_FREE_END, new_free_end);
+    write_slot(buf, slot_id, new_free_end, data_len | flags);
+    Ok(())
+}
+
+/// Replace the tuple in a live slot, keeping its slot id.
+///
+/// The bytes are overwritten in place when the new tuple is no longer than
+/// the old one; otherwise they go to the free gap, compacting the page if
+/// that's what it takes.  Fails (page untouched) with `RowNotFound` if the
+/// slot is not live, or `PageFull` if the page can't hold the new tuple
+/// even after compaction.
+pub fn update_tuple(
+    buf: &mut [u8],
+    slot_id: SlotId,
+    flags: u16,
+    tuple_data: &[u8],
+) -> StorageResult<()> {
+    let sc = slot_count(buf);
+    let (offset, len) = if slot_id < sc { read_slot(buf, slot_id) } else { (0, 0) };
     if len == 0 {
-        return false; // already deleted
+        return Err(StorageError::RowNotFound(RowId { page_id: page_id(buf), slot_id }));
+    }
+    let data_len = tuple_data.len() as u16;
+
+    if data_len <= len {
+        let start = offset as usize;
+        buf[start..start + tuple_data.len()].copy_from_slice(tuple_data);
+        write_slot(buf, slot_id, offset, data_len | flags);
+        return Ok(());
+    }
+
+    if free_space(buf) < tuple_data.len() {
+        let free = total_free_space(buf) + len as usize;
+        if free < tuple_data.len() {
+            return Err(StorageError::PageFull {
+                page_id: page_id(buf),
+                needed: tuple_data.len(),
+                free,
+            });
+        }
+        write_slot(buf, slot_id, 0, 0);
+        compact(buf);
+    }
+
+    let fe = free_end(buf);
+    let new_free_end = fe - data_len;
+    buf[new_free_end as usize..fe as usize].copy_from_slice(tuple_data);
+    write_u16(buf, OFF_FREE_END, new_free_end);
+    write_slot(buf, slot_id, new_free_end, data_len | flags);
+    Ok(())
+}
+
+/// Payload of a forwarding slot pointing at `target`.
+pub fn encode_forward(target: RowId) -> Vec<u8> {
+    let mut out = Vec::with_capacity(FORWARD_SIZE);
+    out.extend_from_slice(&target.page_id.to_le_bytes());
+    out.extend_from_slice(&target.slot_id.to_le_bytes());
+    out
+}
+
+/// Decode a forwarding slot's payload.
+pub fn decode_forward(data: &[u8]) -> RowId {
+    RowId { page_id: read_u32(data, 0), slot_id: read_u16(data, 4) }
+}
+
+/// Where a forwarding slot points, or `None` if the slot isn't one.
+pub fn forward_target(buf: &[u8], slot_id: SlotId) -> Option<RowId> {
+    if tuple_flags(buf, slot_id) & TUPLE_FORWARD == 0 {
+        return None;
+    }
+    get_tuple(buf, slot_id).map(decode_forward)
+}
+
+/// Delete a tuple by marking its slot as a tombstone (length = 0), then
+/// drop any tombstones left at the end of the slot array (`trim_slots`).
+/// Does NOT reclaim the tuple bytes immediately — call `compact` for that.
+/// Fails with `RowNotFound` if the slot is out of range or already deleted.
+pub fn delete_tuple(buf: &mut [u8], slot_id: SlotId) -> StorageResult<()> {
+    let sc = slot_count(buf);
+    if slot_id >= sc || read_slot(buf, slot_id).1 == 0 {
+        return Err(StorageError::RowNotFound(RowId { page_id: page_id(buf), slot_id }));
     }
     write_slot(buf, slot_id, 0, 0);
-    true
+    // Undoing an insert must hand back the slot it appended, or an aborted
+    // transaction can leave too little room to undo its deletes.
+    trim_slots(buf);
+    Ok(())
 }
 
 /// Read a tuple's raw bytes.  Returns `None` if the slot is a tombstone
@@ -214,21 +441,22 @@ pub fn compact(buf: &mut [u8]) {
     let page_size = buf.len();
     let sc = slot_count(buf);
 
-    // Collect live tuples: (slot_id, data_copy)
-    let mut live: Vec<(SlotId, Vec<u8>)> = Vec::new();
+    // Collect live tuples: (slot_id, flags, data_copy)
+    let mut live: Vec<(SlotId, u16, Vec<u8>)> = Vec::new();
     for i in 0..sc {
         let (off, len) = read_slot(buf, i);
         if len > 0 {
-            live.push((i, buf[off as usize..(off + len) as usize].to_vec()));
+            let flags = tuple_flags(buf, i);
+            live.push((i, flags, buf[off as usize..(off + len) as usize].to_vec()));
         }
     }
 
     // Rewrite tuples from the bottom
     let mut cursor = page_size;
-    for (sid, data) in &live {
+    for (sid, flags, data) in &live {
         cursor -= data.len();
         buf[cursor..cursor + data.len()].copy_from_slice(data);
-        write_slot(buf, *sid, cursor as u16, data.len() as u16);
+        write_slot(buf, *sid, cursor as u16, data.len() as u16 | flags);
     }
 
     // Zero the free gap
@@ -238,6 +466,22 @@ pub fn compact(buf: &mut [u8]) {
     write_u16(buf, OFF_FREE_END, cursor as u16);
 }
 
+/// Drop tombstone slots from the end of the slot array, handing their
+/// bytes back to the free gap.  Returns how many slots were removed.
+pub fn trim_slots(buf: &mut [u8]) -> u16 {
+    let sc = slot_count(buf);
+    let mut keep = sc;
+    while keep > 0 && read_slot(buf, keep - 1).1 == 0 {
+        keep -= 1;
+    }
+    if keep < sc {
+        buf[slot_offset(keep)..slot_offset(sc)].fill(0);
+        write_u16(buf, OFF_SLOT_COUNT, keep);
+        write_u16(buf, OFF_FREE_START, slot_offset(keep) as u16);
+    }
+    sc - keep
+}
+
 // ── Tests ──────────────────────────────────────────────────────────
 
 #[cfg(test)]
@@ -260,6 +504,7 @@ mod tests {
         assert_eq!(free_end(&buf), 128);
         assert_eq!(free_space(&buf), 128 - PAGE_HEADER_SIZE);
         assert_eq!(next_page(&buf), INVALID_PAGE);
+        assert_eq!(page_lsn(&buf), INVALID_LSN);
     }
 
     #[test]
@@ -294,7 +539,7 @@ mod tests {
         let s0 = insert_tuple(&mut buf, b"aaa").unwrap();
         let s1 = insert_tuple(&mut buf, b"bbb").unwrap();
 
-        assert!(delete_tuple(&mut buf, s0));
+        assert!(delete_tuple(&mut buf, s0).is_ok());
         assert!(get_tuple(&buf, s0).is_none());
 
         // New insert should reuse slot 0
@@ -305,17 +550,33 @@ mod tests {
         assert_eq!(get_tuple(&buf, s1).unwrap(), b"bbb");
     }
 
+    #[test]
+    fn checksum_detects_flipped_bits() {
+        let mut buf = make_page(128);
+        insert_tuple(&mut buf, b"hello").unwrap();
+        stamp_checksum(&mut buf);
+        assert!(verify_checksum(&buf));
+        // Restamping is stable: the stored checksum isn't part of the input.
+        let crc = checksum(&buf);
+        stamp_checksum(&mut buf);
+        assert_eq!(checksum(&buf), crc);
+
+        buf[100] ^= 0x10;
+        assert!(!verify_checksum(&buf));
+        assert!(verify_checksum(&[0u8; 128]));
+    }
+
     #[test]
     fn page_full() {
-        // Tiny page: 64 bytes. Header=16, so 48 bytes for slots+tuples.
+        // Tiny page: 64 bytes. Header=24, so 40 bytes for slots+tuples.
         let mut buf = make_page(64);
         // Each insert: 4 bytes slot + N bytes tuple data
-        // First: 4 slot + 20 data = 24 consumed → 24 remaining
-        assert!(insert_tuple(&mut buf, &[0u8; 20]).is_some());
-        // Second: 4 slot + 20 data = 24 consumed → 0 remaining
-        assert!(insert_tuple(&mut buf, &[0u8; 20]).is_some());
+        // First: 4 slot + 16 data = 20 consumed → 20 remaining
+        assert!(insert_tuple(&mut buf, &[0u8; 16]).is_ok());
+        // Second: 4 slot + 16 data = 20 consumed → 0 remaining
+        assert!(insert_tuple(&mut buf, &[0u8; 16]).is_ok());
         // Third: no room
-        assert!(insert_tuple(&mut buf, &[0u8; 1]).is_none());
+        assert!(insert_tuple(&mut buf, &[0u8; 1]).is_err());
     }
 
     #[test]
@@ -326,10 +587,10 @@ mod tests {
         let s2 = insert_tuple(&mut buf, &[0xCC; 20]).unwrap();
 
         let before = free_space(&buf);
-        delete_tuple(&mut buf, s0);
-        delete_tuple(&mut buf, s2);
-        // Free space hasn't changed (just tombstoned)
-        assert_eq!(free_space(&buf), before);
+        delete_tuple(&mut buf, s0).unwrap();
+        delete_tuple(&mut buf, s2).unwrap();
+        // Only the trailing slot came back (the tuples are just tombstoned)
+        assert_eq!(free_space(&buf), before + SLOT_SIZE);
 
         compact(&mut buf);
         // Now we should have reclaimed 40 bytes of tuple data
@@ -337,4 +598,88 @@ mod tests {
         // Live tuple still readable
         assert_eq!(get_tuple(&buf, _s1).unwrap(), &[0xBB; 20]);
     }
+
+    #[test]
+    fn insert_at_restores_slot() {
+        let mut buf = make_page(128);
+        let s0 = insert_tuple(&mut buf, b"aaa").unwrap();
+        insert_tuple(&mut buf, b"bbb").unwrap();
+        delete_tuple(&mut buf, s0).unwrap();
+
+        // Live slot is rejected, tombstone is refilled in place
+        assert!(insert_tuple_at(&mut buf, 1, 0, b"zz").is_err());
+        assert!(insert_tuple_at(&mut buf, s0, 0, b"aaa").is_ok());
+        assert_eq!(get_tuple(&buf, s0).unwrap(), b"aaa");
+
+        // Past the end: gap slots become tombstones
+        assert!(insert_tuple_at(&mut buf, 4, 0, b"eee").is_ok());
+        assert_eq!(slot_count(&buf), 5);
+        assert!(get_tuple(&buf, 2).is_none());
+        assert!(get_tuple(&buf, 3).is_none());
+        assert_eq!(get_tuple(&buf, 4).unwrap(), b"eee");
+    }
+
+    #[test]
+    fn update_in_place_and_grow() {
+        let mut buf = make_page(64);
+        let s0 = insert_tuple(&mut buf, &[0xAA; 10]).unwrap();
+        let s1 = insert_tuple(&mut buf, &[0xBB; 10]).unwrap();
+        delete_tuple(&mut buf, s1).unwrap();
+
+        // Shrinking rewrites the bytes where they are
+        let (off, _) = read_slot(&buf, s0);
+        assert!(update_tuple(&mut buf, s0, 0, b"short").is_ok());
+        assert_eq!(read_slot(&buf, s0), (off, 5));
+
+        // Growing past the free gap compacts away the dead tuple first
+        assert!(update_tuple(&mut buf, s0, TUPLE_MOVED, &[0xCC; 30]).is_ok());
+        assert_eq!(get_tuple(&buf, s0).unwrap(), &[0xCC; 30]);
+        assert_eq!(tuple_flags(&buf, s0), TUPLE_MOVED);
+        assert!(matches!(
+            update_tuple(&mut buf, s0, 0, &[0xDD; 40]),
+            Err(StorageError::PageFull { .. })
+        ));
+        assert_eq!(get_tuple(&buf, s0).unwrap(), &[0xCC; 30]);
+    }
+
+    #[test]
+    fn forwarding_slot() {
+        let mut buf = make_page(128);
+        let s0 = insert_tuple(&mut buf, b"tuple!").unwrap();
+        let target = RowId { page_id: 7, slot_id: 3 };
+        assert_eq!(forward_target(&buf, s0), None);
+        assert!(update_tuple(&mut buf, s0, TUPLE_FORWARD, &encode_forward(target)).is_ok());
+        assert_eq!(forward_target(&buf, s0), Some(target));
+
+        // Flags survive compaction, and deleting clears them
+        compact(&mut buf);
+        assert_eq!(forward_target(&buf, s0), Some(target));
+        delete_tuple(&mut buf, s0).unwrap();
+        assert_eq!(tuple_flags(&buf, s0), 0);
+    }
+
+    #[test]
+    fn trim_drops_trailing_tombstones() {
+        let mut buf = make_page(128);
+        for t in [b"aa", b"bb", b"cc", b"dd"] {
+            insert_tuple(&mut buf, t).unwrap();
+        }
+        delete_tuple(&mut buf, 0).unwrap();
+        delete_tuple(&mut buf, 2).unwrap();
+        assert_eq!(slot_count(&buf), 4);
+
+        // Deleting the last slot takes the tombstone before it along, but
+        // slot 0 stays in front of a live slot
+        let before = free_space(&buf);
+        delete_tuple(&mut buf, 3).unwrap();
+        assert_eq!(slot_count(&buf), 2);
+        assert_eq!(free_space(&buf), before + 2 * SLOT_SIZE);
+        assert_eq!(get_tuple(&buf, 1).unwrap(), b"bb");
+        assert_eq!(trim_slots(&mut buf), 0);
+
+        // Gap tombstones created by a placed insert go with it
+        insert_tuple_at(&mut buf, 4, 0, b"ee").unwrap();
+        delete_tuple(&mut buf, 4).unwrap();
+        assert_eq!(slot_count(&buf), 2);
+    }
 }
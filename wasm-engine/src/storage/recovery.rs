@@ -0,0 +1,558 @@
+//! ARIES-style crash recovery and transaction rollback.
+//!
+//! Recovery makes three passes over the durable log:
+//!
+//! 1. **Analysis** — rebuild the active transaction table (transactions with
+//!    no `Commit`/`End`, and their last LSN) and the dirty page table (the
+//!    first LSN that touched each page, its recLSN).
+//! 2. **Redo** — repeat history from the smallest recLSN: every page record
+//!    (including CLRs) whose LSN is newer than the page's LSN is reapplied.
+//! 3. **Undo** — roll back the losers in reverse LSN order, writing a
+//!    compensation log record (CLR) for every undone change so that a crash
+//!    during recovery never undoes the same change twice.
+//!
+//! `rollback` reuses the undo machinery to abort a single live transaction,
+//! and `rollback_to` to undo just the tail of one (statement atomicity).
+//!
+//! Logical index records (B+tree and hash) have no page to redo; their
+//! physical effects are logged by the index's own system transactions.
+//! They are undone by running the inverse tree operation *before* writing
+//! the CLR, so a crash in between simply repeats the (idempotent) inverse
+//! operation.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use crate::storage::types::*;
+use crate::storage::page;
+use crate::storage::error::{StorageError, StorageResult};
+use crate::storage::overflow;
+use crate::storage::btree;
+use crate::storage::hash_index;
+use crate::storage::buffer_pool::BufferPoolManager;
+use crate::storage::wal::{LogBody, LogRecord};
+
+/// Summary of a recovery run, for display.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecoveryReport {
+    /// Durable records read during analysis.
+    pub records_scanned: usize,
+    /// LSN redo started from (0 if nothing needed redo).
+    pub redo_start: Lsn,
+    /// Records whose effect was reapplied to a page.
+    pub redo_applied: usize,
+    /// Changes rolled back during undo.
+    pub undo_applied: usize,
+    /// Transactions that were active at the crash and got rolled back.
+    pub losers: Vec<TxnId>,
+    /// Transactions whose rollback stopped at a page that couldn't be read
+    /// or undone.  They stay active in the log for the next recovery.
+    pub unfinished: Vec<TxnId>,
+}
+
+/// Recover the buffer pool's pages from the durable log after a crash.
+pub fn recover(bpm: &mut BufferPoolManager) -> RecoveryReport {
+    let records = bpm.log.durable_records();
+    let mut report = RecoveryReport {
+        records_scanned: records.len(),
+        ..Default::default()
+    };
+
+    // ── Analysis ──
+    let mut att: BTreeMap<TxnId, Lsn> = BTreeMap::new();
+    let mut dpt: HashMap<PageId, Lsn> = HashMap::new();
+    let mut max_txn: TxnId = 0;
+    for rec in &records {
+        max_txn = max_txn.max(rec.txn_id);
+        match rec.body {
+            LogBody::Commit | LogBody::End => {
+                att.remove(&rec.txn_id);
+            }
+            _ => {
+                att.insert(rec.txn_id, rec.lsn);
+            }
+        }
+        if let Some(pid) = rec.body.page_id() {
+            dpt.entry(pid).or_insert(rec.lsn);
+        }
+    }
+    bpm.log.reserve_txn_ids(max_txn + 1);
+
+    // ── Redo ──
+    if let Some(&start) = dpt.values().min() {
+        report.redo_start = start;
+        for rec in records.iter().filter(|r| r.lsn >= start) {
+            let Some(pid) = rec.body.page_id() else { continue };
+            if dpt.get(&pid).is_some_and(|&rec_lsn| rec.lsn >= rec_lsn)
+                && redo(bpm, rec.lsn, &rec.body)
+            {
+                report.redo_applied += 1;
+            }
+        }
+    }
+
+    // ── Undo ──
+    for (&txn, &last) in &att {
+        bpm.log.set_last_lsn(txn, last);
+    }
+    let mut to_undo: BTreeSet<(Lsn, TxnId)> = att.iter().map(|(&t, &l)| (l, t)).collect();
+    while let Some((lsn, txn)) = to_undo.pop_last() {
+        let Some(rec) = bpm.log.record(lsn) else { continue };
+        // A page that can't be read leaves the loser without `End`, so the
+        // next recovery finishes rolling it back.
+        let Ok((next, undone)) = undo_step(bpm, &rec) else {
+            report.unfinished.push(txn);
+            continue;
+        };
+        if undone {
+            report.undo_applied += 1;
+        }
+        if next == INVALID_LSN {
+            bpm.log.end(txn);
+        } else {
+            to_undo.insert((next, txn));
+        }
+    }
+
+    report.unfinished.sort_unstable();
+    report.losers = att.keys().copied().filter(|t| !report.unfinished.contains(t)).collect();
+    bpm.log.flush_all();
+    report
+}
+
+/// Roll back a live transaction: log `Abort`, undo its changes newest-first
+/// (writing CLRs), then log `End`.  Returns the number of changes undone.
+///
+/// If a page can't be read the rollback stops there and the transaction is
+/// left without `End`; calling `rollback` again picks up where it stopped.
+pub fn rollback(bpm: &mut BufferPoolManager, txn: TxnId) -> StorageResult<usize> {
+    let last = bpm.log.last_lsn(txn);
+    bpm.log.append(txn, LogBody::Abort);
+    let undone = undo_chain(bpm, last, INVALID_LSN)?;
+    bpm.log.end(txn);
+    Ok(undone)
+}
+
+/// Partially roll back a live transaction, undoing every change it logged
+/// after `savepoint` (an LSN previously read from `LogManager::last_lsn`).
+/// The transaction stays active.  Returns the number of changes undone.
+pub fn rollback_to(
+    bpm: &mut BufferPoolManager,
+    txn: TxnId,
+    savepoint: Lsn,
+) -> StorageResult<usize> {
+    let last = bpm.log.last_lsn(txn);
+    undo_chain(bpm, last, savepoint)
+}
+
+/// Follow an undo chain from `from` back to (but excluding) `stop`.
+fn undo_chain(bpm: &mut BufferPoolManager, from: Lsn, stop: Lsn) -> StorageResult<usize> {
+    let mut next = from;
+    let mut undone = 0;
+    while next != INVALID_LSN && next > stop {
+        let Some(rec) = bpm.log.record(next) else { break };
+        let (n, did) = undo_step(bpm, &rec)?;
+        if did {
+            undone += 1;
+        }
+        next = n;
+    }
+    Ok(undone)
+}
+
+/// Undo one record.  Returns the next LSN to undo for the same transaction
+/// and whether a change was actually reversed.
+///
+/// The page is read before the CLR is logged, so a read error leaves the
+/// record to be undone later.  Once the page is in hand the undo must
+/// apply: every later change of the transaction has already been undone,
+/// so the page has room for it again.  Failing that is a broken invariant,
+/// reported as `UndoFailed`.
+fn undo_step(bpm: &mut BufferPoolManager, rec: &LogRecord) -> StorageResult<(Lsn, bool)> {
+    if let LogBody::Clr { undo_next, .. } = rec.body {
+        return Ok((undo_next, false));
+    }
+    let Some(action) = rec.body.undo_action() else {
+        return Ok((rec.prev_lsn, false));
+    };
+    if action.is_logical() {
+        undo_logical(bpm, &action)?;
+        bpm.log.append(
+            rec.txn_id,
+            LogBody::Clr { undo_next: rec.prev_lsn, redo: Box::new(action) },
+        );
+        return Ok((rec.prev_lsn, true));
+    }
+    if let Some(page_id) = action.page_id().filter(|&pid| bpm.disk.is_allocated(pid)) {
+        bpm.fetch_page(page_id)?;
+        bpm.unpin_page(page_id, false);
+    }
+    let clr_lsn = bpm.log.append(
+        rec.txn_id,
+        LogBody::Clr { undo_next: rec.prev_lsn, redo: Box::new(action.clone()) },
+    );
+    if !redo(bpm, clr_lsn, &action) {
+        return Err(StorageError::UndoFailed(rec.lsn));
+    }
+    Ok((rec.prev_lsn, true))
+}
+
+/// Run a logical index action against the tree.
+fn undo_logical(bpm: &mut BufferPoolManager, action: &LogBody) -> StorageResult<()> {
+    match action {
+        LogBody::IndexInsert { root, key, rid } => {
+            btree::insert_entry(bpm, *root, key, *rid)?;
+        }
+        LogBody::IndexDelete { root, key, rid } => {
+            btree::delete_entry(bpm, *root, key, *rid)?;
+        }
+        LogBody::HashInsert { dir, key, rid } => {
+            hash_index::insert_entry(bpm, *dir, key, *rid)?;
+        }
+        LogBody::HashDelete { dir, key, rid } => {
+            hash_index::delete_entry(bpm, *dir, key, *rid)?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Apply a page-level log body if the page hasn't seen `lsn` yet.
+/// Returns `true` if the page was changed.
+fn redo(bpm: &mut BufferPoolManager, lsn: Lsn, body: &LogBody) -> bool {
+    match body {
+        LogBody::Clr { redo: inner, .. } => redo(bpm, lsn, inner),
+        LogBody::NewPage { page_id, page_type } => {
+            if !bpm.disk.is_allocated(*page_id) {
+                bpm.disk.allocate_specific(*page_id);
+            }
+            apply_to_page(bpm, *page_id, lsn, |buf| {
+                page::page_init(buf, *page_id, *page_type);
+                true
+            })
+        }
+        LogBody::PageImage { page_id, image } => {
+            if !bpm.disk.is_allocated(*page_id) {
+                bpm.disk.allocate_specific(*page_id);
+            }
+            apply_to_page(bpm, *page_id, lsn, |buf| {
+                buf.copy_from_slice(image);
+                true
+            })
+        }
+        LogBody::FreePage { page_id, .. } => {
+            if !bpm.disk.is_allocated(*page_id) {
+                return false;
+            }
+            let Ok(fid) = bpm.fetch_page(*page_id) else { return false };
+            let stale = page::page_lsn(bpm.frame_data(fid)) < lsn;
+            bpm.unpin_page(*page_id, false);
+            if stale {
+                bpm.log.flush(lsn);
+                bpm.delete_page(*page_id);
+            }
+            stale
+        }
+        LogBody::SetNext { page_id, new_next, .. } => {
+            apply_to_page(bpm, *page_id, lsn, |buf| {
+                page::set_next_page(buf, *new_next);
+                true
+            })
+        }
+        LogBody::InsertTuple { rid, flags, data } => {
+            apply_to_page(bpm, rid.page_id, lsn, |buf| {
+                page::insert_tuple_at(buf, rid.slot_id, *flags, data).is_ok()
+            })
+        }
+        LogBody::DeleteTuple { rid, .. } => {
+            apply_to_page(bpm, rid.page_id, lsn, |buf| {
+                page::delete_tuple(buf, rid.slot_id).is_ok()
+            })
+        }
+        LogBody::UpdateTuple { rid, after_flags, after, .. } => {
+            apply_to_page(bpm, rid.page_id, lsn, |buf| {
+                page::update_tuple(buf, rid.slot_id, *after_flags, after).is_ok()
+            })
+        }
+        LogBody::OverflowData { page_id, data } => {
+            apply_to_page(bpm, *page_id, lsn, |buf| {
+                overflow::write_chunk(buf, data);
+                true
+            })
+        }
+        LogBody::IndexPage { page_id, after, .. } => {
+            apply_to_page(bpm, *page_id, lsn, |buf| {
+                buf.copy_from_slice(after);
+                true
+            })
+        }
+        LogBody::Begin
+        | LogBody::Commit
+        | LogBody::Abort
+        | LogBody::End
+        | LogBody::IndexInsert { .. }
+        | LogBody::IndexDelete { .. }
+        | LogBody::HashInsert { .. }
+        | LogBody::HashDelete { .. } => false,
+    }
+}
+
+/// Fetch `page_id`, run `op` on its bytes if the page LSN is older than
+/// `lsn`, and stamp the page with `lsn` when `op` reports a change.
+fn apply_to_page(
+    bpm: &mut BufferPoolManager,
+    page_id: PageId,
+    lsn: Lsn,
+    op: impl FnOnce(&mut [u8]) -> bool,
+) -> bool {
+    let Ok(fid) = bpm.fetch_page(page_id) else { return false };
+    let buf = bpm.frame_data_mut(fid);
+    let applied = page::page_lsn(buf) < lsn && op(buf);
+    if applied {
+        page::set_page_lsn(buf, lsn);
+    }
+    bpm.unpin_page(page_id, applied);
+    applied
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::disk::DiskManager;
+    use crate::storage::fault::{FaultKind, Trigger};
+    use crate::storage::schema::*;
+    use crate::storage::table::TableHeap;
+
+    fn make_bpm() -> BufferPoolManager {
+        BufferPoolManager::new(4, DiskManager::new(128, 64))
+    }
+
+    fn schema() -> Schema {
+        Schema::new(vec![
+            Column { name: "id".into(), col_type: ColumnType::Int32, nullable: false },
+            Column { name: "note".into(), col_type: ColumnType::VarChar(1000), nullable: false },
+        ])
+    }
+
+    fn row(id: i32, note: &str) -> Vec<Value> {
+        vec![Value::Int32(id), Value::VarChar(note.into())]
+    }
+
+    /// A one-column table, so a few rows fill a 128-byte page exactly.
+    fn notes() -> Schema {
+        Schema::new(vec![
+            Column { name: "note".into(), col_type: ColumnType::VarChar(1000), nullable: false },
+        ])
+    }
+
+    fn note(s: &str) -> Vec<Value> {
+        vec![Value::VarChar(s.into())]
+    }
+
+    #[test]
+    fn committed_work_survives_crash() {
+        let mut bpm = make_bpm();
+        let txn = bpm.log.begin();
+        let mut table = TableHeap::create("t".into(), schema(), 64, &mut bpm, txn).unwrap();
+        let r0 = table.insert(&mut bpm, txn, &row(1, "one")).unwrap();
+        let r1 = table.insert(&mut bpm, txn, &row(2, &"x".repeat(150))).unwrap();
+        bpm.log.commit(txn);
+
+        // Nothing was ever flushed — every page lives only in the pool.
+        bpm.crash();
+        let report = recover(&mut bpm);
+        assert!(report.redo_applied > 0);
+        assert!(report.losers.is_empty());
+
+        assert_eq!(table.get(&mut bpm, r0).unwrap(), row(1, "one"));
+        assert_eq!(table.get(&mut bpm, r1).unwrap(), row(2, &"x".repeat(150)));
+    }
+
+    #[test]
+    fn uncommitted_work_is_undone() {
+        let mut bpm = make_bpm();
+        let t1 = bpm.log.begin();
+        let mut table = TableHeap::create("t".into(), schema(), 64, &mut bpm, t1).unwrap();
+        let kept = table.insert(&mut bpm, t1, &row(1, "keep")).unwrap();
+        bpm.log.commit(t1);
+        let allocated = bpm.disk.num_allocated();
+
+        let t2 = bpm.log.begin();
+        let lost = table.insert(&mut bpm, t2, &row(2, &"y".repeat(200))).unwrap();
+        table.delete(&mut bpm, t2, kept).unwrap();
+        // Steal: push t2's dirty pages (and its log) to disk before the crash.
+        bpm.flush_all();
+
+        bpm.crash();
+        let report = recover(&mut bpm);
+        assert_eq!(report.losers, vec![t2]);
+        assert!(report.undo_applied >= 2);
+
+        assert_eq!(table.get(&mut bpm, kept).unwrap(), row(1, "keep"));
+        assert!(table.get(&mut bpm, lost).is_err());
+        assert_eq!(bpm.disk.num_allocated(), allocated); // overflow pages freed
+    }
+
+    #[test]
+    fn unreadable_loser_is_left_for_the_next_recovery() {
+        let mut bpm = make_bpm();
+        let t1 = bpm.log.begin();
+        let mut table = TableHeap::create("t".into(), schema(), 64, &mut bpm, t1).unwrap();
+        let kept = table.insert(&mut bpm, t1, &row(1, "keep")).unwrap();
+        bpm.log.commit(t1);
+        let t2 = bpm.log.begin();
+        table.delete(&mut bpm, t2, kept).unwrap();
+        bpm.flush_all();
+
+        // Every read of the row's page fails, through redo and into undo
+        bpm.crash();
+        for _ in 0..8 {
+            bpm.disk.faults.arm(FaultKind::ReadError, Trigger::Page(kept.page_id));
+        }
+        let report = recover(&mut bpm);
+        assert!(report.losers.is_empty());
+        assert_eq!(report.unfinished, vec![t2]);
+
+        bpm.disk.faults.disarm_all();
+        let report = recover(&mut bpm);
+        assert_eq!(report.losers, vec![t2]);
+        assert!(report.unfinished.is_empty());
+        assert_eq!(table.get(&mut bpm, kept).unwrap(), row(1, "keep"));
+    }
+
+    #[test]
+    fn rollback_restores_deleted_overflow_row() {
+        let mut bpm = make_bpm();
+        let t1 = bpm.log.begin();
+        let mut table = TableHeap::create("t".into(), schema(), 64, &mut bpm, t1).unwrap();
+        let rid = table.insert(&mut bpm, t1, &row(7, &"z".repeat(300))).unwrap();
+        bpm.log.commit(t1);
+
+        let t2 = bpm.log.begin();
+        assert!(table.delete(&mut bpm, t2, rid).is_ok());
+        assert!(table.get(&mut bpm, rid).is_err());
+        assert!(rollback(&mut bpm, t2).unwrap() > 0);
+
+        assert_eq!(table.get(&mut bpm, rid).unwrap(), row(7, &"z".repeat(300)));
+    }
+
+    #[test]
+    fn rollback_undoes_forwarding_update() {
+        let mut bpm = make_bpm();
+        let t1 = bpm.log.begin();
+        let mut table = TableHeap::create("t".into(), schema(), 64, &mut bpm, t1).unwrap();
+        let rids: Vec<RowId> = (0..5)
+            .map(|i| table.insert(&mut bpm, t1, &row(i, "0123456789")).unwrap())
+            .collect();
+        bpm.log.commit(t1);
+
+        let t2 = bpm.log.begin();
+        table.update(&mut bpm, t2, rids[0], &row(0, &"u".repeat(40))).unwrap();
+        table.update(&mut bpm, t2, rids[1], &row(1, &"v".repeat(100))).unwrap();
+        assert!(rollback(&mut bpm, t2).unwrap() > 0);
+
+        assert_eq!(table.get(&mut bpm, rids[0]).unwrap(), row(0, "0123456789"));
+        assert_eq!(table.get(&mut bpm, rids[1]).unwrap(), row(1, "0123456789"));
+        assert_eq!(table.scan(&mut bpm).len(), 5);
+    }
+
+    #[test]
+    fn rollback_has_room_to_restore_deleted_row() {
+        let mut bpm = make_bpm();
+        let t1 = bpm.log.begin();
+        let mut table = TableHeap::create("t".into(), notes(), 64, &mut bpm, t1).unwrap();
+        let a = table.insert(&mut bpm, t1, &note(&"a".repeat(45))).unwrap();
+        let b = table.insert(&mut bpm, t1, &note(&"b".repeat(45))).unwrap();
+        bpm.log.commit(t1);
+
+        // The short row reuses a's slot and the next one appends a slot,
+        // which the rollback has to give back before a fits again.
+        let t2 = bpm.log.begin();
+        table.delete(&mut bpm, t2, a).unwrap();
+        let short = table.insert(&mut bpm, t2, &note("c")).unwrap();
+        let appended = table.insert(&mut bpm, t2, &note(&"d".repeat(37))).unwrap();
+        assert_eq!(short, a);
+        assert_eq!(appended, RowId { page_id: a.page_id, slot_id: 2 });
+        assert_eq!(rollback(&mut bpm, t2).unwrap(), 3);
+
+        assert_eq!(table.get(&mut bpm, a).unwrap(), note(&"a".repeat(45)));
+        assert_eq!(table.get(&mut bpm, b).unwrap(), note(&"b".repeat(45)));
+
+        // Same squeeze for an update that shrank a row
+        let t3 = bpm.log.begin();
+        table.update(&mut bpm, t3, a, &note("c")).unwrap();
+        let appended = table.insert(&mut bpm, t3, &note(&"d".repeat(37))).unwrap();
+        assert_eq!(appended.page_id, a.page_id);
+        assert_eq!(rollback(&mut bpm, t3).unwrap(), 2);
+        assert_eq!(table.get(&mut bpm, a).unwrap(), note(&"a".repeat(45)));
+    }
+
+    #[test]
+    fn vacuumed_pages_are_redone_from_images() {
+        let mut bpm = make_bpm();
+        let t1 = bpm.log.begin();
+        let mut table = TableHeap::create("t".into(), notes(), 64, &mut bpm, t1).unwrap();
+        let kept = table.insert(&mut bpm, t1, &note(&"k".repeat(40))).unwrap();
+        let gone: Vec<RowId> = ["x", "y"]
+            .iter()
+            .map(|s| table.insert(&mut bpm, t1, &note(s)).unwrap())
+            .collect();
+        for rid in gone {
+            table.delete(&mut bpm, t1, rid).unwrap();
+        }
+        bpm.log.commit(t1);
+        bpm.flush_all();
+
+        // The new row only fits in the space vacuum compacted together, and
+        // redo has to start from the compacted page
+        let t2 = bpm.log.begin();
+        assert_eq!(table.vacuum(&mut bpm, t2).pages_compacted, 1);
+        bpm.log.commit(t2);
+        assert!(bpm.log.durable_records().iter().any(|r| {
+            matches!(r.body, LogBody::PageImage { page_id, .. } if page_id == kept.page_id)
+        }));
+        let t3 = bpm.log.begin();
+        let added = table.insert(&mut bpm, t3, &note(&"n".repeat(50))).unwrap();
+        assert_eq!(added.page_id, kept.page_id);
+        bpm.log.commit(t3);
+
+        bpm.crash();
+        recover(&mut bpm);
+        assert_eq!(table.get(&mut bpm, kept).unwrap(), note(&"k".repeat(40)));
+        assert_eq!(table.get(&mut bpm, added).unwrap(), note(&"n".repeat(50)));
+    }
+
+    #[test]
+    fn rollback_to_savepoint_keeps_earlier_work() {
+        let mut bpm = make_bpm();
+        let txn = bpm.log.begin();
+        let mut table = TableHeap::create("t".into(), schema(), 64, &mut bpm, txn).unwrap();
+        let first = table.insert(&mut bpm, txn, &row(1, "first")).unwrap();
+        let savepoint = bpm.log.last_lsn(txn);
+        let second = table.insert(&mut bpm, txn, &row(2, &"s".repeat(120))).unwrap();
+
+        assert!(rollback_to(&mut bpm, txn, savepoint).unwrap() > 0);
+        assert!(table.get(&mut bpm, first).is_ok());
+        assert!(table.get(&mut bpm, second).is_err());
+    }
+
+    #[test]
+    fn recovery_is_idempotent() {
+        let mut bpm = make_bpm();
+        let t1 = bpm.log.begin();
+        let mut table = TableHeap::create("t".into(), schema(), 64, &mut bpm, t1).unwrap();
+        let rid = table.insert(&mut bpm, t1, &row(1, "a")).unwrap();
+        bpm.log.commit(t1);
+        let t2 = bpm.log.begin();
+        table.insert(&mut bpm, t2, &row(2, "b")).unwrap();
+        bpm.flush_all();
+
+        bpm.crash();
+        recover(&mut bpm);
+        bpm.crash(); // crash again before anything is written back
+        let second = recover(&mut bpm);
+        assert!(second.losers.is_empty()); // t2 already ended by the CLR pass
+
+        let rows = table.scan(&mut bpm);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].0, rid);
+    }
+}
@@ -0,0 +1,861 @@
+//! Page replacement policies.
+//!
+//! The buffer pool asks its `Replacer` which frame to evict when it needs a
+//! free one.  Every policy sees the same events — a frame was accessed, a
+//! frame's pin count dropped to zero or rose above it, a frame was emptied —
+//! and only differs in how it picks a victim among the evictable frames.
+//!
+//! Accesses carry the page id as well as the frame id so that policies with
+//! "ghost" lists (2Q, ARC) can recognise a page that was recently evicted
+//! even though it comes back in a different frame.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use crate::storage::types::*;
+
+// ── Policy selection ───────────────────────────────────────────────
+
+/// Which replacement policy a buffer pool uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplacementPolicy {
+    /// Least recently used.
+    Lru,
+    /// Second chance: a hand sweeps the frames, clearing reference bits.
+    Clock,
+    /// Evict the frame whose K-th most recent access is oldest.
+    LruK(u32),
+    /// FIFO probation queue, ghost queue, and an LRU main queue.
+    TwoQ,
+    /// Adaptive replacement cache: balances recency and frequency.
+    Arc,
+    /// First in, first out; accesses don't matter.
+    Fifo,
+}
+
+impl ReplacementPolicy {
+    /// Numeric tag used in snapshots.
+    pub fn tag(&self) -> u8 {
+        match self {
+            ReplacementPolicy::Lru => 0,
+            ReplacementPolicy::Clock => 1,
+            ReplacementPolicy::LruK(_) => 2,
+            ReplacementPolicy::TwoQ => 3,
+            ReplacementPolicy::Arc => 4,
+            ReplacementPolicy::Fifo => 5,
+        }
+    }
+
+    /// Inverse of `tag`; `k` is only used by LRU-K.
+    pub fn from_tag(tag: u8, k: u32) -> Option<Self> {
+        Some(match tag {
+            0 => ReplacementPolicy::Lru,
+            1 => ReplacementPolicy::Clock,
+            2 => ReplacementPolicy::LruK(k),
+            3 => ReplacementPolicy::TwoQ,
+            4 => ReplacementPolicy::Arc,
+            5 => ReplacementPolicy::Fifo,
+            _ => return None,
+        })
+    }
+
+    /// Display name, also accepted by `parse`.
+    pub fn name(&self) -> String {
+        match self {
+            ReplacementPolicy::Lru => "LRU".into(),
+            ReplacementPolicy::Clock => "Clock".into(),
+            ReplacementPolicy::LruK(k) => format!("LRU-{}", k),
+            ReplacementPolicy::TwoQ => "2Q".into(),
+            ReplacementPolicy::Arc => "ARC".into(),
+            ReplacementPolicy::Fifo => "FIFO".into(),
+        }
+    }
+
+    /// Parse a policy name (case-insensitive): `LRU`, `Clock`, `LRU-K`
+    /// (K = 2) or `LRU-<n>`, `2Q`, `ARC`, `FIFO`.
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim().to_ascii_lowercase();
+        Some(match s.as_str() {
+            "lru" => ReplacementPolicy::Lru,
+            "clock" => ReplacementPolicy::Clock,
+            "lru-k" | "lruk" => ReplacementPolicy::LruK(2),
+            "2q" => ReplacementPolicy::TwoQ,
+            "arc" => ReplacementPolicy::Arc,
+            "fifo" => ReplacementPolicy::Fifo,
+            _ => {
+                let k: u32 = s.strip_prefix("lru-")?.parse().ok()?;
+                if k == 0 {
+                    return None;
+                }
+                ReplacementPolicy::LruK(k)
+            }
+        })
+    }
+}
+
+/// Build a replacer for `capacity` frames.
+pub fn make_replacer(policy: ReplacementPolicy, capacity: usize) -> Box<dyn Replacer> {
+    match policy {
+        ReplacementPolicy::Lru => Box::new(LruReplacer::new(capacity)),
+        ReplacementPolicy::Clock => Box::new(ClockReplacer::new(capacity)),
+        ReplacementPolicy::LruK(k) => Box::new(LruKReplacer::new(capacity, k)),
+        ReplacementPolicy::TwoQ => Box::new(TwoQReplacer::new(capacity)),
+        ReplacementPolicy::Arc => Box::new(ArcReplacer::new(capacity)),
+        ReplacementPolicy::Fifo => Box::new(FifoReplacer::new(capacity)),
+    }
+}
+
+// ── Replacer trait ─────────────────────────────────────────────────
+
+/// Policy-specific internals, for visualization.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplacerState {
+    /// Everything is in the eviction order.
+    Lru,
+    Fifo,
+    Clock {
+        hand: FrameId,
+        /// Per frame.
+        ref_bits: Vec<bool>,
+    },
+    LruK {
+        k: u32,
+        /// Per frame: accesses remembered (at most K) and backward
+        /// K-distance (`None` = infinite, fewer than K accesses).
+        history: Vec<(u32, Option<u64>)>,
+    },
+    TwoQ {
+        a1_in: Vec<FrameId>,
+        /// Ghost entries: pages recently evicted from `a1_in`.
+        a1_out: Vec<PageId>,
+        am: Vec<FrameId>,
+    },
+    Arc {
+        /// Target size of `t1`.
+        p: u32,
+        t1: Vec<FrameId>,
+        t2: Vec<FrameId>,
+        /// Ghost entries for `t1` / `t2`.
+        b1: Vec<PageId>,
+        b2: Vec<PageId>,
+    },
+}
+
+/// A page replacement policy over a fixed set of frames.
+pub trait Replacer {
+    fn policy(&self) -> ReplacementPolicy;
+
+    /// `frame_id` (holding `page_id`) was accessed.
+    fn record_access(&mut self, frame_id: FrameId, page_id: PageId);
+
+    /// Mark a frame as evictable (pin count 0) or not.
+    fn set_evictable(&mut self, frame_id: FrameId, evictable: bool);
+
+    /// Choose and forget a victim among the evictable frames.
+    fn evict(&mut self) -> Option<FrameId>;
+
+    /// Forget a frame entirely (its page was deleted).
+    fn remove(&mut self, frame_id: FrameId);
+
+    /// Number of evictable frames.
+    fn size(&self) -> usize;
+
+    /// Tracked frames, next victim first (ignoring pins).
+    fn eviction_order(&self) -> Vec<FrameId>;
+
+    fn state(&self) -> ReplacerState;
+}
+
+// ── LRU ────────────────────────────────────────────────────────────
+
+/// Tracks which frames are evictable and picks the least-recently-used one.
+pub struct LruReplacer {
+    /// LRU order: front = least recent, back = most recent.
+    order: VecDeque<FrameId>,
+    /// Set of frames that are currently evictable (pin_count == 0).
+    evictable: HashSet<FrameId>,
+}
+
+impl LruReplacer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            order: VecDeque::with_capacity(capacity),
+            evictable: HashSet::with_capacity(capacity),
+        }
+    }
+
+    /// LRU order (front=least recent) for visualization.
+    pub fn lru_order(&self) -> &VecDeque<FrameId> {
+        &self.order
+    }
+}
+
+impl Replacer for LruReplacer {
+    fn policy(&self) -> ReplacementPolicy {
+        ReplacementPolicy::Lru
+    }
+
+    /// Record that a frame was accessed (move to back = most recent).
+    fn record_access(&mut self, frame_id: FrameId, _page_id: PageId) {
+        self.order.retain(|&f| f != frame_id);
+        self.order.push_back(frame_id);
+    }
+
+    fn set_evictable(&mut self, frame_id: FrameId, evictable: bool) {
+        set_flag(&mut self.evictable, frame_id, evictable);
+    }
+
+    /// Evict the least-recently-used evictable frame.
+    fn evict(&mut self) -> Option<FrameId> {
+        // Walk from front (least recent) and find the first evictable frame
+        let frame_id = take_first_evictable(&mut self.order, &self.evictable)?;
+        self.evictable.remove(&frame_id);
+        Some(frame_id)
+    }
+
+    fn remove(&mut self, frame_id: FrameId) {
+        self.order.retain(|&f| f != frame_id);
+        self.evictable.remove(&frame_id);
+    }
+
+    fn size(&self) -> usize {
+        self.evictable.len()
+    }
+
+    fn eviction_order(&self) -> Vec<FrameId> {
+        self.order.iter().copied().collect()
+    }
+
+    fn state(&self) -> ReplacerState {
+        ReplacerState::Lru
+    }
+}
+
+// ── FIFO ───────────────────────────────────────────────────────────
+
+/// Evicts the frame whose page has been resident longest.
+pub struct FifoReplacer {
+    /// Load order: front = oldest.
+    queue: VecDeque<FrameId>,
+    evictable: HashSet<FrameId>,
+}
+
+impl FifoReplacer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            queue: VecDeque::with_capacity(capacity),
+            evictable: HashSet::with_capacity(capacity),
+        }
+    }
+}
+
+impl Replacer for FifoReplacer {
+    fn policy(&self) -> ReplacementPolicy {
+        ReplacementPolicy::Fifo
+    }
+
+    /// Only the first access after a load counts.
+    fn record_access(&mut self, frame_id: FrameId, _page_id: PageId) {
+        if !self.queue.contains(&frame_id) {
+            self.queue.push_back(frame_id);
+        }
+    }
+
+    fn set_evictable(&mut self, frame_id: FrameId, evictable: bool) {
+        set_flag(&mut self.evictable, frame_id, evictable);
+    }
+
+    fn evict(&mut self) -> Option<FrameId> {
+        let frame_id = take_first_evictable(&mut self.queue, &self.evictable)?;
+        self.evictable.remove(&frame_id);
+        Some(frame_id)
+    }
+
+    fn remove(&mut self, frame_id: FrameId) {
+        self.queue.retain(|&f| f != frame_id);
+        self.evictable.remove(&frame_id);
+    }
+
+    fn size(&self) -> usize {
+        self.evictable.len()
+    }
+
+    fn eviction_order(&self) -> Vec<FrameId> {
+        self.queue.iter().copied().collect()
+    }
+
+    fn state(&self) -> ReplacerState {
+        ReplacerState::Fifo
+    }
+}
+
+// ── Clock ──────────────────────────────────────────────────────────
+
+/// Second-chance replacement.  Frames form a ring in frame-id order; an
+/// access sets the frame's reference bit, and the hand clears set bits as it
+/// sweeps, evicting the first evictable frame whose bit is already clear.
+pub struct ClockReplacer {
+    hand: usize,
+    ref_bits: Vec<bool>,
+    /// Frames currently holding a page the replacer knows about.
+    in_ring: Vec<bool>,
+    evictable: Vec<bool>,
+}
+
+impl ClockReplacer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            hand: 0,
+            ref_bits: vec![false; capacity],
+            in_ring: vec![false; capacity],
+            evictable: vec![false; capacity],
+        }
+    }
+}
+
+impl Replacer for ClockReplacer {
+    fn policy(&self) -> ReplacementPolicy {
+        ReplacementPolicy::Clock
+    }
+
+    fn record_access(&mut self, frame_id: FrameId, _page_id: PageId) {
+        self.in_ring[frame_id as usize] = true;
+        self.ref_bits[frame_id as usize] = true;
+    }
+
+    fn set_evictable(&mut self, frame_id: FrameId, evictable: bool) {
+        self.evictable[frame_id as usize] = evictable;
+    }
+
+    fn evict(&mut self) -> Option<FrameId> {
+        let n = self.ref_bits.len();
+        // Two full sweeps: the first may only clear reference bits.
+        for _ in 0..2 * n {
+            let f = self.hand;
+            self.hand = (self.hand + 1) % n;
+            if !self.in_ring[f] || !self.evictable[f] {
+                continue;
+            }
+            if self.ref_bits[f] {
+                self.ref_bits[f] = false;
+            } else {
+                self.in_ring[f] = false;
+                self.evictable[f] = false;
+                return Some(f as FrameId);
+            }
+        }
+        None
+    }
+
+    fn remove(&mut self, frame_id: FrameId) {
+        let f = frame_id as usize;
+        self.in_ring[f] = false;
+        self.ref_bits[f] = false;
+        self.evictable[f] = false;
+    }
+
+    fn size(&self) -> usize {
+        self.evictable.iter().filter(|&&e| e).count()
+    }
+
+    /// Frames in the order the hand will reach them, unreferenced first.
+    fn eviction_order(&self) -> Vec<FrameId> {
+        let n = self.ref_bits.len();
+        let ring: Vec<usize> = (0..n)
+            .map(|i| (self.hand + i) % n)
+            .filter(|&f| self.in_ring[f])
+            .collect();
+        let clear = ring.iter().filter(|&&f| !self.ref_bits[f]);
+        let set = ring.iter().filter(|&&f| self.ref_bits[f]);
+        clear.chain(set).map(|&f| f as FrameId).collect()
+    }
+
+    fn state(&self) -> ReplacerState {
+        ReplacerState::Clock { hand: self.hand as FrameId, ref_bits: self.ref_bits.clone() }
+    }
+}
+
+// ── LRU-K ──────────────────────────────────────────────────────────
+
+/// Evicts the frame with the largest backward K-distance: the time since its
+/// K-th most recent access.  Frames with fewer than K accesses have infinite
+/// distance and go first, oldest first access first.
+pub struct LruKReplacer {
+    k: usize,
+    /// Logical clock, ticked on every access.
+    now: u64,
+    /// Last K access times per frame, oldest first.
+    history: Vec<VecDeque<u64>>,
+    evictable: Vec<bool>,
+}
+
+impl LruKReplacer {
+    pub fn new(capacity: usize, k: u32) -> Self {
+        Self {
+            k: k.max(1) as usize,
+            now: 0,
+            history: vec![VecDeque::new(); capacity],
+            evictable: vec![false; capacity],
+        }
+    }
+
+    /// Backward K-distance of a frame (`None` = infinite).
+    fn k_distance(&self, f: usize) -> Option<u64> {
+        let h = &self.history[f];
+        (h.len() >= self.k).then(|| self.now - h[0])
+    }
+
+    /// Sort key: infinite distance first, then oldest remembered access.
+    fn victim_key(&self, f: usize) -> (bool, u64) {
+        (self.history[f].len() >= self.k, self.history[f][0])
+    }
+}
+
+impl Replacer for LruKReplacer {
+    fn policy(&self) -> ReplacementPolicy {
+        ReplacementPolicy::LruK(self.k as u32)
+    }
+
+    fn record_access(&mut self, frame_id: FrameId, _page_id: PageId) {
+        self.now += 1;
+        let h = &mut self.history[frame_id as usize];
+        h.push_back(self.now);
+        if h.len() > self.k {
+            h.pop_front();
+        }
+    }
+
+    fn set_evictable(&mut self, frame_id: FrameId, evictable: bool) {
+        self.evictable[frame_id as usize] = evictable;
+    }
+
+    fn evict(&mut self) -> Option<FrameId> {
+        let victim = (0..self.history.len())
+            .filter(|&f| self.evictable[f] && !self.history[f].is_empty())
+            .min_by_key(|&f| self.victim_key(f))?;
+        self.history[victim].clear();
+        self.evictable[victim] = false;
+        Some(victim as FrameId)
+    }
+
+    fn remove(&mut self, frame_id: FrameId) {
+        self.history[frame_id as usize].clear();
+        self.evictable[frame_id as usize] = false;
+    }
+
+    fn size(&self) -> usize {
+        self.evictable.iter().filter(|&&e| e).count()
+    }
+
+    fn eviction_order(&self) -> Vec<FrameId> {
+        let mut frames: Vec<usize> =
+            (0..self.history.len()).filter(|&f| !self.history[f].is_empty()).collect();
+        frames.sort_by_key(|&f| self.victim_key(f));
+        frames.into_iter().map(|f| f as FrameId).collect()
+    }
+
+    fn state(&self) -> ReplacerState {
+        ReplacerState::LruK {
+            k: self.k as u32,
+            history: (0..self.history.len())
+                .map(|f| (self.history[f].len() as u32, self.k_distance(f)))
+                .collect(),
+        }
+    }
+}
+
+// ── 2Q ─────────────────────────────────────────────────────────────
+
+/// Full 2Q.  New pages enter the FIFO `a1_in`; pages evicted from it are
+/// remembered in the ghost queue `a1_out`.  A page re-referenced while its
+/// ghost is remembered is promoted to the LRU queue `am`.
+pub struct TwoQReplacer {
+    a1_in: VecDeque<FrameId>,
+    a1_out: VecDeque<PageId>,
+    am: VecDeque<FrameId>,
+    /// Page held by each tracked frame (to leave a ghost behind).
+    pages: HashMap<FrameId, PageId>,
+    evictable: HashSet<FrameId>,
+    /// Target size of `a1_in`.
+    k_in: usize,
+    /// Capacity of `a1_out`.
+    k_out: usize,
+}
+
+impl TwoQReplacer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            a1_in: VecDeque::new(),
+            a1_out: VecDeque::new(),
+            am: VecDeque::new(),
+            pages: HashMap::new(),
+            evictable: HashSet::new(),
+            k_in: (capacity / 4).max(1),
+            k_out: (capacity / 2).max(1),
+        }
+    }
+}
+
+impl Replacer for TwoQReplacer {
+    fn policy(&self) -> ReplacementPolicy {
+        ReplacementPolicy::TwoQ
+    }
+
+    fn record_access(&mut self, frame_id: FrameId, page_id: PageId) {
+        if self.am.contains(&frame_id) {
+            self.am.retain(|&f| f != frame_id);
+            self.am.push_back(frame_id);
+            return;
+        }
+        if self.a1_in.contains(&frame_id) {
+            return; // correlated reference: stays put
+        }
+        self.pages.insert(frame_id, page_id);
+        if let Some(pos) = self.a1_out.iter().position(|&p| p == page_id) {
+            self.a1_out.remove(pos);
+            self.am.push_back(frame_id);
+        } else {
+            self.a1_in.push_back(frame_id);
+        }
+    }
+
+    fn set_evictable(&mut self, frame_id: FrameId, evictable: bool) {
+        set_flag(&mut self.evictable, frame_id, evictable);
+    }
+
+    fn evict(&mut self) -> Option<FrameId> {
+        let from_in = self.a1_in.len() > self.k_in
+            || !self.am.iter().any(|f| self.evictable.contains(f));
+        let frame_id = if from_in {
+            take_first_evictable(&mut self.a1_in, &self.evictable)
+                .inspect(|f| {
+                    if let Some(&page) = self.pages.get(f) {
+                        self.a1_out.push_back(page);
+                        if self.a1_out.len() > self.k_out {
+                            self.a1_out.pop_front();
+                        }
+                    }
+                })
+        } else {
+            take_first_evictable(&mut self.am, &self.evictable)
+        }?;
+        self.pages.remove(&frame_id);
+        self.evictable.remove(&frame_id);
+        Some(frame_id)
+    }
+
+    fn remove(&mut self, frame_id: FrameId) {
+        self.a1_in.retain(|&f| f != frame_id);
+        self.am.retain(|&f| f != frame_id);
+        self.pages.remove(&frame_id);
+        self.evictable.remove(&frame_id);
+    }
+
+    fn size(&self) -> usize {
+        self.evictable.len()
+    }
+
+    fn eviction_order(&self) -> Vec<FrameId> {
+        if self.a1_in.len() > self.k_in || self.am.is_empty() {
+            self.a1_in.iter().chain(&self.am).copied().collect()
+        } else {
+            self.am.iter().chain(&self.a1_in).copied().collect()
+        }
+    }
+
+    fn state(&self) -> ReplacerState {
+        ReplacerState::TwoQ {
+            a1_in: self.a1_in.iter().copied().collect(),
+            a1_out: self.a1_out.iter().copied().collect(),
+            am: self.am.iter().copied().collect(),
+        }
+    }
+}
+
+// ── ARC ────────────────────────────────────────────────────────────
+
+/// Adaptive Replacement Cache.  `t1` holds pages seen once recently, `t2`
+/// pages seen at least twice; `b1`/`b2` remember pages recently evicted from
+/// each.  A hit in a ghost list shifts the target size `p` of `t1` toward
+/// the list that would have kept the page.
+pub struct ArcReplacer {
+    capacity: usize,
+    p: usize,
+    t1: VecDeque<FrameId>,
+    t2: VecDeque<FrameId>,
+    b1: VecDeque<PageId>,
+    b2: VecDeque<PageId>,
+    pages: HashMap<FrameId, PageId>,
+    evictable: HashSet<FrameId>,
+}
+
+impl ArcReplacer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            p: 0,
+            t1: VecDeque::new(),
+            t2: VecDeque::new(),
+            b1: VecDeque::new(),
+            b2: VecDeque::new(),
+            pages: HashMap::new(),
+            evictable: HashSet::new(),
+        }
+    }
+
+    /// Whether the next victim should come from `t1`.
+    fn prefer_t1(&self) -> bool {
+        let t1_evictable = self.t1.iter().any(|f| self.evictable.contains(f));
+        let t2_evictable = self.t2.iter().any(|f| self.evictable.contains(f));
+        t1_evictable && (self.t1.len() > self.p || !t2_evictable)
+    }
+}
+
+impl Replacer for ArcReplacer {
+    fn policy(&self) -> ReplacementPolicy {
+        ReplacementPolicy::Arc
+    }
+
+    fn record_access(&mut self, frame_id: FrameId, page_id: PageId) {
+        // Hit: promote to the frequency side.
+        if self.t1.contains(&frame_id) || self.t2.contains(&frame_id) {
+            self.t1.retain(|&f| f != frame_id);
+            self.t2.retain(|&f| f != frame_id);
+            self.t2.push_back(frame_id);
+            return;
+        }
+
+        self.pages.insert(frame_id, page_id);
+        if let Some(pos) = self.b1.iter().position(|&p| p == page_id) {
+            let delta = (self.b2.len() / self.b1.len()).max(1);
+            self.p = (self.p + delta).min(self.capacity);
+            self.b1.remove(pos);
+            self.t2.push_back(frame_id);
+        } else if let Some(pos) = self.b2.iter().position(|&p| p == page_id) {
+            let delta = (self.b1.len() / self.b2.len()).max(1);
+            self.p = self.p.saturating_sub(delta);
+            self.b2.remove(pos);
+            self.t2.push_back(frame_id);
+        } else {
+            self.t1.push_back(frame_id);
+            if self.t1.len() + self.b1.len() > self.capacity {
+                self.b1.pop_front();
+            }
+            while self.t1.len() + self.t2.len() + self.b1.len() + self.b2.len()
+                > 2 * self.capacity
+                && !self.b2.is_empty()
+            {
+                self.b2.pop_front();
+            }
+        }
+    }
+
+    fn set_evictable(&mut self, frame_id: FrameId, evictable: bool) {
+        set_flag(&mut self.evictable, frame_id, evictable);
+    }
+
+    fn evict(&mut self) -> Option<FrameId> {
+        let (list, ghosts) = if self.prefer_t1() {
+            (&mut self.t1, &mut self.b1)
+        } else {
+            (&mut self.t2, &mut self.b2)
+        };
+        let frame_id = take_first_evictable(list, &self.evictable)?;
+        if let Some(page) = self.pages.remove(&frame_id) {
+            ghosts.push_back(page);
+            if ghosts.len() > self.capacity {
+                ghosts.pop_front();
+            }
+        }
+        self.evictable.remove(&frame_id);
+        Some(frame_id)
+    }
+
+    fn remove(&mut self, frame_id: FrameId) {
+        self.t1.retain(|&f| f != frame_id);
+        self.t2.retain(|&f| f != frame_id);
+        self.pages.remove(&frame_id);
+        self.evictable.remove(&frame_id);
+    }
+
+    fn size(&self) -> usize {
+        self.evictable.len()
+    }
+
+    fn eviction_order(&self) -> Vec<FrameId> {
+        if self.t1.len() > self.p || self.t2.is_empty() {
+            self.t1.iter().chain(&self.t2).copied().collect()
+        } else {
+            self.t2.iter().chain(&self.t1).copied().collect()
+        }
+    }
+
+    fn state(&self) -> ReplacerState {
+        ReplacerState::Arc {
+            p: self.p as u32,
+            t1: self.t1.iter().copied().collect(),
+            t2: self.t2.iter().copied().collect(),
+            b1: self.b1.iter().copied().collect(),
+            b2: self.b2.iter().copied().collect(),
+        }
+    }
+}
+
+// ── Helpers ────────────────────────────────────────────────────────
+
+fn set_flag(set: &mut HashSet<FrameId>, frame_id: FrameId, on: bool) {
+    if on {
+        set.insert(frame_id);
+    } else {
+        set.remove(&frame_id);
+    }
+}
+
+/// Remove and return the first frame in `queue` that is evictable.
+fn take_first_evictable(
+    queue: &mut VecDeque<FrameId>,
+    evictable: &HashSet<FrameId>,
+) -> Option<FrameId> {
+    let pos = queue.iter().position(|f| evictable.contains(f))?;
+    queue.remove(pos)
+}
+
+// ── Tests ──────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Access each frame (page id = frame id + 100) and make it evictable.
+    fn load(r: &mut dyn Replacer, frames: &[FrameId]) {
+        for &f in frames {
+            r.record_access(f, f + 100);
+            r.set_evictable(f, true);
+        }
+    }
+
+    #[test]
+    fn lru_replacer_order() {
+        let mut r = LruReplacer::new(4);
+        r.record_access(0, 0);
+        r.record_access(1, 1);
+        r.record_access(2, 2);
+        r.set_evictable(0, true);
+        r.set_evictable(1, true);
+        r.set_evictable(2, true);
+
+        // Should evict 0 (least recent)
+        assert_eq!(r.evict(), Some(0));
+        assert_eq!(r.evict(), Some(1));
+        assert_eq!(r.evict(), Some(2));
+        assert_eq!(r.evict(), None);
+    }
+
+    #[test]
+    fn lru_access_reorder() {
+        let mut r = LruReplacer::new(4);
+        r.record_access(0, 0);
+        r.record_access(1, 1);
+        r.record_access(2, 2);
+        r.set_evictable(0, true);
+        r.set_evictable(1, true);
+        r.set_evictable(2, true);
+
+        // Touch 0 again — now 1 is LRU
+        r.record_access(0, 0);
+        assert_eq!(r.evict(), Some(1));
+    }
+
+    #[test]
+    fn fifo_ignores_reaccess() {
+        let mut r = FifoReplacer::new(4);
+        load(&mut r, &[0, 1, 2]);
+        r.record_access(0, 100);
+        assert_eq!(r.evict(), Some(0));
+        r.set_evictable(1, false);
+        assert_eq!(r.evict(), Some(2));
+        assert_eq!(r.evict(), None);
+    }
+
+    #[test]
+    fn clock_gives_second_chance() {
+        let mut r = ClockReplacer::new(3);
+        load(&mut r, &[0, 1, 2]);
+        // All bits set: the hand clears 0, 1, 2 and comes back to evict 0.
+        assert_eq!(r.evict(), Some(0));
+        r.record_access(1, 101); // 1 gets its bit back
+        assert_eq!(r.evict(), Some(2));
+        match r.state() {
+            ReplacerState::Clock { hand, ref_bits } => {
+                assert_eq!(hand, 0);
+                assert_eq!(ref_bits, vec![false, false, false]);
+            }
+            other => panic!("unexpected state {:?}", other),
+        }
+    }
+
+    #[test]
+    fn lru_k_prefers_infinite_distance() {
+        let mut r = LruKReplacer::new(4, 2);
+        load(&mut r, &[0, 1, 2]);
+        r.record_access(0, 100);
+        r.record_access(2, 102);
+        // Frame 1 was accessed once: infinite K-distance.
+        assert_eq!(r.evict(), Some(1));
+        // 0's second-most-recent access (t=1) is older than 2's (t=3).
+        assert_eq!(r.evict(), Some(0));
+        if let ReplacerState::LruK { history, .. } = r.state() {
+            assert_eq!(history[2], (2, Some(2)));
+        }
+    }
+
+    #[test]
+    fn two_q_promotes_ghost_hits() {
+        let mut r = TwoQReplacer::new(4); // k_in = 1, k_out = 2
+        load(&mut r, &[0, 1]);
+        assert_eq!(r.evict(), Some(0)); // a1_in over target → FIFO victim
+        // Page 100 comes back (in frame 0): ghost hit → am.
+        r.record_access(0, 100);
+        r.set_evictable(0, true);
+        match r.state() {
+            ReplacerState::TwoQ { a1_in, a1_out, am } => {
+                assert_eq!(a1_in, vec![1]);
+                assert!(a1_out.is_empty());
+                assert_eq!(am, vec![0]);
+            }
+            other => panic!("unexpected state {:?}", other),
+        }
+    }
+
+    #[test]
+    fn arc_adapts_on_ghost_hit() {
+        let mut r = ArcReplacer::new(2);
+        load(&mut r, &[0, 1]);
+        r.record_access(1, 101); // 1 → t2
+        assert_eq!(r.evict(), Some(0)); // t1 victim, page 100 → b1
+        r.record_access(0, 100); // ghost hit in b1: p grows, page → t2
+        match r.state() {
+            ReplacerState::Arc { p, t1, t2, b1, .. } => {
+                assert_eq!(p, 1);
+                assert!(t1.is_empty());
+                assert_eq!(t2, vec![1, 0]);
+                assert!(b1.is_empty());
+            }
+            other => panic!("unexpected state {:?}", other),
+        }
+    }
+
+    #[test]
+    fn policy_names_roundtrip() {
+        for p in [
+            ReplacementPolicy::Lru,
+            ReplacementPolicy::Clock,
+            ReplacementPolicy::LruK(3),
+            ReplacementPolicy::TwoQ,
+            ReplacementPolicy::Arc,
+            ReplacementPolicy::Fifo,
+        ] {
+            assert_eq!(ReplacementPolicy::parse(&p.name()), Some(p));
+        }
+        assert_eq!(ReplacementPolicy::parse("lru-k"), Some(ReplacementPolicy::LruK(2)));
+        assert_eq!(ReplacementPolicy::parse("mru"), None);
+    }
+}
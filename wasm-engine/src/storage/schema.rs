@@ -11,13 +11,25 @@
 //!     Bool    → 1 byte  (0x00 or 0x01)
 //!     VarChar → u16 LE length + UTF-8 bytes  (0xFFFF = overflow pointer)
 //!     Blob    → u16 LE length + raw bytes     (0xFFFF = overflow pointer)
+//!     Int16   → 2 bytes LE
+//!     Int64   → 8 bytes LE
+//!     Float32 → 4 bytes LE
+//!     Decimal → 8 bytes LE, the value × 10^scale as an i64
+//!     Date    → 4 bytes LE, days since 1970-01-01 as an i32
+//!     Timestamp → 8 bytes LE, microseconds since 1970-01-01 UTC as an i64
+//!     Uuid    → 16 bytes, in the order they are written
+//!     Char(n) → n bytes of UTF-8, padded with spaces (trimmed on decode)
 //!
 //! Null values: bit set in null_bitmap.
 //!     Fixed-size null: still occupies full column width (zeroed).
 //!     Var-size null:   u16 length = 0.
 //! ```
 
+use std::cmp::Ordering;
+use std::fmt;
+use crate::storage::datetime;
 use crate::storage::types::PageId;
+use crate::storage::error::{StorageError, StorageResult};
 
 /// Overflow sentinel: when a VarChar/Blob length prefix is 0xFFFF,
 /// the next 8 bytes are an overflow pointer.
@@ -50,6 +62,9 @@ impl OverflowPointer {
 
 // ── Column types ───────────────────────────────────────────────────
 
+/// Largest Decimal precision: every value must fit an i64 once scaled.
+pub const MAX_DECIMAL_PRECISION: u8 = 18;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ColumnType {
     Int32,
@@ -58,6 +73,14 @@ pub enum ColumnType {
     Bool,
     VarChar(u16),  // max length in bytes
     Blob(u16),     // max length in bytes
+    Int16,
+    Int64,
+    Float32,
+    Decimal(u8, u8), // precision (total digits), scale (digits after the point)
+    Date,
+    Timestamp,
+    Uuid,
+    Char(u16),     // exact length in bytes, space-padded
 }
 
 impl ColumnType {
@@ -70,6 +93,14 @@ impl ColumnType {
             ColumnType::Float64 => 8,
             ColumnType::Bool => 1,
             ColumnType::VarChar(_) | ColumnType::Blob(_) => 2, // length prefix
+            ColumnType::Int16 => 2,
+            ColumnType::Int64 => 8,
+            ColumnType::Float32 => 4,
+            ColumnType::Decimal(..) => 8,
+            ColumnType::Date => 4,
+            ColumnType::Timestamp => 8,
+            ColumnType::Uuid => 16,
+            ColumnType::Char(n) => *n as usize,
         }
     }
 
@@ -86,13 +117,81 @@ impl ColumnType {
             ColumnType::Bool => 3,
             ColumnType::VarChar(_) => 4,
             ColumnType::Blob(_) => 5,
+            ColumnType::Int16 => 6,
+            ColumnType::Int64 => 7,
+            ColumnType::Float32 => 8,
+            ColumnType::Decimal(..) => 9,
+            ColumnType::Date => 10,
+            ColumnType::Timestamp => 11,
+            ColumnType::Uuid => 12,
+            ColumnType::Char(_) => 13,
+        }
+    }
+
+    /// Inverse of `type_tag`; `param` is the value of `type_param`.
+    pub fn from_tag(tag: u8, param: u16) -> Option<Self> {
+        Some(match tag {
+            0 => ColumnType::Int32,
+            1 => ColumnType::UInt32,
+            2 => ColumnType::Float64,
+            3 => ColumnType::Bool,
+            4 => ColumnType::VarChar(param),
+            5 => ColumnType::Blob(param),
+            6 => ColumnType::Int16,
+            7 => ColumnType::Int64,
+            8 => ColumnType::Float32,
+            9 => ColumnType::Decimal((param >> 8) as u8, param as u8),
+            10 => ColumnType::Date,
+            11 => ColumnType::Timestamp,
+            12 => ColumnType::Uuid,
+            13 => ColumnType::Char(param),
+            _ => return None,
+        })
+    }
+
+    /// The type's parameter packed into a u16: the length of a
+    /// VarChar/Blob/Char, `precision << 8 | scale` for a Decimal, and 0 for
+    /// everything else.
+    pub fn type_param(&self) -> u16 {
+        match self {
+            ColumnType::VarChar(n) | ColumnType::Blob(n) | ColumnType::Char(n) => *n,
+            ColumnType::Decimal(p, s) => (*p as u16) << 8 | *s as u16,
+            _ => 0,
+        }
+    }
+
+    /// Check the type's parameters: a Decimal needs 1..=18 digits of
+    /// precision and a scale no larger than that, a Char at least one byte.
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            ColumnType::Decimal(p, _) if *p == 0 || *p > MAX_DECIMAL_PRECISION => Err(format!(
+                "{} needs a precision between 1 and {}",
+                self, MAX_DECIMAL_PRECISION
+            )),
+            ColumnType::Decimal(p, s) if s > p => {
+                Err(format!("{} has a scale larger than its precision", self))
+            }
+            ColumnType::Char(0) => Err(format!("{} must hold at least one byte", self)),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for ColumnType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColumnType::VarChar(n) => write!(f, "VarChar({})", n),
+            ColumnType::Blob(n) => write!(f, "Blob({})", n),
+            ColumnType::Char(n) => write!(f, "Char({})", n),
+            ColumnType::Decimal(p, s) => write!(f, "Decimal({},{})", p, s),
+            t => write!(f, "{:?}", t),
         }
     }
 }
 
 // ── Column + Schema ────────────────────────────────────────────────
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Column {
     pub name: String,
     pub col_type: ColumnType,
@@ -115,7 +214,66 @@ impl Schema {
 
     /// Bytes needed for the null bitmap.
     pub fn null_bitmap_size(&self) -> usize {
-        (self.columns.len() + 7) / 8
+        self.columns.len().div_ceil(8)
+    }
+
+    /// Check every column's type parameters (see `ColumnType::validate`).
+    pub fn validate(&self) -> StorageResult<()> {
+        for col in &self.columns {
+            col.col_type.validate().map_err(|problem| {
+                StorageError::SchemaMismatch(format!("Column '{}' {}", col.name, problem))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Check that `values` is a row of this schema: one value per column,
+    /// of the column's type, NULL only where allowed, and variable-length
+    /// values within their maximum length.
+    pub fn check(&self, values: &[Value]) -> StorageResult<()> {
+        if values.len() != self.columns.len() {
+            return Err(StorageError::SchemaMismatch(format!(
+                "Expected {} values, got {}",
+                self.columns.len(),
+                values.len()
+            )));
+        }
+        for (col, val) in self.columns.iter().zip(values) {
+            let too_long = |len: usize, max: u16| {
+                (len > max as usize).then(|| format!("holds at most {} bytes", max))
+            };
+            let problem = match (&col.col_type, val) {
+                (_, Value::Null) => (!col.nullable).then(|| "is not nullable".to_string()),
+                (ColumnType::Int32, Value::Int32(_))
+                | (ColumnType::UInt32, Value::UInt32(_))
+                | (ColumnType::Float64, Value::Float64(_))
+                | (ColumnType::Bool, Value::Bool(_))
+                | (ColumnType::Int16, Value::Int16(_))
+                | (ColumnType::Int64, Value::Int64(_))
+                | (ColumnType::Float32, Value::Float32(_))
+                | (ColumnType::Date, Value::Date(_))
+                | (ColumnType::Timestamp, Value::Timestamp(_))
+                | (ColumnType::Uuid, Value::Uuid(_)) => None,
+                (ColumnType::VarChar(max), Value::VarChar(s)) => too_long(s.len(), *max),
+                (ColumnType::Blob(max), Value::Blob(b)) => too_long(b.len(), *max),
+                (ColumnType::Char(max), Value::VarChar(s)) => too_long(s.len(), *max),
+                (ColumnType::Decimal(_, scale), Value::Decimal(_, s)) if s != scale => {
+                    Some(format!("has scale {}, got a value of scale {}", scale, s))
+                }
+                (ColumnType::Decimal(precision, _), Value::Decimal(v, _)) => {
+                    (v.unsigned_abs() >= 10u64.pow(*precision as u32))
+                        .then(|| format!("holds at most {} digits", precision))
+                }
+                (col_type, val) => Some(format!("is {:?}, got {:?}", col_type, val)),
+            };
+            if let Some(problem) = problem {
+                return Err(StorageError::SchemaMismatch(format!(
+                    "Column '{}' {}",
+                    col.name, problem
+                )));
+            }
+        }
+        Ok(())
     }
 
     /// Minimum tuple size (all variable-length fields empty, no nulls).
@@ -138,6 +296,16 @@ pub enum Value {
     Bool(bool),
     VarChar(String),
     Blob(Vec<u8>),
+    Int16(i16),
+    Int64(i64),
+    Float32(f32),
+    /// Unscaled value and scale: `Decimal(12345, 2)` is 123.45.
+    Decimal(i64, u8),
+    /// Days since 1970-01-01.
+    Date(i32),
+    /// Microseconds since 1970-01-01 00:00:00 UTC.
+    Timestamp(i64),
+    Uuid([u8; 16]),
     Null,
 }
 
@@ -149,13 +317,282 @@ impl Value {
             Value::UInt32(_) => 4,
             Value::Float64(_) => 8,
             Value::Bool(_) => 1,
+            Value::VarChar(_) if !col_type.is_variable() => col_type.fixed_size(), // Char
             Value::VarChar(s) => 2 + s.len(),
             Value::Blob(b) => 2 + b.len(),
+            Value::Int16(_) => 2,
+            Value::Int64(_) | Value::Decimal(..) | Value::Timestamp(_) => 8,
+            Value::Float32(_) | Value::Date(_) => 4,
+            Value::Uuid(_) => 16,
             Value::Null => col_type.fixed_size(),
         }
     }
 }
 
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Int32(v) => write!(f, "{}", v),
+            Value::UInt32(v) => write!(f, "{}", v),
+            Value::Float64(v) => write!(f, "{}", v),
+            Value::Bool(v) => write!(f, "{}", v),
+            Value::VarChar(s) => write!(f, "{}", s),
+            Value::Blob(b) => {
+                write!(f, "0x")?;
+                b.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+            }
+            Value::Int16(v) => write!(f, "{}", v),
+            Value::Int64(v) => write!(f, "{}", v),
+            Value::Float32(v) => write!(f, "{}", v),
+            Value::Decimal(v, scale) => write!(f, "{}", format_decimal(*v, *scale)),
+            Value::Date(d) => write!(f, "{}", datetime::format_date(*d)),
+            Value::Timestamp(t) => write!(f, "{}", datetime::format_timestamp(*t)),
+            Value::Uuid(u) => write!(f, "{}", format_uuid(u)),
+            Value::Null => write!(f, "NULL"),
+        }
+    }
+}
+
+/// Render an unscaled decimal: `format_decimal(-5, 2)` is `-0.05`.
+pub fn format_decimal(v: i64, scale: u8) -> String {
+    let digits = v.unsigned_abs().to_string();
+    let scale = scale as usize;
+    let sign = if v < 0 { "-" } else { "" };
+    if scale == 0 {
+        return format!("{}{}", sign, digits);
+    }
+    let digits = format!("{:0>width$}", digits, width = scale + 1);
+    let (int, frac) = digits.split_at(digits.len() - scale);
+    format!("{}{}.{}", sign, int, frac)
+}
+
+/// Parse decimal text (`-12.5`, `.25`, `7`) into an unscaled value at
+/// `scale`.  Fails on exponents, on more fractional digits than `scale`,
+/// and on values that do not fit an i64.
+pub fn parse_decimal(text: &str, scale: u8) -> Option<i64> {
+    let (negative, rest) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let (int, frac) = rest.split_once('.').unwrap_or((rest, ""));
+    let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if int.len() + frac.len() == 0 || !all_digits(int) || !all_digits(frac) {
+        return None;
+    }
+    let frac = frac.trim_end_matches('0');
+    if frac.len() > scale as usize {
+        return None;
+    }
+    let mut v: i64 = 0;
+    let padding = scale as usize - frac.len();
+    for b in int.bytes().chain(frac.bytes()).chain(std::iter::repeat_n(b'0', padding)) {
+        v = v.checked_mul(10)?.checked_add((b - b'0') as i64)?;
+    }
+    Some(if negative { -v } else { v })
+}
+
+/// Hyphenated lowercase form, `xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx`.
+pub fn format_uuid(u: &[u8; 16]) -> String {
+    let mut out = String::with_capacity(36);
+    for (i, byte) in u.iter().enumerate() {
+        if matches!(i, 4 | 6 | 8 | 10) {
+            out.push('-');
+        }
+        out.push_str(&format!("{:02x}", byte));
+    }
+    out
+}
+
+/// Parse a UUID in hyphenated form or as 32 bare hex digits.
+pub fn parse_uuid(text: &str) -> Option<[u8; 16]> {
+    let hex = if text.len() == 36 {
+        let b = text.as_bytes();
+        if [8, 13, 18, 23].iter().any(|&i| b[i] != b'-') {
+            return None;
+        }
+        text.replace('-', "")
+    } else {
+        text.to_string()
+    };
+    if hex.len() != 32 || !hex.is_ascii() {
+        return None;
+    }
+    let mut out = [0u8; 16];
+    for (i, byte) in out.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).ok()?;
+    }
+    Some(out)
+}
+
+/// Total order over values: NULL sorts first, values of the same type
+/// compare naturally (floats by `total_cmp`), and values of different types
+/// compare by type.
+pub fn cmp_values(a: &Value, b: &Value) -> Ordering {
+    fn rank(v: &Value) -> u8 {
+        match v {
+            Value::Null => 0,
+            Value::Bool(_) => 1,
+            Value::Int32(_) => 2,
+            Value::UInt32(_) => 3,
+            Value::Float64(_) => 4,
+            Value::VarChar(_) => 5,
+            Value::Blob(_) => 6,
+            Value::Int16(_) => 7,
+            Value::Int64(_) => 8,
+            Value::Float32(_) => 9,
+            Value::Decimal(..) => 10,
+            Value::Date(_) => 11,
+            Value::Timestamp(_) => 12,
+            Value::Uuid(_) => 13,
+        }
+    }
+    match (a, b) {
+        (Value::Int32(x), Value::Int32(y)) => x.cmp(y),
+        (Value::UInt32(x), Value::UInt32(y)) => x.cmp(y),
+        (Value::Float64(x), Value::Float64(y)) => x.total_cmp(y),
+        (Value::Bool(x), Value::Bool(y)) => x.cmp(y),
+        (Value::VarChar(x), Value::VarChar(y)) => x.cmp(y),
+        (Value::Blob(x), Value::Blob(y)) => x.cmp(y),
+        (Value::Int16(x), Value::Int16(y)) => x.cmp(y),
+        (Value::Int64(x), Value::Int64(y)) => x.cmp(y),
+        (Value::Float32(x), Value::Float32(y)) => x.total_cmp(y),
+        (Value::Decimal(x, xs), Value::Decimal(y, ys)) => {
+            // Bring both to the larger scale; an i128 holds any i64 × 10^18.
+            let scale = (*xs).max(*ys);
+            let widen = |v: i64, s: u8| v as i128 * 10i128.pow((scale - s) as u32);
+            widen(*x, *xs).cmp(&widen(*y, *ys))
+        }
+        (Value::Date(x), Value::Date(y)) => x.cmp(y),
+        (Value::Timestamp(x), Value::Timestamp(y)) => x.cmp(y),
+        (Value::Uuid(x), Value::Uuid(y)) => x.cmp(y),
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+// ── Index keys ─────────────────────────────────────────────────────
+
+/// Index keys are fixed width so index pages can hold plain arrays:
+///
+/// ```text
+/// [type_tag: u8][payload, zero-padded to key_size - 1]
+///     fixed types → same bytes as in a tuple
+///     VarChar/Blob → u16 LE length + bytes
+///     Char         → as VarChar, without the padding
+///     Decimal      → i64 LE + scale u8
+/// ```
+///
+/// Returns the key width for a column type.
+pub fn key_size(col_type: &ColumnType) -> usize {
+    1 + match col_type {
+        ColumnType::VarChar(n) | ColumnType::Blob(n) | ColumnType::Char(n) => 2 + *n as usize,
+        ColumnType::Decimal(..) => 9,
+        t => t.fixed_size(),
+    }
+}
+
+/// Encode a value as an index key of width `size`.  Returns `None` for NULL
+/// (never indexed) or a value too long for the key.
+pub fn encode_key(value: &Value, size: usize) -> Option<Vec<u8>> {
+    let mut out = key_bytes(value)?;
+    if out.len() > size {
+        return None;
+    }
+    out.resize(size, 0);
+    Some(out)
+}
+
+/// A value's key before padding: its length is the narrowest key width
+/// that holds the value.  `None` for NULL.
+pub fn key_bytes(value: &Value) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    match value {
+        Value::Int32(v) => {
+            out.push(ColumnType::Int32.type_tag());
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        Value::UInt32(v) => {
+            out.push(ColumnType::UInt32.type_tag());
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        Value::Float64(v) => {
+            out.push(ColumnType::Float64.type_tag());
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        Value::Bool(v) => {
+            out.push(ColumnType::Bool.type_tag());
+            out.push(*v as u8);
+        }
+        Value::VarChar(s) => {
+            out.push(ColumnType::VarChar(0).type_tag());
+            out.extend_from_slice(&(s.len() as u16).to_le_bytes());
+            out.extend_from_slice(s.as_bytes());
+        }
+        Value::Blob(b) => {
+            out.push(ColumnType::Blob(0).type_tag());
+            out.extend_from_slice(&(b.len() as u16).to_le_bytes());
+            out.extend_from_slice(b);
+        }
+        Value::Int16(v) => {
+            out.push(ColumnType::Int16.type_tag());
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        Value::Int64(v) => {
+            out.push(ColumnType::Int64.type_tag());
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        Value::Float32(v) => {
+            out.push(ColumnType::Float32.type_tag());
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        Value::Decimal(v, scale) => {
+            out.push(ColumnType::Decimal(0, 0).type_tag());
+            out.extend_from_slice(&v.to_le_bytes());
+            out.push(*scale);
+        }
+        Value::Date(v) => {
+            out.push(ColumnType::Date.type_tag());
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        Value::Timestamp(v) => {
+            out.push(ColumnType::Timestamp.type_tag());
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        Value::Uuid(u) => {
+            out.push(ColumnType::Uuid.type_tag());
+            out.extend_from_slice(u);
+        }
+        Value::Null => return None,
+    }
+    Some(out)
+}
+
+/// Decode an index key back into a value.
+pub fn decode_key(key: &[u8]) -> Value {
+    let p = &key[1..];
+    let var_len = || u16::from_le_bytes([p[0], p[1]]) as usize;
+    match key[0] {
+        0 => Value::Int32(i32::from_le_bytes(le(p))),
+        1 => Value::UInt32(u32::from_le_bytes(le(p))),
+        2 => Value::Float64(f64::from_le_bytes(le(p))),
+        3 => Value::Bool(p[0] != 0),
+        4 => Value::VarChar(String::from_utf8_lossy(&p[2..2 + var_len()]).into_owned()),
+        5 => Value::Blob(p[2..2 + var_len()].to_vec()),
+        6 => Value::Int16(i16::from_le_bytes(le(p))),
+        7 => Value::Int64(i64::from_le_bytes(le(p))),
+        8 => Value::Float32(f32::from_le_bytes(le(p))),
+        9 => Value::Decimal(i64::from_le_bytes(le(p)), p[8]),
+        10 => Value::Date(i32::from_le_bytes(le(p))),
+        11 => Value::Timestamp(i64::from_le_bytes(le(p))),
+        12 => Value::Uuid(le(p)),
+        _ => Value::Null,
+    }
+}
+
+/// Compare two encoded keys by the values they hold.
+pub fn cmp_keys(a: &[u8], b: &[u8]) -> Ordering {
+    cmp_values(&decode_key(a), &decode_key(b))
+}
+
 // ── Encoding ───────────────────────────────────────────────────────
 
 /// Encode a row of values into tuple bytes according to the schema.
@@ -164,8 +601,10 @@ impl Value {
 /// handle writing it to overflow pages and pass the overflow pointer here.
 /// For simplicity, this function does NOT handle overflow itself — it encodes
 /// what it's given.  Use `encode_tuple_with_overflow` for overflow-aware encoding.
-pub fn encode_tuple(schema: &Schema, values: &[Value]) -> Vec<u8> {
-    assert_eq!(values.len(), schema.columns.len());
+///
+/// Fails with `SchemaMismatch` if the values don't fit the schema.
+pub fn encode_tuple(schema: &Schema, values: &[Value]) -> StorageResult<Vec<u8>> {
+    schema.check(values)?;
 
     let mut buf = Vec::with_capacity(schema.min_tuple_size());
 
@@ -179,56 +618,64 @@ pub fn encode_tuple(schema: &Schema, values: &[Value]) -> Vec<u8> {
     }
 
     // Column data
-    for (i, val) in values.iter().enumerate() {
-        let col = &schema.columns[i];
-        match val {
-            Value::Int32(v) => buf.extend_from_slice(&v.to_le_bytes()),
-            Value::UInt32(v) => buf.extend_from_slice(&v.to_le_bytes()),
-            Value::Float64(v) => buf.extend_from_slice(&v.to_le_bytes()),
-            Value::Bool(v) => buf.push(if *v { 1 } else { 0 }),
-            Value::VarChar(s) => {
-                let bytes = s.as_bytes();
-                buf.extend_from_slice(&(bytes.len() as u16).to_le_bytes());
-                buf.extend_from_slice(bytes);
-            }
-            Value::Blob(b) => {
-                buf.extend_from_slice(&(b.len() as u16).to_le_bytes());
-                buf.extend_from_slice(b);
-            }
-            Value::Null => {
-                // Write zero bytes for the column's fixed size
-                match &col.col_type {
-                    ColumnType::Int32 | ColumnType::UInt32 => {
-                        buf.extend_from_slice(&[0u8; 4]);
-                    }
-                    ColumnType::Float64 => {
-                        buf.extend_from_slice(&[0u8; 8]);
-                    }
-                    ColumnType::Bool => {
-                        buf.push(0);
-                    }
-                    ColumnType::VarChar(_) | ColumnType::Blob(_) => {
-                        buf.extend_from_slice(&0u16.to_le_bytes()); // length = 0
-                    }
-                }
-            }
+    for (col, val) in schema.columns.iter().zip(values) {
+        encode_value(&col.col_type, val, &mut buf);
+    }
+
+    Ok(buf)
+}
+
+/// Append one column's bytes.  NULL fills the column's fixed width with
+/// zeros, which for a variable-length column is a zero length prefix.
+fn encode_value(col_type: &ColumnType, val: &Value, buf: &mut Vec<u8>) {
+    match (col_type, val) {
+        (_, Value::Null) => buf.resize(buf.len() + col_type.fixed_size(), 0),
+        (ColumnType::Char(n), Value::VarChar(s)) => {
+            buf.extend_from_slice(s.as_bytes());
+            buf.resize(buf.len() + *n as usize - s.len(), b' ');
         }
+        (_, Value::VarChar(s)) => {
+            buf.extend_from_slice(&(s.len() as u16).to_le_bytes());
+            buf.extend_from_slice(s.as_bytes());
+        }
+        (_, Value::Blob(b)) => {
+            buf.extend_from_slice(&(b.len() as u16).to_le_bytes());
+            buf.extend_from_slice(b);
+        }
+        (_, Value::Int32(v)) => buf.extend_from_slice(&v.to_le_bytes()),
+        (_, Value::UInt32(v)) => buf.extend_from_slice(&v.to_le_bytes()),
+        (_, Value::Float64(v)) => buf.extend_from_slice(&v.to_le_bytes()),
+        (_, Value::Bool(v)) => buf.push(*v as u8),
+        (_, Value::Int16(v)) => buf.extend_from_slice(&v.to_le_bytes()),
+        (_, Value::Int64(v) | Value::Decimal(v, _) | Value::Timestamp(v)) => {
+            buf.extend_from_slice(&v.to_le_bytes())
+        }
+        (_, Value::Float32(v)) => buf.extend_from_slice(&v.to_le_bytes()),
+        (_, Value::Date(v)) => buf.extend_from_slice(&v.to_le_bytes()),
+        (_, Value::Uuid(u)) => buf.extend_from_slice(u),
     }
+}
 
-    buf
+/// The first `N` bytes of `data` as an array, for `from_le_bytes`.
+fn le<const N: usize>(data: &[u8]) -> [u8; N] {
+    data[..N].try_into().unwrap()
 }
 
+/// Values moved out of a tuple: (column_index, raw_data) pairs that need
+/// overflow storage.
+pub type Overflows = Vec<(usize, Vec<u8>)>;
+
 /// Encode a tuple, replacing large variable-length values with overflow pointers.
-/// Returns (encoded_bytes, list of (column_index, raw_data) that need overflow storage).
+/// Returns (encoded_bytes, values that need overflow storage).
 pub fn encode_tuple_with_overflow(
     schema: &Schema,
     values: &[Value],
     overflow_threshold: u32,
-) -> (Vec<u8>, Vec<(usize, Vec<u8>)>) {
-    assert_eq!(values.len(), schema.columns.len());
+) -> StorageResult<(Vec<u8>, Overflows)> {
+    schema.check(values)?;
 
     let mut buf = Vec::with_capacity(schema.min_tuple_size());
-    let mut overflows: Vec<(usize, Vec<u8>)> = Vec::new();
+    let mut overflows: Overflows = Vec::new();
 
     // Null bitmap
     let bm_size = schema.null_bitmap_size();
@@ -240,54 +687,24 @@ pub fn encode_tuple_with_overflow(
     }
 
     // Column data
-    for (i, val) in values.iter().enumerate() {
-        let col = &schema.columns[i];
-        match val {
-            Value::Int32(v) => buf.extend_from_slice(&v.to_le_bytes()),
-            Value::UInt32(v) => buf.extend_from_slice(&v.to_le_bytes()),
-            Value::Float64(v) => buf.extend_from_slice(&v.to_le_bytes()),
-            Value::Bool(v) => buf.push(if *v { 1 } else { 0 }),
-            Value::VarChar(s) => {
-                let bytes = s.as_bytes();
-                if bytes.len() > overflow_threshold as usize {
-                    // Mark as overflow: sentinel length + placeholder pointer
-                    buf.extend_from_slice(&OVERFLOW_SENTINEL.to_le_bytes());
-                    // Placeholder overflow pointer (will be patched by caller)
-                    buf.extend_from_slice(&[0u8; OverflowPointer::SIZE]);
-                    overflows.push((i, bytes.to_vec()));
-                } else {
-                    buf.extend_from_slice(&(bytes.len() as u16).to_le_bytes());
-                    buf.extend_from_slice(bytes);
-                }
-            }
-            Value::Blob(b) => {
-                if b.len() > overflow_threshold as usize {
-                    buf.extend_from_slice(&OVERFLOW_SENTINEL.to_le_bytes());
-                    buf.extend_from_slice(&[0u8; OverflowPointer::SIZE]);
-                    overflows.push((i, b.clone()));
-                } else {
-                    buf.extend_from_slice(&(b.len() as u16).to_le_bytes());
-                    buf.extend_from_slice(b);
-                }
-            }
-            Value::Null => {
-                match &col.col_type {
-                    ColumnType::Int32 | ColumnType::UInt32 => {
-                        buf.extend_from_slice(&[0u8; 4]);
-                    }
-                    ColumnType::Float64 => {
-                        buf.extend_from_slice(&[0u8; 8]);
-                    }
-                    ColumnType::Bool => buf.push(0),
-                    ColumnType::VarChar(_) | ColumnType::Blob(_) => {
-                        buf.extend_from_slice(&0u16.to_le_bytes());
-                    }
-                }
-            }
+    for (i, (col, val)) in schema.columns.iter().zip(values).enumerate() {
+        let bytes = match val {
+            Value::VarChar(s) if col.col_type.is_variable() => s.as_bytes(),
+            Value::Blob(b) => b.as_slice(),
+            _ => &[],
+        };
+        if bytes.len() > overflow_threshold as usize {
+            // Mark as overflow: sentinel length + placeholder pointer
+            buf.extend_from_slice(&OVERFLOW_SENTINEL.to_le_bytes());
+            // Placeholder overflow pointer (will be patched by caller)
+            buf.extend_from_slice(&[0u8; OverflowPointer::SIZE]);
+            overflows.push((i, bytes.to_vec()));
+        } else {
+            encode_value(&col.col_type, val, &mut buf);
         }
     }
 
-    (buf, overflows)
+    Ok((buf, overflows))
 }
 
 /// Patch an overflow pointer into an already-encoded tuple at the right offset.
@@ -351,33 +768,6 @@ pub fn decode_tuple(schema: &Schema, data: &[u8]) -> Vec<Value> {
         }
 
         match &col.col_type {
-            ColumnType::Int32 => {
-                let v = i32::from_le_bytes([
-                    data[offset], data[offset + 1], data[offset + 2], data[offset + 3],
-                ]);
-                offset += 4;
-                values.push(Value::Int32(v));
-            }
-            ColumnType::UInt32 => {
-                let v = u32::from_le_bytes([
-                    data[offset], data[offset + 1], data[offset + 2], data[offset + 3],
-                ]);
-                offset += 4;
-                values.push(Value::UInt32(v));
-            }
-            ColumnType::Float64 => {
-                let v = f64::from_le_bytes([
-                    data[offset], data[offset + 1], data[offset + 2], data[offset + 3],
-                    data[offset + 4], data[offset + 5], data[offset + 6], data[offset + 7],
-                ]);
-                offset += 8;
-                values.push(Value::Float64(v));
-            }
-            ColumnType::Bool => {
-                let v = data[offset] != 0;
-                offset += 1;
-                values.push(Value::Bool(v));
-            }
             ColumnType::VarChar(_) => {
                 let len = u16::from_le_bytes([data[offset], data[offset + 1]]);
                 offset += 2;
@@ -405,12 +795,60 @@ pub fn decode_tuple(schema: &Schema, data: &[u8]) -> Vec<Value> {
                     values.push(Value::Blob(b));
                 }
             }
+            col_type => {
+                let size = col_type.fixed_size();
+                values.push(decode_fixed(col_type, &data[offset..offset + size]));
+                offset += size;
+            }
         }
     }
 
     values
 }
 
+/// Decode the bytes of a fixed-size column.
+fn decode_fixed(col_type: &ColumnType, b: &[u8]) -> Value {
+    match col_type {
+        ColumnType::Int32 => Value::Int32(i32::from_le_bytes(le(b))),
+        ColumnType::UInt32 => Value::UInt32(u32::from_le_bytes(le(b))),
+        ColumnType::Float64 => Value::Float64(f64::from_le_bytes(le(b))),
+        ColumnType::Bool => Value::Bool(b[0] != 0),
+        ColumnType::Int16 => Value::Int16(i16::from_le_bytes(le(b))),
+        ColumnType::Int64 => Value::Int64(i64::from_le_bytes(le(b))),
+        ColumnType::Float32 => Value::Float32(f32::from_le_bytes(le(b))),
+        ColumnType::Decimal(_, scale) => Value::Decimal(i64::from_le_bytes(le(b)), *scale),
+        ColumnType::Date => Value::Date(i32::from_le_bytes(le(b))),
+        ColumnType::Timestamp => Value::Timestamp(i64::from_le_bytes(le(b))),
+        ColumnType::Uuid => Value::Uuid(le(b)),
+        ColumnType::Char(_) => {
+            Value::VarChar(String::from_utf8_lossy(b).trim_end_matches(' ').to_string())
+        }
+        ColumnType::VarChar(_) | ColumnType::Blob(_) => unreachable!("variable-length column"),
+    }
+}
+
+/// Find every overflow pointer in an encoded tuple.
+/// Returns (column_index, pointer) pairs in column order.
+pub fn tuple_overflow_pointers(schema: &Schema, data: &[u8]) -> Vec<(usize, OverflowPointer)> {
+    let mut out = Vec::new();
+    let mut offset = schema.null_bitmap_size();
+    for (i, col) in schema.columns.iter().enumerate() {
+        if !col.col_type.is_variable() {
+            offset += col.col_type.fixed_size();
+            continue;
+        }
+        let len = u16::from_le_bytes([data[offset], data[offset + 1]]);
+        offset += 2;
+        if len == OVERFLOW_SENTINEL {
+            out.push((i, OverflowPointer::decode(&data[offset..offset + OverflowPointer::SIZE])));
+            offset += OverflowPointer::SIZE;
+        } else {
+            offset += len as usize;
+        }
+    }
+    out
+}
+
 /// Check if a decoded value is an unresolved overflow pointer (raw 8-byte blob).
 pub fn is_overflow_placeholder(val: &Value, col: &Column) -> bool {
     matches!(
@@ -440,10 +878,10 @@ mod tests {
         let values = vec![
             Value::Int32(42),
             Value::VarChar("Alice".into()),
-            Value::Float64(3.14),
+            Value::Float64(2.5),
             Value::Bool(true),
         ];
-        let encoded = encode_tuple(&schema, &values);
+        let encoded = encode_tuple(&schema, &values).unwrap();
         let decoded = decode_tuple(&schema, &encoded);
         assert_eq!(decoded, values);
     }
@@ -457,7 +895,7 @@ mod tests {
             Value::Null,
             Value::Bool(false),
         ];
-        let encoded = encode_tuple(&schema, &values);
+        let encoded = encode_tuple(&schema, &values).unwrap();
         let decoded = decode_tuple(&schema, &encoded);
         assert_eq!(decoded, values);
     }
@@ -470,7 +908,7 @@ mod tests {
             Column { name: "c".into(), col_type: ColumnType::Int32, nullable: true },
         ]);
         let values = vec![Value::Null, Value::Int32(5), Value::Null];
-        let encoded = encode_tuple(&schema, &values);
+        let encoded = encode_tuple(&schema, &values).unwrap();
         // Bitmap byte: bits 0 and 2 set = 0b00000101 = 5
         assert_eq!(encoded[0], 5);
     }
@@ -484,7 +922,7 @@ mod tests {
         let big_string = "x".repeat(200);
         let values = vec![Value::Int32(1), Value::VarChar(big_string.clone())];
 
-        let (encoded, overflows) = encode_tuple_with_overflow(&schema, &values, 100);
+        let (encoded, overflows) = encode_tuple_with_overflow(&schema, &values, 100).unwrap();
         assert_eq!(overflows.len(), 1);
         assert_eq!(overflows[0].0, 1); // column index 1
         assert_eq!(overflows[0].1, big_string.as_bytes().to_vec());
@@ -502,7 +940,7 @@ mod tests {
             Column { name: "bio".into(), col_type: ColumnType::VarChar(1000), nullable: false },
         ]);
         let values = vec![Value::Int32(1), Value::VarChar("x".repeat(200))];
-        let (mut encoded, _) = encode_tuple_with_overflow(&schema, &values, 100);
+        let (mut encoded, _) = encode_tuple_with_overflow(&schema, &values, 100).unwrap();
 
         let ptr = OverflowPointer { page_id: 42, total_len: 200 };
         patch_overflow_pointer(&schema, &mut encoded, 1, &ptr);
@@ -511,4 +949,126 @@ mod tests {
         let decoded_ptr = OverflowPointer::decode(&encoded[7..15]);
         assert_eq!(decoded_ptr, ptr);
     }
+
+    #[test]
+    fn key_roundtrip_and_order() {
+        let size = key_size(&ColumnType::VarChar(8));
+        assert_eq!(size, 11);
+        let a = encode_key(&Value::VarChar("apple".into()), size).unwrap();
+        let b = encode_key(&Value::VarChar("banana".into()), size).unwrap();
+        assert_eq!(a.len(), size);
+        assert_eq!(decode_key(&a), Value::VarChar("apple".into()));
+        assert_eq!(cmp_keys(&a, &b), Ordering::Less);
+
+        assert!(encode_key(&Value::VarChar("too long!".into()), size).is_none());
+        assert_eq!(key_bytes(&Value::VarChar("too long!".into())).unwrap().len(), 12);
+        assert!(encode_key(&Value::Null, size).is_none());
+
+        let k = key_size(&ColumnType::Int32);
+        let neg = encode_key(&Value::Int32(-5), k).unwrap();
+        let pos = encode_key(&Value::Int32(3), k).unwrap();
+        assert_eq!(cmp_keys(&neg, &pos), Ordering::Less);
+    }
+
+    #[test]
+    fn wide_types_roundtrip() {
+        let schema = Schema::new(vec![
+            Column { name: "a".into(), col_type: ColumnType::Int16, nullable: false },
+            Column { name: "b".into(), col_type: ColumnType::Int64, nullable: false },
+            Column { name: "c".into(), col_type: ColumnType::Float32, nullable: false },
+            Column { name: "d".into(), col_type: ColumnType::Decimal(6, 2), nullable: true },
+            Column { name: "e".into(), col_type: ColumnType::Date, nullable: false },
+            Column { name: "f".into(), col_type: ColumnType::Timestamp, nullable: false },
+            Column { name: "g".into(), col_type: ColumnType::Uuid, nullable: false },
+            Column { name: "h".into(), col_type: ColumnType::Char(4), nullable: false },
+        ]);
+        assert_eq!(schema.min_tuple_size(), 1 + 2 + 8 + 4 + 8 + 4 + 8 + 16 + 4);
+        let values = vec![
+            Value::Int16(-300),
+            Value::Int64(i64::MIN),
+            Value::Float32(1.5),
+            Value::Decimal(-999_999, 2),
+            Value::Date(-1),
+            Value::Timestamp(1_709_214_300_000_000),
+            Value::Uuid([7; 16]),
+            Value::VarChar("ab".into()),
+        ];
+        let encoded = encode_tuple(&schema, &values).unwrap();
+        assert_eq!(encoded.len(), schema.min_tuple_size());
+        assert_eq!(&encoded[encoded.len() - 4..], b"ab  ");
+        assert_eq!(decode_tuple(&schema, &encoded), values);
+
+        let mut with_null = values.clone();
+        with_null[3] = Value::Null;
+        assert_eq!(decode_tuple(&schema, &encode_tuple(&schema, &with_null).unwrap()), with_null);
+
+        let mut bad = values.clone();
+        bad[3] = Value::Decimal(1_000_000, 2);
+        assert!(encode_tuple(&schema, &bad).is_err());
+        bad[3] = Value::Decimal(5, 1);
+        assert!(encode_tuple(&schema, &bad).is_err());
+        bad[3] = Value::Null;
+        bad[7] = Value::VarChar("abcde".into());
+        assert!(encode_tuple(&schema, &bad).is_err());
+
+        for col in &schema.columns {
+            let tag = col.col_type.type_tag();
+            assert_eq!(ColumnType::from_tag(tag, col.col_type.type_param()), Some(col.col_type.clone()));
+        }
+    }
+
+    #[test]
+    fn wide_type_keys_and_text() {
+        let size = key_size(&ColumnType::Decimal(10, 2));
+        let a = encode_key(&Value::Decimal(-150, 2), size).unwrap();
+        let b = encode_key(&Value::Decimal(25, 2), size).unwrap();
+        assert_eq!(decode_key(&a), Value::Decimal(-150, 2));
+        assert_eq!(cmp_keys(&a, &b), Ordering::Less);
+        assert_eq!(cmp_values(&Value::Decimal(15, 1), &Value::Decimal(150, 2)), Ordering::Equal);
+
+        let size = key_size(&ColumnType::Timestamp);
+        let early = encode_key(&Value::Timestamp(-1), size).unwrap();
+        let late = encode_key(&Value::Timestamp(1), size).unwrap();
+        assert_eq!(cmp_keys(&early, &late), Ordering::Less);
+
+        assert_eq!(format_decimal(-5, 2), "-0.05");
+        assert_eq!(format_decimal(1234, 0), "1234");
+        assert_eq!(parse_decimal("-.5", 2), Some(-50));
+        assert_eq!(parse_decimal("3.10", 1), Some(31));
+        assert_eq!(parse_decimal("3.14", 1), None);
+        assert_eq!(parse_decimal("1.", 0), Some(1));
+        assert_eq!(parse_decimal(".", 0), None);
+        assert_eq!(parse_decimal("99999999999999999999", 0), None);
+
+        let uuid = parse_uuid("123e4567-e89b-12d3-a456-426614174000").unwrap();
+        assert_eq!(format_uuid(&uuid), "123e4567-e89b-12d3-a456-426614174000");
+        assert_eq!(parse_uuid("123e4567e89b12d3a456426614174000"), Some(uuid));
+        assert_eq!(parse_uuid("123e4567-e89b-12d3-a456_426614174000"), None);
+
+        assert_eq!(Value::Date(19_782).to_string(), "2024-02-29");
+        assert_eq!(ColumnType::Decimal(10, 2).to_string(), "Decimal(10,2)");
+        assert!(ColumnType::Decimal(19, 0).validate().is_err());
+        assert!(ColumnType::Char(0).validate().is_err());
+    }
+
+    #[test]
+    fn find_overflow_pointers() {
+        let schema = Schema::new(vec![
+            Column { name: "a".into(), col_type: ColumnType::Blob(1000), nullable: false },
+            Column { name: "b".into(), col_type: ColumnType::VarChar(1000), nullable: false },
+            Column { name: "c".into(), col_type: ColumnType::Blob(1000), nullable: false },
+        ]);
+        let values = vec![
+            Value::Blob(vec![7; 200]),
+            Value::VarChar("short".into()),
+            Value::Blob(vec![9; 300]),
+        ];
+        let (mut encoded, _) = encode_tuple_with_overflow(&schema, &values, 100).unwrap();
+        let p0 = OverflowPointer { page_id: 3, total_len: 200 };
+        let p2 = OverflowPointer { page_id: 8, total_len: 300 };
+        patch_overflow_pointer(&schema, &mut encoded, 0, &p0);
+        patch_overflow_pointer(&schema, &mut encoded, 2, &p2);
+
+        assert_eq!(tuple_overflow_pointers(&schema, &encoded), vec![(0, p0), (2, p2)]);
+    }
 }
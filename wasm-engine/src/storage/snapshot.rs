@@ -4,8 +4,13 @@
 
 use crate::storage::types::*;
 use crate::storage::page;
+use crate::storage::btree;
+use crate::storage::hash_index;
+use crate::storage::fsm;
+use crate::storage::fault::Trigger;
 use crate::storage::buffer_pool::BufferPoolManager;
-use crate::storage::schema::Schema;
+use crate::storage::replacer::ReplacerState;
+use crate::storage::schema::{decode_key, Schema};
 
 // ── Helpers ────────────────────────────────────────────────────────
 
@@ -13,7 +18,14 @@ fn push_u8(buf: &mut Vec<u8>, v: u8) { buf.push(v); }
 fn push_u16(buf: &mut Vec<u8>, v: u16) { buf.extend_from_slice(&v.to_le_bytes()); }
 fn push_u32(buf: &mut Vec<u8>, v: u32) { buf.extend_from_slice(&v.to_le_bytes()); }
 fn push_u64(buf: &mut Vec<u8>, v: u64) { buf.extend_from_slice(&v.to_le_bytes()); }
-fn push_f64(buf: &mut Vec<u8>, v: f64) { buf.extend_from_slice(&v.to_le_bytes()); }
+
+/// u32 count followed by the u32 values.
+fn push_list(buf: &mut Vec<u8>, items: &[u32]) {
+    push_u32(buf, items.len() as u32);
+    for &v in items {
+        push_u32(buf, v);
+    }
+}
 
 // ── Buffer Pool Snapshot ───────────────────────────────────────────
 
@@ -31,7 +43,7 @@ fn push_f64(buf: &mut Vec<u8>, v: f64) { buf.extend_from_slice(&v.to_le_bytes())
 /// page_table_len : u32
 ///   (page_id: u32, frame_id: u32) × page_table_len
 /// lru_order_len : u32
-///   frame_id: u32 × lru_order_len
+///   frame_id: u32 × lru_order_len   (eviction order, next victim first)
 /// hit_count    : u64
 /// miss_count   : u64
 /// disk_read_count  : u64
@@ -39,6 +51,20 @@ fn push_f64(buf: &mut Vec<u8>, v: f64) { buf.extend_from_slice(&v.to_le_bytes())
 /// disk_num_allocated : u32
 /// disk_max_pages     : u32
 /// disk_base_ptr      : u32
+/// io_elapsed_us      : u64  (simulated time spent on disk I/O)
+/// io_sequential      : u64  (accesses at or right after the head)
+/// io_random          : u64  (accesses that needed a seek)
+/// io_head            : u32  (last page accessed, INVALID_PAGE if none)
+/// policy_tag   : u8   (0=LRU, 1=Clock, 2=LRU-K, 3=2Q, 4=ARC, 5=FIFO)
+/// policy state, by tag:
+///     LRU, FIFO : nothing (the eviction order says it all)
+///     Clock     : hand u32, ref_bit u8 × pool_size
+///     LRU-K     : k u32, per frame: history_len u32, k_distance u64
+///                 (u64::MAX = infinite)
+///     2Q        : list(a1_in frames), list(a1_out pages), list(am frames)
+///     ARC       : p u32, list(t1 frames), list(t2 frames),
+///                 list(b1 pages), list(b2 pages)
+///   where list(x) = len u32, then u32 × len
 /// ```
 pub fn snapshot_buffer_pool(bpm: &BufferPoolManager) -> Vec<u8> {
     let mut buf = Vec::with_capacity(512);
@@ -62,12 +88,8 @@ pub fn snapshot_buffer_pool(bpm: &BufferPoolManager) -> Vec<u8> {
         push_u32(&mut buf, frame_id);
     }
 
-    // LRU order
-    let lru = bpm.replacer().lru_order();
-    push_u32(&mut buf, lru.len() as u32);
-    for &fid in lru {
-        push_u32(&mut buf, fid);
-    }
+    // Eviction order
+    push_list(&mut buf, &bpm.replacer().eviction_order());
 
     // Stats
     push_u64(&mut buf, bpm.hit_count);
@@ -77,6 +99,42 @@ pub fn snapshot_buffer_pool(bpm: &BufferPoolManager) -> Vec<u8> {
     push_u32(&mut buf, bpm.disk.num_allocated());
     push_u32(&mut buf, bpm.disk.max_pages());
     push_u32(&mut buf, bpm.disk.storage_base_ptr() as u32);
+    let clock = &bpm.disk.clock;
+    push_u64(&mut buf, clock.elapsed_us);
+    push_u64(&mut buf, clock.sequential_count);
+    push_u64(&mut buf, clock.random_count);
+    push_u32(&mut buf, clock.head.unwrap_or(INVALID_PAGE));
+
+    // Replacement policy
+    push_u8(&mut buf, bpm.replacer().policy().tag());
+    match bpm.replacer().state() {
+        ReplacerState::Lru | ReplacerState::Fifo => {}
+        ReplacerState::Clock { hand, ref_bits } => {
+            push_u32(&mut buf, hand);
+            for bit in ref_bits {
+                push_u8(&mut buf, bit as u8);
+            }
+        }
+        ReplacerState::LruK { k, history } => {
+            push_u32(&mut buf, k);
+            for (len, dist) in history {
+                push_u32(&mut buf, len);
+                push_u64(&mut buf, dist.unwrap_or(u64::MAX));
+            }
+        }
+        ReplacerState::TwoQ { a1_in, a1_out, am } => {
+            push_list(&mut buf, &a1_in);
+            push_list(&mut buf, &a1_out);
+            push_list(&mut buf, &am);
+        }
+        ReplacerState::Arc { p, t1, t2, b1, b2 } => {
+            push_u32(&mut buf, p);
+            push_list(&mut buf, &t1);
+            push_list(&mut buf, &t2);
+            push_list(&mut buf, &b1);
+            push_list(&mut buf, &b2);
+        }
+    }
 
     buf
 }
@@ -94,10 +152,25 @@ pub fn snapshot_buffer_pool(bpm: &BufferPoolManager) -> Vec<u8> {
 /// for each page 0..max_pages:
 ///     is_allocated : u8
 ///     page_type    : u8   (only meaningful if allocated; read from disk bytes)
+///     is_corrupt   : u8   (allocated, but the disk bytes fail their checksum)
+/// fault_seed     : u64
+/// armed_len      : u32
+///   armed_len entries, each:
+///     id           : u32
+///     kind         : u8   (0 torn write, 1 dropped write, 2 bit flip, 3 read error)
+///     trigger      : u8   (0 = Nth operation, 1 = page id)
+///     value        : u32  (N or page id)
+/// fired_len      : u32
+///   fired_len entries, each:
+///     id           : u32
+///     kind         : u8
+///     page_id      : u32
+///     op           : u32  (which read or write it hit)
+///     detail       : u32  (bytes landed for a torn write, bit index for a flip)
 /// ```
 pub fn snapshot_disk(bpm: &BufferPoolManager) -> Vec<u8> {
     let max = bpm.disk.max_pages();
-    let mut buf = Vec::with_capacity(16 + max as usize * 2);
+    let mut buf = Vec::with_capacity(16 + max as usize * 3);
 
     push_u32(&mut buf, max);
     push_u32(&mut buf, bpm.page_size());
@@ -105,17 +178,47 @@ pub fn snapshot_disk(bpm: &BufferPoolManager) -> Vec<u8> {
     push_u32(&mut buf, bpm.disk.storage_base_ptr() as u32);
 
     let bitmap = bpm.disk.allocation_bitmap();
-    for i in 0..max as usize {
-        push_u8(&mut buf, bitmap[i] as u8);
-        if bitmap[i] {
-            // Read page_type from the raw disk bytes
-            let page_data = bpm.disk.page_data(i as PageId);
-            push_u8(&mut buf, page_data[4]); // page_type byte at offset 4
-        } else {
-            push_u8(&mut buf, PageType::Free as u8);
+    for (i, &allocated) in bitmap.iter().enumerate() {
+        push_u8(&mut buf, allocated as u8);
+        // Read page_type from the raw disk bytes
+        match bpm.disk.peek_page(i as PageId) {
+            Ok(page_data) if allocated => {
+                push_u8(&mut buf, page_data[4]);
+                push_u8(&mut buf, !page::verify_checksum(&page_data) as u8);
+            }
+            Ok(_) => {
+                push_u8(&mut buf, PageType::Free as u8);
+                push_u8(&mut buf, 0);
+            }
+            Err(_) => {
+                push_u8(&mut buf, PageType::Free as u8);
+                push_u8(&mut buf, allocated as u8);
+            }
         }
     }
 
+    let faults = &bpm.disk.faults;
+    push_u64(&mut buf, faults.seed());
+    push_u32(&mut buf, faults.armed().len() as u32);
+    for f in faults.armed() {
+        push_u32(&mut buf, f.id);
+        push_u8(&mut buf, f.kind.tag());
+        let (trigger, value) = match f.trigger {
+            Trigger::Nth(n) => (0, n as u32),
+            Trigger::Page(pid) => (1, pid),
+        };
+        push_u8(&mut buf, trigger);
+        push_u32(&mut buf, value);
+    }
+    push_u32(&mut buf, faults.fired().len() as u32);
+    for f in faults.fired() {
+        push_u32(&mut buf, f.id);
+        push_u8(&mut buf, f.kind.tag());
+        push_u32(&mut buf, f.page_id);
+        push_u32(&mut buf, f.op as u32);
+        push_u32(&mut buf, f.detail);
+    }
+
     buf
 }
 
@@ -133,14 +236,16 @@ pub fn snapshot_disk(bpm: &BufferPoolManager) -> Vec<u8> {
 /// free_end       : u16
 /// next_page_id   : u32
 /// free_space     : u16
+/// page_lsn       : u32
 /// num_slots      : u16
 /// for each slot:
 ///     offset     : u16
 ///     length     : u16
+///     kind       : u8   (0 = tuple, 1 = moved tuple, 2 = forwarding slot)
 /// raw_bytes      : [u8; page_size]   (the entire page)
 /// ```
 pub fn snapshot_page(bpm: &mut BufferPoolManager, page_id: PageId) -> Option<Vec<u8>> {
-    let frame_id = bpm.fetch_page(page_id)?;
+    let frame_id = bpm.fetch_page(page_id).ok()?;
     let data = &bpm.frames[frame_id as usize].data;
     let ps = bpm.page_size();
 
@@ -155,6 +260,7 @@ pub fn snapshot_page(bpm: &mut BufferPoolManager, page_id: PageId) -> Option<Vec
     push_u16(&mut buf, page::free_end(data));
     push_u32(&mut buf, page::next_page(data));
     push_u16(&mut buf, page::free_space(data) as u16);
+    push_u32(&mut buf, page::page_lsn(data));
 
     // Slots
     push_u16(&mut buf, sc);
@@ -162,6 +268,7 @@ pub fn snapshot_page(bpm: &mut BufferPoolManager, page_id: PageId) -> Option<Vec
         let (off, len) = page::read_slot(data, i);
         push_u16(&mut buf, off);
         push_u16(&mut buf, len);
+        push_u8(&mut buf, (page::tuple_flags(data, i) >> 14) as u8);
     }
 
     // Raw bytes
@@ -171,6 +278,163 @@ pub fn snapshot_page(bpm: &mut BufferPoolManager, page_id: PageId) -> Option<Vec
     Some(buf)
 }
 
+// ── B+tree Index Snapshot ──────────────────────────────────────────
+
+/// Encode the shape of a B+tree for drawing.  Nodes are listed
+/// breadth-first, so taking snapshots before and after a mutation shows
+/// which pages split or merged.
+///
+/// Format:
+/// ```text
+/// root_page_id   : u32
+/// key_size       : u16
+/// node_count     : u32
+/// for each node (breadth-first):
+///     page_id    : u32
+///     depth      : u16  (root = 0)
+///     is_leaf    : u8
+///     next_leaf  : u32  (INVALID_PAGE for internal nodes / last leaf)
+///     num_keys   : u16
+///     for each key:
+///         text_len : u16
+///         text     : UTF-8 bytes (the key value, displayed)
+///         rid_page : u32
+///         rid_slot : u16
+///     children   : u32 × (num_keys + 1)   (internal nodes only)
+/// ```
+pub fn snapshot_index(bpm: &mut BufferPoolManager, root: PageId) -> Option<Vec<u8>> {
+    let mut buf = Vec::with_capacity(256);
+    let mut nodes = vec![(btree::read_node(bpm, root)?, 0u16)];
+    let mut i = 0;
+    while i < nodes.len() {
+        let depth = nodes[i].1;
+        for child in nodes[i].0.children() {
+            let Some(node) = btree::read_node(bpm, child) else { continue };
+            nodes.push((node, depth + 1));
+        }
+        i += 1;
+    }
+
+    push_u32(&mut buf, root);
+    push_u16(&mut buf, nodes[0].0.key_size as u16);
+    push_u32(&mut buf, nodes.len() as u32);
+    for (node, depth) in &nodes {
+        push_u32(&mut buf, node.page_id);
+        push_u16(&mut buf, *depth);
+        push_u8(&mut buf, node.is_leaf as u8);
+        push_u32(&mut buf, if node.is_leaf { node.next } else { INVALID_PAGE });
+        push_u16(&mut buf, node.entries.len() as u16);
+        for e in &node.entries {
+            let text = decode_key(&e.key).to_string();
+            push_u16(&mut buf, text.len() as u16);
+            buf.extend_from_slice(text.as_bytes());
+            push_u32(&mut buf, e.rid.page_id);
+            push_u16(&mut buf, e.rid.slot_id);
+        }
+        for child in node.children() {
+            push_u32(&mut buf, child);
+        }
+    }
+
+    Some(buf)
+}
+
+// ── Hash Index Snapshot ────────────────────────────────────────────
+
+/// Encode an extendible hash index: the directory, then each distinct
+/// bucket in directory order.
+///
+/// Format:
+/// ```text
+/// directory_page_id : u32
+/// global_depth      : u8
+/// key_size          : u16
+/// bucket_capacity   : u16
+/// dir_size          : u32   (2^global_depth)
+/// for each directory slot:
+///     bucket_page_id : u32
+///     local_depth    : u8
+/// bucket_count      : u32
+/// for each distinct bucket:
+///     page_id      : u32
+///     local_depth  : u8
+///     chain_len    : u16   (pages, including this one)
+///     for each page in the chain:
+///         page_id     : u32
+///         num_entries : u16
+/// ```
+pub fn snapshot_hash_index(bpm: &mut BufferPoolManager, dir_page: PageId) -> Option<Vec<u8>> {
+    let dir = hash_index::read_directory(bpm, dir_page)?;
+    let capacity = hash_index::bucket_capacity(bpm.page_size() as usize, dir.key_size);
+    let mut buf = Vec::with_capacity(64 + dir.buckets.len() * 5);
+
+    push_u32(&mut buf, dir_page);
+    push_u8(&mut buf, dir.global_depth);
+    push_u16(&mut buf, dir.key_size as u16);
+    push_u16(&mut buf, capacity as u16);
+    push_u32(&mut buf, dir.buckets.len() as u32);
+    for (&bucket, &depth) in dir.buckets.iter().zip(&dir.local_depths) {
+        push_u32(&mut buf, bucket);
+        push_u8(&mut buf, depth);
+    }
+
+    let mut seen: Vec<(PageId, u8)> = Vec::new();
+    for (&bucket, &depth) in dir.buckets.iter().zip(&dir.local_depths) {
+        if !seen.iter().any(|&(p, _)| p == bucket) {
+            seen.push((bucket, depth));
+        }
+    }
+    push_u32(&mut buf, seen.len() as u32);
+    for (head, depth) in seen {
+        let chain = hash_index::read_chain(bpm, head).unwrap_or_default();
+        push_u32(&mut buf, head);
+        push_u8(&mut buf, depth);
+        push_u16(&mut buf, chain.len() as u16);
+        for b in &chain {
+            push_u32(&mut buf, b.page_id);
+            push_u16(&mut buf, b.entries.len() as u16);
+        }
+    }
+
+    Some(buf)
+}
+
+// ── Free Space Map Snapshot ────────────────────────────────────────
+
+/// Encode a table's free space map: the map pages, then one entry per
+/// tracked data page.
+///
+/// Format:
+/// ```text
+/// granularity    : u16  (bytes per category step)
+/// map_page_count : u16
+/// map_page_ids   : u32 × map_page_count
+/// entry_count    : u32
+/// for each entry:
+///     page_id    : u32
+///     category   : u8
+///     approx_free: u16  (category × granularity)
+/// ```
+pub fn snapshot_fsm(bpm: &mut BufferPoolManager, root: PageId) -> Vec<u8> {
+    let page_size = bpm.page_size() as usize;
+    let map_pages = fsm::page_ids(bpm, root);
+    let entries = fsm::entries(bpm, root);
+
+    let mut buf = Vec::with_capacity(8 + map_pages.len() * 4 + entries.len() * 7);
+    push_u16(&mut buf, fsm::granularity(page_size) as u16);
+    push_u16(&mut buf, map_pages.len() as u16);
+    for pid in map_pages {
+        push_u32(&mut buf, pid);
+    }
+    push_u32(&mut buf, entries.len() as u32);
+    for (pid, cat) in entries {
+        push_u32(&mut buf, pid);
+        push_u8(&mut buf, cat);
+        push_u16(&mut buf, fsm::approx_free(page_size, cat) as u16);
+    }
+    buf
+}
+
 // ── Table Snapshot ─────────────────────────────────────────────────
 
 /// Encode table metadata for visualization.
@@ -187,7 +451,7 @@ pub fn snapshot_page(bpm: &mut BufferPoolManager, page_id: PageId) -> Option<Vec
 ///     name       : UTF-8 bytes
 ///     type_tag   : u8
 ///     nullable   : u8
-///     max_len    : u16  (for VarChar/Blob, 0 otherwise)
+///     type_param : u16  (VarChar/Blob/Char length, Decimal precision << 8 | scale, else 0)
 /// page_count     : u32
 /// page_ids       : u32 × page_count
 /// ```
@@ -216,12 +480,7 @@ pub fn snapshot_table(
         buf.extend_from_slice(col_name);
         push_u8(&mut buf, col.col_type.type_tag());
         push_u8(&mut buf, col.nullable as u8);
-        let max_len = match &col.col_type {
-            crate::storage::schema::ColumnType::VarChar(n) => *n,
-            crate::storage::schema::ColumnType::Blob(n) => *n,
-            _ => 0,
-        };
-        push_u16(&mut buf, max_len);
+        push_u16(&mut buf, col.col_type.type_param());
     }
 
     // Pages
@@ -1,13 +1,42 @@
 //! Table Heap — manages a table as a linked list of data pages.
 //!
-//! Each table owns a chain of pages.  Insertions go to the last page with
-//! enough free space; if none fits, a new page is allocated and appended.
+//! Each table owns a chain of pages.  Insertions go to a page the table's
+//! free space map says has room; if none does, a new page is allocated and
+//! appended.  Every tuple change reports the page's new free space to the
+//! map.
+//! Every mutation is logged under the caller's transaction, and the table's
+//! B+tree and hash indexes are kept in step with it.
+//!
+//! Updates keep the RowId stable.  A tuple that outgrows its page moves
+//! elsewhere and its home slot becomes a forwarding slot pointing at it;
+//! reads and scans follow the pointer, and scans skip the moved tuple.
 
 use crate::storage::types::*;
+use crate::storage::error::{StorageError, StorageResult};
 use crate::storage::page;
 use crate::storage::schema::*;
 use crate::storage::overflow;
+use crate::storage::fsm;
+use crate::storage::btree::{self, BTreeIndex};
+use crate::storage::hash_index::{self, HashIndex};
 use crate::storage::buffer_pool::BufferPoolManager;
+use crate::storage::wal::LogBody;
+
+/// Summary of a vacuum pass, for display.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VacuumReport {
+    /// Data pages visited.
+    pub pages_scanned: usize,
+    /// Pages whose dead tuple bytes were squeezed out.
+    pub pages_compacted: usize,
+    /// Tombstone slots dropped from the ends of slot arrays.
+    pub slots_trimmed: usize,
+    /// Empty pages unlinked from the chain and returned to the disk.
+    pub pages_freed: usize,
+    /// Growth of the free gap across all visited pages, counting a freed
+    /// page as entirely free.
+    pub bytes_reclaimed: usize,
+}
 
 /// A table stored as a heap (unordered linked list of pages).
 #[derive(Debug, Clone)]
@@ -18,144 +47,317 @@ pub struct TableHeap {
     pub schema: Schema,
     /// First page in the chain.
     pub first_page_id: PageId,
+    /// Last page in the chain, where new pages are linked.
+    pub last_page_id: PageId,
+    /// First page of the free space map.
+    pub fsm_page_id: PageId,
     /// Number of live rows.
     pub row_count: u32,
+    /// B+tree indexes, at most one per column.
+    pub indexes: Vec<BTreeIndex>,
+    /// Extendible hash indexes, at most one per column.
+    pub hash_indexes: Vec<HashIndex>,
     /// Overflow threshold (bytes).
     overflow_threshold: u32,
 }
 
 impl TableHeap {
-    /// Create a new table, allocating its first page.
+    /// Create a new table, allocating its first page on behalf of `txn`.
     pub fn create(
         name: String,
         schema: Schema,
         overflow_threshold: u32,
         bpm: &mut BufferPoolManager,
-    ) -> Option<Self> {
-        let (page_id, _frame_id) = bpm.new_page()?;
+        txn: TxnId,
+    ) -> StorageResult<Self> {
+        let (page_id, frame_id) = bpm.new_page_logged(txn, PageType::Data)?;
+        let free = page::total_free_space(bpm.frame_data(frame_id));
         bpm.unpin_page(page_id, true);
+        let fsm_page_id = fsm::create(bpm, txn).ok_or_else(|| out_of_space(bpm))?;
+        if !fsm::add_page(bpm, txn, fsm_page_id, page_id, free) {
+            return Err(out_of_space(bpm));
+        }
 
-        Some(Self {
+        Ok(Self {
             name,
             schema,
             first_page_id: page_id,
+            last_page_id: page_id,
+            fsm_page_id,
             row_count: 0,
+            indexes: Vec::new(),
+            hash_indexes: Vec::new(),
             overflow_threshold,
         })
     }
 
-    /// Insert a row.  Returns the RowId on success.
+    /// Reopen a table whose pages already exist (loaded from the catalog).
+    /// Row count, last page and free space map are recomputed from the
+    /// pages; indexes are attached by the caller.
+    pub fn open(
+        name: String,
+        schema: Schema,
+        overflow_threshold: u32,
+        first_page_id: PageId,
+        fsm_page_id: PageId,
+        bpm: &mut BufferPoolManager,
+    ) -> Self {
+        let mut table = Self {
+            name,
+            schema,
+            first_page_id,
+            last_page_id: first_page_id,
+            fsm_page_id,
+            row_count: 0,
+            indexes: Vec::new(),
+            hash_indexes: Vec::new(),
+            overflow_threshold,
+        };
+        table.recount(bpm);
+        table
+    }
+
+    /// Insert a row on behalf of `txn`.  Returns the RowId on success.
+    ///
+    /// On failure, overflow or data pages allocated so far stay allocated
+    /// until `txn` is rolled back.
     pub fn insert(
         &mut self,
         bpm: &mut BufferPoolManager,
+        txn: TxnId,
+        values: &[Value],
+    ) -> StorageResult<RowId> {
+        let encoded = self.encode(bpm, txn, values)?;
+        let row_id = self.place_tuple(bpm, txn, encoded, 0)?;
+        for index in &self.indexes {
+            let Some(key) = encode_key(&values[index.column], index.key_size) else { continue };
+            if !btree::insert(bpm, txn, index.root_page_id, &key, row_id)? {
+                return Err(out_of_space(bpm));
+            }
+        }
+        for index in &self.hash_indexes {
+            let Some(key) = encode_key(&values[index.column], index.key_size) else { continue };
+            if !hash_index::insert(bpm, txn, index.directory_page_id, &key, row_id)? {
+                return Err(out_of_space(bpm));
+            }
+        }
+        self.row_count += 1;
+        Ok(row_id)
+    }
+
+    /// Encode a row, writing its large values to fresh overflow chains.
+    fn encode(
+        &self,
+        bpm: &mut BufferPoolManager,
+        txn: TxnId,
         values: &[Value],
-    ) -> Option<RowId> {
+    ) -> StorageResult<Vec<u8>> {
         // Encode the tuple, handling overflow for large values
         let (mut encoded, overflows) =
-            encode_tuple_with_overflow(&self.schema, values, self.overflow_threshold);
+            encode_tuple_with_overflow(&self.schema, values, self.overflow_threshold)?;
 
         // Write overflow pages and patch pointers
         for (col_idx, data) in &overflows {
-            let ptr = overflow::write_overflow(bpm, data)?;
+            let ptr = overflow::write_overflow(bpm, txn, data)?;
             patch_overflow_pointer(&self.schema, &mut encoded, *col_idx, &ptr);
         }
+        Ok(encoded)
+    }
 
-        // Find a page with enough space
-        let mut current_page_id = self.first_page_id;
-        let mut prev_page_id = INVALID_PAGE;
+    /// Put an encoded tuple on a page the free space map says has room,
+    /// appending a page if none has any.  `flags` are the new slot's flags.
+    fn place_tuple(
+        &mut self,
+        bpm: &mut BufferPoolManager,
+        txn: TxnId,
+        encoded: Vec<u8>,
+        flags: u16,
+    ) -> StorageResult<RowId> {
+        let needed = encoded.len() + SLOT_SIZE; // may need a new slot
 
-        loop {
-            let frame_id = bpm.fetch_page(current_page_id)?;
-            let free = page::free_space(&bpm.frames[frame_id as usize].data);
-            let needed = encoded.len() + SLOT_SIZE; // may need a new slot
-
-            if free >= needed {
-                // Insert here
-                let slot_id = page::insert_tuple(
-                    &mut bpm.frames[frame_id as usize].data,
-                    &encoded,
-                ).expect("free_space check passed but insert failed");
-                bpm.unpin_page(current_page_id, true);
-                self.row_count += 1;
-                return Some(RowId { page_id: current_page_id, slot_id });
+        // A stale entry is corrected on the spot, so the search moves on
+        while let Some(page_id) = fsm::find(bpm, self.fsm_page_id, needed) {
+            let frame_id = bpm.fetch_page(page_id)?;
+            let data = &mut bpm.frames[frame_id as usize].data;
+            let free = match page::page_type(data) {
+                PageType::Data => page::total_free_space(data),
+                _ => 0,
+            };
+            if free < needed {
+                bpm.unpin_page(page_id, false);
+                fsm::record(bpm, self.fsm_page_id, page_id, free);
+                continue;
             }
 
-            let next = page::next_page(&bpm.frames[frame_id as usize].data);
-            bpm.unpin_page(current_page_id, false);
-
-            if next == INVALID_PAGE {
-                prev_page_id = current_page_id;
-                break;
+            if page::free_space(data) < needed {
+                page::compact(data);
             }
-            current_page_id = next;
+            let slot_id = page::insert_tuple(data, &encoded)
+                .expect("free_space check passed but insert failed");
+            page::set_tuple_flags(data, slot_id, flags);
+            let free = page::total_free_space(data);
+            let row_id = RowId { page_id, slot_id };
+            bpm.log_update(txn, frame_id, LogBody::InsertTuple { rid: row_id, flags, data: encoded });
+            bpm.unpin_page(page_id, true);
+            fsm::record(bpm, self.fsm_page_id, page_id, free);
+            return Ok(row_id);
         }
 
-        // No page had space — allocate a new one
-        let (new_page_id, new_frame_id) = bpm.new_page()?;
-
-        // Link from previous last page
-        if prev_page_id != INVALID_PAGE {
-            let prev_fid = bpm.fetch_page(prev_page_id)?;
-            page::set_next_page(&mut bpm.frames[prev_fid as usize].data, new_page_id);
-            bpm.unpin_page(prev_page_id, true);
+        // No page has room — allocate a new one and link it from the last page
+        let (new_page_id, new_frame_id) = bpm.new_page_logged(txn, PageType::Data)?;
+        if let Err(e) = bpm.set_next_page_logged(txn, self.last_page_id, new_page_id) {
+            bpm.unpin_page(new_page_id, true);
+            return Err(e);
         }
+        self.last_page_id = new_page_id;
 
-        let slot_id = page::insert_tuple(
-            &mut bpm.frames[new_frame_id as usize].data,
-            &encoded,
-        )?;
+        let data = &mut bpm.frames[new_frame_id as usize].data;
+        let slot_id = match page::insert_tuple(data, &encoded) {
+            Ok(slot_id) => slot_id,
+            Err(e) => {
+                bpm.unpin_page(new_page_id, true);
+                return Err(e);
+            }
+        };
+        page::set_tuple_flags(data, slot_id, flags);
+        let free = page::total_free_space(data);
+        let row_id = RowId { page_id: new_page_id, slot_id };
+        bpm.log_update(
+            txn,
+            new_frame_id,
+            LogBody::InsertTuple { rid: row_id, flags, data: encoded },
+        );
         bpm.unpin_page(new_page_id, true);
-        self.row_count += 1;
+        if !fsm::add_page(bpm, txn, self.fsm_page_id, new_page_id, free) {
+            return Err(out_of_space(bpm));
+        }
 
-        Some(RowId { page_id: new_page_id, slot_id })
+        Ok(row_id)
     }
 
-    /// Delete a row by RowId.
+    /// Delete a row by RowId on behalf of `txn`.
+    ///
+    /// The row's overflow chains are *not* freed here: an abort must be able
+    /// to restore the tuple with its original pointers.  They are returned so
+    /// the caller can free them once `txn` commits.  Fails with
+    /// `RowNotFound` if the row doesn't exist.
     pub fn delete(
         &mut self,
         bpm: &mut BufferPoolManager,
+        txn: TxnId,
         row_id: RowId,
-    ) -> bool {
-        let Some(frame_id) = bpm.fetch_page(row_id.page_id) else {
-            return false;
-        };
+    ) -> StorageResult<Vec<OverflowPointer>> {
+        let (at, tuple_data) = self.locate(bpm, row_id)?;
+        if at != row_id {
+            self.delete_slot(bpm, txn, at)?;
+        }
+        self.delete_slot(bpm, txn, row_id)?;
+        let pointers = tuple_overflow_pointers(&self.schema, &tuple_data)
+            .into_iter()
+            .map(|(_, ptr)| ptr)
+            .collect();
 
-        // Read the tuple first to find overflow pointers to clean up
-        if let Some(tuple_data) = page::get_tuple(&bpm.frames[frame_id as usize].data, row_id.slot_id) {
-            let decoded = decode_tuple(&self.schema, tuple_data);
-            // Clean up overflows
-            for (i, val) in decoded.iter().enumerate() {
-                if is_overflow_placeholder(val, &self.schema.columns[i]) {
-                    if let Value::Blob(ptr_bytes) = val {
-                        let ptr = OverflowPointer::decode(ptr_bytes);
-                        bpm.unpin_page(row_id.page_id, false);
-                        overflow::delete_overflow(bpm, &ptr);
-                        // Re-fetch the frame
-                        let refetched = bpm.fetch_page(row_id.page_id);
-                        if refetched.is_none() { return false; }
-                    }
-                }
+        if !self.indexes.is_empty() || !self.hash_indexes.is_empty() {
+            let mut values = decode_tuple(&self.schema, &tuple_data);
+            self.resolve_overflows(bpm, &tuple_data, &mut values)?;
+            for index in &self.indexes {
+                let Some(key) = encode_key(&values[index.column], index.key_size) else {
+                    continue;
+                };
+                btree::delete(bpm, txn, index.root_page_id, &key, row_id)?;
+            }
+            for index in &self.hash_indexes {
+                let Some(key) = encode_key(&values[index.column], index.key_size) else {
+                    continue;
+                };
+                hash_index::delete(bpm, txn, index.directory_page_id, &key, row_id)?;
             }
         }
 
-        // Ensure we have the frame
-        let frame_id = match bpm.page_to_frame(row_id.page_id) {
-            Some(fid) => fid,
-            None => {
-                let fid = bpm.fetch_page(row_id.page_id);
-                match fid {
-                    Some(f) => f,
-                    None => return false,
-                }
+        self.row_count -= 1;
+        Ok(pointers)
+    }
+
+    /// Replace a row's values on behalf of `txn`, keeping its RowId.
+    ///
+    /// The tuple is rewritten in its slot when its page can hold the new
+    /// encoding; otherwise it moves to a page with room and the home slot
+    /// forwards to it.  Large values get fresh overflow chains; the old
+    /// chains are returned for the caller to free once `txn` commits, as
+    /// with `delete`.  Fails if the row doesn't exist or no page could take
+    /// the tuple, in which case `txn` must be rolled back.
+    pub fn update(
+        &mut self,
+        bpm: &mut BufferPoolManager,
+        txn: TxnId,
+        row_id: RowId,
+        values: &[Value],
+    ) -> StorageResult<Vec<OverflowPointer>> {
+        let (at, old_data) = self.locate(bpm, row_id)?;
+        let moved = at != row_id;
+        let encoded = self.encode(bpm, txn, values)?;
+
+        if self.rewrite_slot(bpm, txn, row_id, 0, &encoded) {
+            // Fits at home again — drop the copy it had moved to
+            if moved {
+                self.delete_slot(bpm, txn, at)?;
             }
-        };
+        } else if !(moved && self.rewrite_slot(bpm, txn, at, page::TUPLE_MOVED, &encoded)) {
+            let target = self.place_tuple(bpm, txn, encoded, page::TUPLE_MOVED)?;
+            let forward = page::encode_forward(target);
+            if !self.rewrite_slot(bpm, txn, row_id, page::TUPLE_FORWARD, &forward) {
+                return Err(bpm.take_error_or(StorageError::PageFull {
+                    page_id: row_id.page_id,
+                    needed: forward.len(),
+                    free: 0,
+                }));
+            }
+            if moved {
+                self.delete_slot(bpm, txn, at)?;
+            }
+        }
 
-        let ok = page::delete_tuple(&mut bpm.frames[frame_id as usize].data, row_id.slot_id);
-        bpm.unpin_page(row_id.page_id, ok);
-        if ok {
-            self.row_count -= 1;
+        if !self.indexes.is_empty() || !self.hash_indexes.is_empty() {
+            let mut old = decode_tuple(&self.schema, &old_data);
+            self.resolve_overflows(bpm, &old_data, &mut old)?;
+            for index in &self.indexes {
+                let old_key = encode_key(&old[index.column], index.key_size);
+                let new_key = encode_key(&values[index.column], index.key_size);
+                if old_key == new_key {
+                    continue;
+                }
+                if let Some(key) = old_key {
+                    btree::delete(bpm, txn, index.root_page_id, &key, row_id)?;
+                }
+                if let Some(key) = new_key
+                    && !btree::insert(bpm, txn, index.root_page_id, &key, row_id)?
+                {
+                    return Err(out_of_space(bpm));
+                }
+            }
+            for index in &self.hash_indexes {
+                let old_key = encode_key(&old[index.column], index.key_size);
+                let new_key = encode_key(&values[index.column], index.key_size);
+                if old_key == new_key {
+                    continue;
+                }
+                if let Some(key) = old_key {
+                    hash_index::delete(bpm, txn, index.directory_page_id, &key, row_id)?;
+                }
+                if let Some(key) = new_key
+                    && !hash_index::insert(bpm, txn, index.directory_page_id, &key, row_id)?
+                {
+                    return Err(out_of_space(bpm));
+                }
+            }
         }
-        ok
+
+        Ok(
+            tuple_overflow_pointers(&self.schema, &old_data)
+                .into_iter()
+                .map(|(_, ptr)| ptr)
+                .collect(),
+        )
     }
 
     /// Get a single row by RowId.
@@ -163,35 +365,16 @@ impl TableHeap {
         &self,
         bpm: &mut BufferPoolManager,
         row_id: RowId,
-    ) -> Option<Vec<Value>> {
-        let frame_id = bpm.fetch_page(row_id.page_id)?;
-        let tuple_data = page::get_tuple(&bpm.frames[frame_id as usize].data, row_id.slot_id)?;
-        let mut values = decode_tuple(&self.schema, tuple_data);
-        bpm.unpin_page(row_id.page_id, false);
-
-        // Resolve overflow pointers
-        for (i, val) in values.iter_mut().enumerate() {
-            if is_overflow_placeholder(val, &self.schema.columns[i]) {
-                if let Value::Blob(ptr_bytes) = val {
-                    let ptr = OverflowPointer::decode(ptr_bytes);
-                    if let Some(data) = overflow::read_overflow(bpm, &ptr) {
-                        match &self.schema.columns[i].col_type {
-                            ColumnType::VarChar(_) => {
-                                *val = Value::VarChar(
-                                    String::from_utf8_lossy(&data).into_owned()
-                                );
-                            }
-                            ColumnType::Blob(_) => {
-                                *val = Value::Blob(data);
-                            }
-                            _ => {}
-                        }
-                    }
-                }
-            }
-        }
+    ) -> StorageResult<Vec<Value>> {
+        let (_, tuple_data) = self.locate(bpm, row_id)?;
+        let mut values = decode_tuple(&self.schema, &tuple_data);
+        self.resolve_overflows(bpm, &tuple_data, &mut values)?;
+        Ok(values)
+    }
 
-        Some(values)
+    /// Whether `row_id` names a live row.
+    pub fn contains(&self, bpm: &mut BufferPoolManager, row_id: RowId) -> bool {
+        self.locate(bpm, row_id).is_ok()
     }
 
     /// Sequential scan — returns all live rows with their RowIds.
@@ -199,77 +382,443 @@ impl TableHeap {
         &self,
         bpm: &mut BufferPoolManager,
     ) -> Vec<(RowId, Vec<Value>)> {
-        let mut results = Vec::new();
-        let mut current_page_id = self.first_page_id;
+        self.iter(bpm).collect()
+    }
 
-        while current_page_id != INVALID_PAGE {
-            let Some(frame_id) = bpm.fetch_page(current_page_id) else { break };
-            let sc = page::slot_count(&bpm.frames[frame_id as usize].data);
+    /// Stream the table's rows in page order (see `TableIterator`).
+    pub fn iter<'a>(&'a self, bpm: &'a mut BufferPoolManager) -> TableIterator<'a> {
+        self.iter_from(bpm, RowId { page_id: self.first_page_id, slot_id: 0 })
+    }
 
-            for slot_id in 0..sc {
-                if let Some(tuple_data) =
-                    page::get_tuple(&bpm.frames[frame_id as usize].data, slot_id)
-                {
-                    let values = decode_tuple(&self.schema, tuple_data);
-                    let row_id = RowId { page_id: current_page_id, slot_id };
-                    results.push((row_id, values));
-                }
+    /// Stream rows starting at `start` (inclusive), which must be on one of
+    /// this table's pages.  Used to resume a paginated scan.
+    pub fn iter_from<'a>(
+        &'a self,
+        bpm: &'a mut BufferPoolManager,
+        start: RowId,
+    ) -> TableIterator<'a> {
+        TableIterator {
+            table: self,
+            bpm,
+            page_id: start.page_id,
+            frame_id: None,
+            slot_id: start.slot_id,
+        }
+    }
+
+    /// Build a B+tree index on `column` on behalf of `txn`, indexing every
+    /// existing row.  NULLs are not indexed.  Returns the index's root page.
+    /// Fails with `KeyTooLarge` if three keys don't fit in a node.
+    pub fn create_index(
+        &mut self,
+        bpm: &mut BufferPoolManager,
+        txn: TxnId,
+        column: usize,
+    ) -> StorageResult<PageId> {
+        let key_size = self.index_key_size(column)?;
+        let page_size = bpm.page_size() as usize;
+        let fits = |k| btree::capacity(page_size, k, false) >= 3;
+        if !fits(key_size) {
+            let max = (0..key_size).rev().find(|&k| fits(k)).unwrap_or(0);
+            return Err(StorageError::KeyTooLarge { size: key_size, max });
+        }
+        let root = btree::create(bpm, txn, key_size).ok_or_else(|| out_of_space(bpm))?;
+        for (row_id, values) in self.scan(bpm) {
+            let Some(key) = encode_key(&values[column], key_size) else { continue };
+            if !btree::insert(bpm, txn, root, &key, row_id)? {
+                return Err(out_of_space(bpm));
             }
+        }
+        self.indexes.push(BTreeIndex { column, root_page_id: root, key_size });
+        Ok(root)
+    }
 
-            let next = page::next_page(&bpm.frames[frame_id as usize].data);
-            bpm.unpin_page(current_page_id, false);
-            current_page_id = next;
-        }
-
-        // Resolve overflow pointers in results
-        for (_row_id, values) in results.iter_mut() {
-            for (i, val) in values.iter_mut().enumerate() {
-                if is_overflow_placeholder(val, &self.schema.columns[i]) {
-                    if let Value::Blob(ptr_bytes) = val {
-                        let ptr = OverflowPointer::decode(ptr_bytes);
-                        if let Some(data) = overflow::read_overflow(bpm, &ptr) {
-                            match &self.schema.columns[i].col_type {
-                                ColumnType::VarChar(_) => {
-                                    *val = Value::VarChar(
-                                        String::from_utf8_lossy(&data).into_owned()
-                                    );
-                                }
-                                ColumnType::Blob(_) => {
-                                    *val = Value::Blob(data);
-                                }
-                                _ => {}
-                            }
-                        }
-                    }
-                }
+    /// The index on `column`, if there is one.
+    pub fn index_on(&self, column: usize) -> Option<&BTreeIndex> {
+        self.indexes.iter().find(|i| i.column == column)
+    }
+
+    /// Build an extendible hash index on `column` on behalf of `txn`,
+    /// indexing every existing row.  NULLs are not indexed.  Returns the
+    /// directory page.  Fails with `KeyTooLarge` if two keys don't fit in a
+    /// bucket.
+    pub fn create_hash_index(
+        &mut self,
+        bpm: &mut BufferPoolManager,
+        txn: TxnId,
+        column: usize,
+    ) -> StorageResult<PageId> {
+        let key_size = self.index_key_size(column)?;
+        let page_size = bpm.page_size() as usize;
+        let fits = |k| hash_index::bucket_capacity(page_size, k) >= 2;
+        if !fits(key_size) {
+            let max = (0..key_size).rev().find(|&k| fits(k)).unwrap_or(0);
+            return Err(StorageError::KeyTooLarge { size: key_size, max });
+        }
+        let dir = hash_index::create(bpm, txn, key_size).ok_or_else(|| out_of_space(bpm))?;
+        for (row_id, values) in self.scan(bpm) {
+            let Some(key) = encode_key(&values[column], key_size) else { continue };
+            if !hash_index::insert(bpm, txn, dir, &key, row_id)? {
+                return Err(out_of_space(bpm));
             }
         }
+        self.hash_indexes.push(HashIndex { column, directory_page_id: dir, key_size });
+        Ok(dir)
+    }
 
-        results
+    /// The hash index on `column`, if there is one.
+    pub fn hash_index_on(&self, column: usize) -> Option<&HashIndex> {
+        self.hash_indexes.iter().find(|i| i.column == column)
     }
 
-    /// Get the list of page IDs owned by this table.
-    pub fn page_ids(&self, bpm: &mut BufferPoolManager) -> Vec<PageId> {
+    /// Get the list of page IDs owned by this table.  Fails if a page in
+    /// the chain can't be read, rather than return the chain cut short.
+    pub fn page_ids(&self, bpm: &mut BufferPoolManager) -> StorageResult<Vec<PageId>> {
         let mut ids = Vec::new();
         let mut current = self.first_page_id;
         while current != INVALID_PAGE {
             ids.push(current);
-            if let Some(fid) = bpm.fetch_page(current) {
-                let next = page::next_page(&bpm.frames[fid as usize].data);
-                bpm.unpin_page(current, false);
-                current = next;
+            let fid = bpm.fetch_page(current)?;
+            let next = page::next_page(&bpm.frames[fid as usize].data);
+            bpm.unpin_page(current, false);
+            current = next;
+        }
+        Ok(ids)
+    }
+
+    /// Whether `page_id` is one of this table's heap pages.  Asks the free
+    /// space map, which tracks every page in the chain, instead of walking
+    /// the chain itself.
+    pub fn owns_page(&self, bpm: &mut BufferPoolManager, page_id: PageId) -> StorageResult<bool> {
+        fsm::contains(bpm, self.fsm_page_id, page_id)
+    }
+
+    /// Reclaim dead space on behalf of `txn`: compact fragmented pages,
+    /// trim trailing tombstone slots, and unlink pages left with no slots
+    /// at all (except the first) and free them.
+    ///
+    /// Compaction and trimming move bytes but not rows.  Later records
+    /// still depend on where they left the bytes and how many slots there
+    /// are, so each page they touch is logged whole (`PageImage`) for redo
+    /// to copy back.  Unlinking and freeing are logged as usual.
+    ///
+    /// Tombstones are only safe to drop once no running transaction could
+    /// undo the delete that left them, so callers must not vacuum while
+    /// other transactions are active.
+    pub fn vacuum(&mut self, bpm: &mut BufferPoolManager, txn: TxnId) -> VacuumReport {
+        let mut report = VacuumReport::default();
+        let usable = bpm.page_size() as usize - PAGE_HEADER_SIZE;
+        let mut prev = INVALID_PAGE;
+        let mut current = self.first_page_id;
+
+        while current != INVALID_PAGE {
+            let Ok(frame_id) = bpm.fetch_page(current) else { break };
+            report.pages_scanned += 1;
+            let data = &mut bpm.frames[frame_id as usize].data;
+            let next = page::next_page(data);
+            let before = page::free_space(data);
+
+            let trimmed = page::trim_slots(data) as usize;
+            report.slots_trimmed += trimmed;
+            let compact = page::free_space(data) < page::total_free_space(data);
+            if compact {
+                page::compact(data);
+                report.pages_compacted += 1;
+            }
+            let after = page::free_space(data);
+            let empty = page::slot_count(data) == 0;
+            let changed = trimmed > 0 || compact;
+            if changed {
+                let image = data.clone();
+                bpm.log_update(txn, frame_id, LogBody::PageImage { page_id: current, image });
+            }
+            bpm.unpin_page(current, changed);
+
+            if empty && prev != INVALID_PAGE && bpm.set_next_page_logged(txn, prev, next).is_ok() {
+                if bpm.free_page_logged(txn, current) {
+                    report.pages_freed += 1;
+                    report.bytes_reclaimed += usable - before;
+                }
+            } else {
+                report.bytes_reclaimed += after - before;
+                prev = current;
+            }
+            current = next;
+        }
+
+        self.recount(bpm);
+        report
+    }
+
+    /// Recompute `row_count`, the last page and the free space map from the
+    /// pages (after recovery or rollback has changed them behind the heap's
+    /// back).  Leaves them alone if the chain can't be read; the error
+    /// stays in `bpm` for the caller to report.
+    pub fn recount(&mut self, bpm: &mut BufferPoolManager) {
+        let Ok(page_ids) = self.page_ids(bpm) else { return };
+        let mut count = 0;
+        let mut free = Vec::new();
+        for pid in page_ids {
+            let Ok(fid) = bpm.fetch_page(pid) else { continue };
+            let data = &bpm.frames[fid as usize].data;
+            count += (0..page::slot_count(data))
+                .filter(|&s| {
+                    page::read_slot(data, s).1 != 0
+                        && page::tuple_flags(data, s) & page::TUPLE_MOVED == 0
+                })
+                .count() as u32;
+            free.push((pid, page::total_free_space(data)));
+            bpm.unpin_page(pid, false);
+        }
+        self.row_count = count;
+        if let Some(&(last, _)) = free.last() {
+            self.last_page_id = last;
+        }
+        fsm::rebuild(bpm, self.fsm_page_id, &free);
+    }
+
+    // ── Internal ───────────────────────────────────────────────────
+
+    /// Key width for an index on `column`.
+    fn index_key_size(&self, column: usize) -> StorageResult<usize> {
+        let col = self.schema.columns.get(column)
+            .ok_or_else(|| StorageError::ColumnNotFound(format!("#{}", column)))?;
+        Ok(key_size(&col.col_type))
+    }
+
+    /// Follow `row_id` to its tuple: the slot holding it (`row_id` itself
+    /// unless forwarded) and its bytes.  `RowNotFound` for empty slots and
+    /// for moved tuples, which are only addressable through their home slot.
+    fn locate(&self, bpm: &mut BufferPoolManager, row_id: RowId) -> StorageResult<(RowId, Vec<u8>)> {
+        let (flags, data) = read_slot(bpm, row_id)?;
+        if flags & page::TUPLE_MOVED != 0 {
+            return Err(StorageError::RowNotFound(row_id));
+        }
+        if flags & page::TUPLE_FORWARD != 0 {
+            let target = page::decode_forward(&data);
+            return Ok((target, read_slot(bpm, target)?.1));
+        }
+        Ok((row_id, data))
+    }
+
+    /// Tombstone the slot at `rid`, logging its old contents.  Fails with
+    /// `RowNotFound` if the slot is already empty.
+    fn delete_slot(&self, bpm: &mut BufferPoolManager, txn: TxnId, rid: RowId) -> StorageResult<()> {
+        let frame_id = bpm.fetch_page(rid.page_id)?;
+        let data = &mut bpm.frames[frame_id as usize].data;
+        let flags = page::tuple_flags(data, rid.slot_id);
+        let Some(old) = page::get_tuple(data, rid.slot_id).map(|t| t.to_vec()) else {
+            bpm.unpin_page(rid.page_id, false);
+            return Err(StorageError::RowNotFound(rid));
+        };
+        page::delete_tuple(data, rid.slot_id).expect("slot was just read");
+        let free = page::total_free_space(data);
+        bpm.log_update(txn, frame_id, LogBody::DeleteTuple { rid, flags, data: old });
+        bpm.unpin_page(rid.page_id, true);
+        fsm::record(bpm, self.fsm_page_id, rid.page_id, free);
+        Ok(())
+    }
+
+    /// Replace the contents of the live slot at `rid`, logging before and
+    /// after.  Returns `false` (nothing changed) if its page can't hold it.
+    fn rewrite_slot(
+        &self,
+        bpm: &mut BufferPoolManager,
+        txn: TxnId,
+        rid: RowId,
+        flags: u16,
+        tuple_data: &[u8],
+    ) -> bool {
+        let Ok(frame_id) = bpm.fetch_page(rid.page_id) else { return false };
+        let data = &mut bpm.frames[frame_id as usize].data;
+        let before_flags = page::tuple_flags(data, rid.slot_id);
+        let Some(before) = page::get_tuple(data, rid.slot_id).map(|t| t.to_vec()) else {
+            bpm.unpin_page(rid.page_id, false);
+            return false;
+        };
+        if page::update_tuple(data, rid.slot_id, flags, tuple_data).is_err() {
+            bpm.unpin_page(rid.page_id, false);
+            return false;
+        }
+        let free = page::total_free_space(data);
+        bpm.log_update(
+            txn,
+            frame_id,
+            LogBody::UpdateTuple {
+                rid,
+                before_flags,
+                before,
+                after_flags: flags,
+                after: tuple_data.to_vec(),
+            },
+        );
+        bpm.unpin_page(rid.page_id, true);
+        fsm::record(bpm, self.fsm_page_id, rid.page_id, free);
+        true
+    }
+
+    /// Replace overflow placeholders in `values` with the real data.
+    fn resolve_overflows(
+        &self,
+        bpm: &mut BufferPoolManager,
+        tuple_data: &[u8],
+        values: &mut [Value],
+    ) -> StorageResult<()> {
+        for (i, ptr) in tuple_overflow_pointers(&self.schema, tuple_data) {
+            let data = overflow::read_overflow(bpm, &ptr)?;
+            match &self.schema.columns[i].col_type {
+                ColumnType::VarChar(_) => {
+                    values[i] = Value::VarChar(String::from_utf8_lossy(&data).into_owned());
+                }
+                ColumnType::Blob(_) => {
+                    values[i] = Value::Blob(data);
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+// ── Iterator ───────────────────────────────────────────────────────
+
+/// A lazy scan over a table's rows.
+///
+/// Holds at most one page pinned: the page it is walking.  That page is
+/// released before following a forwarding slot or reading a row's overflow
+/// values, which are only loaded when the row is yielded, and fetched again
+/// for the next row.
+pub struct TableIterator<'a> {
+    table: &'a TableHeap,
+    bpm: &'a mut BufferPoolManager,
+    /// Page being walked; `INVALID_PAGE` once exhausted.
+    page_id: PageId,
+    /// Frame of `page_id` while it is pinned.
+    frame_id: Option<FrameId>,
+    /// Next slot to look at.
+    slot_id: SlotId,
+}
+
+impl TableIterator<'_> {
+    /// Where the next call to `next` starts looking, or `None` at the end.
+    pub fn position(&self) -> Option<RowId> {
+        (self.page_id != INVALID_PAGE)
+            .then_some(RowId { page_id: self.page_id, slot_id: self.slot_id })
+    }
+
+    /// The next row on the current page.  At the end of the page, move on
+    /// to the following one and return `None`, so a caller can take the
+    /// table a page at a time.
+    pub fn next_in_page(&mut self) -> Option<(RowId, Vec<Value>)> {
+        self.advance(true)
+    }
+
+    fn release(&mut self) {
+        if self.frame_id.take().is_some() {
+            self.bpm.unpin_page(self.page_id, false);
+        }
+    }
+
+    fn advance(&mut self, stop_at_page_end: bool) -> Option<(RowId, Vec<Value>)> {
+        loop {
+            if self.page_id == INVALID_PAGE {
+                return None;
+            }
+            let frame_id = match self.frame_id {
+                Some(fid) => fid,
+                None => {
+                    let Ok(fid) = self.bpm.fetch_page(self.page_id) else {
+                        self.page_id = INVALID_PAGE;
+                        return None;
+                    };
+                    self.frame_id = Some(fid);
+                    fid
+                }
+            };
+
+            let data = self.bpm.frame_data(frame_id);
+            if self.slot_id >= page::slot_count(data) {
+                let next = page::next_page(data);
+                self.release();
+                self.page_id = next;
+                self.slot_id = 0;
+                if stop_at_page_end {
+                    return None;
+                }
+                continue;
+            }
+
+            let row_id = RowId { page_id: self.page_id, slot_id: self.slot_id };
+            self.slot_id += 1;
+            let flags = page::tuple_flags(data, row_id.slot_id);
+            if flags & page::TUPLE_MOVED != 0 {
+                continue;
+            }
+            let Some(slot_data) = page::get_tuple(data, row_id.slot_id).map(|t| t.to_vec())
+            else {
+                continue;
+            };
+
+            let tuple_data = if flags & page::TUPLE_FORWARD != 0 {
+                self.release();
+                let Ok((_, moved)) = read_slot(self.bpm, page::decode_forward(&slot_data))
+                else {
+                    continue;
+                };
+                moved
             } else {
-                break;
+                slot_data
+            };
+
+            let mut values = decode_tuple(&self.table.schema, &tuple_data);
+            if !tuple_overflow_pointers(&self.table.schema, &tuple_data).is_empty() {
+                self.release();
+                // A chain that can't be read leaves its placeholder; the
+                // reason stays in the pool's last error
+                let _ = self.table.resolve_overflows(self.bpm, &tuple_data, &mut values);
             }
+            return Some((row_id, values));
         }
-        ids
     }
 }
 
+impl Iterator for TableIterator<'_> {
+    type Item = (RowId, Vec<Value>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.advance(false)
+    }
+}
+
+impl Drop for TableIterator<'_> {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+/// Flags and bytes of the slot at `rid`; `RowNotFound` if it's empty.
+fn read_slot(bpm: &mut BufferPoolManager, rid: RowId) -> StorageResult<(u16, Vec<u8>)> {
+    let frame_id = bpm.fetch_page(rid.page_id)?;
+    let data = &bpm.frames[frame_id as usize].data;
+    let slot = page::get_tuple(data, rid.slot_id)
+        .map(|t| (page::tuple_flags(data, rid.slot_id), t.to_vec()));
+    bpm.unpin_page(rid.page_id, false);
+    slot.ok_or(StorageError::RowNotFound(rid))
+}
+
+/// Error for a step that could only report failure: the pool's reason if
+/// it recorded one, otherwise the disk ran out of pages.
+fn out_of_space(bpm: &mut BufferPoolManager) -> StorageError {
+    bpm.take_error_or(StorageError::DiskFull)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::storage::disk::DiskManager;
+    use crate::storage::fault::{FaultKind, Trigger};
+
+    const TXN: TxnId = 1;
 
     fn make_bpm(page_size: u32) -> BufferPoolManager {
         let dm = DiskManager::new(page_size, 64);
@@ -288,10 +837,10 @@ mod tests {
     fn create_and_insert() {
         let mut bpm = make_bpm(128);
         let mut table = TableHeap::create(
-            "users".into(), test_schema(), 64, &mut bpm
+            "users".into(), test_schema(), 64, &mut bpm, TXN
         ).unwrap();
 
-        let row_id = table.insert(&mut bpm, &[
+        let row_id = table.insert(&mut bpm, TXN, &[
             Value::Int32(1),
             Value::VarChar("Alice".into()),
             Value::Bool(true),
@@ -306,10 +855,10 @@ mod tests {
     fn insert_and_get() {
         let mut bpm = make_bpm(128);
         let mut table = TableHeap::create(
-            "users".into(), test_schema(), 64, &mut bpm
+            "users".into(), test_schema(), 64, &mut bpm, TXN
         ).unwrap();
 
-        let row_id = table.insert(&mut bpm, &[
+        let row_id = table.insert(&mut bpm, TXN, &[
             Value::Int32(42),
             Value::VarChar("Bob".into()),
             Value::Bool(false),
@@ -328,32 +877,60 @@ mod tests {
         let schema = Schema::new(vec![
             Column { name: "id".into(), col_type: ColumnType::Int32, nullable: false },
         ]);
-        let mut table = TableHeap::create("nums".into(), schema, 32, &mut bpm).unwrap();
+        let mut table = TableHeap::create("nums".into(), schema, 32, &mut bpm, TXN).unwrap();
 
         // Each tuple: 1 byte null bitmap + 4 bytes int = 5 bytes
-        // Page: 64 - 16 header = 48 usable. Each insert: 5 bytes data + 4 bytes slot = 9.
-        // First page fits ~5 tuples. Insert 20 to force multiple pages.
+        // Page: 64 - 24 header = 40 usable. Each insert: 5 bytes data + 4 bytes slot = 9.
+        // First page fits ~4 tuples. Insert 20 to force multiple pages.
         let mut row_ids = Vec::new();
         for i in 0..20 {
-            let rid = table.insert(&mut bpm, &[Value::Int32(i)]).unwrap();
+            let rid = table.insert(&mut bpm, TXN, &[Value::Int32(i)]).unwrap();
             row_ids.push(rid);
         }
         assert_eq!(table.row_count, 20);
 
         // Verify we used multiple pages
-        let pages = table.page_ids(&mut bpm);
+        let pages = table.page_ids(&mut bpm).unwrap();
         assert!(pages.len() > 1, "Expected multiple pages, got {}", pages.len());
     }
 
+    #[test]
+    fn insert_reuses_space_found_in_fsm() {
+        let mut bpm = make_bpm(64);
+        let schema = Schema::new(vec![
+            Column { name: "id".into(), col_type: ColumnType::Int32, nullable: false },
+        ]);
+        let mut table = TableHeap::create("nums".into(), schema, 32, &mut bpm, TXN).unwrap();
+        let rids: Vec<RowId> = (0..20)
+            .map(|i| table.insert(&mut bpm, TXN, &[Value::Int32(i)]).unwrap())
+            .collect();
+        let first = table.first_page_id;
+        let pages = table.page_ids(&mut bpm).unwrap();
+        assert!(pages.len() > 2);
+        assert_eq!(fsm::entries(&mut bpm, table.fsm_page_id).len(), pages.len());
+
+        // Freed bytes on the first page show up in the map...
+        for rid in rids.iter().filter(|r| r.page_id == first) {
+            table.delete(&mut bpm, TXN, *rid).unwrap();
+        }
+        let entries = fsm::entries(&mut bpm, table.fsm_page_id);
+        assert!(entries[0].1 as usize >= 5 + SLOT_SIZE);
+
+        // ...so the next insert lands there without a new page
+        let rid = table.insert(&mut bpm, TXN, &[Value::Int32(99)]).unwrap();
+        assert_eq!(rid.page_id, first);
+        assert_eq!(table.page_ids(&mut bpm).unwrap(), pages);
+    }
+
     #[test]
     fn scan_returns_all_rows() {
         let mut bpm = make_bpm(128);
         let mut table = TableHeap::create(
-            "users".into(), test_schema(), 64, &mut bpm
+            "users".into(), test_schema(), 64, &mut bpm, TXN
         ).unwrap();
 
         for i in 0..5 {
-            table.insert(&mut bpm, &[
+            table.insert(&mut bpm, TXN, &[
                 Value::Int32(i),
                 Value::VarChar(format!("user_{}", i)),
                 Value::Bool(i % 2 == 0),
@@ -367,23 +944,250 @@ mod tests {
         assert_eq!(rows[4].1[0], Value::Int32(4));
     }
 
+    #[test]
+    fn iterator_pins_one_page_and_resumes() {
+        let mut bpm = make_bpm(128);
+        let mut table = TableHeap::create(
+            "users".into(), test_schema(), 64, &mut bpm, TXN
+        ).unwrap();
+        for i in 0..12 {
+            let name = if i % 4 == 0 { "o".repeat(100) } else { format!("user_{}", i) };
+            table.insert(&mut bpm, TXN, &[
+                Value::Int32(i), Value::VarChar(name), Value::Bool(true),
+            ]).unwrap();
+        }
+        let expected = table.scan(&mut bpm);
+        assert_eq!(expected.len(), 12);
+
+        let mut seen = Vec::new();
+        let mut iter = table.iter(&mut bpm);
+        while let Some(row) = iter.next() {
+            let pinned = iter.bpm.frames.iter().filter(|f| f.pin_count > 0).count();
+            assert!(pinned <= 1);
+            seen.push(row);
+        }
+        assert_eq!(seen, expected);
+        assert!(iter.position().is_none());
+        drop(iter);
+        assert!(bpm.frames.iter().all(|f| f.pin_count == 0));
+
+        // Resume from the fifth row
+        let rest: Vec<_> = table.iter_from(&mut bpm, expected[4].0).collect();
+        assert_eq!(rest, expected[4..]);
+    }
+
     #[test]
     fn delete_row() {
         let mut bpm = make_bpm(128);
         let mut table = TableHeap::create(
-            "users".into(), test_schema(), 64, &mut bpm
+            "users".into(), test_schema(), 64, &mut bpm, TXN
         ).unwrap();
 
-        let r0 = table.insert(&mut bpm, &[
+        let r0 = table.insert(&mut bpm, TXN, &[
             Value::Int32(1), Value::VarChar("A".into()), Value::Bool(true),
         ]).unwrap();
-        let r1 = table.insert(&mut bpm, &[
+        let r1 = table.insert(&mut bpm, TXN, &[
             Value::Int32(2), Value::VarChar("B".into()), Value::Bool(false),
         ]).unwrap();
 
-        assert!(table.delete(&mut bpm, r0));
+        assert!(table.delete(&mut bpm, TXN, r0).is_ok());
         assert_eq!(table.row_count, 1);
-        assert!(table.get(&mut bpm, r0).is_none());
-        assert!(table.get(&mut bpm, r1).is_some());
+        assert_eq!(table.get(&mut bpm, r0), Err(StorageError::RowNotFound(r0)));
+        assert!(table.get(&mut bpm, r1).is_ok());
+        assert_eq!(table.delete_slot(&mut bpm, TXN, r0), Err(StorageError::RowNotFound(r0)));
+
+        // A page that can't be read is an error, not a skipped delete
+        bpm.flush_all();
+        bpm.crash();
+        bpm.disk.faults.arm(FaultKind::ReadError, Trigger::Page(r1.page_id));
+        assert_eq!(table.delete_slot(&mut bpm, TXN, r1).unwrap_err().code(), "IO_ERROR");
+        assert!(table.get(&mut bpm, r1).is_ok());
+    }
+
+    #[test]
+    fn vacuum_compacts_and_frees_empty_pages() {
+        let mut bpm = make_bpm(64);
+        let schema = Schema::new(vec![
+            Column { name: "id".into(), col_type: ColumnType::Int32, nullable: false },
+        ]);
+        let mut table = TableHeap::create("nums".into(), schema, 32, &mut bpm, TXN).unwrap();
+        let rids: Vec<RowId> = (0..20)
+            .map(|i| table.insert(&mut bpm, TXN, &[Value::Int32(i)]).unwrap())
+            .collect();
+        let pages = table.page_ids(&mut bpm).unwrap();
+        let allocated = bpm.disk.num_allocated();
+
+        // Empty the second page entirely, and punch holes in the first
+        for rid in &rids {
+            if rid.page_id == pages[1] || (rid.page_id == pages[0] && rid.slot_id % 2 == 1) {
+                table.delete(&mut bpm, TXN, *rid).unwrap();
+            }
+        }
+        let live = table.scan(&mut bpm);
+
+        let report = table.vacuum(&mut bpm, TXN);
+        assert_eq!(report.pages_scanned, pages.len());
+        assert_eq!(report.pages_freed, 1);
+        assert!(report.pages_compacted >= 1);
+        assert_eq!(report.slots_trimmed, 0); // deletes already trimmed the tail
+        assert!(report.bytes_reclaimed > 0);
+        assert_eq!(bpm.disk.num_allocated(), allocated - 1);
+
+        let mut remaining = pages.clone();
+        remaining.remove(1);
+        assert_eq!(table.page_ids(&mut bpm).unwrap(), remaining);
+        assert_eq!(table.scan(&mut bpm), live);
+        assert_eq!(table.row_count as usize, live.len());
+
+        // A second pass has nothing left to do
+        let again = table.vacuum(&mut bpm, TXN);
+        assert_eq!(again.pages_freed + again.pages_compacted + again.bytes_reclaimed, 0);
+    }
+
+    #[test]
+    fn index_tracks_inserts_and_deletes() {
+        let mut bpm = make_bpm(1024); // room for three 258-byte VarChar keys
+        let mut table = TableHeap::create(
+            "users".into(), test_schema(), 64, &mut bpm, TXN
+        ).unwrap();
+        let mut rids = Vec::new();
+        for i in 0..5 {
+            rids.push(table.insert(&mut bpm, TXN, &[
+                Value::Int32(i), Value::VarChar(format!("n{}", i % 2)), Value::Bool(true),
+            ]).unwrap());
+        }
+
+        let root = table.create_index(&mut bpm, TXN, 1).unwrap();
+        let size = key_size(&ColumnType::VarChar(255));
+        let key = |s: &str| encode_key(&Value::VarChar(s.into()), size).unwrap();
+        assert_eq!(btree::lookup(&mut bpm, root, &key("n0")).len(), 3);
+
+        let extra = table.insert(&mut bpm, TXN, &[
+            Value::Int32(9), Value::VarChar("n1".into()), Value::Bool(false),
+        ]).unwrap();
+        table.delete(&mut bpm, TXN, rids[0]).unwrap();
+        assert_eq!(btree::lookup(&mut bpm, root, &key("n0")), vec![rids[2], rids[4]]);
+        assert!(btree::lookup(&mut bpm, root, &key("n1")).contains(&extra));
+    }
+
+    #[test]
+    fn hash_index_tracks_inserts_and_deletes() {
+        let mut bpm = make_bpm(128);
+        let schema = Schema::new(vec![
+            Column { name: "id".into(), col_type: ColumnType::Int32, nullable: false },
+            Column { name: "group".into(), col_type: ColumnType::UInt32, nullable: true },
+        ]);
+        let mut table = TableHeap::create("t".into(), schema, 64, &mut bpm, TXN).unwrap();
+        let dir = table.create_hash_index(&mut bpm, TXN, 1).unwrap();
+        let size = key_size(&ColumnType::UInt32);
+        let key = |g: u32| encode_key(&Value::UInt32(g), size).unwrap();
+
+        let mut rids = Vec::new();
+        for i in 0..30 {
+            let group = if i == 0 { Value::Null } else { Value::UInt32(i as u32 % 4) };
+            rids.push(table.insert(&mut bpm, TXN, &[Value::Int32(i), group]).unwrap());
+        }
+        assert_eq!(hash_index::lookup(&mut bpm, dir, &key(0)).len(), 7); // NULL skipped
+        assert_eq!(hash_index::lookup(&mut bpm, dir, &key(1)).len(), 8);
+
+        table.delete(&mut bpm, TXN, rids[1]).unwrap();
+        assert!(!hash_index::lookup(&mut bpm, dir, &key(1)).contains(&rids[1]));
+        assert_eq!(hash_index::lookup(&mut bpm, dir, &key(1)).len(), 7);
+    }
+
+    #[test]
+    fn update_forwards_when_page_is_full() {
+        let mut bpm = make_bpm(128);
+        let mut table = TableHeap::create(
+            "users".into(), test_schema(), 64, &mut bpm, TXN
+        ).unwrap();
+        let row = |i: i32, name: &str| {
+            vec![Value::Int32(i), Value::VarChar(name.into()), Value::Bool(true)]
+        };
+        // 13-byte tuples + 4-byte slots: six rows leave two bytes free
+        let rids: Vec<RowId> = (0..6)
+            .map(|i| table.insert(&mut bpm, TXN, &row(i, &format!("usr_{}", i))).unwrap())
+            .collect();
+        assert_eq!(table.page_ids(&mut bpm).unwrap().len(), 1);
+
+        // Same size: rewritten in place
+        table.update(&mut bpm, TXN, rids[1], &row(1, "USR_1")).unwrap();
+        assert_eq!(table.get(&mut bpm, rids[1]).unwrap(), row(1, "USR_1"));
+
+        // Too big for the page: moves, but the RowId still finds it
+        let long = "x".repeat(40);
+        table.update(&mut bpm, TXN, rids[0], &row(0, &long)).unwrap();
+        assert_eq!(table.page_ids(&mut bpm).unwrap().len(), 2);
+        assert_eq!(table.get(&mut bpm, rids[0]).unwrap(), row(0, &long));
+        let scanned = table.scan(&mut bpm);
+        assert_eq!(scanned.len(), 6);
+        assert_eq!(scanned[0], (rids[0], row(0, &long)));
+        table.recount(&mut bpm);
+        assert_eq!(table.row_count, 6);
+
+        // Shrinks back home; the moved copy goes away
+        table.update(&mut bpm, TXN, rids[0], &row(0, "a")).unwrap();
+        assert_eq!(table.get(&mut bpm, rids[0]).unwrap(), row(0, "a"));
+        table.recount(&mut bpm);
+        assert_eq!(table.row_count, 6);
+
+        // Deleting a forwarded row removes both slots
+        table.update(&mut bpm, TXN, rids[2], &row(2, &long)).unwrap();
+        table.delete(&mut bpm, TXN, rids[2]).unwrap();
+        assert!(table.get(&mut bpm, rids[2]).is_err());
+        table.recount(&mut bpm);
+        assert_eq!(table.row_count, 5);
+    }
+
+    #[test]
+    fn update_swaps_overflow_and_index_entries() {
+        let mut bpm = make_bpm(128);
+        let mut table = TableHeap::create(
+            "users".into(), test_schema(), 64, &mut bpm, TXN
+        ).unwrap();
+        let root = table.create_index(&mut bpm, TXN, 0).unwrap();
+        let big = "b".repeat(150);
+        let rid = table.insert(&mut bpm, TXN, &[
+            Value::Int32(1), Value::VarChar(big.clone()), Value::Bool(true),
+        ]).unwrap();
+
+        let old_chains = table.update(&mut bpm, TXN, rid, &[
+            Value::Int32(2), Value::VarChar("small".into()), Value::Bool(false),
+        ]).unwrap();
+        assert_eq!(old_chains.len(), 1);
+        assert_eq!(table.get(&mut bpm, rid).unwrap()[1], Value::VarChar("small".into()));
+
+        let key = |i: i32| encode_key(&Value::Int32(i), key_size(&ColumnType::Int32)).unwrap();
+        assert!(btree::lookup(&mut bpm, root, &key(1)).is_empty());
+        assert_eq!(btree::lookup(&mut bpm, root, &key(2)), vec![rid]);
+
+        let chains = table.update(&mut bpm, TXN, rid, &[
+            Value::Int32(2), Value::VarChar(big.clone()), Value::Bool(false),
+        ]).unwrap();
+        assert!(chains.is_empty());
+        assert_eq!(table.get(&mut bpm, rid).unwrap()[1], Value::VarChar(big));
+        assert_eq!(btree::lookup(&mut bpm, root, &key(2)), vec![rid]);
+    }
+
+    #[test]
+    fn failures_say_why() {
+        let mut bpm = make_bpm(128);
+        let mut table = TableHeap::create(
+            "users".into(), test_schema(), 64, &mut bpm, TXN
+        ).unwrap();
+
+        let wrong = table.insert(&mut bpm, TXN, &[
+            Value::Int32(1), Value::Int32(2), Value::Bool(true),
+        ]);
+        assert!(matches!(wrong, Err(StorageError::SchemaMismatch(_))));
+        // A 255-byte VarChar key leaves no room for three entries per node
+        assert!(matches!(
+            table.create_index(&mut bpm, TXN, 1),
+            Err(StorageError::KeyTooLarge { size: 258, max }) if max < 258
+        ));
+        assert_eq!(
+            table.create_index(&mut bpm, TXN, 9),
+            Err(StorageError::ColumnNotFound("#9".into()))
+        );
     }
 }
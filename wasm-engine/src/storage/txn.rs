@@ -0,0 +1,220 @@
+//! Transaction manager.
+//!
+//! Tracks running transactions on top of the write-ahead log.  Commit forces
+//! the log; abort walks the transaction's undo chain (`recovery::rollback`),
+//! which reverses inserts, deletes and every page allocation — including
+//! overflow chains written by `overflow::write_overflow`.
+//!
+//! Overflow chains orphaned by a delete are not freed until commit.  Freeing
+//! them earlier would let another transaction reuse the pages, and an abort
+//! could then no longer restore the row with its original pointers.
+//!
+//! For the same reason only one transaction may write at a time.  There is
+//! no locking, so a second writer could fill a slot the first one emptied,
+//! or a page it allocated, and the first one's abort would then undo its
+//! change on top of the second's.  The first write claims the engine for the
+//! transaction (`claim_write`) until it commits or aborts; others can still
+//! read.
+
+use std::collections::HashMap;
+use crate::storage::types::*;
+use crate::storage::overflow;
+use crate::storage::recovery;
+use crate::storage::error::{StorageError, StorageResult};
+use crate::storage::schema::OverflowPointer;
+use crate::storage::buffer_pool::BufferPoolManager;
+
+/// Per-transaction bookkeeping.
+#[derive(Debug, Default)]
+struct Transaction {
+    /// Overflow chains to free when the transaction commits.
+    pending_frees: Vec<OverflowPointer>,
+}
+
+pub struct TransactionManager {
+    active: HashMap<TxnId, Transaction>,
+    /// The transaction allowed to change data, if one has.
+    writer: Option<TxnId>,
+}
+
+impl TransactionManager {
+    pub fn new() -> Self {
+        Self { active: HashMap::new(), writer: None }
+    }
+
+    /// Start a transaction.
+    pub fn begin(&mut self, bpm: &mut BufferPoolManager) -> TxnId {
+        let txn = bpm.log.begin();
+        self.active.insert(txn, Transaction::default());
+        txn
+    }
+
+    /// Start a transaction that writes straight away.  Fails with
+    /// `TxnsActive`, without starting one, if another transaction is
+    /// writing.
+    pub fn begin_write(&mut self, bpm: &mut BufferPoolManager) -> StorageResult<TxnId> {
+        self.check_writer(None)?;
+        let txn = self.begin(bpm);
+        self.writer = Some(txn);
+        Ok(txn)
+    }
+
+    /// Let `txn` write until it commits or aborts.  Fails with `TxnsActive`
+    /// if another transaction is writing.
+    pub fn claim_write(&mut self, txn: TxnId) -> StorageResult<()> {
+        self.check_writer(Some(txn))?;
+        self.writer = Some(txn);
+        Ok(())
+    }
+
+    fn check_writer(&self, txn: Option<TxnId>) -> StorageResult<()> {
+        match self.writer {
+            Some(w) if Some(w) != txn => Err(StorageError::TxnsActive(format!(
+                "Transaction {} has uncommitted changes; commit or abort it first", w
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn is_active(&self, txn: TxnId) -> bool {
+        self.active.contains_key(&txn)
+    }
+
+    /// IDs of all running transactions, ascending.
+    pub fn active(&self) -> Vec<TxnId> {
+        let mut ids: Vec<TxnId> = self.active.keys().copied().collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Schedule overflow chains to be freed when `txn` commits.
+    pub fn defer_free(&mut self, txn: TxnId, ptrs: Vec<OverflowPointer>) {
+        if let Some(t) = self.active.get_mut(&txn) {
+            t.pending_frees.extend(ptrs);
+        }
+    }
+
+    /// Commit: free deferred overflow chains, then log `Commit` and force the
+    /// log.  Returns `false` if `txn` is not active.
+    pub fn commit(&mut self, bpm: &mut BufferPoolManager, txn: TxnId) -> bool {
+        let Some(t) = self.active.remove(&txn) else {
+            return false;
+        };
+        self.release(txn);
+        for ptr in &t.pending_frees {
+            overflow::delete_overflow(bpm, txn, ptr);
+        }
+        bpm.log.commit(txn);
+        true
+    }
+
+    /// Abort: undo every change `txn` made.  Returns `false` if `txn` is not
+    /// active.  If a page can't be read the transaction stays active, so the
+    /// abort can be retried.
+    pub fn abort(&mut self, bpm: &mut BufferPoolManager, txn: TxnId) -> StorageResult<bool> {
+        if !self.active.contains_key(&txn) {
+            return Ok(false);
+        }
+        recovery::rollback(bpm, txn)?;
+        self.active.remove(&txn);
+        self.release(txn);
+        Ok(true)
+    }
+
+    /// Forget all running transactions (their state died with the crash;
+    /// recovery rolls them back from the log).
+    pub fn crash(&mut self) {
+        self.active.clear();
+        self.writer = None;
+    }
+
+    fn release(&mut self, txn: TxnId) {
+        if self.writer == Some(txn) {
+            self.writer = None;
+        }
+    }
+}
+
+impl Default for TransactionManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::disk::DiskManager;
+    use crate::storage::schema::*;
+    use crate::storage::table::TableHeap;
+
+    fn setup() -> (BufferPoolManager, TransactionManager, TableHeap) {
+        let mut bpm = BufferPoolManager::new(8, DiskManager::new(128, 64));
+        let mut tm = TransactionManager::new();
+        let schema = Schema::new(vec![
+            Column { name: "id".into(), col_type: ColumnType::Int32, nullable: false },
+            Column { name: "body".into(), col_type: ColumnType::Blob(1000), nullable: false },
+        ]);
+        let txn = tm.begin(&mut bpm);
+        let table = TableHeap::create("t".into(), schema, 64, &mut bpm, txn).unwrap();
+        tm.commit(&mut bpm, txn);
+        (bpm, tm, table)
+    }
+
+    fn row(id: i32, len: usize) -> Vec<Value> {
+        vec![Value::Int32(id), Value::Blob(vec![id as u8; len])]
+    }
+
+    #[test]
+    fn abort_undoes_inserts_and_overflow() {
+        let (mut bpm, mut tm, mut table) = setup();
+        let allocated = bpm.disk.num_allocated();
+
+        let txn = tm.begin(&mut bpm);
+        let r0 = table.insert(&mut bpm, txn, &row(1, 10)).unwrap();
+        let r1 = table.insert(&mut bpm, txn, &row(2, 300)).unwrap();
+        assert!(bpm.disk.num_allocated() > allocated);
+
+        assert!(tm.abort(&mut bpm, txn).unwrap());
+        table.recount(&mut bpm);
+        assert!(table.get(&mut bpm, r0).is_err());
+        assert!(table.get(&mut bpm, r1).is_err());
+        assert_eq!(table.row_count, 0);
+        assert_eq!(bpm.disk.num_allocated(), allocated);
+        assert!(!tm.is_active(txn));
+    }
+
+    #[test]
+    fn abort_restores_deleted_rows() {
+        let (mut bpm, mut tm, mut table) = setup();
+        let t1 = tm.begin(&mut bpm);
+        let rid = table.insert(&mut bpm, t1, &row(5, 250)).unwrap();
+        tm.commit(&mut bpm, t1);
+
+        let t2 = tm.begin(&mut bpm);
+        let ptrs = table.delete(&mut bpm, t2, rid).unwrap();
+        assert_eq!(ptrs.len(), 1);
+        tm.defer_free(t2, ptrs);
+        tm.abort(&mut bpm, t2).unwrap();
+
+        assert_eq!(table.get(&mut bpm, rid).unwrap(), row(5, 250));
+    }
+
+    #[test]
+    fn commit_frees_deferred_overflow() {
+        let (mut bpm, mut tm, mut table) = setup();
+        let allocated = bpm.disk.num_allocated();
+        let t1 = tm.begin(&mut bpm);
+        let rid = table.insert(&mut bpm, t1, &row(5, 250)).unwrap();
+        tm.commit(&mut bpm, t1);
+        let with_row = bpm.disk.num_allocated();
+
+        let t2 = tm.begin(&mut bpm);
+        let ptrs = table.delete(&mut bpm, t2, rid).unwrap();
+        tm.defer_free(t2, ptrs);
+        assert_eq!(bpm.disk.num_allocated(), with_row); // still held
+        assert!(tm.commit(&mut bpm, t2));
+        assert_eq!(bpm.disk.num_allocated(), allocated);
+        assert!(!tm.commit(&mut bpm, t2)); // no longer active
+    }
+}
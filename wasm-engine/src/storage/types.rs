@@ -7,8 +7,15 @@ pub type FrameId = u32;
 /// Slot identifier — index into a page's slot array.
 pub type SlotId = u16;
 
+/// Log sequence number — position of a record in the write-ahead log.
+/// LSN 0 is never assigned and means "no record".
+pub type Lsn = u32;
+
+/// Transaction identifier.
+pub type TxnId = u32;
+
 /// A globally unique tuple address: (page, slot).
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct RowId {
     pub page_id: PageId,
     pub slot_id: SlotId,
@@ -17,21 +24,30 @@ pub struct RowId {
 /// Sentinel value meaning "no page" / "no frame" / "no next".
 pub const INVALID_PAGE: PageId = u32::MAX;
 
+/// Sentinel LSN meaning "no previous record".
+pub const INVALID_LSN: Lsn = 0;
+
 // ── Page layout constants ──────────────────────────────────────────
 
-/// Page header is 16 bytes:
+/// Page header is 24 bytes:
 ///   [0..4]   page_id   : u32
-///   [4]      page_type  : u8   (0=Data, 1=Overflow, 2=Free)
+///   [4]      page_type  : u8   (0=Data, 1=Overflow, 2=Free, 3=Index,
+///                               4=HashDirectory, 5=HashBucket,
+///                               6=FreeSpaceMap, 7=Superblock,
+///                               8=Catalog, 9=Temp)
 ///   [5..7]   slot_count : u16
 ///   [7..9]   free_start : u16  (byte offset: end of slot array)
 ///   [9..11]  free_end   : u16  (byte offset: start of tuple data from bottom)
 ///   [11..15] next_page  : u32
-///   [15]     _reserved  : u8
-pub const PAGE_HEADER_SIZE: usize = 16;
+///   [15..19] page_lsn   : u32  (LSN of the last logged change to this page)
+///   [19]     _reserved  : u8
+///   [20..24] checksum   : u32  (CRC-32 of the page, stamped on write-back)
+pub const PAGE_HEADER_SIZE: usize = 24;
 
 /// Each slot is 4 bytes:
 ///   [0..2] offset : u16  (byte offset of tuple within page)
-///   [2..4] length : u16  (0 = deleted tombstone)
+///   [2..4] length : u16  (0 = deleted tombstone; top two bits are
+///                         flags, see `page::TUPLE_FORWARD`)
 pub const SLOT_SIZE: usize = 4;
 
 /// Page type discriminants.
@@ -41,6 +57,15 @@ pub enum PageType {
     Data = 0,
     Overflow = 1,
     Free = 2,
+    Index = 3,
+    HashDirectory = 4,
+    HashBucket = 5,
+    FreeSpaceMap = 6,
+    Superblock = 7,
+    Catalog = 8,
+    /// Scratch space for a running query: spilled rows, sorted runs.
+    /// Never logged, and freed when the query finishes.
+    Temp = 9,
 }
 
 impl PageType {
@@ -49,6 +74,13 @@ impl PageType {
             0 => PageType::Data,
             1 => PageType::Overflow,
             2 => PageType::Free,
+            3 => PageType::Index,
+            4 => PageType::HashDirectory,
+            5 => PageType::HashBucket,
+            6 => PageType::FreeSpaceMap,
+            7 => PageType::Superblock,
+            8 => PageType::Catalog,
+            9 => PageType::Temp,
             _ => PageType::Free,
         }
     }
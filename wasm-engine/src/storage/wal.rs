@@ -0,0 +1,599 @@
+//! Write-ahead log.
+//!
+//! Every change to a page is described by a log record *before* the page is
+//! allowed to reach disk.  Records are appended to an in-memory tail and
+//! serialized onto the "log device" (a byte vector) when flushed.  The buffer
+//! pool enforces the WAL rule: a dirty frame is written back only after the
+//! log has been flushed up to that page's LSN.  Only the flushed portion of
+//! the log survives a crash.
+//!
+//! Record wire format (little-endian):
+//!
+//! ```text
+//! record_len : u32   (bytes that follow this field)
+//! lsn        : u32
+//! txn_id     : u32
+//! prev_lsn   : u32   (previous record of the same transaction, 0 = none)
+//! kind       : u8
+//! payload    : kind-specific (see `LogBody::encode_payload`)
+//! ```
+
+use std::collections::HashMap;
+use crate::storage::types::*;
+
+// ── Log record bodies ──────────────────────────────────────────────
+
+/// What a log record describes.  Page-level bodies are physiological: they
+/// name a page and an operation on it, and can be redone or undone alone.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LogBody {
+    Begin,
+    Commit,
+    Abort,
+    End,
+    /// A page was allocated and initialized as `page_type`.
+    NewPage { page_id: PageId, page_type: PageType },
+    /// A page was returned to the disk free list.  `image` is its last
+    /// contents so the free can be undone.
+    FreePage { page_id: PageId, image: Vec<u8> },
+    /// A freed page was brought back with exactly these bytes (CLR only).
+    PageImage { page_id: PageId, image: Vec<u8> },
+    /// The page's `next_page` pointer changed.
+    SetNext { page_id: PageId, old_next: PageId, new_next: PageId },
+    /// A tuple was placed at `rid` with slot `flags` (see `page::TUPLE_*`).
+    InsertTuple { rid: RowId, flags: u16, data: Vec<u8> },
+    /// The tuple at `rid` was tombstoned.  `flags`/`data` are the old slot.
+    DeleteTuple { rid: RowId, flags: u16, data: Vec<u8> },
+    /// An overflow page's payload was written.
+    OverflowData { page_id: PageId, data: Vec<u8> },
+    /// Compensation record written while undoing.  `redo` is the action
+    /// that was applied; `undo_next` is where undo resumes.
+    Clr { undo_next: Lsn, redo: Box<LogBody> },
+    /// An index page (B+tree node, hash directory or bucket) or a catalog
+    /// page changed from `before` to `after`.
+    IndexPage { page_id: PageId, before: Vec<u8>, after: Vec<u8> },
+    /// Logical: (key, rid) was added to the B+tree rooted at `root`.  The
+    /// page changes are logged separately; undo removes the entry again
+    /// wherever it lives by then.
+    IndexInsert { root: PageId, key: Vec<u8>, rid: RowId },
+    /// Logical: (key, rid) was removed from the B+tree rooted at `root`.
+    IndexDelete { root: PageId, key: Vec<u8>, rid: RowId },
+    /// Logical: (key, rid) was added to the hash index with directory `dir`.
+    HashInsert { dir: PageId, key: Vec<u8>, rid: RowId },
+    /// Logical: (key, rid) was removed from the hash index with directory `dir`.
+    HashDelete { dir: PageId, key: Vec<u8>, rid: RowId },
+    /// The tuple at `rid` was rewritten from `before` to `after`, each with
+    /// its slot flags.
+    UpdateTuple {
+        rid: RowId,
+        before_flags: u16,
+        before: Vec<u8>,
+        after_flags: u16,
+        after: Vec<u8>,
+    },
+}
+
+impl LogBody {
+    fn kind(&self) -> u8 {
+        match self {
+            LogBody::Begin => 0,
+            LogBody::Commit => 1,
+            LogBody::Abort => 2,
+            LogBody::End => 3,
+            LogBody::NewPage { .. } => 4,
+            LogBody::FreePage { .. } => 5,
+            LogBody::PageImage { .. } => 6,
+            LogBody::SetNext { .. } => 7,
+            LogBody::InsertTuple { .. } => 8,
+            LogBody::DeleteTuple { .. } => 9,
+            LogBody::OverflowData { .. } => 10,
+            LogBody::Clr { .. } => 11,
+            LogBody::IndexPage { .. } => 12,
+            LogBody::IndexInsert { .. } => 13,
+            LogBody::IndexDelete { .. } => 14,
+            LogBody::HashInsert { .. } => 15,
+            LogBody::HashDelete { .. } => 16,
+            LogBody::UpdateTuple { .. } => 17,
+        }
+    }
+
+    /// The page this record modifies, if any.
+    pub fn page_id(&self) -> Option<PageId> {
+        match self {
+            LogBody::NewPage { page_id, .. }
+            | LogBody::FreePage { page_id, .. }
+            | LogBody::PageImage { page_id, .. }
+            | LogBody::SetNext { page_id, .. }
+            | LogBody::OverflowData { page_id, .. }
+            | LogBody::IndexPage { page_id, .. } => Some(*page_id),
+            LogBody::InsertTuple { rid, .. }
+            | LogBody::DeleteTuple { rid, .. }
+            | LogBody::UpdateTuple { rid, .. } => Some(rid.page_id),
+            LogBody::Clr { redo, .. } => redo.page_id(),
+            LogBody::Begin
+            | LogBody::Commit
+            | LogBody::Abort
+            | LogBody::End
+            | LogBody::IndexInsert { .. }
+            | LogBody::IndexDelete { .. }
+            | LogBody::HashInsert { .. }
+            | LogBody::HashDelete { .. } => None,
+        }
+    }
+
+    /// Whether this is a logical record, undone by re-running the inverse
+    /// operation rather than by touching a named page.
+    pub fn is_logical(&self) -> bool {
+        matches!(
+            self,
+            LogBody::IndexInsert { .. }
+                | LogBody::IndexDelete { .. }
+                | LogBody::HashInsert { .. }
+                | LogBody::HashDelete { .. }
+        )
+    }
+
+    /// The action that reverses this one, or `None` if there is nothing to
+    /// undo (transaction control records, payload writes on pages whose
+    /// allocation is undone separately, and CLRs).
+    pub fn undo_action(&self) -> Option<LogBody> {
+        match self {
+            LogBody::NewPage { page_id, .. } => {
+                Some(LogBody::FreePage { page_id: *page_id, image: Vec::new() })
+            }
+            LogBody::FreePage { page_id, image } => {
+                Some(LogBody::PageImage { page_id: *page_id, image: image.clone() })
+            }
+            LogBody::SetNext { page_id, old_next, new_next } => Some(LogBody::SetNext {
+                page_id: *page_id,
+                old_next: *new_next,
+                new_next: *old_next,
+            }),
+            LogBody::InsertTuple { rid, flags, data } => {
+                Some(LogBody::DeleteTuple { rid: *rid, flags: *flags, data: data.clone() })
+            }
+            LogBody::DeleteTuple { rid, flags, data } => {
+                Some(LogBody::InsertTuple { rid: *rid, flags: *flags, data: data.clone() })
+            }
+            LogBody::UpdateTuple { rid, before_flags, before, after_flags, after } => {
+                Some(LogBody::UpdateTuple {
+                    rid: *rid,
+                    before_flags: *after_flags,
+                    before: after.clone(),
+                    after_flags: *before_flags,
+                    after: before.clone(),
+                })
+            }
+            LogBody::IndexPage { page_id, before, after } => Some(LogBody::IndexPage {
+                page_id: *page_id,
+                before: after.clone(),
+                after: before.clone(),
+            }),
+            LogBody::IndexInsert { root, key, rid } => {
+                Some(LogBody::IndexDelete { root: *root, key: key.clone(), rid: *rid })
+            }
+            LogBody::IndexDelete { root, key, rid } => {
+                Some(LogBody::IndexInsert { root: *root, key: key.clone(), rid: *rid })
+            }
+            LogBody::HashInsert { dir, key, rid } => {
+                Some(LogBody::HashDelete { dir: *dir, key: key.clone(), rid: *rid })
+            }
+            LogBody::HashDelete { dir, key, rid } => {
+                Some(LogBody::HashInsert { dir: *dir, key: key.clone(), rid: *rid })
+            }
+            _ => None,
+        }
+    }
+
+    fn encode_payload(&self, buf: &mut Vec<u8>) {
+        match self {
+            LogBody::Begin | LogBody::Commit | LogBody::Abort | LogBody::End => {}
+            LogBody::NewPage { page_id, page_type } => {
+                push_u32(buf, *page_id);
+                buf.push(*page_type as u8);
+            }
+            LogBody::FreePage { page_id, image } | LogBody::PageImage { page_id, image } => {
+                push_u32(buf, *page_id);
+                push_bytes(buf, image);
+            }
+            LogBody::SetNext { page_id, old_next, new_next } => {
+                push_u32(buf, *page_id);
+                push_u32(buf, *old_next);
+                push_u32(buf, *new_next);
+            }
+            LogBody::InsertTuple { rid, flags, data }
+            | LogBody::DeleteTuple { rid, flags, data } => {
+                push_u32(buf, rid.page_id);
+                buf.extend_from_slice(&rid.slot_id.to_le_bytes());
+                buf.extend_from_slice(&flags.to_le_bytes());
+                push_bytes(buf, data);
+            }
+            LogBody::UpdateTuple { rid, before_flags, before, after_flags, after } => {
+                push_u32(buf, rid.page_id);
+                buf.extend_from_slice(&rid.slot_id.to_le_bytes());
+                buf.extend_from_slice(&before_flags.to_le_bytes());
+                push_bytes(buf, before);
+                buf.extend_from_slice(&after_flags.to_le_bytes());
+                push_bytes(buf, after);
+            }
+            LogBody::OverflowData { page_id, data } => {
+                push_u32(buf, *page_id);
+                push_bytes(buf, data);
+            }
+            LogBody::Clr { undo_next, redo } => {
+                push_u32(buf, *undo_next);
+                buf.push(redo.kind());
+                redo.encode_payload(buf);
+            }
+            LogBody::IndexPage { page_id, before, after } => {
+                push_u32(buf, *page_id);
+                push_bytes(buf, before);
+                push_bytes(buf, after);
+            }
+            LogBody::IndexInsert { root: page_id, key, rid }
+            | LogBody::IndexDelete { root: page_id, key, rid }
+            | LogBody::HashInsert { dir: page_id, key, rid }
+            | LogBody::HashDelete { dir: page_id, key, rid } => {
+                push_u32(buf, *page_id);
+                push_bytes(buf, key);
+                push_u32(buf, rid.page_id);
+                buf.extend_from_slice(&rid.slot_id.to_le_bytes());
+            }
+        }
+    }
+
+    fn decode_payload(kind: u8, r: &mut Reader) -> Option<Self> {
+        Some(match kind {
+            0 => LogBody::Begin,
+            1 => LogBody::Commit,
+            2 => LogBody::Abort,
+            3 => LogBody::End,
+            4 => LogBody::NewPage {
+                page_id: r.u32()?,
+                page_type: PageType::from_u8(r.u8()?),
+            },
+            5 => LogBody::FreePage { page_id: r.u32()?, image: r.bytes()? },
+            6 => LogBody::PageImage { page_id: r.u32()?, image: r.bytes()? },
+            7 => LogBody::SetNext {
+                page_id: r.u32()?,
+                old_next: r.u32()?,
+                new_next: r.u32()?,
+            },
+            8 => LogBody::InsertTuple { rid: r.row_id()?, flags: r.u16()?, data: r.bytes()? },
+            9 => LogBody::DeleteTuple { rid: r.row_id()?, flags: r.u16()?, data: r.bytes()? },
+            10 => LogBody::OverflowData { page_id: r.u32()?, data: r.bytes()? },
+            11 => {
+                let undo_next = r.u32()?;
+                let inner_kind = r.u8()?;
+                let redo = Box::new(Self::decode_payload(inner_kind, r)?);
+                LogBody::Clr { undo_next, redo }
+            }
+            12 => LogBody::IndexPage { page_id: r.u32()?, before: r.bytes()?, after: r.bytes()? },
+            13 => LogBody::IndexInsert { root: r.u32()?, key: r.bytes()?, rid: r.row_id()? },
+            14 => LogBody::IndexDelete { root: r.u32()?, key: r.bytes()?, rid: r.row_id()? },
+            15 => LogBody::HashInsert { dir: r.u32()?, key: r.bytes()?, rid: r.row_id()? },
+            16 => LogBody::HashDelete { dir: r.u32()?, key: r.bytes()?, rid: r.row_id()? },
+            17 => LogBody::UpdateTuple {
+                rid: r.row_id()?,
+                before_flags: r.u16()?,
+                before: r.bytes()?,
+                after_flags: r.u16()?,
+                after: r.bytes()?,
+            },
+            _ => return None,
+        })
+    }
+}
+
+// ── Log records ────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogRecord {
+    pub lsn: Lsn,
+    pub txn_id: TxnId,
+    /// Previous record written by the same transaction (undo chain).
+    pub prev_lsn: Lsn,
+    pub body: LogBody,
+}
+
+impl LogRecord {
+    /// Serialize to the on-log wire format (including the length prefix).
+    pub fn encode(&self) -> Vec<u8> {
+        let mut body = Vec::with_capacity(32);
+        push_u32(&mut body, self.lsn);
+        push_u32(&mut body, self.txn_id);
+        push_u32(&mut body, self.prev_lsn);
+        body.push(self.body.kind());
+        self.body.encode_payload(&mut body);
+
+        let mut out = Vec::with_capacity(body.len() + 4);
+        push_u32(&mut out, body.len() as u32);
+        out.extend_from_slice(&body);
+        out
+    }
+
+    /// Decode one record from the front of `data`.  Returns the record and
+    /// the number of bytes consumed, or `None` if the bytes are truncated or
+    /// malformed.
+    pub fn decode(data: &[u8]) -> Option<(Self, usize)> {
+        let mut r = Reader { data, pos: 0 };
+        let len = r.u32()? as usize;
+        let end = 4 + len;
+        if data.len() < end {
+            return None;
+        }
+        let mut r = Reader { data: &data[..end], pos: 4 };
+        let lsn = r.u32()?;
+        let txn_id = r.u32()?;
+        let prev_lsn = r.u32()?;
+        let kind = r.u8()?;
+        let body = LogBody::decode_payload(kind, &mut r)?;
+        Some((LogRecord { lsn, txn_id, prev_lsn, body }, end))
+    }
+}
+
+// ── Log manager ────────────────────────────────────────────────────
+
+/// Owns the log: the durable byte stream plus the not-yet-flushed tail.
+pub struct LogManager {
+    /// The "log device" — everything that has been flushed.
+    durable: Vec<u8>,
+    /// Byte offset of each durable record, indexed by `lsn - 1`.
+    durable_offsets: Vec<usize>,
+    /// Records appended but not yet flushed (lost on crash).
+    tail: Vec<LogRecord>,
+    /// LSN the next appended record will receive.
+    next_lsn: Lsn,
+    /// Highest LSN known to be on the log device.
+    flushed_lsn: Lsn,
+    /// Last LSN written by each running transaction.
+    last_lsn: HashMap<TxnId, Lsn>,
+    next_txn_id: TxnId,
+    /// Number of flushes that actually wrote bytes.
+    pub flush_count: u64,
+}
+
+impl LogManager {
+    pub fn new() -> Self {
+        Self {
+            durable: Vec::new(),
+            durable_offsets: Vec::new(),
+            tail: Vec::new(),
+            next_lsn: 1,
+            flushed_lsn: INVALID_LSN,
+            last_lsn: HashMap::new(),
+            next_txn_id: 1,
+            flush_count: 0,
+        }
+    }
+
+    /// Start a new transaction and log its `Begin` record.
+    pub fn begin(&mut self) -> TxnId {
+        let txn = self.next_txn_id;
+        self.next_txn_id += 1;
+        self.append(txn, LogBody::Begin);
+        txn
+    }
+
+    /// Log `Commit` for `txn` and force the log to disk.
+    pub fn commit(&mut self, txn: TxnId) -> Lsn {
+        let lsn = self.append(txn, LogBody::Commit);
+        self.flush(lsn);
+        self.last_lsn.remove(&txn);
+        lsn
+    }
+
+    /// Log `End` for `txn` (rollback finished) and forget it.
+    pub fn end(&mut self, txn: TxnId) -> Lsn {
+        let lsn = self.append(txn, LogBody::End);
+        self.last_lsn.remove(&txn);
+        lsn
+    }
+
+    /// Append a record for `txn`, chaining it to the transaction's previous
+    /// record.  Returns the new LSN.
+    pub fn append(&mut self, txn: TxnId, body: LogBody) -> Lsn {
+        let lsn = self.next_lsn;
+        self.next_lsn += 1;
+        let prev_lsn = self.last_lsn.insert(txn, lsn).unwrap_or(INVALID_LSN);
+        self.tail.push(LogRecord { lsn, txn_id: txn, prev_lsn, body });
+        lsn
+    }
+
+    /// Make every record with LSN <= `up_to` durable.
+    pub fn flush(&mut self, up_to: Lsn) {
+        if up_to <= self.flushed_lsn || self.tail.is_empty() {
+            return;
+        }
+        let n = self.tail.iter().take_while(|r| r.lsn <= up_to).count();
+        for rec in self.tail.drain(..n) {
+            self.durable_offsets.push(self.durable.len());
+            self.durable.extend_from_slice(&rec.encode());
+            self.flushed_lsn = rec.lsn;
+        }
+        self.flush_count += 1;
+    }
+
+    /// Flush the entire tail.
+    pub fn flush_all(&mut self) {
+        self.flush(Lsn::MAX);
+    }
+
+    /// Simulate a crash: the unflushed tail and all in-memory transaction
+    /// state are lost.  The durable bytes survive.
+    pub fn crash(&mut self) {
+        self.tail.clear();
+        self.last_lsn.clear();
+        self.next_lsn = self.flushed_lsn + 1;
+        self.next_txn_id = 1;
+    }
+
+    /// Look up a record by LSN, in the tail or on the log device.
+    pub fn record(&self, lsn: Lsn) -> Option<LogRecord> {
+        if lsn == INVALID_LSN {
+            return None;
+        }
+        if lsn > self.flushed_lsn {
+            return self.tail.iter().find(|r| r.lsn == lsn).cloned();
+        }
+        let off = *self.durable_offsets.get(lsn as usize - 1)?;
+        LogRecord::decode(&self.durable[off..]).map(|(rec, _)| rec)
+    }
+
+    /// Decode every durable record in LSN order.
+    pub fn durable_records(&self) -> Vec<LogRecord> {
+        let mut out = Vec::with_capacity(self.durable_offsets.len());
+        let mut pos = 0;
+        while let Some((rec, used)) = LogRecord::decode(&self.durable[pos..]) {
+            out.push(rec);
+            pos += used;
+        }
+        out
+    }
+
+    /// Last LSN written by `txn` (0 if it has written nothing).
+    pub fn last_lsn(&self, txn: TxnId) -> Lsn {
+        self.last_lsn.get(&txn).copied().unwrap_or(INVALID_LSN)
+    }
+
+    /// Reinstate a transaction's undo chain head (used by recovery).
+    pub fn set_last_lsn(&mut self, txn: TxnId, lsn: Lsn) {
+        self.last_lsn.insert(txn, lsn);
+    }
+
+    /// Ensure future transaction IDs are at least `min`.
+    pub fn reserve_txn_ids(&mut self, min: TxnId) {
+        self.next_txn_id = self.next_txn_id.max(min);
+    }
+
+    // ── Accessors ──────────────────────────────────────────────────
+
+    pub fn flushed_lsn(&self) -> Lsn {
+        self.flushed_lsn
+    }
+
+    pub fn next_lsn(&self) -> Lsn {
+        self.next_lsn
+    }
+
+    /// Raw bytes on the log device.
+    pub fn durable_bytes(&self) -> &[u8] {
+        &self.durable
+    }
+
+    /// Number of records waiting in the unflushed tail.
+    pub fn tail_len(&self) -> usize {
+        self.tail.len()
+    }
+}
+
+impl Default for LogManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ── Encoding helpers ───────────────────────────────────────────────
+
+fn push_u32(buf: &mut Vec<u8>, v: u32) {
+    buf.extend_from_slice(&v.to_le_bytes());
+}
+
+/// u32 length prefix followed by the bytes.
+fn push_bytes(buf: &mut Vec<u8>, data: &[u8]) {
+    push_u32(buf, data.len() as u32);
+    buf.extend_from_slice(data);
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, n: usize) -> Option<&[u8]> {
+        let s = self.data.get(self.pos..self.pos + n)?;
+        self.pos += n;
+        Some(s)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|b| b[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.take(2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take(4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn bytes(&mut self) -> Option<Vec<u8>> {
+        let len = self.u32()? as usize;
+        self.take(len).map(|b| b.to_vec())
+    }
+
+    fn row_id(&mut self) -> Option<RowId> {
+        Some(RowId { page_id: self.u32()?, slot_id: self.u16()? })
+    }
+}
+
+// ── Tests ──────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_roundtrip() {
+        let rec = LogRecord {
+            lsn: 7,
+            txn_id: 3,
+            prev_lsn: 5,
+            body: LogBody::Clr {
+                undo_next: 4,
+                redo: Box::new(LogBody::InsertTuple {
+                    rid: RowId { page_id: 2, slot_id: 9 },
+                    flags: 0,
+                    data: vec![1, 2, 3],
+                }),
+            },
+        };
+        let bytes = rec.encode();
+        let (decoded, used) = LogRecord::decode(&bytes).unwrap();
+        assert_eq!(decoded, rec);
+        assert_eq!(used, bytes.len());
+    }
+
+    #[test]
+    fn append_chains_prev_lsn() {
+        let mut log = LogManager::new();
+        let t1 = log.begin();
+        let t2 = log.begin();
+        let a = log.append(t1, LogBody::SetNext { page_id: 0, old_next: 1, new_next: 2 });
+        log.append(t2, LogBody::Abort);
+        let b = log.append(t1, LogBody::NewPage { page_id: 4, page_type: PageType::Data });
+
+        let rec = log.record(b).unwrap();
+        assert_eq!(rec.prev_lsn, a);
+        assert_eq!(log.record(a).unwrap().prev_lsn, 1); // t1's Begin
+    }
+
+    #[test]
+    fn crash_loses_unflushed_tail() {
+        let mut log = LogManager::new();
+        let t = log.begin();
+        log.commit(t); // forces LSNs 1..=2
+        let t2 = log.begin();
+        log.append(t2, LogBody::NewPage { page_id: 0, page_type: PageType::Data });
+        assert_eq!(log.tail_len(), 2);
+
+        log.crash();
+        let recs = log.durable_records();
+        assert_eq!(recs.len(), 2);
+        assert_eq!(recs[1].body, LogBody::Commit);
+        assert_eq!(log.next_lsn(), 3);
+        assert!(log.record(3).is_none());
+    }
+}
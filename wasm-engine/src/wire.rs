@@ -0,0 +1,836 @@
+//! The JSON the `StorageEngine` wasm class speaks: typed conversions
+//! between `json::Json` documents and the engine's config, schemas, values
+//! and reports.
+//!
+//! Values follow their column's type strictly: integers must be written as
+//! integers and fit the column, and floats must be finite numbers or one of
+//! the strings `"NaN"`, `"Infinity"` and `"-Infinity"` (which is also how
+//! non-finite floats are written out).  Blobs are accepted as `"0x…"` hex,
+//! `"base64:…"` or an array of byte values, and always come out as hex, so
+//! a row read with `get` can be written back unchanged.
+//!
+//! The same holds for the wider types.  Int64 values beyond ±2^53 (which a
+//! JavaScript number cannot hold exactly) are written as strings and read
+//! from either form; Decimals are written as strings such as `"12.50"` and
+//! read from a string or a number with no more fractional digits than the
+//! column's scale; Dates are `"YYYY-MM-DD"`, Timestamps ISO 8601 strings
+//! (written out in UTC, `"2024-02-29T13:45:00Z"`), and Uuids hyphenated
+//! hex strings.  A Char column takes a string and gives it back without
+//! its trailing spaces.
+
+use crate::database::Rows;
+use crate::exec::PlanProfile;
+use crate::exec::aggregate::AggregateFunction;
+use crate::exec::sort::SortTrace;
+use crate::json::Json;
+use crate::sql::QueryResult;
+use crate::storage::config::EngineConfig;
+use crate::storage::datetime;
+use crate::storage::error::{StorageError, StorageResult};
+use crate::storage::recovery::RecoveryReport;
+use crate::storage::replacer::ReplacementPolicy;
+use crate::storage::schema::*;
+use crate::storage::table::VacuumReport;
+use crate::storage::types::*;
+
+// ── Config ─────────────────────────────────────────────────────────
+
+/// Parse an engine config object.  Missing keys keep their defaults and
+/// unknown keys are ignored.
+pub fn parse_config(text: &str) -> StorageResult<EngineConfig> {
+    let doc = Json::parse(text)?;
+    let Json::Object(members) = &doc else {
+        return Err(malformed(format!("Config must be an object, got {}", doc.kind())));
+    };
+
+    let mut config = EngineConfig::default_config();
+    for (key, value) in members {
+        let field = match key.as_str() {
+            "page_size" => &mut config.page_size,
+            "pool_size" => &mut config.pool_size,
+            "disk_capacity" => &mut config.disk_capacity,
+            "overflow_threshold" => &mut config.overflow_threshold,
+            "seek_us" => &mut config.io_cost.seek_us,
+            "seq_transfer_us" => &mut config.io_cost.seq_transfer_us,
+            "random_transfer_us" => &mut config.io_cost.random_transfer_us,
+            "write_penalty_us" => &mut config.io_cost.write_penalty_us,
+            "replacement_policy" => {
+                let name = value.as_str().ok_or_else(|| {
+                    malformed(format!("replacement_policy must be a string, got {}", value))
+                })?;
+                config.replacement_policy = ReplacementPolicy::parse(name)
+                    .ok_or_else(|| malformed(format!("Unknown replacement policy: {}", name)))?;
+                continue;
+            }
+            _ => continue,
+        };
+        *field = json_u32(value).ok_or_else(|| {
+            malformed(format!("{} must be an integer from 0 to {}, got {}", key, u32::MAX, value))
+        })?;
+    }
+    Ok(config)
+}
+
+pub fn config_to_json(config: &EngineConfig) -> Json {
+    Json::object([
+        ("page_size", config.page_size.into()),
+        ("pool_size", config.pool_size.into()),
+        ("disk_capacity", config.disk_capacity.into()),
+        ("overflow_threshold", config.overflow_threshold.into()),
+        ("replacement_policy", config.replacement_policy.name().into()),
+        ("seek_us", config.io_cost.seek_us.into()),
+        ("seq_transfer_us", config.io_cost.seq_transfer_us.into()),
+        ("random_transfer_us", config.io_cost.random_transfer_us.into()),
+        ("write_penalty_us", config.io_cost.write_penalty_us.into()),
+    ])
+}
+
+// ── Schemas ────────────────────────────────────────────────────────
+
+/// Parse `{ "columns": [ { "name", "type", "nullable"? }, ... ] }`, where
+/// `type` is `"Int32"`, `"UInt32"`, `"Float64"`, `"Bool"`,
+/// `{ "VarChar": n }` or `{ "Blob": n }`.
+pub fn parse_schema(text: &str) -> StorageResult<Schema> {
+    let doc = Json::parse(text)?;
+    let columns = doc
+        .get("columns")
+        .ok_or_else(|| malformed("Schema needs a \"columns\" array"))?
+        .as_array()
+        .ok_or_else(|| malformed("\"columns\" must be an array"))?;
+    if columns.is_empty() {
+        return Err(StorageError::SchemaMismatch("Schema must have at least one column".into()));
+    }
+
+    let columns = columns
+        .iter()
+        .enumerate()
+        .map(|(i, col)| parse_column(col).map_err(|e| malformed(format!("columns[{}]: {}", i, e))))
+        .collect::<StorageResult<Vec<Column>>>()?;
+    for (i, col) in columns.iter().enumerate() {
+        if columns[..i].iter().any(|c| c.name == col.name) {
+            return Err(StorageError::SchemaMismatch(format!(
+                "Duplicate column name '{}'", col.name
+            )));
+        }
+    }
+    let schema = Schema::new(columns);
+    schema.validate()?;
+    Ok(schema)
+}
+
+fn parse_column(json: &Json) -> StorageResult<Column> {
+    if !matches!(json, Json::Object(_)) {
+        return Err(malformed(format!("expected an object, got {}", json.kind())));
+    }
+    let name = json
+        .get("name")
+        .and_then(Json::as_str)
+        .ok_or_else(|| malformed("\"name\" must be a string"))?;
+    let nullable = match json.get("nullable") {
+        None | Some(Json::Null) => false,
+        Some(Json::Bool(b)) => *b,
+        Some(other) => return Err(malformed(format!("\"nullable\" must be a boolean, got {}", other))),
+    };
+    let col_type = match json.get("type") {
+        Some(Json::String(name)) => match name.as_str() {
+            "Int32" => ColumnType::Int32,
+            "UInt32" => ColumnType::UInt32,
+            "Float64" => ColumnType::Float64,
+            "Bool" => ColumnType::Bool,
+            "Int16" => ColumnType::Int16,
+            "Int64" => ColumnType::Int64,
+            "Float32" => ColumnType::Float32,
+            "Date" => ColumnType::Date,
+            "Timestamp" => ColumnType::Timestamp,
+            "Uuid" => ColumnType::Uuid,
+            _ => return Err(malformed(format!("Unknown type: {}", name))),
+        },
+        Some(Json::Object(members)) if members.len() == 1 && members[0].0 == "Decimal" => {
+            let params = &members[0].1;
+            let digits = |json: &Json| json_u32(json).filter(|&n| n <= u8::MAX as u32);
+            match params.as_array() {
+                Some([p, s]) if digits(p).is_some() && digits(s).is_some() => {
+                    ColumnType::Decimal(digits(p).unwrap() as u8, digits(s).unwrap() as u8)
+                }
+                _ => return Err(malformed(format!(
+                    "Decimal takes [precision, scale], got {}", params
+                ))),
+            }
+        }
+        Some(Json::Object(members)) if members.len() == 1 => {
+            let (kind, len) = &members[0];
+            let len = json_u32(len)
+                .filter(|&n| n < OVERFLOW_SENTINEL as u32)
+                .ok_or_else(|| malformed(format!(
+                    "{} length must be an integer below {}, got {}", kind, OVERFLOW_SENTINEL, len
+                )))? as u16;
+            match kind.as_str() {
+                "VarChar" => ColumnType::VarChar(len),
+                "Blob" => ColumnType::Blob(len),
+                "Char" => ColumnType::Char(len),
+                _ => return Err(malformed(format!("Unknown type: {}", kind))),
+            }
+        }
+        Some(other) => return Err(malformed(format!("Invalid type: {}", other))),
+        None => return Err(malformed("Missing \"type\"")),
+    };
+    Ok(Column { name: name.to_string(), col_type, nullable })
+}
+
+pub fn schema_to_json(schema: &Schema) -> Json {
+    Json::object([("columns", Json::Array(schema.columns.iter().map(column_to_json).collect()))])
+}
+
+/// `{ "name": ..., "type": ..., "nullable": ... }`, as in a schema.
+pub fn column_to_json(c: &Column) -> Json {
+    let col_type = match &c.col_type {
+        ColumnType::Int32 => "Int32".into(),
+        ColumnType::UInt32 => "UInt32".into(),
+        ColumnType::Float64 => "Float64".into(),
+        ColumnType::Bool => "Bool".into(),
+        ColumnType::VarChar(n) => Json::object([("VarChar", (*n).into())]),
+        ColumnType::Blob(n) => Json::object([("Blob", (*n).into())]),
+        ColumnType::Char(n) => Json::object([("Char", (*n).into())]),
+        ColumnType::Decimal(p, s) => {
+            Json::object([("Decimal", Json::Array(vec![(*p).into(), (*s).into()]))])
+        }
+        t => t.to_string().into(),
+    };
+    Json::object([
+        ("name", c.name.as_str().into()),
+        ("type", col_type),
+        ("nullable", c.nullable.into()),
+    ])
+}
+
+// ── Values ─────────────────────────────────────────────────────────
+
+/// Parse a row: a JSON array with one value per column.
+pub fn parse_values(text: &str, schema: &Schema) -> StorageResult<Vec<Value>> {
+    let doc = Json::parse(text)?;
+    let items = doc
+        .as_array()
+        .ok_or_else(|| malformed(format!("Values must be an array, got {}", doc.kind())))?;
+    if items.len() != schema.columns.len() {
+        return Err(StorageError::SchemaMismatch(format!(
+            "Expected {} values, got {}", schema.columns.len(), items.len()
+        )));
+    }
+    items.iter().zip(&schema.columns).map(|(v, col)| value_from_json(v, col)).collect()
+}
+
+/// Parse an index key or range bound for `col`; `null` gives `None`.
+pub fn parse_key(text: &str, col: &Column) -> StorageResult<Option<Value>> {
+    match Json::parse(text)? {
+        Json::Null => Ok(None),
+        doc => value_from_json(&doc, col).map(Some),
+    }
+}
+
+/// Convert one JSON value for `col`.
+pub fn value_from_json(json: &Json, col: &Column) -> StorageResult<Value> {
+    let invalid = || StorageError::SchemaMismatch(format!(
+        "Column '{}': {} is not a valid {}", col.name, json, col.col_type
+    ));
+    let value = match (&col.col_type, json) {
+        (_, Json::Null) if col.nullable => Value::Null,
+        (_, Json::Null) => {
+            return Err(StorageError::SchemaMismatch(format!(
+                "Column '{}' is not nullable", col.name
+            )));
+        }
+        (ColumnType::Int32, Json::Number(n)) => Value::Int32(n.parse().map_err(|_| invalid())?),
+        (ColumnType::UInt32, Json::Number(n)) => Value::UInt32(n.parse().map_err(|_| invalid())?),
+        (ColumnType::Float64, Json::Number(n)) => {
+            let v: f64 = n.parse().map_err(|_| invalid())?;
+            if !v.is_finite() {
+                return Err(StorageError::SchemaMismatch(format!(
+                    "Column '{}': {} is out of range for Float64", col.name, n
+                )));
+            }
+            Value::Float64(v)
+        }
+        (ColumnType::Float64, Json::String(s)) => match s.as_str() {
+            "NaN" => Value::Float64(f64::NAN),
+            "Infinity" => Value::Float64(f64::INFINITY),
+            "-Infinity" => Value::Float64(f64::NEG_INFINITY),
+            _ => return Err(invalid()),
+        },
+        (ColumnType::Bool, Json::Bool(b)) => Value::Bool(*b),
+        (ColumnType::VarChar(_), Json::String(s)) => Value::VarChar(s.clone()),
+        // Char pads with spaces, so trailing ones are not part of the value
+        (ColumnType::Char(_), Json::String(s)) => Value::VarChar(s.trim_end_matches(' ').into()),
+        (ColumnType::Int16, Json::Number(n)) => Value::Int16(n.parse().map_err(|_| invalid())?),
+        (ColumnType::Int64, Json::Number(n) | Json::String(n)) => {
+            Value::Int64(n.parse().map_err(|_| invalid())?)
+        }
+        (ColumnType::Float32, Json::Number(n)) => {
+            let v: f32 = n.parse().map_err(|_| invalid())?;
+            if !v.is_finite() {
+                return Err(StorageError::SchemaMismatch(format!(
+                    "Column '{}': {} is out of range for Float32", col.name, n
+                )));
+            }
+            Value::Float32(v)
+        }
+        (ColumnType::Float32, Json::String(s)) => match s.as_str() {
+            "NaN" => Value::Float32(f32::NAN),
+            "Infinity" => Value::Float32(f32::INFINITY),
+            "-Infinity" => Value::Float32(f32::NEG_INFINITY),
+            _ => return Err(invalid()),
+        },
+        (ColumnType::Decimal(_, scale), Json::Number(n) | Json::String(n)) => {
+            Value::Decimal(parse_decimal(n, *scale).ok_or_else(invalid)?, *scale)
+        }
+        (ColumnType::Date, Json::String(s)) => {
+            Value::Date(datetime::parse_date(s).ok_or_else(invalid)?)
+        }
+        (ColumnType::Timestamp, Json::String(s)) => {
+            Value::Timestamp(datetime::parse_timestamp(s).ok_or_else(invalid)?)
+        }
+        (ColumnType::Uuid, Json::String(s)) => Value::Uuid(parse_uuid(s).ok_or_else(invalid)?),
+        (ColumnType::Blob(_), _) => Value::Blob(blob_from_json(json).ok_or_else(|| {
+            StorageError::SchemaMismatch(format!(
+                "Column '{}': {} is not a valid Blob (use \"0x…\" hex, \"base64:…\" or an array of bytes)",
+                col.name, json
+            ))
+        })?),
+        _ => return Err(invalid()),
+    };
+    Ok(value)
+}
+
+/// Decode the bytes of a Blob written as `"0x…"` hex, `"base64:…"` or an
+/// array of integers from 0 to 255.
+pub fn blob_from_json(json: &Json) -> Option<Vec<u8>> {
+    match json {
+        Json::String(s) => {
+            if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+                decode_hex(hex)
+            } else {
+                decode_base64(s.strip_prefix("base64:")?)
+            }
+        }
+        Json::Array(items) => items
+            .iter()
+            .map(|item| match item {
+                Json::Number(n) => n.parse::<u8>().ok(),
+                _ => None,
+            })
+            .collect(),
+        _ => None,
+    }
+}
+
+/// Parse a row whose Blob columns are supplied separately as raw bytes:
+/// one entry of `blobs` per Blob column, in column order (`None` for
+/// NULL).  The JSON array still has an entry for every column, with
+/// `null` as the placeholder at each Blob position.
+pub fn parse_values_with_blobs(
+    text: &str,
+    schema: &Schema,
+    blobs: Vec<Option<Vec<u8>>>,
+) -> StorageResult<Vec<Value>> {
+    let blob_columns = schema.columns.iter().filter(|c| matches!(c.col_type, ColumnType::Blob(_))).count();
+    if blobs.len() != blob_columns {
+        return Err(StorageError::SchemaMismatch(format!(
+            "Expected {} blobs, got {}", blob_columns, blobs.len()
+        )));
+    }
+    let doc = Json::parse(text)?;
+    let items = doc
+        .as_array()
+        .ok_or_else(|| malformed(format!("Values must be an array, got {}", doc.kind())))?;
+    if items.len() != schema.columns.len() {
+        return Err(StorageError::SchemaMismatch(format!(
+            "Expected {} values, got {}", schema.columns.len(), items.len()
+        )));
+    }
+
+    let mut blobs = blobs.into_iter();
+    items
+        .iter()
+        .zip(&schema.columns)
+        .map(|(item, col)| match (&col.col_type, item) {
+            (ColumnType::Blob(_), Json::Null) => match blobs.next().flatten() {
+                Some(bytes) => Ok(Value::Blob(bytes)),
+                None => value_from_json(&Json::Null, col),
+            },
+            (ColumnType::Blob(_), _) => Err(StorageError::SchemaMismatch(format!(
+                "Column '{}' takes its bytes from the blobs; put null in the values", col.name
+            ))),
+            _ => value_from_json(item, col),
+        })
+        .collect()
+}
+
+/// Largest integer a JavaScript number holds exactly, 2^53 - 1.
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+pub fn value_to_json(value: &Value) -> Json {
+    match value {
+        Value::Int32(n) => (*n).into(),
+        Value::UInt32(n) => (*n).into(),
+        // `{:?}` is the shortest text that reads back as the same f64
+        Value::Float64(f) if f.is_finite() => Json::Number(format!("{:?}", f)),
+        Value::Float64(f) if f.is_nan() => "NaN".into(),
+        Value::Float64(f) if *f > 0.0 => "Infinity".into(),
+        Value::Float64(_) => "-Infinity".into(),
+        Value::Bool(b) => (*b).into(),
+        Value::VarChar(s) => s.as_str().into(),
+        Value::Blob(_) => value.to_string().into(),
+        Value::Int16(n) => (*n).into(),
+        Value::Int64(n) if n.unsigned_abs() <= MAX_SAFE_INTEGER => (*n).into(),
+        Value::Int64(n) => n.to_string().into(),
+        Value::Float32(f) if f.is_finite() => Json::Number(format!("{:?}", f)),
+        Value::Float32(f) if f.is_nan() => "NaN".into(),
+        Value::Float32(f) if *f > 0.0 => "Infinity".into(),
+        Value::Float32(_) => "-Infinity".into(),
+        Value::Decimal(..) | Value::Date(_) | Value::Timestamp(_) | Value::Uuid(_) => {
+            value.to_string().into()
+        }
+        Value::Null => Json::Null,
+    }
+}
+
+pub fn values_to_json(values: &[Value]) -> Json {
+    Json::Array(values.iter().map(value_to_json).collect())
+}
+
+/// `[{ "row_id": "p:s", "values": [...] }, ...]`
+pub fn rows_to_json(rows: &Rows) -> Json {
+    Json::Array(
+        rows.iter()
+            .map(|(rid, values)| {
+                Json::object([("row_id", row_id_to_json(*rid)), ("values", values_to_json(values))])
+            })
+            .collect(),
+    )
+}
+
+/// `{ "columns": [...], "rows": [[...], ...], "rows_affected": n, "plan": {...} }`,
+/// with columns written as in a schema and `plan` null except for a SELECT.
+pub fn query_result_to_json(result: &QueryResult) -> Json {
+    Json::object([
+        ("columns", Json::Array(result.columns.iter().map(column_to_json).collect())),
+        ("rows", Json::Array(result.rows.iter().map(|r| values_to_json(r)).collect())),
+        ("rows_affected", result.rows_affected.into()),
+        ("plan", result.plan.as_ref().map_or(Json::Null, plan_profile_to_json)),
+    ])
+}
+
+/// `{ "operator": "SeqScan", "detail": "t", "rows_produced": n,
+/// "pages_touched": n, "page_reads": n, "page_writes": n, "children": [...] }`
+pub fn plan_profile_to_json(plan: &PlanProfile) -> Json {
+    let s = &plan.stats;
+    Json::object([
+        ("operator", plan.operator.into()),
+        ("detail", plan.detail.as_str().into()),
+        ("rows_produced", s.rows_produced.into()),
+        ("pages_touched", s.pages_touched.into()),
+        ("page_reads", s.page_reads.into()),
+        ("page_writes", s.page_writes.into()),
+        ("children", Json::Array(plan.children.iter().map(plan_profile_to_json).collect())),
+    ])
+}
+
+/// `{ "memory_frames", "input_rows", "runs": [{ "id", "pass", "rows",
+/// "pages" }], "passes": [[{ "inputs", "output" }]] }`
+pub fn sort_trace_to_json(trace: &SortTrace) -> Json {
+    let runs = trace.runs.iter().map(|r| {
+        Json::object([
+            ("id", r.id.into()),
+            ("pass", r.pass.into()),
+            ("rows", r.rows.into()),
+            ("pages", Json::Array(r.pages.iter().map(|&p| p.into()).collect())),
+        ])
+    });
+    let passes = trace.passes.iter().map(|pass| {
+        Json::Array(
+            pass.iter()
+                .map(|m| {
+                    Json::object([
+                        ("inputs", Json::Array(m.inputs.iter().map(|&i| i.into()).collect())),
+                        ("output", m.output.into()),
+                    ])
+                })
+                .collect(),
+        )
+    });
+    Json::object([
+        ("memory_frames", trace.memory_frames.into()),
+        ("input_rows", trace.input_rows.into()),
+        ("runs", Json::Array(runs.collect())),
+        ("passes", Json::Array(passes.collect())),
+    ])
+}
+
+/// Parse a JSON array of strings, such as column names.
+pub fn parse_names(text: &str) -> StorageResult<Vec<String>> {
+    let doc = Json::parse(text)?;
+    let items = doc
+        .as_array()
+        .ok_or_else(|| malformed(format!("Expected an array of names, got {}", doc.kind())))?;
+    items
+        .iter()
+        .map(|item| item.as_str().map(String::from).ok_or_else(|| malformed("Names must be strings")))
+        .collect()
+}
+
+/// Parse an aggregate written as `sum(price)`, or `count(*)`.
+pub fn parse_aggregate(text: &str) -> StorageResult<(AggregateFunction, Option<&str>)> {
+    let invalid = || malformed(format!("Invalid aggregate: '{}'", text));
+    let (name, rest) = text.trim().split_once('(').ok_or_else(invalid)?;
+    let arg = rest.strip_suffix(')').ok_or_else(invalid)?.trim();
+    let function = AggregateFunction::parse(name.trim()).ok_or_else(invalid)?;
+    match arg {
+        "" => Err(invalid()),
+        "*" if function == AggregateFunction::Count => Ok((function, None)),
+        "*" => Err(invalid()),
+        column => Ok((function, Some(column))),
+    }
+}
+
+// ── RowIds and reports ─────────────────────────────────────────────
+
+/// Parse a RowId written as "page_id:slot_id".
+pub fn parse_row_id(s: &str) -> StorageResult<RowId> {
+    let (page_id, slot_id) = s
+        .split_once(':')
+        .ok_or_else(|| malformed("RowId format: page_id:slot_id"))?;
+    Ok(RowId {
+        page_id: page_id.parse().map_err(|_| malformed(format!("Invalid page_id: {}", page_id)))?,
+        slot_id: slot_id.parse().map_err(|_| malformed(format!("Invalid slot_id: {}", slot_id)))?,
+    })
+}
+
+pub fn row_id_to_json(rid: RowId) -> Json {
+    format!("{}:{}", rid.page_id, rid.slot_id).into()
+}
+
+pub fn vacuum_report_to_json(report: &VacuumReport) -> Json {
+    Json::object([
+        ("pages_scanned", report.pages_scanned.into()),
+        ("pages_compacted", report.pages_compacted.into()),
+        ("slots_trimmed", report.slots_trimmed.into()),
+        ("pages_freed", report.pages_freed.into()),
+        ("bytes_reclaimed", report.bytes_reclaimed.into()),
+    ])
+}
+
+pub fn recovery_report_to_json(report: &RecoveryReport) -> Json {
+    Json::object([
+        ("records_scanned", report.records_scanned.into()),
+        ("redo_start", report.redo_start.into()),
+        ("redo_applied", report.redo_applied.into()),
+        ("undo_applied", report.undo_applied.into()),
+        ("losers", report.losers.clone().into()),
+        ("unfinished", report.unfinished.clone().into()),
+    ])
+}
+
+// ── Helpers ────────────────────────────────────────────────────────
+
+fn json_u32(json: &Json) -> Option<u32> {
+    match json {
+        Json::Number(n) => n.parse().ok(),
+        _ => None,
+    }
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Standard base64 (`+` and `/`), with or without `=` padding.
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim_end_matches('=');
+    if text.len() % 4 == 1 {
+        return None;
+    }
+    let sextet = |c: u8| match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    };
+    let mut out = Vec::with_capacity(text.len() * 3 / 4);
+    for chunk in text.as_bytes().chunks(4) {
+        let mut bits = 0u32;
+        for (i, &c) in chunk.iter().enumerate() {
+            bits |= (sextet(c)? as u32) << (18 - 6 * i);
+        }
+        out.extend_from_slice(&bits.to_be_bytes()[1..chunk.len()]);
+    }
+    Some(out)
+}
+
+fn malformed(msg: impl Into<String>) -> StorageError {
+    StorageError::Parse(msg.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exec::OperatorStats;
+
+    fn col(name: &str, col_type: ColumnType) -> Column {
+        Column { name: name.into(), col_type, nullable: true }
+    }
+
+    #[test]
+    fn config_keys_match_exactly() {
+        let config = parse_config(r#"{"xpage_size": 9, "page_size": 256, "replacement_policy": "ARC"}"#).unwrap();
+        assert_eq!(config.page_size, 256);
+        assert_eq!(config.replacement_policy, ReplacementPolicy::Arc);
+        assert!(matches!(parse_config(r#"{"pool_size": -1}"#), Err(StorageError::Parse(_))));
+        assert!(matches!(parse_config(r#"{"pool_size": 4.5}"#), Err(StorageError::Parse(_))));
+
+        let text = config_to_json(&config).to_string();
+        assert_eq!(parse_config(&text).unwrap().page_size, 256);
+    }
+
+    #[test]
+    fn schema_round_trip() {
+        let text = r#"{"columns":[{"name":"{\"name\": \"x\"}","type":{"VarChar":40},"nullable":true},{"name":"n","type":"Int32","nullable":false}]}"#;
+        let schema = parse_schema(text).unwrap();
+        assert_eq!(schema.columns[0].name, r#"{"name": "x"}"#);
+        assert_eq!(schema.columns[0].col_type, ColumnType::VarChar(40));
+        assert_eq!(schema_to_json(&schema).to_string(), text);
+
+        assert_eq!(
+            parse_schema(r#"{"columns":[{"name":"a","type":"Int128"}]}"#).err(),
+            Some(malformed("columns[0]: Unknown type: Int128"))
+        );
+        assert!(matches!(
+            parse_schema(r#"{"columns":[{"name":"a","type":"Int32"},{"name":"a","type":"Bool"}]}"#),
+            Err(StorageError::SchemaMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn values_round_trip_losslessly() {
+        let schema = Schema::new(vec![
+            col("i", ColumnType::Int32),
+            col("u", ColumnType::UInt32),
+            col("f", ColumnType::Float64),
+            col("b", ColumnType::Bool),
+            col("s", ColumnType::VarChar(50)),
+        ]);
+        let rows = [
+            vec![
+                Value::Int32(i32::MIN),
+                Value::UInt32(u32::MAX),
+                Value::Float64(0.1 + 0.2),
+                Value::Bool(true),
+                Value::VarChar("\"q\" \\ {[,]} \u{1} é 😀".into()),
+            ],
+            vec![
+                Value::Null,
+                Value::Null,
+                Value::Float64(f64::NEG_INFINITY),
+                Value::Null,
+                Value::VarChar(String::new()),
+            ],
+            vec![
+                Value::Int32(0),
+                Value::UInt32(0),
+                Value::Float64(-0.0),
+                Value::Bool(false),
+                Value::Null,
+            ],
+        ];
+        for row in &rows {
+            let text = values_to_json(row).to_string();
+            let back = parse_values(&text, &schema).unwrap();
+            assert_eq!(values_to_json(&back).to_string(), text);
+            for (a, b) in back.iter().zip(row) {
+                assert_eq!(cmp_values(a, b), std::cmp::Ordering::Equal, "{}", text);
+            }
+        }
+
+        let nan = parse_values(r#"[1, 2, "NaN", true, "x"]"#, &schema).unwrap();
+        assert!(matches!(nan[2], Value::Float64(f) if f.is_nan()));
+        assert_eq!(
+            parse_values(r#"[1, 2, "é😀", true, "x"]"#, &schema).unwrap_err().code(),
+            "SCHEMA_MISMATCH"
+        );
+        assert_eq!(
+            parse_values(r#"["é😀"]"#, &Schema::new(vec![col("s", ColumnType::VarChar(9))])),
+            Ok(vec![Value::VarChar("é😀".into())])
+        );
+    }
+
+    #[test]
+    fn wide_types() {
+        let text = r#"{"columns":[{"name":"a","type":"Int16","nullable":false},{"name":"b","type":"Int64","nullable":false},{"name":"c","type":"Float32","nullable":false},{"name":"d","type":{"Decimal":[10,2]},"nullable":false},{"name":"e","type":"Date","nullable":false},{"name":"f","type":"Timestamp","nullable":false},{"name":"g","type":"Uuid","nullable":false},{"name":"h","type":{"Char":4},"nullable":false}]}"#;
+        let schema = parse_schema(text).unwrap();
+        assert_eq!(schema.columns[3].col_type, ColumnType::Decimal(10, 2));
+        assert_eq!(schema.columns[7].col_type, ColumnType::Char(4));
+        assert_eq!(schema_to_json(&schema).to_string(), text);
+
+        let row = parse_values(
+            r#"[-7, "9007199254740993", 0.1, 12.5, "2024-02-29", "2024-02-29T14:45:00+01:00",
+                "00112233-4455-6677-8899-AABBCCDDEEFF", "ab  "]"#,
+            &schema,
+        )
+        .unwrap();
+        assert_eq!(
+            row,
+            vec![
+                Value::Int16(-7),
+                Value::Int64(9_007_199_254_740_993),
+                Value::Float32(0.1),
+                Value::Decimal(1250, 2),
+                Value::Date(19_782),
+                Value::Timestamp(1_709_214_300_000_000),
+                Value::Uuid([0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff]),
+                Value::VarChar("ab".into()),
+            ]
+        );
+        let out = values_to_json(&row).to_string();
+        assert_eq!(
+            out,
+            r#"[-7,"9007199254740993",0.1,"12.50","2024-02-29","2024-02-29T13:45:00Z","00112233-4455-6677-8899-aabbccddeeff","ab"]"#
+        );
+        assert_eq!(parse_values(&out, &schema).unwrap(), row);
+        assert_eq!(value_to_json(&Value::Int64(-42)).to_string(), "-42");
+
+        let err = |i: usize, json: &str| {
+            let mut items = [
+                "0", "0", "0", "0", "\"2024-01-01\"", "\"2024-01-01\"",
+                "\"00000000000000000000000000000000\"", "\"\"",
+            ];
+            items[i] = json;
+            let values = parse_values(&format!("[{}]", items.join(",")), &schema);
+            values.and_then(|v| schema.check(&v)).unwrap_err().to_string()
+        };
+        assert_eq!(err(0, "40000"), "Column 'a': 40000 is not a valid Int16");
+        assert_eq!(err(3, "1.005"), "Column 'd': 1.005 is not a valid Decimal(10,2)");
+        assert_eq!(err(3, "1e2"), "Column 'd': 1e2 is not a valid Decimal(10,2)");
+        assert_eq!(err(3, "123456789"), "Column 'd' holds at most 10 digits");
+        assert_eq!(err(4, "\"2023-02-29\""), "Column 'e': \"2023-02-29\" is not a valid Date");
+        assert_eq!(err(6, "\"0011\""), "Column 'g': \"0011\" is not a valid Uuid");
+        assert_eq!(err(7, "\"abcde\""), "Column 'h' holds at most 4 bytes");
+        assert_eq!(
+            parse_schema(r#"{"columns":[{"name":"d","type":{"Decimal":[4,5]}}]}"#).err(),
+            Some(StorageError::SchemaMismatch(
+                "Column 'd' Decimal(4,5) has a scale larger than its precision".into()
+            ))
+        );
+    }
+
+    #[test]
+    fn values_reject_bad_numbers() {
+        let schema = Schema::new(vec![col("i", ColumnType::Int32), col("f", ColumnType::Float64)]);
+        let err = |text: &str| parse_values(text, &schema).unwrap_err().to_string();
+        assert_eq!(err("[1, 1e400]"), "Column 'f': 1e400 is out of range for Float64");
+        assert_eq!(err("[2147483648, 0]"), "Column 'i': 2147483648 is not a valid Int32");
+        assert_eq!(err("[1.5, 0]"), "Column 'i': 1.5 is not a valid Int32");
+        assert_eq!(err(r#"["1", 0]"#), "Column 'i': \"1\" is not a valid Int32");
+        assert_eq!(err("[1, 0"), "Invalid JSON: Expected ',' or ']', found end of input at line 1, column 6");
+        assert_eq!(parse_values("[1, 1e2]", &schema).unwrap()[1], Value::Float64(100.0));
+    }
+
+    #[test]
+    fn blob_encodings() {
+        let schema = Schema::new(vec![col("b", ColumnType::Blob(64))]);
+        let bytes: Vec<u8> = (0..=255).step_by(17).collect();
+        let blob = |text: &str| parse_values(text, &schema).map(|v| v[0].clone());
+
+        let hex = values_to_json(&[Value::Blob(bytes.clone())]).to_string();
+        assert_eq!(blob(&hex), Ok(Value::Blob(bytes.clone())));
+        assert_eq!(blob(r#"["0XFF00"]"#), Ok(Value::Blob(vec![0xFF, 0])));
+        assert_eq!(blob(r#"["base64:3q2+7w=="]"#), Ok(Value::Blob(vec![0xDE, 0xAD, 0xBE, 0xEF])));
+        assert_eq!(blob(r#"["base64:3q2+7w"]"#), Ok(Value::Blob(vec![0xDE, 0xAD, 0xBE, 0xEF])));
+        assert_eq!(blob(r#"["base64:"]"#), Ok(Value::Blob(Vec::new())));
+        assert_eq!(blob("[[0, 127, 255]]"), Ok(Value::Blob(vec![0, 127, 255])));
+
+        for bad in [r#"["cafe"]"#, r#"["0xabc"]"#, r#"["0xzz"]"#, r#"["base64:a"]"#, "[[256]]", "[[1.5]]"] {
+            assert_eq!(blob(bad).unwrap_err().code(), "SCHEMA_MISMATCH", "{}", bad);
+        }
+    }
+
+    #[test]
+    fn blobs_supplied_separately() {
+        let schema = Schema::new(vec![
+            col("id", ColumnType::Int32),
+            col("a", ColumnType::Blob(16)),
+            col("b", ColumnType::Blob(16)),
+        ]);
+        let values = parse_values_with_blobs("[7, null, null]", &schema, vec![Some(vec![0, 1]), None]).unwrap();
+        assert_eq!(values, vec![Value::Int32(7), Value::Blob(vec![0, 1]), Value::Null]);
+        assert!(parse_values_with_blobs("[7, null, null]", &schema, vec![None]).is_err());
+        assert!(parse_values_with_blobs(r#"[7, "0x00", null]"#, &schema, vec![None, None]).is_err());
+    }
+
+    #[test]
+    fn row_ids() {
+        let rid = RowId { page_id: 12, slot_id: 3 };
+        assert_eq!(parse_row_id("12:3"), Ok(rid));
+        assert_eq!(row_id_to_json(rid).to_string(), r#""12:3""#);
+        assert!(parse_row_id("12").is_err());
+        assert!(parse_row_id("12:x").is_err());
+    }
+
+    #[test]
+    fn query_results() {
+        let result = QueryResult {
+            columns: vec![col("n", ColumnType::Decimal(18, 2)), col("d", ColumnType::Date)],
+            rows: vec![vec![Value::Decimal(1250, 2), Value::Date(0)]],
+            rows_affected: 0,
+            plan: None,
+        };
+        assert_eq!(
+            query_result_to_json(&result).to_string(),
+            r#"{"columns":[{"name":"n","type":{"Decimal":[18,2]},"nullable":true},{"name":"d","type":"Date","nullable":true}],"rows":[["12.50","1970-01-01"]],"rows_affected":0,"plan":null}"#
+        );
+        let plan = PlanProfile {
+            operator: "SeqScan",
+            detail: "t".into(),
+            stats: OperatorStats { rows_produced: 3, pages_touched: 2, page_reads: 1, page_writes: 0 },
+            children: Vec::new(),
+        };
+        assert_eq!(
+            plan_profile_to_json(&plan).to_string(),
+            r#"{"operator":"SeqScan","detail":"t","rows_produced":3,"pages_touched":2,"page_reads":1,"page_writes":0,"children":[]}"#
+        );
+    }
+
+    #[test]
+    fn aggregate_calls() {
+        assert_eq!(parse_aggregate("count(*)").unwrap(), (AggregateFunction::Count, None));
+        assert_eq!(parse_aggregate(" SUM( price ) ").unwrap(), (AggregateFunction::Sum, Some("price")));
+        for bad in ["sum(*)", "avg()", "median(x)", "max x"] {
+            assert_eq!(parse_aggregate(bad).unwrap_err().code(), "PARSE_ERROR", "{}", bad);
+        }
+        assert_eq!(parse_names(r#"["a", "b"]"#).unwrap(), ["a", "b"]);
+        assert!(parse_names("[1]").is_err());
+    }
+
+    #[test]
+    fn sort_traces() {
+        use crate::exec::sort::{SortMerge, SortRun};
+        let trace = SortTrace {
+            memory_frames: 3,
+            input_rows: 5,
+            runs: vec![
+                SortRun { id: 0, pass: 0, rows: 3, pages: vec![9, 10] },
+                SortRun { id: 1, pass: 0, rows: 2, pages: vec![11] },
+            ],
+            passes: vec![vec![SortMerge { inputs: vec![0, 1], output: None }]],
+        };
+        assert_eq!(
+            sort_trace_to_json(&trace).to_string(),
+            r#"{"memory_frames":3,"input_rows":5,"runs":[{"id":0,"pass":0,"rows":3,"pages":[9,10]},{"id":1,"pass":0,"rows":2,"pages":[11]}],"passes":[[{"inputs":[0,1],"output":null}]]}"#
+        );
+    }
+}